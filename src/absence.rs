@@ -0,0 +1,268 @@
+//! Absences for MatteriaTrack
+//!
+//! Vacation, sick leave, public holidays and comp time, stored per day. Most
+//! kinds credit the day's expected hours so the flextime balance and weekly
+//! stats don't read as underperformance; comp time is taken from the balance
+//! instead. Absence days bridge tracking streaks rather than breaking them.
+
+use crate::error::{Error, Result};
+use crate::theme::MateriaTheme;
+use chrono::{DateTime, NaiveDate, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AbsenceKind {
+    Vacation,
+    Sick,
+    Holiday,
+    CompTime,
+}
+
+impl AbsenceKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Vacation => "vacation",
+            Self::Sick => "sick",
+            Self::Holiday => "holiday",
+            Self::CompTime => "comp-time",
+        }
+    }
+
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::Vacation => "🏖️",
+            Self::Sick => "🤒",
+            Self::Holiday => "🎉",
+            Self::CompTime => "⏳",
+        }
+    }
+
+    /// Whether the day's expected hours count as worked. Comp time spends
+    /// overtime, so it leaves the expectation in place.
+    pub fn credits_time(self) -> bool {
+        self != Self::CompTime
+    }
+}
+
+impl fmt::Display for AbsenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for AbsenceKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "vacation" | "leave" => Ok(Self::Vacation),
+            "sick" => Ok(Self::Sick),
+            "holiday" | "public-holiday" => Ok(Self::Holiday),
+            "comp-time" | "comptime" | "comp" => Ok(Self::CompTime),
+            _ => Err(Error::InvalidInput(format!(
+                "Unknown absence type '{}' (expected vacation, sick, holiday or comp-time)",
+                s
+            ))),
+        }
+    }
+}
+
+/// One day (or half day) away from work.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Absence {
+    pub id: i64,
+    pub date: NaiveDate,
+    pub kind: AbsenceKind,
+    pub half_day: bool,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Absence {
+    pub fn new(date: NaiveDate, kind: AbsenceKind) -> Self {
+        Self {
+            id: 0,
+            date,
+            kind,
+            half_day: false,
+            note: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_half_day(mut self, half_day: bool) -> Self {
+        self.half_day = half_day;
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    /// Share of the day taken off.
+    pub fn fraction(&self) -> f64 {
+        if self.half_day {
+            0.5
+        } else {
+            1.0
+        }
+    }
+
+    /// Share of the day's expected hours credited as worked.
+    pub fn credit(&self) -> f64 {
+        if self.kind.credits_time() {
+            self.fraction()
+        } else {
+            0.0
+        }
+    }
+
+    /// `vacation` or `sick (half day)`.
+    pub fn label(&self) -> String {
+        if self.half_day {
+            format!("{} (half day)", self.kind)
+        } else {
+            self.kind.to_string()
+        }
+    }
+}
+
+/// Days off per kind; half days count as 0.5.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AbsenceSummary {
+    pub days: f64,
+    pub by_kind: BTreeMap<AbsenceKind, f64>,
+}
+
+impl AbsenceSummary {
+    pub fn build(absences: &[Absence]) -> Self {
+        let mut summary = Self::default();
+        for a in absences {
+            summary.days += a.fraction();
+            *summary.by_kind.entry(a.kind).or_default() += a.fraction();
+        }
+        summary
+    }
+
+    /// `2.5 days (vacation 2, sick 0.5)`.
+    pub fn formatted(&self) -> String {
+        let kinds: Vec<String> = self
+            .by_kind
+            .iter()
+            .map(|(kind, days)| format!("{} {}", kind, days_number(*days)))
+            .collect();
+        format!(
+            "{} day{} ({})",
+            days_number(self.days),
+            if self.days == 1.0 { "" } else { "s" },
+            kinds.join(", ")
+        )
+    }
+}
+
+fn days_number(days: f64) -> String {
+    if days.fract() == 0.0 {
+        format!("{}", days as i64)
+    } else {
+        format!("{:.1}", days)
+    }
+}
+
+/// Dates that may sit inside a tracking streak without breaking it.
+pub fn bridging_days(absences: &[Absence]) -> BTreeSet<NaiveDate> {
+    absences.iter().map(|a| a.date).collect()
+}
+
+pub fn format_list(absences: &[Absence], theme: MateriaTheme) -> String {
+    let (r, g, b) = theme.primary_color();
+    let mut output = format!(
+        "\n{} {} {}\n",
+        theme.icon(),
+        "Absences".truecolor(r, g, b).bold(),
+        theme.materia_icon()
+    );
+    output.push_str(&"━".repeat(50));
+    output.push('\n');
+
+    if absences.is_empty() {
+        output.push_str("\n  No absences recorded.\n");
+    } else {
+        output.push('\n');
+        for a in absences {
+            output.push_str(&format!(
+                "  {:>4}  {}  {} {}{}\n",
+                format!("#{}", a.id).dimmed(),
+                a.date.format("%a %Y-%m-%d"),
+                a.kind.icon(),
+                a.label().bold(),
+                a.note
+                    .as_deref()
+                    .map(|n| format!("  {}", n.dimmed()))
+                    .unwrap_or_default()
+            ));
+        }
+        output.push_str(&format!(
+            "\n  Total: {}\n",
+            AbsenceSummary::build(absences).formatted()
+        ));
+    }
+
+    output.push('\n');
+    output.push_str(&"━".repeat(50));
+    output.push('\n');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 12, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_kinds() {
+        assert_eq!(
+            "comp-time".parse::<AbsenceKind>().unwrap(),
+            AbsenceKind::CompTime
+        );
+        assert_eq!(
+            "Public-Holiday".parse::<AbsenceKind>().unwrap(),
+            AbsenceKind::Holiday
+        );
+        assert!("party".parse::<AbsenceKind>().is_err());
+        assert_eq!(
+            serde_json::to_string(&AbsenceKind::CompTime).unwrap(),
+            "\"comp-time\""
+        );
+    }
+
+    #[test]
+    fn test_credit_and_half_days() {
+        let half_vacation = Absence::new(date(24), AbsenceKind::Vacation).with_half_day(true);
+        assert_eq!(half_vacation.credit(), 0.5);
+        assert_eq!(half_vacation.label(), "vacation (half day)");
+        assert_eq!(Absence::new(date(28), AbsenceKind::CompTime).credit(), 0.0);
+        assert_eq!(Absence::new(date(25), AbsenceKind::Holiday).credit(), 1.0);
+    }
+
+    #[test]
+    fn test_summary() {
+        let absences = [
+            Absence::new(date(22), AbsenceKind::Vacation),
+            Absence::new(date(23), AbsenceKind::Vacation),
+            Absence::new(date(24), AbsenceKind::Sick).with_half_day(true),
+        ];
+        let summary = AbsenceSummary::build(&absences);
+        assert_eq!(summary.days, 2.5);
+        assert_eq!(summary.formatted(), "2.5 days (vacation 2, sick 0.5)");
+        assert_eq!(bridging_days(&absences).len(), 3);
+    }
+}
@@ -4,9 +4,9 @@
 
 use crate::database::Database;
 use crate::error::Result;
-use chrono::{DateTime, Datelike, Local, Timelike, Utc};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Timelike, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
 use std::path::PathBuf;
 
@@ -266,7 +266,7 @@ pub static ACHIEVEMENTS: &[Achievement] = &[
     },
 ];
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AchievementProgress {
     pub unlocked: HashMap<String, DateTime<Utc>>,
     pub themes_used: Vec<String>,
@@ -316,7 +316,7 @@ impl AchievementProgress {
     }
 
     fn progress_path() -> Result<PathBuf> {
-        crate::config::Config::config_dir().map(|d| d.join("achievements.json"))
+        crate::config::Config::profile_dir().map(|d| d.join("achievements.json"))
     }
 
     pub fn is_unlocked(&self, id: &str) -> bool {
@@ -508,39 +508,20 @@ impl AchievementChecker {
         let total_seconds: i64 = entries.iter().map(|e| e.duration().num_seconds()).sum();
         self.progress.total_hours = total_seconds as f64 / 3600.0;
 
-        // Calculate consecutive days
-        self.calculate_consecutive_days(&entries);
+        // Calculate consecutive days; days off don't break the streak
+        let absent = crate::absence::bridging_days(&db.list_absences(None, None)?);
+        self.calculate_consecutive_days(&entries, &absent);
 
         Ok(())
     }
 
-    fn calculate_consecutive_days(&mut self, entries: &[crate::models::Entry]) {
-        if entries.is_empty() {
-            self.progress.consecutive_days = 0;
-            return;
-        }
-
-        let mut dates: Vec<_> = entries
-            .iter()
-            .map(|e| e.start.with_timezone(&Local).date_naive())
-            .collect();
-        dates.sort();
-        dates.dedup();
-
-        let today = Local::now().date_naive();
-        let mut streak = 0;
-        let mut current = today;
-
-        for date in dates.iter().rev() {
-            if *date == current || *date == current - chrono::Duration::days(1) {
-                streak += 1;
-                current = *date;
-            } else {
-                break;
-            }
-        }
-
-        self.progress.consecutive_days = streak;
+    fn calculate_consecutive_days(
+        &mut self,
+        entries: &[crate::models::Entry],
+        absent: &BTreeSet<NaiveDate>,
+    ) {
+        self.progress.consecutive_days =
+            consecutive_days(entries, absent, Local::now().date_naive());
     }
 
     pub fn check_time_based(&mut self, start_time: DateTime<Utc>) -> Vec<&'static Achievement> {
@@ -742,10 +723,64 @@ pub fn trigger_easter_egg(code: &str) -> Option<String> {
     }
 }
 
+/// Days with tracked time in a row up to `today` (or yesterday, while
+/// nothing is tracked today yet). Days in `absent` bridge the streak.
+fn consecutive_days(
+    entries: &[crate::models::Entry],
+    absent: &BTreeSet<NaiveDate>,
+    today: NaiveDate,
+) -> u32 {
+    let tracked: BTreeSet<NaiveDate> = entries
+        .iter()
+        .map(|e| e.start.with_timezone(&Local).date_naive())
+        .collect();
+    let Some(&first) = tracked.first() else {
+        return 0;
+    };
+
+    let mut day = today;
+    if !tracked.contains(&day) {
+        day -= chrono::Duration::days(1);
+    }
+    let mut streak = 0;
+    while day >= first {
+        if tracked.contains(&day) {
+            streak += 1;
+        } else if !absent.contains(&day) {
+            break;
+        }
+        day -= chrono::Duration::days(1);
+    }
+    streak
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_consecutive_days_bridge_absences() {
+        use chrono::TimeZone;
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let entries: Vec<_> = [5, 6, 7, 12, 13]
+            .iter()
+            .map(|&d| {
+                let mut e = crate::models::Entry::new(1, 1);
+                e.start = Local
+                    .from_local_datetime(&day(d).and_hms_opt(10, 0, 0).unwrap())
+                    .unwrap()
+                    .with_timezone(&Utc);
+                e
+            })
+            .collect();
+
+        assert_eq!(consecutive_days(&entries, &BTreeSet::new(), day(14)), 2);
+        assert_eq!(consecutive_days(&entries, &BTreeSet::new(), day(15)), 0);
+        let vacation: BTreeSet<_> = (8..=11).map(day).collect();
+        assert_eq!(consecutive_days(&entries, &vacation, day(14)), 5);
+        assert_eq!(consecutive_days(&[], &vacation, day(14)), 0);
+    }
+
     #[test]
     fn test_achievement_count() {
         assert_eq!(ACHIEVEMENTS.len(), 24);
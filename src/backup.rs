@@ -0,0 +1,425 @@
+//! Full backups for MatteriaTrack
+//!
+//! A backup is one JSON document holding everything a profile owns: projects,
+//! tasks, entries with their commits, absences, the running timer, calendar
+//! events and achievement progress. Row ids and sync UUIDs are kept, so
+//! restoring into an empty database reproduces it exactly and other devices
+//! still recognise every entity on the next `mtrack sync`.
+//!
+//! Layout (version 1):
+//!
+//! ```json
+//! {
+//!   "format": "materiatrack-backup",
+//!   "version": 1,
+//!   "created_at": "2026-10-18T09:00:00+00:00",
+//!   "projects": [{ "id": 1, "uuid": "…", "name": "Acme", "color": "#FF6432", … }],
+//!   "tasks": [{ "id": 1, "uuid": "…", "project_id": 1, "name": "api", "git_repo": null, … }],
+//!   "entries": [{ "id": 1, "uuid": "…", "project_id": 1, "task_id": 1, "start": "…",
+//!                 "end": "…", "notes": null, "git_commits": [], "commits": [ … ] }],
+//!   "absences": [{ "id": 1, "date": "2026-12-24", "kind": "vacation", … }],
+//!   "active_entry": null,
+//!   "calendar_events": [ … ],
+//!   "achievements": { "unlocked": { … }, … }
+//! }
+//! ```
+//!
+//! Readers accept any version up to [`BACKUP_VERSION`]; new fields are added
+//! with defaults so older backups keep loading.
+
+use crate::absence::Absence;
+use crate::achievements::AchievementProgress;
+use crate::calendar::CalendarEvent;
+use crate::database::{self, Database};
+use crate::error::{Error, Result};
+use crate::models::{Entry, EntryCommit, EntryId, Project, Task};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Value of the `format` field, identifying the document.
+pub const BACKUP_FORMAT: &str = "materiatrack-backup";
+
+/// Newest layout this build reads and the one it writes.
+pub const BACKUP_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Backup {
+    pub format: String,
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub projects: Vec<BackupProject>,
+    pub tasks: Vec<BackupTask>,
+    pub entries: Vec<BackupEntry>,
+    #[serde(default)]
+    pub absences: Vec<Absence>,
+    /// Entry of the running timer
+    #[serde(default)]
+    pub active_entry: Option<EntryId>,
+    #[serde(default)]
+    pub calendar_events: Vec<CalendarEvent>,
+    #[serde(default)]
+    pub achievements: AchievementProgress,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupProject {
+    pub uuid: Option<String>,
+    #[serde(flatten)]
+    pub project: Project,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupTask {
+    pub uuid: Option<String>,
+    #[serde(flatten)]
+    pub task: Task,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    pub uuid: Option<String>,
+    #[serde(flatten)]
+    pub entry: Entry,
+    #[serde(default)]
+    pub commits: Vec<EntryCommit>,
+}
+
+/// What [`Backup::restore`] wrote to the database.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RestoreReport {
+    pub projects: usize,
+    pub tasks: usize,
+    pub entries: usize,
+    pub commits: usize,
+    pub absences: usize,
+}
+
+impl RestoreReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} projects, {} tasks, {} entries, {} commits, {} absences",
+            self.projects, self.tasks, self.entries, self.commits, self.absences
+        )
+    }
+}
+
+impl Backup {
+    /// Snapshot `db` together with the profile's calendar events and achievements.
+    pub fn capture(
+        db: &Database,
+        calendar_events: Vec<CalendarEvent>,
+        achievements: AchievementProgress,
+    ) -> Result<Self> {
+        db.with_conn(|conn| {
+            Ok(Self {
+                format: BACKUP_FORMAT.to_string(),
+                version: BACKUP_VERSION,
+                created_at: Utc::now(),
+                projects: read_projects(conn)?,
+                tasks: read_tasks(conn)?,
+                entries: read_entries(conn)?,
+                absences: read_absences(conn)?,
+                active_entry: conn
+                    .query_row(
+                        "SELECT entry_id FROM active_tracking WHERE id = 1",
+                        [],
+                        |row| row.get(0),
+                    )
+                    .optional()?,
+                calendar_events,
+                achievements,
+            })
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse a backup, rejecting other documents and newer versions.
+    pub fn from_json(text: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(text)?;
+        if value.get("format").and_then(|f| f.as_str()) != Some(BACKUP_FORMAT) {
+            return Err(Error::InvalidInput(
+                "not a MatteriaTrack backup (create one with `mtrack export --full`)".into(),
+            ));
+        }
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+        if version > BACKUP_VERSION as u64 {
+            return Err(Error::InvalidInput(format!(
+                "backup version {} is newer than this build supports ({})",
+                version, BACKUP_VERSION
+            )));
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Write the database part into `db`, which must be empty, in one
+    /// transaction. Calendar events and achievements are left to the caller.
+    pub fn restore(&self, db: &Database) -> Result<RestoreReport> {
+        db.transaction(|conn| {
+            let existing: i64 = conn.query_row(
+                "SELECT (SELECT COUNT(*) FROM projects) + (SELECT COUNT(*) FROM entries) \
+                 + (SELECT COUNT(*) FROM absences)",
+                [],
+                |row| row.get(0),
+            )?;
+            if existing > 0 {
+                return Err(Error::InvalidInput(
+                    "backups can only be restored into an empty database (try a new profile)"
+                        .into(),
+                ));
+            }
+
+            let mut report = RestoreReport::default();
+            for p in &self.projects {
+                conn.execute(
+                    "INSERT INTO projects (id, uuid, name, color, created_at, updated_at) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        p.project.id,
+                        p.uuid,
+                        p.project.name,
+                        p.project.color,
+                        p.project.created_at.to_rfc3339(),
+                        p.project.updated_at.to_rfc3339()
+                    ],
+                )?;
+                report.projects += 1;
+            }
+
+            for t in &self.tasks {
+                conn.execute(
+                    "INSERT INTO tasks (id, uuid, project_id, name, git_repo, created_at, updated_at) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        t.task.id,
+                        t.uuid,
+                        t.task.project_id,
+                        t.task.name,
+                        t.task.git_repo,
+                        t.task.created_at.to_rfc3339(),
+                        t.task.updated_at.to_rfc3339()
+                    ],
+                )?;
+                report.tasks += 1;
+            }
+
+            for e in &self.entries {
+                let entry = &e.entry;
+                conn.execute(
+                    "INSERT INTO entries (id, uuid, project_id, task_id, start_time, end_time, \
+                     notes, git_commits) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        entry.id,
+                        e.uuid,
+                        entry.project_id,
+                        entry.task_id,
+                        entry.start.to_rfc3339(),
+                        entry.end.map(|t| t.to_rfc3339()),
+                        entry.notes,
+                        serde_json::to_string(&entry.git_commits)?
+                    ],
+                )?;
+                for c in &e.commits {
+                    conn.execute(
+                        "INSERT INTO entry_commits (id, entry_id, hash, repo_path, author, \
+                         author_email, committed_at, summary, files_changed, insertions, deletions) \
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                        params![
+                            c.id,
+                            entry.id,
+                            c.hash,
+                            c.repo_path,
+                            c.author,
+                            c.author_email,
+                            c.committed_at.map(|t| t.to_rfc3339()),
+                            c.summary,
+                            c.files_changed,
+                            c.insertions,
+                            c.deletions
+                        ],
+                    )?;
+                    report.commits += 1;
+                }
+                report.entries += 1;
+            }
+
+            for a in &self.absences {
+                conn.execute(
+                    "INSERT INTO absences (id, date, kind, half_day, note, created_at) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        a.id,
+                        a.date.to_string(),
+                        a.kind.name(),
+                        a.half_day,
+                        a.note,
+                        a.created_at.to_rfc3339()
+                    ],
+                )?;
+                report.absences += 1;
+            }
+
+            if let Some(entry_id) = self.active_entry {
+                conn.execute(
+                    "INSERT INTO active_tracking (id, entry_id) VALUES (1, ?1)",
+                    params![entry_id],
+                )?;
+            }
+
+            Ok(report)
+        })
+    }
+}
+
+fn read_projects(conn: &Connection) -> Result<Vec<BackupProject>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, color, created_at, updated_at, uuid FROM projects ORDER BY id",
+    )?;
+    let projects = stmt
+        .query_map([], |row| {
+            Ok(BackupProject {
+                uuid: row.get(5)?,
+                project: database::row_to_project(row)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(projects)
+}
+
+fn read_tasks(conn: &Connection) -> Result<Vec<BackupTask>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, name, git_repo, created_at, updated_at, uuid FROM tasks ORDER BY id",
+    )?;
+    let tasks = stmt
+        .query_map([], |row| {
+            Ok(BackupTask {
+                uuid: row.get(6)?,
+                task: database::row_to_task(row)?,
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(tasks)
+}
+
+fn read_entries(conn: &Connection) -> Result<Vec<BackupEntry>> {
+    let mut commits: HashMap<EntryId, Vec<EntryCommit>> = HashMap::new();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM entry_commits c ORDER BY c.id",
+        database::COMMIT_COLUMNS
+    ))?;
+    for commit in stmt.query_map([], database::row_to_commit)? {
+        let commit = commit?;
+        commits.entry(commit.entry_id).or_default().push(commit);
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, task_id, start_time, end_time, notes, git_commits, uuid \
+         FROM entries ORDER BY id",
+    )?;
+    let entries = stmt
+        .query_map([], |row| {
+            Ok(BackupEntry {
+                uuid: row.get(7)?,
+                entry: database::row_to_entry(row)?,
+                commits: Vec::new(),
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(entries
+        .into_iter()
+        .map(|mut e| {
+            e.commits = commits.remove(&e.entry.id).unwrap_or_default();
+            e
+        })
+        .collect())
+}
+
+fn read_absences(conn: &Connection) -> Result<Vec<Absence>> {
+    let mut stmt = conn
+        .prepare("SELECT id, date, kind, half_day, note, created_at FROM absences ORDER BY id")?;
+    let absences = stmt
+        .query_map([], database::row_to_absence)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(absences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::absence::AbsenceKind;
+    use chrono::{Duration, NaiveDate};
+
+    fn sample_db() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        let mut project = Project::new("Acme").with_color("#00FF00");
+        db.create_project(&mut project).unwrap();
+        let mut task = Task::new(project.id, "api").with_git_repo("/src/acme");
+        db.create_task(&mut task).unwrap();
+
+        let mut done = Entry::new(project.id, task.id)
+            .with_start(Utc::now() - Duration::hours(3))
+            .with_notes("shipped, finally");
+        done.end = Some(done.start + Duration::hours(1));
+        db.create_entry(&mut done).unwrap();
+        db.replace_entry_commits(done.id, &[EntryCommit::new("abc1234", "Fix login")])
+            .unwrap();
+
+        let mut running = Entry::new(project.id, task.id);
+        db.create_entry(&mut running).unwrap();
+        db.set_active_tracking(running.id).unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2026, 12, 24).unwrap();
+        db.save_absence(&mut Absence::new(day, AbsenceKind::Vacation).with_half_day(true))
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_restore_reproduces_database() {
+        let backup = Backup::capture(&sample_db(), Vec::new(), Default::default()).unwrap();
+        assert_eq!(backup.entries[0].commits.len(), 1);
+        assert_eq!(backup.active_entry, Some(backup.entries[1].entry.id));
+
+        let restored = Database::open_in_memory().unwrap();
+        let report = Backup::from_json(&backup.to_json().unwrap())
+            .unwrap()
+            .restore(&restored)
+            .unwrap();
+        assert_eq!(
+            report.summary(),
+            "1 projects, 1 tasks, 2 entries, 1 commits, 1 absences"
+        );
+
+        let again = Backup::capture(&restored, Vec::new(), Default::default()).unwrap();
+        assert_eq!(
+            Backup {
+                created_at: backup.created_at,
+                ..again
+            },
+            backup
+        );
+    }
+
+    #[test]
+    fn test_restore_refuses_non_empty_database() {
+        let db = sample_db();
+        let backup = Backup::capture(&db, Vec::new(), Default::default()).unwrap();
+        assert!(backup.restore(&db).is_err());
+    }
+
+    #[test]
+    fn test_from_json_checks_format_and_version() {
+        assert!(Backup::from_json("[]").is_err());
+        assert!(Backup::from_json(r#"{"format": "other", "version": 1}"#).is_err());
+
+        let mut backup = Backup::capture(&sample_db(), Vec::new(), Default::default()).unwrap();
+        backup.version = BACKUP_VERSION + 1;
+        let err = Backup::from_json(&backup.to_json().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("newer"));
+    }
+}
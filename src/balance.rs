@@ -0,0 +1,444 @@
+//! Overtime and flextime balance for MatteriaTrack
+//!
+//! Compares tracked time with the contracted hours from `[schedule]` day by
+//! day, rolls the difference up into weeks and months and keeps a running
+//! balance from the start date on.
+
+use crate::absence::Absence;
+use crate::config::ScheduleConfig;
+use crate::error::{Error, Result};
+use crate::models::EntryWithDetails;
+use crate::stats::format_seconds;
+use crate::theme::MateriaTheme;
+use chrono::{Datelike, NaiveDate};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// The schedule together with the public holidays and absences it refers to.
+#[derive(Debug, Clone, Default)]
+pub struct WorkCalendar {
+    schedule: ScheduleConfig,
+    holidays: BTreeMap<NaiveDate, String>,
+    absences: BTreeMap<NaiveDate, Absence>,
+}
+
+impl WorkCalendar {
+    pub fn new(schedule: ScheduleConfig, holidays: BTreeMap<NaiveDate, String>) -> Self {
+        Self {
+            schedule,
+            holidays,
+            absences: BTreeMap::new(),
+        }
+    }
+
+    /// Credit the expected hours of absence days.
+    pub fn with_absences(mut self, absences: impl IntoIterator<Item = Absence>) -> Self {
+        self.absences
+            .extend(absences.into_iter().map(|a| (a.date, a)));
+        self
+    }
+
+    /// Read the holidays file named in `schedule`.
+    pub fn load(schedule: &ScheduleConfig) -> Result<Self> {
+        Ok(Self::new(schedule.clone(), schedule.holidays()?))
+    }
+
+    pub fn schedule(&self) -> &ScheduleConfig {
+        &self.schedule
+    }
+
+    pub fn holiday(&self, date: NaiveDate) -> Option<&str> {
+        self.holidays.get(&date).map(String::as_str)
+    }
+
+    pub fn absence(&self, date: NaiveDate) -> Option<&Absence> {
+        self.absences.get(&date)
+    }
+
+    /// Contracted seconds for `date`; public holidays expect nothing and
+    /// absences credit their share of the day.
+    pub fn expected_seconds(&self, date: NaiveDate) -> i64 {
+        if self.holidays.contains_key(&date) {
+            return 0;
+        }
+        let expected = self.schedule.expected_seconds(date);
+        match self.absence(date) {
+            Some(a) => (expected as f64 * (1.0 - a.credit())).round() as i64,
+            None => expected,
+        }
+    }
+}
+
+/// Which rows `mtrack balance` lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceView {
+    Day,
+    Week,
+    Month,
+}
+
+impl FromStr for BalanceView {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "day" | "days" => Ok(Self::Day),
+            "week" | "weeks" => Ok(Self::Week),
+            "month" | "months" => Ok(Self::Month),
+            _ => Err(Error::InvalidInput(format!(
+                "Unknown view '{}' (expected day, week or month)",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Balance {
+    pub from: NaiveDate,
+    pub until: NaiveDate,
+    pub expected_seconds: i64,
+    pub tracked_seconds: i64,
+    /// Tracked minus expected over the whole range
+    pub balance_seconds: i64,
+    pub days: Vec<DayBalance>,
+    pub weeks: Vec<PeriodBalance>,
+    pub months: Vec<PeriodBalance>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DayBalance {
+    pub date: NaiveDate,
+    pub expected_seconds: i64,
+    pub tracked_seconds: i64,
+    pub delta_seconds: i64,
+    /// Running balance at the end of the day
+    pub balance_seconds: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub holiday: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub absence: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodBalance {
+    pub label: String,
+    pub from: NaiveDate,
+    pub until: NaiveDate,
+    pub expected_seconds: i64,
+    pub tracked_seconds: i64,
+    pub delta_seconds: i64,
+    /// Running balance at the end of the period
+    pub balance_seconds: i64,
+}
+
+impl Balance {
+    /// Balance of `entries` from `from` to `until`, both inclusive. Entries
+    /// count towards the local day they started on.
+    pub fn build(
+        entries: &[EntryWithDetails],
+        from: NaiveDate,
+        until: NaiveDate,
+        calendar: &WorkCalendar,
+    ) -> Self {
+        let mut tracked: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+        for e in entries {
+            let date = e.entry.start_local().date_naive();
+            if (from..=until).contains(&date) {
+                *tracked.entry(date).or_default() += e.entry.duration().num_seconds();
+            }
+        }
+
+        let mut balance = 0;
+        let days: Vec<DayBalance> = from
+            .iter_days()
+            .take_while(|d| *d <= until)
+            .map(|date| {
+                let expected_seconds = calendar.expected_seconds(date);
+                let tracked_seconds = tracked.get(&date).copied().unwrap_or(0);
+                let delta_seconds = tracked_seconds - expected_seconds;
+                balance += delta_seconds;
+                DayBalance {
+                    date,
+                    expected_seconds,
+                    tracked_seconds,
+                    delta_seconds,
+                    balance_seconds: balance,
+                    holiday: calendar.holiday(date).map(str::to_string),
+                    absence: calendar.absence(date).map(Absence::label),
+                }
+            })
+            .collect();
+
+        let weeks = roll_up(&days, |d| {
+            let week = d.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        });
+        let months = roll_up(&days, |d| d.format("%Y-%m").to_string());
+
+        Self {
+            from,
+            until,
+            expected_seconds: days.iter().map(|d| d.expected_seconds).sum(),
+            tracked_seconds: days.iter().map(|d| d.tracked_seconds).sum(),
+            balance_seconds: balance,
+            days,
+            weeks,
+            months,
+        }
+    }
+
+    pub fn format_pretty(&self, theme: MateriaTheme, view: BalanceView) -> String {
+        let (r, g, b) = theme.primary_color();
+        let mut output = String::new();
+
+        output.push_str(&format!(
+            "\n{} {} {}\n",
+            theme.icon(),
+            format!("Flextime balance: {} – {}", self.from, self.until)
+                .truecolor(r, g, b)
+                .bold(),
+            theme.materia_icon()
+        ));
+        output.push_str(&"━".repeat(60));
+        output.push('\n');
+        output.push_str(&format!(
+            "\n⏱️  Tracked: {}   📋 Expected: {}   ⚖️  Balance: {}\n\n",
+            format_seconds(self.tracked_seconds)
+                .truecolor(r, g, b)
+                .bold(),
+            format_seconds(self.expected_seconds),
+            colored_delta(self.balance_seconds).bold()
+        ));
+
+        let heading = match view {
+            BalanceView::Day => "Day",
+            BalanceView::Week => "Week",
+            BalanceView::Month => "Month",
+        };
+        output.push_str(&format!(
+            "  {:<16} {:>10} {:>10} {:>10} {:>10}\n",
+            heading.bold(),
+            "expected",
+            "tracked",
+            "delta",
+            "balance"
+        ));
+
+        let row = |label: String, expected: i64, tracked: i64, delta: i64, balance: i64| {
+            format!(
+                "  {:<16} {:>10} {:>10} {} {}\n",
+                label,
+                format_seconds(expected),
+                format_seconds(tracked),
+                colored_delta_padded(delta),
+                colored_delta_padded(balance)
+            )
+        };
+        match view {
+            BalanceView::Day => {
+                for d in &self.days {
+                    output.push_str(&row(
+                        d.date.format("%a %Y-%m-%d").to_string(),
+                        d.expected_seconds,
+                        d.tracked_seconds,
+                        d.delta_seconds,
+                        d.balance_seconds,
+                    ));
+                    if let Some(ref holiday) = d.holiday {
+                        output.push_str(&format!("    🎉 {}\n", holiday.dimmed()));
+                    }
+                    if let Some(ref absence) = d.absence {
+                        output.push_str(&format!("    💤 {}\n", absence.dimmed()));
+                    }
+                }
+            }
+            BalanceView::Week | BalanceView::Month => {
+                let periods = if view == BalanceView::Week {
+                    &self.weeks
+                } else {
+                    &self.months
+                };
+                for p in periods {
+                    output.push_str(&row(
+                        p.label.clone(),
+                        p.expected_seconds,
+                        p.tracked_seconds,
+                        p.delta_seconds,
+                        p.balance_seconds,
+                    ));
+                }
+            }
+        }
+
+        output.push('\n');
+        output.push_str(&"━".repeat(60));
+        output.push('\n');
+        output
+    }
+}
+
+fn roll_up(days: &[DayBalance], key: impl Fn(NaiveDate) -> String) -> Vec<PeriodBalance> {
+    let mut periods: Vec<PeriodBalance> = Vec::new();
+    for d in days {
+        let label = key(d.date);
+        match periods.last_mut() {
+            Some(p) if p.label == label => {
+                p.until = d.date;
+                p.expected_seconds += d.expected_seconds;
+                p.tracked_seconds += d.tracked_seconds;
+                p.delta_seconds += d.delta_seconds;
+                p.balance_seconds = d.balance_seconds;
+            }
+            _ => periods.push(PeriodBalance {
+                label,
+                from: d.date,
+                until: d.date,
+                expected_seconds: d.expected_seconds,
+                tracked_seconds: d.tracked_seconds,
+                delta_seconds: d.delta_seconds,
+                balance_seconds: d.balance_seconds,
+            }),
+        }
+    }
+    periods
+}
+
+/// `+1h 30m`, `-45m` or `±0m`.
+pub fn format_delta(seconds: i64) -> String {
+    match seconds.signum() {
+        0 => "±0m".to_string(),
+        1 => format!("+{}", format_seconds(seconds)),
+        _ => format!("-{}", format_seconds(-seconds)),
+    }
+}
+
+fn colored_delta(seconds: i64) -> colored::ColoredString {
+    paint(format_delta(seconds), seconds)
+}
+
+fn colored_delta_padded(seconds: i64) -> colored::ColoredString {
+    paint(format!("{:>10}", format_delta(seconds)), seconds)
+}
+
+fn paint(text: String, seconds: i64) -> colored::ColoredString {
+    match seconds.signum() {
+        0 => text.normal(),
+        1 => text.green(),
+        _ => text.red(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Database, ImportRow};
+    use crate::period::local_midnight;
+    use chrono::Duration;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    fn entries(days: &[(u32, i64)]) -> Vec<EntryWithDetails> {
+        let db = Database::open_in_memory().unwrap();
+        let rows: Vec<ImportRow> = days
+            .iter()
+            .map(|&(d, hours)| {
+                let start = local_midnight(date(d)) + Duration::hours(9);
+                ImportRow {
+                    project: "Acme".into(),
+                    task: "work".into(),
+                    start,
+                    end: Some(start + Duration::hours(hours)),
+                    notes: None,
+                }
+            })
+            .collect();
+        db.bulk_insert(&rows).unwrap();
+        db.list_entries_with_details_between(None, None, None)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_daily_deltas_and_running_balance() {
+        // Thu 1st to Sun 4th; Friday is a short day
+        let mut schedule = ScheduleConfig::default();
+        schedule.hours.insert("fri".into(), 6.0);
+        let calendar = WorkCalendar::new(schedule, BTreeMap::new());
+        let balance = Balance::build(
+            &entries(&[(1, 9), (2, 5), (3, 2)]),
+            date(1),
+            date(4),
+            &calendar,
+        );
+
+        let deltas: Vec<i64> = balance
+            .days
+            .iter()
+            .map(|d| d.delta_seconds / 3600)
+            .collect();
+        assert_eq!(deltas, vec![1, -1, 2, 0]);
+        assert_eq!(balance.days[2].balance_seconds, 2 * 3600);
+        assert_eq!(balance.expected_seconds, 14 * 3600);
+        assert_eq!(balance.balance_seconds, 2 * 3600);
+    }
+
+    #[test]
+    fn test_holidays_and_start_date_expect_nothing() {
+        let schedule = ScheduleConfig {
+            start_date: Some(date(6)),
+            ..Default::default()
+        };
+        let holidays = BTreeMap::from([(date(7), "Founders' Day".to_string())]);
+        let calendar = WorkCalendar::new(schedule, holidays);
+
+        assert_eq!(calendar.expected_seconds(date(5)), 0);
+        assert_eq!(calendar.expected_seconds(date(6)), 8 * 3600);
+        assert_eq!(calendar.expected_seconds(date(7)), 0);
+        let balance = Balance::build(&entries(&[(7, 3)]), date(5), date(7), &calendar);
+        assert_eq!(balance.days[2].holiday.as_deref(), Some("Founders' Day"));
+        assert_eq!(balance.balance_seconds, -5 * 3600);
+    }
+
+    #[test]
+    fn test_absences_credit_expected_hours() {
+        use crate::absence::AbsenceKind;
+        let calendar = WorkCalendar::default().with_absences([
+            Absence::new(date(5), AbsenceKind::Vacation),
+            Absence::new(date(6), AbsenceKind::Sick).with_half_day(true),
+            Absence::new(date(7), AbsenceKind::CompTime),
+        ]);
+        assert_eq!(calendar.expected_seconds(date(5)), 0);
+        assert_eq!(calendar.expected_seconds(date(6)), 4 * 3600);
+        // Comp time is paid from the balance
+        assert_eq!(calendar.expected_seconds(date(7)), 8 * 3600);
+
+        let balance = Balance::build(&entries(&[(6, 4)]), date(5), date(7), &calendar);
+        assert_eq!(balance.days[1].absence.as_deref(), Some("sick (half day)"));
+        assert_eq!(balance.balance_seconds, -8 * 3600);
+    }
+
+    #[test]
+    fn test_weeks_and_months_roll_up() {
+        let calendar = WorkCalendar::default();
+        let balance = Balance::build(&entries(&[(30, 8)]), date(28), date(30), &calendar);
+        assert_eq!(balance.weeks.len(), 1);
+        assert_eq!(balance.weeks[0].label, "2026-W44");
+
+        let autumn = Balance::build(
+            &[],
+            NaiveDate::from_ymd_opt(2026, 9, 28).unwrap(),
+            date(4),
+            &calendar,
+        );
+        let months: Vec<&str> = autumn.months.iter().map(|m| m.label.as_str()).collect();
+        assert_eq!(months, vec!["2026-09", "2026-10"]);
+        assert_eq!(autumn.months[0].expected_seconds, 3 * 8 * 3600);
+        assert_eq!(autumn.months[1].balance_seconds, -5 * 8 * 3600);
+        assert_eq!(format_delta(-5400), "-1h 30m");
+        assert_eq!(format_delta(0), "±0m");
+    }
+}
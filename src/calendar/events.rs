@@ -2,6 +2,7 @@
 
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
 use std::path::PathBuf;
 
@@ -17,7 +18,7 @@ pub enum CalendarEventType {
     Reminder,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CalendarEvent {
     pub id: String,
     pub title: String,
@@ -73,6 +74,14 @@ impl CalendarEvent {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpsertReport {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+}
+
 #[derive(Debug)]
 pub struct EventStore {
     events: Vec<CalendarEvent>,
@@ -114,6 +123,60 @@ impl EventStore {
         self.save()
     }
 
+    /// Replace every stored event, e.g. when restoring a backup.
+    pub fn replace_all(&mut self, events: Vec<CalendarEvent>) -> Result<()> {
+        self.events = events;
+        self.save()
+    }
+
+    /// Add new events and update those whose id is already stored, so the
+    /// same calendar can be imported again. Stored events from any of `uids`
+    /// (ids `uid` or `uid/...`) that `events` no longer contains are removed,
+    /// so cancelled or deleted occurrences go away.
+    pub fn upsert_events(
+        &mut self,
+        events: Vec<CalendarEvent>,
+        uids: &[String],
+    ) -> Result<UpsertReport> {
+        let mut report = UpsertReport::default();
+        let emitted: HashSet<&str> = events.iter().map(|e| e.id.as_str()).collect();
+        let before = self.events.len();
+        self.events.retain(|stored| {
+            emitted.contains(stored.id.as_str())
+                || !uids.iter().any(|uid| {
+                    stored.id == *uid
+                        || stored
+                            .id
+                            .strip_prefix(uid.as_str())
+                            .is_some_and(|rest| rest.starts_with('/'))
+                })
+        });
+        report.removed = before - self.events.len();
+
+        for event in events {
+            match self.events.iter_mut().find(|e| e.id == event.id) {
+                Some(existing) => {
+                    let event = CalendarEvent {
+                        created_at: existing.created_at,
+                        ..event
+                    };
+                    if *existing == event {
+                        report.unchanged += 1;
+                    } else {
+                        *existing = event;
+                        report.updated += 1;
+                    }
+                }
+                None => {
+                    self.events.push(event);
+                    report.added += 1;
+                }
+            }
+        }
+        self.save()?;
+        Ok(report)
+    }
+
     pub fn remove_event(&mut self, id: &str) -> Result<bool> {
         let before_len = self.events.len();
         self.events.retain(|e| e.id != id);
@@ -180,4 +243,41 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_upsert_events_matches_by_id() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let mut store = EventStore::new(temp_file.path().to_path_buf());
+        let date = NaiveDate::from_ymd_opt(2026, 1, 15).unwrap();
+        let mut first = CalendarEvent::new("Planning", date);
+        first.id = "planning@example.com".into();
+        let second = CalendarEvent::new("Retro", date);
+
+        let report = store.upsert_events(vec![first.clone(), second.clone()], &[])?;
+        assert_eq!(report.added, 2);
+
+        let moved = first.clone().with_time("10:00");
+        let report = store.upsert_events(vec![moved, second.clone()], &[])?;
+        assert_eq!(
+            report,
+            UpsertReport {
+                added: 0,
+                updated: 1,
+                unchanged: 1,
+                removed: 0
+            }
+        );
+        assert_eq!(store.get_all()[0].time.as_deref(), Some("10:00"));
+        assert_eq!(store.get_all()[0].created_at, first.created_at);
+
+        // Dropped from the file: its stored events go, others stay.
+        let mut occurrence = CalendarEvent::new("Planning", date);
+        occurrence.id = "planning@example.com/2026-01-16".into();
+        store.upsert_events(vec![occurrence], &[])?;
+        let report = store.upsert_events(Vec::new(), &["planning@example.com".into()])?;
+        assert_eq!(report.removed, 2);
+        assert_eq!(store.get_all(), std::slice::from_ref(&second));
+
+        Ok(())
+    }
 }
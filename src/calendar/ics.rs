@@ -0,0 +1,1303 @@
+//! iCalendar (RFC 5545) reading and writing
+//!
+//! Entries and calendar events are written as VEVENTs. `.ics` files are read
+//! back into calendar events, resolving time zones and expanding simple RRULEs.
+
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+    Weekday,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use super::events::{CalendarEvent, CalendarEventType};
+use crate::error::{Error, Result};
+use crate::models::EntryWithDetails;
+
+/// Recurring events keep at most this many (the latest) occurrences.
+pub const MAX_OCCURRENCES: usize = 1000;
+
+/// Multi-day all-day events are cut off after this many days.
+const MAX_EVENT_DAYS: i64 = 366;
+
+const PRODID: &str = "-//MateriaTrack//MateriaTrack//EN";
+
+/// Entries as a calendar; running entries have no end yet and are left out.
+pub fn entries_to_ics(entries: &[&EntryWithDetails], sanitize: bool) -> String {
+    let mut writer = IcsWriter::new();
+    for entry in entries {
+        writer.add_entry(entry, sanitize);
+    }
+    writer.finish()
+}
+
+/// Calendar events as a calendar.
+pub fn events_to_ics(events: &[CalendarEvent]) -> String {
+    let mut writer = IcsWriter::new();
+    for event in events {
+        writer.add_event(event);
+    }
+    writer.finish()
+}
+
+/// Builds a VCALENDAR document with folded, CRLF-terminated lines.
+pub struct IcsWriter {
+    out: String,
+    stamp: String,
+}
+
+impl IcsWriter {
+    pub fn new() -> Self {
+        let mut writer = Self {
+            out: String::new(),
+            stamp: Utc::now().format("%Y%m%dT%H%M%SZ").to_string(),
+        };
+        writer.line("BEGIN:VCALENDAR");
+        writer.line("VERSION:2.0");
+        writer.line(&format!("PRODID:{}", PRODID));
+        writer.line("CALSCALE:GREGORIAN");
+        writer
+    }
+
+    pub fn add_entry(&mut self, entry: &EntryWithDetails, sanitize: bool) {
+        let Some(end) = entry.entry.end else {
+            return;
+        };
+        self.line("BEGIN:VEVENT");
+        self.line(&format!("UID:entry-{}@materiatrack", entry.entry.id));
+        self.line(&format!("DTSTAMP:{}", self.stamp));
+        self.line(&format!(
+            "DTSTART:{}",
+            entry.entry.start.format("%Y%m%dT%H%M%SZ")
+        ));
+        self.line(&format!("DTEND:{}", end.format("%Y%m%dT%H%M%SZ")));
+        self.text(
+            "SUMMARY",
+            &format!("{} / {}", entry.project_name, entry.task_name),
+        );
+        self.text("CATEGORIES", &entry.project_name);
+        if let Some(notes) = entry.entry.notes.as_deref().filter(|_| !sanitize) {
+            self.text("DESCRIPTION", notes);
+        }
+        self.line("END:VEVENT");
+    }
+
+    /// Events with a `HH:MM` or `HH:MM-HH:MM` time are written as floating
+    /// local times, everything else as all-day events.
+    pub fn add_event(&mut self, event: &CalendarEvent) {
+        self.line("BEGIN:VEVENT");
+        self.text("UID", &event.id);
+        self.line(&format!("DTSTAMP:{}", self.stamp));
+        match event.time.as_deref().and_then(parse_time_range) {
+            Some((start, end)) => {
+                let start = event.date.and_time(start);
+                self.line(&format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")));
+                if let Some(end) = end {
+                    let mut end = event.date.and_time(end);
+                    if end <= start {
+                        end += Duration::days(1);
+                    }
+                    self.line(&format!("DTEND:{}", end.format("%Y%m%dT%H%M%S")));
+                }
+            }
+            None => {
+                self.line(&format!(
+                    "DTSTART;VALUE=DATE:{}",
+                    event.date.format("%Y%m%d")
+                ));
+                self.line(&format!(
+                    "DTEND;VALUE=DATE:{}",
+                    (event.date + Duration::days(1)).format("%Y%m%d")
+                ));
+            }
+        }
+        self.text("SUMMARY", &event.title);
+        if let Some(ref description) = event.description {
+            self.text("DESCRIPTION", description);
+        }
+        self.line(&format!(
+            "CATEGORIES:{}",
+            event_type_category(&event.event_type)
+        ));
+        self.line("END:VEVENT");
+    }
+
+    pub fn finish(mut self) -> String {
+        self.line("END:VCALENDAR");
+        self.out
+    }
+
+    fn text(&mut self, name: &str, value: &str) {
+        self.line(&format!("{}:{}", name, escape_text(value)));
+    }
+
+    /// Fold at 75 octets without splitting a character.
+    fn line(&mut self, line: &str) {
+        let mut width = 0;
+        for c in line.chars() {
+            if width + c.len_utf8() > 75 {
+                self.out.push_str("\r\n ");
+                width = 1;
+            }
+            self.out.push(c);
+            width += c.len_utf8();
+        }
+        self.out.push_str("\r\n");
+    }
+}
+
+impl Default for IcsWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn event_type_category(event_type: &CalendarEventType) -> &'static str {
+    match event_type {
+        CalendarEventType::TrackingSession => "TRACKING-SESSION",
+        CalendarEventType::Custom => "CUSTOM",
+        CalendarEventType::Reminder => "REMINDER",
+    }
+}
+
+fn parse_time_range(time: &str) -> Option<(NaiveTime, Option<NaiveTime>)> {
+    let parse = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").ok();
+    match time.split_once('-') {
+        Some((start, end)) => Some((parse(start)?, Some(parse(end)?))),
+        None => Some((parse(time)?, None)),
+    }
+}
+
+fn escape_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// A content line: `NAME;PARAM=value:value`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn text(&self) -> String {
+        unescape_text(&self.value)
+    }
+}
+
+/// A `BEGIN:NAME` … `END:NAME` block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+impl Component {
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    pub fn properties<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> + 'a {
+        self.properties.iter().filter(move |p| p.name == name)
+    }
+
+    /// Unescaped value of a text property, if present and not blank.
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.property(name)
+            .map(Property::text)
+            .filter(|t| !t.trim().is_empty())
+    }
+}
+
+/// Every VCALENDAR in `input`.
+pub fn parse_calendars(input: &str) -> Result<Vec<Component>> {
+    let mut stack = vec![Component::default()];
+    for (number, line) in unfold(input) {
+        let property = parse_line(&line, number)?;
+        match property.name.as_str() {
+            "BEGIN" => stack.push(Component {
+                name: property.value.trim().to_ascii_uppercase(),
+                ..Default::default()
+            }),
+            "END" => {
+                let name = property.value.trim().to_ascii_uppercase();
+                match stack.pop() {
+                    Some(done) if done.name == name && !stack.is_empty() => {
+                        if let Some(parent) = stack.last_mut() {
+                            parent.components.push(done);
+                        }
+                    }
+                    _ => {
+                        return Err(Error::Parse(format!(
+                            "ics line {}: unexpected END:{}",
+                            number, name
+                        )))
+                    }
+                }
+            }
+            _ => {
+                if let Some(current) = stack.last_mut() {
+                    current.properties.push(property);
+                }
+            }
+        }
+    }
+
+    if let [_, .., open] = stack.as_slice() {
+        return Err(Error::Parse(format!("ics: missing END:{}", open.name)));
+    }
+    let calendars: Vec<Component> = stack
+        .pop()
+        .map(|root| root.components)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| c.name == "VCALENDAR")
+        .collect();
+    if calendars.is_empty() {
+        return Err(Error::Parse("ics: no VCALENDAR found".into()));
+    }
+    Ok(calendars)
+}
+
+/// Join folded lines, keeping the number of the line each one started on.
+fn unfold(input: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (index, raw) in input.trim_start_matches('\u{feff}').lines().enumerate() {
+        if raw.starts_with(' ') || raw.starts_with('\t') {
+            if let Some((_, last)) = lines.last_mut() {
+                last.push_str(&raw[1..]);
+                continue;
+            }
+        }
+        if !raw.trim().is_empty() {
+            lines.push((index + 1, raw.to_string()));
+        }
+    }
+    lines
+}
+
+fn parse_line(line: &str, number: usize) -> Result<Property> {
+    let malformed = || Error::Parse(format!("ics line {}: malformed '{}'", number, line));
+    let bytes = line.as_bytes();
+    let mut i = line.find([';', ':']).ok_or_else(malformed)?;
+    let name = line[..i].trim().to_ascii_uppercase();
+
+    let mut params = Vec::new();
+    while bytes[i] == b';' {
+        let eq = i + 1 + line[i + 1..].find('=').ok_or_else(malformed)?;
+        let key = line[i + 1..eq].to_ascii_uppercase();
+        let mut value = String::new();
+        i = eq + 1;
+        loop {
+            if bytes.get(i) == Some(&b'"') {
+                let close = i + 1 + line[i + 1..].find('"').ok_or_else(malformed)?;
+                value.push_str(&line[i + 1..close]);
+                i = close + 1;
+            } else {
+                let stop = i + line[i..].find([',', ';', ':']).ok_or_else(malformed)?;
+                value.push_str(&line[i..stop]);
+                i = stop;
+            }
+            if bytes.get(i) == Some(&b',') {
+                value.push(',');
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        params.push((key, value));
+        if i >= bytes.len() {
+            return Err(malformed());
+        }
+    }
+
+    if bytes[i] != b':' || name.is_empty() {
+        return Err(malformed());
+    }
+    Ok(Property {
+        name,
+        params,
+        value: line[i + 1..].to_string(),
+    })
+}
+
+/// How a DATE-TIME maps to an instant.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Zone {
+    Utc,
+    /// No zone given: read in the importing user's time zone
+    Floating,
+    Named(chrono_tz::Tz),
+    /// A VTIMEZONE definition whose TZID is not an IANA name
+    Custom(CustomZone),
+}
+
+impl Zone {
+    fn resolve(tzid: &str, custom: &HashMap<String, CustomZone>) -> Self {
+        if let Ok(tz) = tzid.trim_start_matches('/').parse::<chrono_tz::Tz>() {
+            Self::Named(tz)
+        } else if let Some(zone) = custom.get(tzid) {
+            Self::Custom(zone.clone())
+        } else {
+            Self::Floating
+        }
+    }
+}
+
+/// A DATE (all-day) or a wall-clock DATE-TIME in some zone.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IcsTime {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime, Zone),
+}
+
+impl IcsTime {
+    fn parse(value: &str, zone: Zone) -> Option<Self> {
+        let value = value.trim();
+        if value.len() == 8 {
+            return NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()
+                .map(Self::Date);
+        }
+        let (value, zone) = match value.strip_suffix('Z') {
+            Some(value) => (value, Zone::Utc),
+            None => (value, zone),
+        };
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .ok()
+            .map(|t| Self::DateTime(t, zone))
+    }
+
+    fn from_property(property: &Property, zones: &HashMap<String, CustomZone>) -> Option<Self> {
+        let zone = property
+            .param("TZID")
+            .map_or(Zone::Floating, |tzid| Zone::resolve(tzid, zones));
+        Self::parse(&property.value, zone)
+    }
+
+    pub fn is_date(&self) -> bool {
+        matches!(self, Self::Date(_))
+    }
+
+    /// The wall-clock time; midnight for dates.
+    pub fn wall(&self) -> NaiveDateTime {
+        match self {
+            Self::Date(date) => date.and_time(NaiveTime::MIN),
+            Self::DateTime(time, _) => *time,
+        }
+    }
+
+    /// The same kind of time in the same zone, at another wall-clock time.
+    pub fn with_wall(&self, wall: NaiveDateTime) -> Self {
+        match self {
+            Self::Date(_) => Self::Date(wall.date()),
+            Self::DateTime(_, zone) => Self::DateTime(wall, zone.clone()),
+        }
+    }
+
+    /// The instant this denotes; dates and floating times are read in `local`.
+    pub fn to_utc<Tz: TimeZone>(&self, local: &Tz) -> DateTime<Utc> {
+        match self {
+            Self::Date(date) => from_local(local, date.and_time(NaiveTime::MIN)),
+            Self::DateTime(time, zone) => match zone {
+                Zone::Utc => Utc.from_utc_datetime(time),
+                Zone::Floating => from_local(local, *time),
+                Zone::Named(tz) => from_local(tz, *time),
+                Zone::Custom(custom) => Utc.from_utc_datetime(
+                    &(*time - Duration::seconds(custom.offset_at(*time) as i64)),
+                ),
+            },
+        }
+    }
+}
+
+/// Ambiguous times take the earlier instant; times in a DST gap move forward.
+fn from_local<Tz: TimeZone>(tz: &Tz, time: NaiveDateTime) -> DateTime<Utc> {
+    match tz.from_local_datetime(&time) {
+        LocalResult::Single(t) | LocalResult::Ambiguous(t, _) => t.with_timezone(&Utc),
+        LocalResult::None => tz
+            .from_local_datetime(&(time + Duration::hours(1)))
+            .earliest()
+            .map_or_else(|| Utc.from_utc_datetime(&time), |t| t.with_timezone(&Utc)),
+    }
+}
+
+/// A VTIMEZONE: STANDARD and DAYLIGHT observances with their onsets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomZone {
+    observances: Vec<Observance>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Observance {
+    start: NaiveDateTime,
+    offset: i32,
+    rule: Option<RecurrenceRule>,
+}
+
+impl CustomZone {
+    fn from_component(component: &Component) -> Option<(String, Self)> {
+        let tzid = component.property("TZID")?.value.clone();
+        let observances: Vec<Observance> = component
+            .components
+            .iter()
+            .filter(|c| c.name == "STANDARD" || c.name == "DAYLIGHT")
+            .filter_map(|c| {
+                Some(Observance {
+                    start: IcsTime::parse(&c.property("DTSTART")?.value, Zone::Floating)?.wall(),
+                    offset: parse_offset(&c.property("TZOFFSETTO")?.value)?,
+                    rule: c.property("RRULE").and_then(|r| r.value.parse().ok()),
+                })
+            })
+            .collect();
+        if observances.is_empty() {
+            None
+        } else {
+            Some((tzid, Self { observances }))
+        }
+    }
+
+    /// UTC offset in seconds of the observance in effect at wall-clock `time`.
+    fn offset_at(&self, time: NaiveDateTime) -> i32 {
+        self.observances
+            .iter()
+            .filter_map(|o| {
+                let onset = match &o.rule {
+                    Some(rule) => {
+                        let until = rule.until.as_ref().map(IcsTime::wall);
+                        rule.expand(o.start, time.date())
+                            .into_iter()
+                            .rev()
+                            .find(|t| *t <= time && until.map_or(true, |u| *t <= u))
+                    }
+                    None => Some(o.start).filter(|t| *t <= time),
+                }?;
+                Some((onset, o.offset))
+            })
+            .max_by_key(|(onset, _)| *onset)
+            .map_or(self.observances[0].offset, |(_, offset)| offset)
+    }
+}
+
+/// `+0100`, `-0530` or `+013000` as seconds east of UTC.
+fn parse_offset(value: &str) -> Option<i32> {
+    let value = value.trim();
+    let sign = match value.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let digits = value.get(1..)?;
+    if !(digits.len() == 4 || digits.len() == 6) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| digits.get(range).unwrap_or("0").parse::<i32>();
+    let seconds = field(0..2).ok()? * 3600 + field(2..4).ok()? * 60 + field(4..6).unwrap_or(0);
+    Some(sign * seconds)
+}
+
+/// `P1D`, `PT1H30M`, `-PT15M` or `P2W`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut rest = value.strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut in_time = false;
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = r;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        let unit = rest[digits..].chars().next()?;
+        total += match (unit, in_time) {
+            ('W', false) => Duration::weeks(amount),
+            ('D', false) => Duration::days(amount),
+            ('H', true) => Duration::hours(amount),
+            ('M', true) => Duration::minutes(amount),
+            ('S', true) => Duration::seconds(amount),
+            _ => return None,
+        };
+        rest = &rest[digits + unit.len_utf8()..];
+    }
+    Some(total * sign)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The RRULE subset calendars commonly export: FREQ, INTERVAL, COUNT,
+/// UNTIL, BYDAY (with ordinals for monthly/yearly) and BYMONTH (yearly).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<usize>,
+    pub until: Option<IcsTime>,
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    pub by_month: Vec<u32>,
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |part: &str| Error::Parse(format!("invalid RRULE part '{}'", part));
+        let mut frequency = None;
+        let mut rule = Self {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+
+        for part in s.split(';').filter(|p| !p.trim().is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(|| invalid(part))?;
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(Error::Parse(format!("unsupported FREQ={}", value))),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| invalid(part))?
+                }
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| invalid(part))?),
+                "UNTIL" => {
+                    rule.until =
+                        Some(IcsTime::parse(value, Zone::Floating).ok_or_else(|| invalid(part))?)
+                }
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(parse_weekday)
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| invalid(part))?
+                }
+                "BYMONTH" => {
+                    rule.by_month = value
+                        .split(',')
+                        .map(|m| m.trim().parse().ok().filter(|m| (1..=12).contains(m)))
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| invalid(part))?
+                }
+                "WKST" => {}
+                other => return Err(Error::Parse(format!("unsupported RRULE part {}", other))),
+            }
+        }
+
+        rule.frequency = frequency.ok_or_else(|| Error::Parse("RRULE without FREQ".into()))?;
+        let ordinals = rule.by_day.iter().any(|(n, _)| n.is_some());
+        let unsupported = match rule.frequency {
+            Frequency::Daily | Frequency::Weekly => ordinals || !rule.by_month.is_empty(),
+            Frequency::Monthly => !rule.by_month.is_empty(),
+            Frequency::Yearly => !rule.by_day.is_empty() && rule.by_month.is_empty(),
+        };
+        if unsupported {
+            return Err(Error::Parse(format!("unsupported RRULE {}", s)));
+        }
+        Ok(rule)
+    }
+}
+
+fn parse_weekday(s: &str) -> Option<(Option<i32>, Weekday)> {
+    let s = s.trim();
+    let split = s.len().checked_sub(2)?;
+    if !s.is_char_boundary(split) {
+        return None;
+    }
+    let (ordinal, day) = s.split_at(split);
+    let weekday = match day.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    if ordinal.is_empty() {
+        return Some((None, weekday));
+    }
+    let n: i32 = ordinal.parse().ok()?;
+    (n != 0 && n.abs() <= 5).then_some((Some(n), weekday))
+}
+
+impl RecurrenceRule {
+    /// Occurrence start times from `start` through `horizon`, honouring COUNT
+    /// but not UNTIL, which callers compare in their own zone.
+    pub fn expand(&self, start: NaiveDateTime, horizon: NaiveDate) -> Vec<NaiveDateTime> {
+        let mut starts = Vec::new();
+        for period in 0.. {
+            let (first_day, dates) = self.period_dates(start.date(), period);
+            if first_day > horizon {
+                break;
+            }
+            for date in dates.into_iter().filter(|d| *d >= start.date()) {
+                if date > horizon || self.count.is_some_and(|n| starts.len() >= n) {
+                    return starts;
+                }
+                starts.push(date.and_time(start.time()));
+            }
+        }
+        starts
+    }
+
+    /// First day of the `period`-th period and its candidate dates, sorted.
+    fn period_dates(&self, start: NaiveDate, period: i64) -> (NaiveDate, Vec<NaiveDate>) {
+        let step = period * self.interval as i64;
+        match self.frequency {
+            Frequency::Daily => {
+                let day = start + Duration::days(step);
+                let matches =
+                    self.by_day.is_empty() || self.by_day.iter().any(|(_, w)| *w == day.weekday());
+                (day, if matches { vec![day] } else { Vec::new() })
+            }
+            Frequency::Weekly => {
+                let monday = start - Duration::days(start.weekday().num_days_from_monday() as i64)
+                    + Duration::weeks(step);
+                let weekdays: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![start.weekday()]
+                } else {
+                    self.by_day.iter().map(|(_, w)| *w).collect()
+                };
+                let mut dates: Vec<NaiveDate> = weekdays
+                    .into_iter()
+                    .map(|w| monday + Duration::days(w.num_days_from_monday() as i64))
+                    .collect();
+                dates.sort();
+                dates.dedup();
+                (monday, dates)
+            }
+            Frequency::Monthly => {
+                let months = start.year() as i64 * 12 + start.month0() as i64 + step;
+                let (year, month) = (
+                    months.div_euclid(12) as i32,
+                    months.rem_euclid(12) as u32 + 1,
+                );
+                (
+                    first_of_month(year, month),
+                    self.month_dates(year, month, start.day()),
+                )
+            }
+            Frequency::Yearly => {
+                let year = start.year() + step as i32;
+                let months = if self.by_month.is_empty() {
+                    vec![start.month()]
+                } else {
+                    self.by_month.clone()
+                };
+                let mut dates: Vec<NaiveDate> = months
+                    .into_iter()
+                    .flat_map(|m| self.month_dates(year, m, start.day()))
+                    .collect();
+                dates.sort();
+                dates.dedup();
+                (first_of_month(year, 1), dates)
+            }
+        }
+    }
+
+    /// The day-of-month of DTSTART, or the BYDAY matches, within one month.
+    fn month_dates(&self, year: i32, month: u32, day: u32) -> Vec<NaiveDate> {
+        if self.by_day.is_empty() {
+            return NaiveDate::from_ymd_opt(year, month, day)
+                .into_iter()
+                .collect();
+        }
+        let mut dates = Vec::new();
+        for (ordinal, weekday) in &self.by_day {
+            let matching: Vec<NaiveDate> = first_of_month(year, month)
+                .iter_days()
+                .take_while(|d| d.month() == month)
+                .filter(|d| d.weekday() == *weekday)
+                .collect();
+            match ordinal {
+                None => dates.extend(matching),
+                Some(n) if *n > 0 => dates.extend(matching.get(*n as usize - 1)),
+                Some(n) => dates.extend(
+                    matching
+                        .len()
+                        .checked_sub(n.unsigned_abs() as usize)
+                        .and_then(|i| matching.get(i)),
+                ),
+            }
+        }
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+}
+
+fn first_of_month(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(NaiveDate::MAX)
+}
+
+/// A VEVENT with its times resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IcsEvent {
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub categories: Vec<String>,
+    pub status: Option<String>,
+    pub start: IcsTime,
+    pub end: Option<IcsTime>,
+    pub rrule: Option<RecurrenceRule>,
+    pub exdates: Vec<IcsTime>,
+    /// Set on an instance that replaces one occurrence of a recurring event
+    pub recurrence_id: Option<IcsTime>,
+    pub attendees: Vec<Attendee>,
+}
+
+/// An ATTENDEE and their reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attendee {
+    /// Lowercased address without `mailto:`
+    pub address: String,
+    /// PARTSTAT, e.g. `ACCEPTED` or `DECLINED`
+    pub status: Option<String>,
+}
+
+/// One occurrence of an event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occurrence {
+    pub start: IcsTime,
+    pub end: Option<IcsTime>,
+}
+
+impl IcsEvent {
+    fn from_component(
+        component: &Component,
+        zones: &HashMap<String, CustomZone>,
+    ) -> std::result::Result<Self, String> {
+        let time = |name: &str| -> std::result::Result<Option<IcsTime>, String> {
+            component
+                .property(name)
+                .map(|p| {
+                    IcsTime::from_property(p, zones)
+                        .ok_or_else(|| format!("invalid {} '{}'", name, p.value))
+                })
+                .transpose()
+        };
+
+        let start = time("DTSTART")?.ok_or("no DTSTART")?;
+        let end = match time("DTEND")? {
+            Some(end) => Some(end),
+            None => match component.property("DURATION") {
+                Some(p) => {
+                    let duration = parse_duration(&p.value)
+                        .ok_or_else(|| format!("invalid DURATION '{}'", p.value))?;
+                    Some(start.with_wall(start.wall() + duration))
+                }
+                None => None,
+            },
+        };
+        let rrule = component
+            .property("RRULE")
+            .map(|p| p.value.parse::<RecurrenceRule>())
+            .transpose()
+            .map_err(|e| match e {
+                Error::Parse(msg) => msg,
+                e => e.to_string(),
+            })?;
+        let exdates = component
+            .properties("EXDATE")
+            .flat_map(|p| {
+                let zone = p
+                    .param("TZID")
+                    .map_or(Zone::Floating, |tzid| Zone::resolve(tzid, zones));
+                p.value
+                    .split(',')
+                    .filter_map(move |v| IcsTime::parse(v, zone.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        Ok(Self {
+            uid: component
+                .text("UID")
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            summary: component.text("SUMMARY").unwrap_or_default(),
+            description: component.text("DESCRIPTION"),
+            location: component.text("LOCATION"),
+            categories: component
+                .properties("CATEGORIES")
+                .flat_map(|p| split_text_list(&p.value))
+                .collect(),
+            status: component.text("STATUS").map(|s| s.to_ascii_uppercase()),
+            start,
+            end,
+            rrule,
+            exdates,
+            recurrence_id: time("RECURRENCE-ID")?,
+            attendees: component
+                .properties("ATTENDEE")
+                .map(|p| {
+                    let address = p.value.trim();
+                    Attendee {
+                        address: address
+                            .get(..7)
+                            .filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+                            .map_or(address, |_| &address[7..])
+                            .to_lowercase(),
+                        status: p.param("PARTSTAT").map(|s| s.to_ascii_uppercase()),
+                    }
+                })
+                .collect(),
+        })
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.status.as_deref() == Some("CANCELLED")
+    }
+
+    /// Whether one of the addresses in `me` declined the invitation.
+    pub fn declined_by(&self, me: &[String]) -> bool {
+        self.attendees.iter().any(|a| {
+            a.status.as_deref() == Some("DECLINED")
+                && me.iter().any(|m| m.eq_ignore_ascii_case(&a.address))
+        })
+    }
+
+    /// Every occurrence, recurring ones only up to `horizon`.
+    pub fn occurrences<Tz: TimeZone>(&self, horizon: NaiveDate, local: &Tz) -> Vec<Occurrence> {
+        let length = self.end.as_ref().map(|end| end.wall() - self.start.wall());
+        let starts = match &self.rrule {
+            None => vec![self.start.wall()],
+            Some(rule) => {
+                let instant = |wall: NaiveDateTime| self.start.with_wall(wall).to_utc(local);
+                let until = rule.until.as_ref().map(|u| u.to_utc(local));
+                let excluded: Vec<DateTime<Utc>> =
+                    self.exdates.iter().map(|e| e.to_utc(local)).collect();
+                let mut starts = rule.expand(self.start.wall(), horizon);
+                starts.retain(|t| {
+                    let at = instant(*t);
+                    until.map_or(true, |u| at <= u) && !excluded.contains(&at)
+                });
+                if starts.len() > MAX_OCCURRENCES {
+                    starts.drain(..starts.len() - MAX_OCCURRENCES);
+                }
+                starts
+            }
+        };
+        starts
+            .into_iter()
+            .map(|t| Occurrence {
+                start: self.start.with_wall(t),
+                end: length.map(|l| self.start.with_wall(t + l)),
+            })
+            .collect()
+    }
+}
+
+/// A comma-separated TEXT list, honouring escaped commas.
+fn split_text_list(value: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut escaped = false;
+    for c in value.chars() {
+        match (escaped, c) {
+            (false, '\\') => escaped = true,
+            (false, ',') => items.push(String::new()),
+            (true, c) => {
+                escaped = false;
+                if let Some(last) = items.last_mut() {
+                    last.push('\\');
+                    last.push(c);
+                }
+            }
+            (false, c) => {
+                if let Some(last) = items.last_mut() {
+                    last.push(c)
+                }
+            }
+        }
+    }
+    items
+        .iter()
+        .map(|i| unescape_text(i).trim().to_string())
+        .filter(|i| !i.is_empty())
+        .collect()
+}
+
+/// A VEVENT that could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedEvent {
+    pub summary: String,
+    pub reason: String,
+}
+
+/// The events of an `.ics` file, plus the ones that could not be read.
+#[derive(Debug, Clone, Default)]
+pub struct IcsFile {
+    pub events: Vec<IcsEvent>,
+    pub skipped: Vec<SkippedEvent>,
+}
+
+impl IcsFile {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut file = Self::default();
+        for calendar in parse_calendars(input)? {
+            let zones: HashMap<String, CustomZone> = calendar
+                .components
+                .iter()
+                .filter(|c| c.name == "VTIMEZONE")
+                .filter_map(CustomZone::from_component)
+                .collect();
+            for component in calendar.components.iter().filter(|c| c.name == "VEVENT") {
+                match IcsEvent::from_component(component, &zones) {
+                    Ok(event) => file.events.push(event),
+                    Err(reason) => file.skipped.push(SkippedEvent {
+                        summary: component
+                            .text("SUMMARY")
+                            .unwrap_or_else(|| "(untitled)".into()),
+                        reason,
+                    }),
+                }
+            }
+        }
+        Ok(file)
+    }
+
+    /// Every occurrence that is not cancelled, with moved instances of a
+    /// recurring event taking the place of the occurrence they replace.
+    pub fn occurrences<Tz: TimeZone>(
+        &self,
+        horizon: NaiveDate,
+        local: &Tz,
+    ) -> Vec<(&IcsEvent, Occurrence)> {
+        let mut replaced: HashMap<&str, Vec<DateTime<Utc>>> = HashMap::new();
+        for event in &self.events {
+            if let Some(ref id) = event.recurrence_id {
+                replaced
+                    .entry(event.uid.as_str())
+                    .or_default()
+                    .push(id.to_utc(local));
+            }
+        }
+
+        let mut occurrences = Vec::new();
+        for event in self.events.iter().filter(|e| !e.is_cancelled()) {
+            if event.recurrence_id.is_some() {
+                occurrences.push((
+                    event,
+                    Occurrence {
+                        start: event.start.clone(),
+                        end: event.end.clone(),
+                    },
+                ));
+                continue;
+            }
+            let moved = replaced.get(event.uid.as_str());
+            for occurrence in event.occurrences(horizon, local) {
+                let at = occurrence.start.to_utc(local);
+                if !moved.is_some_and(|m| m.contains(&at)) {
+                    occurrences.push((event, occurrence));
+                }
+            }
+        }
+        occurrences
+    }
+
+    /// The distinct UIDs in the file, cancelled events included.
+    pub fn uids(&self) -> Vec<String> {
+        let mut uids: Vec<String> = self.events.iter().map(|e| e.uid.clone()).collect();
+        uids.sort();
+        uids.dedup();
+        uids
+    }
+
+    /// Calendar events for the occurrences, dated in the time zone `local`.
+    ///
+    /// Ids derive from the UID (`uid/date` per day for all-day events), so
+    /// importing the same file again finds the events it created before.
+    pub fn calendar_events<Tz: TimeZone>(
+        &self,
+        horizon: NaiveDate,
+        local: &Tz,
+    ) -> Vec<CalendarEvent> {
+        self.occurrences(horizon, local)
+            .into_iter()
+            .flat_map(|(event, occurrence)| to_calendar_events(event, &occurrence, local))
+            .collect()
+    }
+}
+
+fn to_calendar_events<Tz: TimeZone>(
+    event: &IcsEvent,
+    occurrence: &Occurrence,
+    local: &Tz,
+) -> Vec<CalendarEvent> {
+    let id = match (&event.recurrence_id, &event.rrule) {
+        (Some(original), _) => format!("{}/{}", event.uid, original.wall().date()),
+        (None, Some(_)) => format!("{}/{}", event.uid, occurrence.start.wall().date()),
+        (None, None) => event.uid.clone(),
+    };
+    let event_type = if event
+        .categories
+        .iter()
+        .any(|c| c.eq_ignore_ascii_case("REMINDER"))
+    {
+        CalendarEventType::Reminder
+    } else if event
+        .categories
+        .iter()
+        .any(|c| c.eq_ignore_ascii_case("TRACKING-SESSION"))
+    {
+        CalendarEventType::TrackingSession
+    } else {
+        CalendarEventType::Custom
+    };
+    let description = match (&event.description, &event.location) {
+        (Some(d), Some(l)) => Some(format!("{}\nLocation: {}", d, l)),
+        (Some(d), None) => Some(d.clone()),
+        (None, Some(l)) => Some(format!("Location: {}", l)),
+        (None, None) => None,
+    };
+    let calendar_event = |id: String, date: NaiveDate, time: Option<String>| CalendarEvent {
+        id,
+        title: event.summary.clone(),
+        date,
+        time,
+        event_type: event_type.clone(),
+        description: description.clone(),
+        created_at: Utc::now(),
+    };
+
+    match occurrence.start {
+        IcsTime::Date(first) => {
+            let days = occurrence
+                .end
+                .as_ref()
+                .map_or(1, |end| (end.wall().date() - first).num_days())
+                .clamp(1, MAX_EVENT_DAYS);
+            // One id per day whatever the length, so a re-import after the
+            // event grew or shrank updates the days it still covers.
+            (0..days)
+                .map(|n| {
+                    let date = first + Duration::days(n);
+                    calendar_event(format!("{}/{}", event.uid, date), date, None)
+                })
+                .collect()
+        }
+        IcsTime::DateTime(..) => {
+            let wall = |t: &IcsTime| t.to_utc(local).with_timezone(local).naive_local();
+            let start = wall(&occurrence.start);
+            let end = occurrence.end.as_ref().map(wall).filter(|e| *e > start);
+            let time = match end {
+                Some(end) => format!("{}-{}", start.format("%H:%M"), end.format("%H:%M")),
+                None => start.format("%H:%M").to_string(),
+            };
+            vec![calendar_event(id, start.date(), Some(time))]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn calendar(body: &str) -> String {
+        format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:test\r\n{}END:VCALENDAR\r\n",
+            body
+        )
+    }
+
+    #[test]
+    fn test_writer_escapes_and_folds() {
+        let event = CalendarEvent::new("Review; notes, and \\ more", date(2026, 10, 5))
+            .with_time("09:30-10:15")
+            .with_description("x".repeat(200));
+        let ics = events_to_ics(std::slice::from_ref(&event));
+
+        assert!(ics.contains("SUMMARY:Review\\; notes\\, and \\\\ more\r\n"));
+        assert!(ics.contains("DTSTART:20261005T093000\r\n"));
+        assert!(ics.contains("DTEND:20261005T101500\r\n"));
+        assert!(ics.lines().all(|l| l.len() <= 76));
+
+        let events = IcsFile::parse(&ics)
+            .unwrap()
+            .calendar_events(date(2027, 1, 1), &Utc);
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            CalendarEvent {
+                created_at: event.created_at,
+                ..events[0].clone()
+            },
+            event
+        );
+    }
+
+    #[test]
+    fn test_time_zones_and_all_day_events() {
+        let ics = calendar(concat!(
+            "BEGIN:VTIMEZONE\r\nTZID:W. Europe Standard Time\r\n",
+            "BEGIN:STANDARD\r\nDTSTART:16010101T030000\r\nTZOFFSETFROM:+0200\r\n",
+            "TZOFFSETTO:+0100\r\nRRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=10\r\nEND:STANDARD\r\n",
+            "BEGIN:DAYLIGHT\r\nDTSTART:16010101T020000\r\nTZOFFSETFROM:+0100\r\n",
+            "TZOFFSETTO:+0200\r\nRRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=3\r\nEND:DAYLIGHT\r\n",
+            "END:VTIMEZONE\r\n",
+            "BEGIN:VEVENT\r\nUID:a\r\nSUMMARY:Berlin\r\n",
+            "DTSTART;TZID=Europe/Berlin:20260710T090000\r\nDURATION:PT1H\r\nEND:VEVENT\r\n",
+            "BEGIN:VEVENT\r\nUID:b\r\nSUMMARY:Outlook\r\n",
+            "DTSTART;TZID=W. Europe Standard Time:20261102T090000\r\n",
+            "DTEND;TZID=W. Europe Standard Time:20261102T093000\r\nEND:VEVENT\r\n",
+            "BEGIN:VEVENT\r\nUID:c\r\nSUMMARY:Offsite\r\nDTSTART;VALUE=DATE:20260901\r\n",
+            "DTEND;VALUE=DATE:20260903\r\nEND:VEVENT\r\n",
+        ));
+        let events = IcsFile::parse(&ics)
+            .unwrap()
+            .calendar_events(date(2027, 1, 1), &Utc);
+
+        let times: Vec<(&str, NaiveDate, Option<&str>)> = events
+            .iter()
+            .map(|e| (e.title.as_str(), e.date, e.time.as_deref()))
+            .collect();
+        assert_eq!(
+            times,
+            vec![
+                ("Berlin", date(2026, 7, 10), Some("07:00-08:00")),
+                ("Outlook", date(2026, 11, 2), Some("08:00-08:30")),
+                ("Offsite", date(2026, 9, 1), None),
+                ("Offsite", date(2026, 9, 2), None),
+            ]
+        );
+        assert_eq!(events[3].id, "c/2026-09-02");
+
+        // Shortened to one day, the event keeps the id of the day it still covers.
+        let shorter = ics.replace("DTEND;VALUE=DATE:20260903", "DTEND;VALUE=DATE:20260902");
+        let file = IcsFile::parse(&shorter).unwrap();
+        let offsite: Vec<String> = file
+            .calendar_events(date(2027, 1, 1), &Utc)
+            .into_iter()
+            .filter(|e| e.title == "Offsite")
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(offsite, vec!["c/2026-09-01"]);
+        assert_eq!(file.uids(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_recurrence_with_exceptions() {
+        let ics = calendar(concat!(
+            "BEGIN:VEVENT\r\nUID:standup\r\nSUMMARY:Standup\r\n",
+            "DTSTART:20261005T090000Z\r\nDTEND:20261005T091500Z\r\n",
+            "RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=6\r\n",
+            "EXDATE:20261007T090000Z\r\nEND:VEVENT\r\n",
+            "BEGIN:VEVENT\r\nUID:standup\r\nSUMMARY:Standup (moved)\r\n",
+            "RECURRENCE-ID:20261012T090000Z\r\n",
+            "DTSTART:20261012T140000Z\r\nDTEND:20261012T141500Z\r\nEND:VEVENT\r\n",
+            "BEGIN:VEVENT\r\nUID:x\r\nSUMMARY:Odd\r\nDTSTART:20261005T090000Z\r\n",
+            "RRULE:FREQ=MONTHLY;BYSETPOS=1\r\nEND:VEVENT\r\n",
+        ));
+        let file = IcsFile::parse(&ics).unwrap();
+        assert_eq!(file.skipped.len(), 1);
+        assert_eq!(file.skipped[0].summary, "Odd");
+
+        let events = file.calendar_events(date(2027, 1, 1), &Utc);
+        let days: Vec<(String, Option<&str>)> = events
+            .iter()
+            .map(|e| (e.id.clone(), e.time.as_deref()))
+            .collect();
+        assert_eq!(
+            days,
+            vec![
+                ("standup/2026-10-05".into(), Some("09:00-09:15")),
+                ("standup/2026-10-14".into(), Some("09:00-09:15")),
+                ("standup/2026-10-19".into(), Some("09:00-09:15")),
+                ("standup/2026-10-21".into(), Some("09:00-09:15")),
+                ("standup/2026-10-12".into(), Some("14:00-14:15")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rrule_expansion() {
+        let start = date(2026, 1, 31).and_hms_opt(10, 0, 0).unwrap();
+        let monthly: RecurrenceRule = "FREQ=MONTHLY;COUNT=3".parse().unwrap();
+        let dates: Vec<NaiveDate> = monthly
+            .expand(start, date(2030, 1, 1))
+            .iter()
+            .map(|t| t.date())
+            .collect();
+        assert_eq!(
+            dates,
+            vec![date(2026, 1, 31), date(2026, 3, 31), date(2026, 5, 31)]
+        );
+
+        let last_friday: RecurrenceRule = "FREQ=MONTHLY;INTERVAL=2;BYDAY=-1FR".parse().unwrap();
+        let dates: Vec<NaiveDate> = last_friday
+            .expand(start, date(2026, 6, 1))
+            .iter()
+            .map(|t| t.date())
+            .collect();
+        assert_eq!(dates, vec![date(2026, 3, 27), date(2026, 5, 29)]);
+
+        assert!("FREQ=HOURLY".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=WEEKLY;BYDAY=2MO".parse::<RecurrenceRule>().is_err());
+    }
+}
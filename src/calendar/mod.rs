@@ -3,9 +3,11 @@
 //! Provides TUI calendar interface with Materia theme support
 
 pub mod events;
+pub mod ics;
 pub mod model;
 pub mod tui;
 
 pub use events::{CalendarEvent, CalendarEventType, EventStore};
+pub use ics::IcsFile;
 pub use model::Calendar;
 pub use tui::CalendarTui;
@@ -4,6 +4,7 @@ use chrono::{Datelike, Duration, Local, Month, NaiveDate, Weekday};
 use num_traits::FromPrimitive;
 
 use super::events::CalendarEvent;
+use crate::absence::{Absence, AbsenceSummary};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CalendarView {
@@ -24,6 +25,7 @@ pub struct Calendar {
     pub selected_date: NaiveDate,
     pub view: CalendarView,
     pub events: Vec<CalendarEvent>,
+    pub absences: Vec<Absence>,
     pub input_mode: InputMode,
     pub input_buffer: String,
     pub delete_selection_index: usize,
@@ -37,6 +39,7 @@ impl Calendar {
             selected_date: today,
             view: CalendarView::Month,
             events: Vec::new(),
+            absences: Vec::new(),
             input_mode: InputMode::Normal,
             input_buffer: String::new(),
             delete_selection_index: 0,
@@ -48,6 +51,11 @@ impl Calendar {
         self
     }
 
+    pub fn with_absences(mut self, absences: Vec<Absence>) -> Self {
+        self.absences = absences;
+        self
+    }
+
     pub fn go_to_today(&mut self) {
         let today = Local::now().naive_local().date();
         self.current_date = today;
@@ -165,6 +173,24 @@ impl Calendar {
             .count()
     }
 
+    pub fn get_absence_for_date(&self, date: NaiveDate) -> Option<&Absence> {
+        self.absences.iter().find(|a| a.date == date)
+    }
+
+    /// Days off in the displayed month.
+    pub fn absences_this_month(&self) -> AbsenceSummary {
+        let month: Vec<Absence> = self
+            .absences
+            .iter()
+            .filter(|a| {
+                a.date.month() == self.current_date.month()
+                    && a.date.year() == self.current_date.year()
+            })
+            .cloned()
+            .collect();
+        AbsenceSummary::build(&month)
+    }
+
     pub fn total_events(&self) -> usize {
         self.events.len()
     }
@@ -215,6 +241,27 @@ mod tests {
         assert_eq!(cal.current_date.month(), initial_month);
     }
 
+    #[test]
+    fn test_absences_this_month() {
+        use crate::absence::AbsenceKind;
+        let mut cal = Calendar::new().with_absences(vec![
+            Absence::new(
+                NaiveDate::from_ymd_opt(2026, 12, 24).unwrap(),
+                AbsenceKind::Vacation,
+            )
+            .with_half_day(true),
+            Absence::new(
+                NaiveDate::from_ymd_opt(2027, 1, 1).unwrap(),
+                AbsenceKind::Holiday,
+            ),
+        ]);
+        cal.current_date = NaiveDate::from_ymd_opt(2026, 12, 1).unwrap();
+        assert_eq!(cal.absences_this_month().days, 0.5);
+        assert!(cal
+            .get_absence_for_date(NaiveDate::from_ymd_opt(2027, 1, 1).unwrap())
+            .is_some());
+    }
+
     #[test]
     fn test_get_month_days() {
         let cal = Calendar::new();
@@ -1,5 +1,6 @@
 //! Calendar TUI rendering with ratatui
 
+use crate::absence::{Absence, AbsenceKind};
 use crate::calendar::events::{CalendarEvent, CalendarEventType, EventStore};
 use crate::calendar::model::{Calendar, InputMode};
 use crate::error::Result;
@@ -123,10 +124,10 @@ impl CalendarTui {
                             KeyCode::Esc => {
                                 self.calendar.input_mode = InputMode::Normal;
                             }
-                            KeyCode::Up | KeyCode::Char('w') => {
-                                if self.calendar.delete_selection_index > 0 {
-                                    self.calendar.delete_selection_index -= 1;
-                                }
+                            KeyCode::Up | KeyCode::Char('w')
+                                if self.calendar.delete_selection_index > 0 =>
+                            {
+                                self.calendar.delete_selection_index -= 1;
                             }
                             KeyCode::Down | KeyCode::Char('s') => {
                                 let date = self.calendar.selected_date;
@@ -316,6 +317,7 @@ impl CalendarTui {
                     let day_num = day.day();
                     let events = self.calendar.get_events_for_date(*day);
                     let has_events = !events.is_empty();
+                    let absence = self.calendar.get_absence_for_date(*day);
 
                     let mut style = Style::default();
 
@@ -332,15 +334,19 @@ impl CalendarTui {
                             .fg(Color::Rgb(sr, sg, sb))
                             .add_modifier(Modifier::BOLD);
                     }
+                    // Days off
+                    else if let Some(a) = absence {
+                        style = style.fg(absence_color(a.kind));
+                    }
                     // Show events indicator
                     else if has_events {
                         style = style.fg(Color::Rgb(r, g, b));
                     }
 
-                    let day_str = if has_events {
-                        format!("{:>2}•", day_num)
-                    } else {
-                        format!("{:>2} ", day_num)
+                    let day_str = match absence {
+                        Some(a) => format!("{:>2}{}", day_num, absence_marker(a)),
+                        None if has_events => format!("{:>2}•", day_num),
+                        None => format!("{:>2} ", day_num),
                     };
 
                     Paragraph::new(day_str)
@@ -367,7 +373,7 @@ impl CalendarTui {
             .split(area);
 
         // Stats block
-        let stats_content = vec![
+        let mut stats_content = vec![
             Line::from(vec![
                 Span::styled("Month: ", Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(format!(
@@ -383,8 +389,29 @@ impl CalendarTui {
                 Span::styled("Total: ", Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(format!("{}", self.calendar.total_events())),
             ]),
+            Line::from(vec![
+                Span::styled("Days off: ", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw({
+                    let off = self.calendar.absences_this_month();
+                    if off.days == 0.0 {
+                        "none".to_string()
+                    } else {
+                        off.formatted()
+                    }
+                }),
+            ]),
         ];
 
+        if let Some(a) = self
+            .calendar
+            .get_absence_for_date(self.calendar.selected_date)
+        {
+            stats_content.push(Line::from(vec![
+                Span::styled("Selected: ", Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(a.label(), Style::default().fg(absence_color(a.kind))),
+            ]));
+        }
+
         let stats = Paragraph::new(stats_content)
             .block(
                 Block::default()
@@ -468,6 +495,26 @@ impl CalendarTui {
                 Span::styled("##", Style::default().fg(Color::Rgb(r, g, b))),
                 Span::raw(" - Day with Events"),
             ]),
+            Line::from(vec![
+                Span::raw("  "),
+                Span::styled(
+                    "V",
+                    Style::default().fg(absence_color(AbsenceKind::Vacation)),
+                ),
+                Span::raw(" Vacation  "),
+                Span::styled("S", Style::default().fg(absence_color(AbsenceKind::Sick))),
+                Span::raw(" Sick  "),
+                Span::styled(
+                    "H",
+                    Style::default().fg(absence_color(AbsenceKind::Holiday)),
+                ),
+                Span::raw(" Holiday  "),
+                Span::styled(
+                    "C",
+                    Style::default().fg(absence_color(AbsenceKind::CompTime)),
+                ),
+                Span::raw(" Comp time (lowercase: half day)"),
+            ]),
         ];
 
         let help = Paragraph::new(help_text)
@@ -607,3 +654,27 @@ impl CalendarTui {
         frame.render_widget(list, popup_area);
     }
 }
+
+fn absence_color(kind: AbsenceKind) -> Color {
+    match kind {
+        AbsenceKind::Vacation => Color::Rgb(90, 180, 220),
+        AbsenceKind::Sick => Color::Rgb(220, 120, 120),
+        AbsenceKind::Holiday => Color::Rgb(120, 200, 120),
+        AbsenceKind::CompTime => Color::Rgb(200, 170, 90),
+    }
+}
+
+/// `V`, `S`, `H` or `C` next to the day number; lowercase for half days.
+fn absence_marker(absence: &Absence) -> char {
+    let marker = match absence.kind {
+        AbsenceKind::Vacation => 'V',
+        AbsenceKind::Sick => 'S',
+        AbsenceKind::Holiday => 'H',
+        AbsenceKind::CompTime => 'C',
+    };
+    if absence.half_day {
+        marker.to_ascii_lowercase()
+    } else {
+        marker
+    }
+}
@@ -1,7 +1,7 @@
 //! CLI interface for MatteriaTrack
 
 use crate::theme::icons;
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
 
 const FF_BANNER: &str = r#"
@@ -56,10 +56,25 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub config: Option<String>,
 
-    /// Output format
-    #[arg(short = 'f', long, global = true, default_value = "pretty")]
+    /// Output format (pretty, json, plain, csv, markdown, html, statusbar)
+    #[arg(
+        id = "format",
+        short = 'f',
+        long,
+        global = true,
+        default_value = "pretty"
+    )]
+    format_arg: String,
+
+    /// Parsed `--format`. Kept as a string on the command line because
+    /// `statusbar --format` shares the flag for its bar style.
+    #[arg(skip = OutputFormat::Pretty)]
     pub format: OutputFormat,
 
+    /// Profile to use (overrides MTRACK_PROFILE and `profile switch`)
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
     /// Verbose output
     #[arg(short, long, global = true)]
     pub verbose: bool,
@@ -70,6 +85,10 @@ pub enum OutputFormat {
     Pretty,
     Json,
     Plain,
+    Csv,
+    #[value(alias = "md")]
+    Markdown,
+    Html,
     Statusbar,
 }
 
@@ -137,6 +156,31 @@ pub enum Commands {
         /// Maximum number of entries to show
         #[arg(short = 'n', long, default_value = "20")]
         limit: usize,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// 🔀 List git commits captured during tracked sessions
+    Commits {
+        /// Only show commits for this project
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Show commits since this datetime (ISO8601 or relative)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only show commits by this author
+        #[arg(long)]
+        author: Option<String>,
+
+        /// Maximum number of commits to show
+        #[arg(short = 'n', long, default_value = "50")]
+        limit: usize,
+
+        #[command(flatten)]
+        filter: FilterArgs,
     },
 
     /// 🏆 Manage projects
@@ -166,9 +210,22 @@ pub enum Commands {
         month: bool,
 
         /// Show stats since this date
-        #[arg(long)]
+        #[arg(long, visible_alias = "from")]
         since: Option<String>,
 
+        /// Show stats up to and including this date
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Named period: today, yesterday, this-week, last-week, this-month,
+        /// last-month, this-year, last-year, ytd, YYYY-MM or YYYY
+        #[arg(long, conflicts_with_all = ["today", "week", "month", "since", "until"])]
+        period: Option<String>,
+
+        /// Compare with the previous period or the same period last year
+        #[arg(long, value_name = "previous|same-last-year")]
+        compare: Option<String>,
+
         /// Group stats by project
         #[arg(long)]
         by_project: bool,
@@ -176,6 +233,148 @@ pub enum Commands {
         /// Group stats by task
         #[arg(long)]
         by_task: bool,
+
+        /// Group by one or two of project, task, day, week, month, weekday,
+        /// hour, tag (e.g. `project,week`)
+        #[arg(long, value_name = "KEYS", conflicts_with_all = ["by_project", "by_task"])]
+        group_by: Option<String>,
+
+        /// Add commits, changed lines and commits per hour to every group
+        #[arg(long, conflicts_with = "compare")]
+        commits: bool,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// 🗓️ Weekly timesheet: project/task × weekday grid
+    Timesheet {
+        /// ISO week (2026-W41), a date in the week, or this-week/last-week
+        #[arg(long)]
+        week: Option<String>,
+
+        /// One row per project instead of per task
+        #[arg(long)]
+        by_project: bool,
+
+        /// Round every cell to the nearest N minutes
+        #[arg(long, value_name = "MINUTES")]
+        round: Option<u32>,
+
+        /// Show decimal hours (7.25) instead of hours and minutes (7:15)
+        #[arg(long)]
+        decimal: bool,
+
+        /// Write the timesheet to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// 🗣️ Yesterday / today / blockers summary for the daily standup
+    Standup {
+        /// Template file with {date}, {previous_day}, {total}, {yesterday},
+        /// {today}, {blockers} and {running} placeholders
+        #[arg(long, value_name = "FILE")]
+        template: Option<String>,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// 🕘 Timeline of a day's entries and untracked gaps
+    Timeline {
+        /// Day to show (YYYY-MM-DD, today, yesterday, -3d); default today
+        date: Option<String>,
+
+        /// Show the whole week (Monday to Sunday) containing the date
+        #[arg(long)]
+        week: bool,
+
+        /// Show this many days up to and including the date
+        #[arg(long, value_name = "N", conflicts_with = "week")]
+        days: Option<u32>,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// 🏖️ Vacation, sick days, public holidays and comp time
+    Absence {
+        #[command(subcommand)]
+        command: AbsenceCommands,
+    },
+
+    /// ⚖️ Expected vs tracked hours and the running flextime balance
+    Balance {
+        /// First day to count (default: schedule.start_date, else January 1st)
+        #[arg(long, visible_alias = "since")]
+        from: Option<String>,
+
+        /// Last day to count (default: today)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Rows to list: day, week or month
+        #[arg(long, default_value = "week")]
+        by: String,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// 🟩 Yearly activity heatmap with streaks
+    Heatmap {
+        /// Show a calendar year (default: this year) instead of the last 53 weeks
+        #[arg(long, value_name = "YEAR")]
+        year: Option<Option<i32>>,
+
+        /// Only count time on this project
+        #[arg(short, long)]
+        project: Option<String>,
+
+        /// Plain characters instead of coloured squares
+        #[arg(long)]
+        ascii: bool,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// 🔎 Work patterns: peak hours, session lengths, context switches, focus
+    Insights {
+        /// Look at the current week and the N-1 weeks before it
+        #[arg(long, default_value_t = 12)]
+        weeks: u32,
+
+        /// A named period instead (last-month, ytd, 2026-09, ...)
+        #[arg(long, conflicts_with = "weeks")]
+        period: Option<String>,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// 📄 Shareable reports
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+
+    /// 📈 Prometheus metrics (node_exporter textfile collector)
+    Metrics {
+        /// Write atomically to this file instead of stdout
+        #[arg(long, value_name = "PATH")]
+        textfile: Option<String>,
+
+        /// Rewrite the file every N seconds until interrupted
+        #[arg(long, value_name = "SECONDS", requires = "textfile")]
+        interval: Option<u64>,
+
+        #[command(flatten)]
+        filter: FilterArgs,
     },
 
     /// 🖥️ Output for DWM/i3 statusbar
@@ -191,6 +390,10 @@ pub enum Commands {
         /// Icon prefix
         #[arg(long)]
         icon: Option<String>,
+
+        /// Prefix the output with the active profile name
+        #[arg(long)]
+        show_profile: bool,
     },
 
     /// 🎨 Launch interactive TUI dashboard
@@ -222,7 +425,11 @@ pub enum Commands {
     },
 
     /// 📤 Import data from Zeit or other trackers
+    #[command(args_conflicts_with_subcommands = true)]
     Import {
+        #[command(subcommand)]
+        source: Option<ImportSource>,
+
         /// Import from Zeit database
         #[arg(long)]
         zeit: Option<String>,
@@ -230,11 +437,28 @@ pub enum Commands {
         /// Import from JSON file
         #[arg(long)]
         json: Option<String>,
+
+        /// Restore a backup written by `export --full` into an empty database
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["zeit", "json"])]
+        full: Option<String>,
+
+        /// Show what would be imported without changing anything
+        #[arg(long, conflicts_with = "full")]
+        dry_run: bool,
+
+        /// When an entry exists with other notes: skip, overwrite or keep-both
+        #[arg(
+            long,
+            value_name = "STRATEGY",
+            default_value = "skip",
+            conflicts_with = "full"
+        )]
+        on_conflict: String,
     },
 
     /// 📥 Export data to various formats
     Export {
-        /// Export format (json, csv)
+        /// Export format (json, csv, md, zip, ics)
         #[arg(short = 'F', long = "export-format", default_value = "json")]
         export_format: String,
 
@@ -245,6 +469,61 @@ pub enum Commands {
         /// Export entries since this date
         #[arg(long)]
         since: Option<String>,
+
+        /// Only export these projects (repeatable)
+        #[arg(short, long = "project", value_name = "NAME")]
+        projects: Vec<String>,
+
+        /// Encrypt the file with GPG for this recipient
+        #[arg(long, value_name = "RECIPIENT")]
+        encrypt: Option<String>,
+
+        /// Wrap in an AES-encrypted zip; the password is read from MTRACK_EXPORT_PASSWORD
+        #[arg(long)]
+        password: bool,
+
+        /// Leave out notes and commits
+        #[arg(long)]
+        sanitize: bool,
+
+        /// Add commit count and changed lines columns to CSV exports
+        #[arg(long)]
+        commits: bool,
+
+        /// Write a lossless backup of the whole profile instead
+        #[arg(long, conflicts_with_all = [
+            "export_format", "since", "projects", "encrypt", "password", "sanitize", "commits",
+            "expr", "name",
+        ])]
+        full: bool,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// 🩺 Check the database for inconsistencies
+    Doctor {
+        /// Repair fixable issues inside a single transaction
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// 🔄 Sync with other devices through a shared folder
+    Sync {
+        /// Shared directory (Syncthing, NFS, ...) holding the change logs
+        dir: String,
+    },
+
+    /// 👤 Manage profiles (separate databases and settings)
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+
+    /// 🔐 Manage database encryption at rest
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
     },
 
     /// 🎨 Manage UI themes
@@ -267,6 +546,198 @@ pub enum Commands {
         /// Date for the event (YYYY-MM-DD)
         #[arg(long)]
         date: Option<String>,
+
+        /// Import events from an iCalendar (.ics) file
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["add", "export"])]
+        import: Option<String>,
+
+        /// Export events to an iCalendar (.ics) file
+        #[arg(long, value_name = "FILE", conflicts_with = "add")]
+        export: Option<String>,
+
+        /// Expand recurring events up to this date (YYYY-MM-DD); default a year ahead
+        #[arg(long, value_name = "DATE", requires = "import")]
+        until: Option<String>,
+    },
+}
+
+/// Entry filter options shared by every reading command.
+#[derive(Args, Debug, Clone, Default)]
+pub struct FilterArgs {
+    /// Filter expression, e.g. 'project ~ "acme" and duration > 30m'
+    #[arg(long = "where", value_name = "EXPR")]
+    pub expr: Option<String>,
+
+    /// Apply a saved filter from the [filters] config table
+    #[arg(long = "filter", value_name = "NAME")]
+    pub name: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AbsenceCommands {
+    /// Record a day off, or every working day of a range
+    Add {
+        /// vacation, sick, holiday or comp-time
+        kind: String,
+
+        /// Day off (YYYY-MM-DD, today, yesterday, -3d); default today
+        date: Option<String>,
+
+        /// Last day of a range; only working days are recorded
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only half the day off
+        #[arg(long)]
+        half: bool,
+
+        /// Note shown in the list
+        #[arg(long)]
+        note: Option<String>,
+    },
+
+    /// List recorded absences
+    List {
+        /// First day to list
+        #[arg(long, visible_alias = "since")]
+        from: Option<String>,
+
+        /// Last day to list
+        #[arg(long)]
+        until: Option<String>,
+    },
+
+    /// Remove an absence by its id
+    Remove {
+        /// Id shown by `absence list`
+        id: i64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ImportSource {
+    /// Turn finished calendar meetings (.ics) into entries using the [meetings] rules
+    Ics {
+        /// Calendar file to read
+        file: String,
+
+        /// Show what would be imported without changing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Only meetings starting on or after this date (YYYY-MM-DD, -7d, ...)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Only meetings ending by this date, inclusive (default: now)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Meetings overlapping tracked time: skip or trim (default: meetings.overlap)
+        #[arg(long, value_name = "POLICY")]
+        overlap: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ReportCommands {
+    /// Single offline HTML file with SVG charts and a sortable entries table
+    Html {
+        /// First day of the report (default: start of this month)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Last day of the report, inclusive
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Named period instead: last-month, 2026-09, ytd, ...
+        #[arg(long, conflicts_with_all = ["from", "until"])]
+        period: Option<String>,
+
+        /// Write the report to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// Commits and changed lines per project/task and per tracked hour
+    Code {
+        /// First day of the report (default: start of this month)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Last day of the report, inclusive
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Named period instead: last-month, 2026-09, ytd, ...
+        #[arg(long, conflicts_with_all = ["from", "until"])]
+        period: Option<String>,
+
+        /// Only list the finished sessions that captured no commits
+        #[arg(long)]
+        zero_commits: bool,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DbCommands {
+    /// Encrypt the database at rest (passphrase unless --recipient is given)
+    Encrypt {
+        /// Encrypt to this GPG key instead of a passphrase
+        #[arg(short, long)]
+        recipient: Option<String>,
+    },
+
+    /// Store the database as plain SQLite again
+    Decrypt,
+
+    /// Re-encrypt with a new passphrase or GPG recipient
+    Rekey {
+        /// Encrypt to this GPG key instead of a passphrase
+        #[arg(short, long)]
+        recipient: Option<String>,
+    },
+
+    /// Show how the database is stored
+    Status,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileCommands {
+    /// List profiles and their databases
+    List,
+
+    /// Create a new profile
+    Create {
+        /// Profile name (letters, digits, '-' and '_')
+        name: String,
+
+        /// Database path (defaults to a separate file in the data directory)
+        #[arg(long)]
+        db: Option<String>,
+    },
+
+    /// Make a profile the default for future commands
+    Switch {
+        /// Profile name
+        name: String,
+    },
+
+    /// Remove a profile's settings and state files
+    Remove {
+        /// Profile name
+        name: String,
+
+        /// Also delete the profile's database if it is in the default location
+        #[arg(long)]
+        purge: bool,
     },
 }
 
@@ -409,7 +880,17 @@ pub enum ConfigCommands {
 
 impl Cli {
     pub fn parse_args() -> Self {
-        Self::parse()
+        let mut cli = Self::parse();
+
+        if !matches!(cli.command, Commands::Statusbar { .. }) {
+            cli.format = OutputFormat::from_str(&cli.format_arg, true).unwrap_or_else(|e| {
+                Self::command()
+                    .error(clap::error::ErrorKind::InvalidValue, e)
+                    .exit()
+            });
+        }
+
+        cli
     }
 }
 
@@ -0,0 +1,464 @@
+//! Commit-correlated reporting for MatteriaTrack
+//!
+//! Relates tracked time to the git commits made in each entry's window: how
+//! many commits and changed lines a project or task produced, how many
+//! commits land per tracked hour, and which sessions ended without any commit
+//! at all.
+
+use crate::config::Config;
+use crate::database::Database;
+use crate::error::Result;
+use crate::integrations::git::{GitCommit, GitIntegration};
+use crate::models::{EntryWithDetails, TaskId};
+use crate::stats::{csv_field, format_seconds};
+use crate::theme::MateriaTheme;
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+/// Commits and changed lines over a set of entries.
+///
+/// A commit captured by several overlapping entries is only counted once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct CodeActivity {
+    pub commits: usize,
+    pub insertions: i64,
+    pub deletions: i64,
+    pub tracked_seconds: i64,
+    pub commits_per_hour: f64,
+}
+
+impl CodeActivity {
+    pub fn from_entries<'a>(entries: impl IntoIterator<Item = &'a EntryWithDetails>) -> Self {
+        let mut seen = HashSet::new();
+        let mut activity = Self::default();
+        for e in entries {
+            activity.tracked_seconds += e.entry.duration().num_seconds();
+            for c in &e.commits {
+                let key = if c.hash.is_empty() {
+                    (c.repo_path.as_deref(), c.summary.as_str())
+                } else {
+                    (c.repo_path.as_deref(), c.hash.as_str())
+                };
+                if seen.insert(key) {
+                    activity.commits += 1;
+                    activity.insertions += c.insertions.unwrap_or(0);
+                    activity.deletions += c.deletions.unwrap_or(0);
+                }
+            }
+        }
+        activity.commits_per_hour = per_hour(activity.commits, activity.tracked_seconds);
+        activity
+    }
+
+    /// `+120/-30` style line counts.
+    pub fn lines_formatted(&self) -> String {
+        format!("+{}/-{}", self.insertions, self.deletions)
+    }
+}
+
+/// Look up the commits of finished entries that captured none when they were
+/// finished (`tracking.auto_import_git` is off by default), in the configured
+/// repository or else the one set on the entry's task.
+pub fn fill_missing_commits(
+    entries: &mut [EntryWithDetails],
+    db: &Database,
+    config: &Config,
+) -> Result<()> {
+    let configured = config.git_repo_path()?;
+    let git = GitIntegration::new();
+    let mut task_repos: HashMap<TaskId, Option<PathBuf>> = HashMap::new();
+
+    for e in entries.iter_mut().filter(|e| e.commits.is_empty()) {
+        let Some(end) = e.entry.end else { continue };
+        let repo = match configured {
+            Some(ref path) => Some(path.clone()),
+            None => match task_repos.get(&e.entry.task_id) {
+                Some(repo) => repo.clone(),
+                None => {
+                    let repo = db
+                        .get_task(e.entry.task_id)?
+                        .and_then(|t| t.git_repo)
+                        .filter(|r| !r.is_empty())
+                        .map(PathBuf::from);
+                    task_repos.insert(e.entry.task_id, repo.clone());
+                    repo
+                }
+            },
+        };
+
+        if let Some(repo) = repo {
+            // A moved or deleted repository just leaves the entry without commits.
+            e.commits = git
+                .get_commits_in_range(&repo, e.entry.start, end)
+                .map(|commits| commits.iter().map(GitCommit::to_entry_commit).collect())
+                .unwrap_or_default();
+        }
+    }
+    Ok(())
+}
+
+fn per_hour(commits: usize, seconds: i64) -> f64 {
+    if seconds <= 0 {
+        0.0
+    } else {
+        commits as f64 / (seconds as f64 / 3600.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CodeReport {
+    pub period: String,
+    pub entry_count: usize,
+    pub total: CodeActivity,
+    pub projects: Vec<ProjectCode>,
+    /// Finished entries that captured no commits, longest first
+    pub zero_commit_sessions: Vec<Session>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectCode {
+    pub project: String,
+    #[serde(flatten)]
+    pub activity: CodeActivity,
+    pub tasks: Vec<TaskCode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskCode {
+    pub task: String,
+    #[serde(flatten)]
+    pub activity: CodeActivity,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Session {
+    pub entry_id: i64,
+    pub project: String,
+    pub task: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub seconds: i64,
+    pub notes: Option<String>,
+}
+
+impl CodeReport {
+    pub fn build(entries: &[EntryWithDetails], label: &str) -> Self {
+        let mut by_project: BTreeMap<&str, BTreeMap<&str, Vec<&EntryWithDetails>>> =
+            BTreeMap::new();
+        for e in entries {
+            by_project
+                .entry(&e.project_name)
+                .or_default()
+                .entry(&e.task_name)
+                .or_default()
+                .push(e);
+        }
+
+        let mut projects: Vec<ProjectCode> = by_project
+            .into_iter()
+            .map(|(project, tasks)| {
+                let activity = CodeActivity::from_entries(tasks.values().flatten().copied());
+                let mut tasks: Vec<TaskCode> = tasks
+                    .into_iter()
+                    .map(|(task, members)| TaskCode {
+                        task: task.to_string(),
+                        activity: CodeActivity::from_entries(members),
+                    })
+                    .collect();
+                tasks.sort_by(|a, b| rank(&a.activity, &b.activity));
+                ProjectCode {
+                    project: project.to_string(),
+                    activity,
+                    tasks,
+                }
+            })
+            .collect();
+        projects.sort_by(|a, b| rank(&a.activity, &b.activity));
+
+        let mut zero_commit_sessions: Vec<Session> = entries
+            .iter()
+            .filter(|e| e.commits.is_empty())
+            .filter_map(|e| {
+                let end = e.entry.end?;
+                Some(Session {
+                    entry_id: e.entry.id,
+                    project: e.project_name.clone(),
+                    task: e.task_name.clone(),
+                    start: e.entry.start,
+                    end,
+                    seconds: e.entry.duration().num_seconds(),
+                    notes: e.entry.notes.clone(),
+                })
+            })
+            .collect();
+        zero_commit_sessions.sort_by(|a, b| b.seconds.cmp(&a.seconds).then(a.start.cmp(&b.start)));
+
+        Self {
+            period: label.to_string(),
+            entry_count: entries.len(),
+            total: CodeActivity::from_entries(entries),
+            projects,
+            zero_commit_sessions,
+        }
+    }
+
+    pub fn format_pretty(&self, theme: MateriaTheme, zero_commits_only: bool) -> String {
+        let (r, g, b) = theme.primary_color();
+        let mut output = String::new();
+
+        output.push_str(&format!(
+            "\n{} {} {}\n",
+            theme.icon(),
+            format!("Code activity: {}", self.period)
+                .truecolor(r, g, b)
+                .bold(),
+            theme.materia_icon()
+        ));
+        output.push_str(&"━".repeat(60));
+        output.push('\n');
+
+        if self.entry_count == 0 {
+            output.push_str("\n  No entries in this period.\n\n");
+            return output;
+        }
+
+        if !zero_commits_only {
+            output.push_str(&format!(
+                "\n⏱️  Tracked: {}   🔀 Commits: {}   📝 Lines: {}   ⚡ {:.2} commits/h\n",
+                format_seconds(self.total.tracked_seconds)
+                    .truecolor(r, g, b)
+                    .bold(),
+                self.total.commits.to_string().bold(),
+                self.total.lines_formatted(),
+                self.total.commits_per_hour
+            ));
+
+            output.push_str(&format!("\n{}\n", "By project:".bold()));
+            output.push_str(&format!(
+                "  {:<28} {:>9} {:>8} {:>16} {:>9}\n",
+                "", "tracked", "commits", "lines", "per hour"
+            ));
+            for p in &self.projects {
+                output.push_str(&activity_row(&p.project, &p.activity, 2, true));
+                for t in &p.tasks {
+                    output.push_str(&activity_row(&t.task, &t.activity, 4, false));
+                }
+            }
+        }
+
+        output.push_str(&format!(
+            "\n{}\n",
+            format!(
+                "🚫 Sessions with zero commits ({}):",
+                self.zero_commit_sessions.len()
+            )
+            .bold()
+        ));
+        if self.zero_commit_sessions.is_empty() {
+            output.push_str("  Every session produced at least one commit.\n");
+        }
+        for s in &self.zero_commit_sessions {
+            output.push_str(&format!(
+                "  {}  {:>8}  {} / {}{}\n",
+                s.start
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M"),
+                format_seconds(s.seconds),
+                s.project.bold(),
+                s.task,
+                s.notes
+                    .as_deref()
+                    .map(|n| format!("  {}", n.dimmed()))
+                    .unwrap_or_default()
+            ));
+        }
+
+        output.push('\n');
+        output.push_str(&"━".repeat(60));
+        output.push('\n');
+        output
+    }
+
+    /// One row per project/task with its code activity.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "project,task,seconds,hours,commits,insertions,deletions,commits_per_hour\n",
+        );
+        for p in &self.projects {
+            for t in &p.tasks {
+                let a = &t.activity;
+                csv.push_str(&format!(
+                    "{},{},{},{:.2},{},{},{},{:.2}\n",
+                    csv_field(&p.project),
+                    csv_field(&t.task),
+                    a.tracked_seconds,
+                    a.tracked_seconds as f64 / 3600.0,
+                    a.commits,
+                    a.insertions,
+                    a.deletions,
+                    a.commits_per_hour
+                ));
+            }
+        }
+        csv
+    }
+
+    /// The zero-commit sessions as CSV.
+    pub fn zero_commit_csv(&self) -> String {
+        let mut csv = String::from("entry_id,project,task,start,end,seconds,notes\n");
+        for s in &self.zero_commit_sessions {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                s.entry_id,
+                csv_field(&s.project),
+                csv_field(&s.task),
+                s.start.to_rfc3339(),
+                s.end.to_rfc3339(),
+                s.seconds,
+                csv_field(s.notes.as_deref().unwrap_or(""))
+            ));
+        }
+        csv
+    }
+}
+
+/// Most commits first, then most tracked time.
+fn rank(a: &CodeActivity, b: &CodeActivity) -> std::cmp::Ordering {
+    b.commits
+        .cmp(&a.commits)
+        .then(b.tracked_seconds.cmp(&a.tracked_seconds))
+}
+
+fn activity_row(label: &str, a: &CodeActivity, indent: usize, bold: bool) -> String {
+    let width = 30 - indent;
+    let label: String = label.chars().take(width - 1).collect();
+    let label = format!("{:<width$}", label, width = width);
+    format!(
+        "{}{} {:>9} {:>8} {:>16} {:>9.2}\n",
+        " ".repeat(indent),
+        if bold { label.bold() } else { label.normal() },
+        format_seconds(a.tracked_seconds),
+        a.commits,
+        a.lines_formatted(),
+        a.commits_per_hour
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Entry, EntryCommit};
+    use chrono::{Duration, TimeZone};
+
+    fn entry(
+        project: &str,
+        task: &str,
+        hours: i64,
+        commits: &[(&str, i64, i64)],
+    ) -> EntryWithDetails {
+        let start = Utc.with_ymd_and_hms(2026, 10, 5, 9, 0, 0).unwrap();
+        let mut e = Entry::new(1, 1);
+        e.start = start;
+        e.end = Some(start + Duration::hours(hours));
+        EntryWithDetails {
+            entry: e,
+            project_name: project.into(),
+            task_name: task.into(),
+            project_color: None,
+            commits: commits
+                .iter()
+                .map(|&(hash, ins, del)| EntryCommit {
+                    insertions: Some(ins),
+                    deletions: Some(del),
+                    ..EntryCommit::new(hash, "change")
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_activity_counts_each_commit_once() {
+        let entries = [
+            entry("Acme", "api", 2, &[("aaa", 10, 2), ("bbb", 5, 5)]),
+            // Overlapping window captured `bbb` again
+            entry("Acme", "api", 2, &[("bbb", 5, 5)]),
+        ];
+        let a = CodeActivity::from_entries(&entries);
+        assert_eq!(a.commits, 2);
+        assert_eq!((a.insertions, a.deletions), (15, 7));
+        assert_eq!(a.tracked_seconds, 4 * 3600);
+        assert!((a.commits_per_hour - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_report_groups_by_project_and_task() {
+        let entries = [
+            entry("Acme", "api", 1, &[("a1", 1, 0), ("a2", 1, 0)]),
+            entry("Acme", "docs", 2, &[]),
+            entry("Beta", "ui", 1, &[("b1", 3, 1)]),
+        ];
+        let report = CodeReport::build(&entries, "test");
+
+        assert_eq!(report.total.commits, 3);
+        assert_eq!(report.projects[0].project, "Acme");
+        assert_eq!(report.projects[0].activity.commits, 2);
+        assert_eq!(report.projects[0].tasks[0].task, "api");
+        assert!((report.projects[0].tasks[0].activity.commits_per_hour - 2.0).abs() < 1e-9);
+        assert_eq!(report.projects[0].tasks[1].activity.commits, 0);
+        assert_eq!(report.to_csv().lines().count(), 4);
+    }
+
+    #[test]
+    fn test_fill_missing_commits_from_task_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let start = Utc.with_ymd_and_hms(2026, 10, 5, 9, 0, 0).unwrap();
+        let when = git2::Time::new((start + Duration::minutes(30)).timestamp(), 0);
+        let sig = git2::Signature::new("Dev", "dev@example.com", &when).unwrap();
+        std::fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("a.txt")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Add a", &tree, &[])
+            .unwrap();
+
+        let db = Database::open_in_memory().unwrap();
+        let mut project = crate::models::Project::new("Acme");
+        db.create_project(&mut project).unwrap();
+        let mut task = crate::models::Task::new(project.id, "api")
+            .with_git_repo(dir.path().display().to_string());
+        db.create_task(&mut task).unwrap();
+
+        let mut entries = [entry("Acme", "api", 1, &[]), entry("Acme", "api", 1, &[])];
+        entries[0].entry.task_id = task.id;
+        entries[1].entry.task_id = task.id;
+        entries[1].entry.start = start + Duration::hours(2);
+        entries[1].entry.end = Some(start + Duration::hours(3));
+        fill_missing_commits(&mut entries, &db, &Config::default()).unwrap();
+
+        assert_eq!(entries[0].commits.len(), 1);
+        assert_eq!(entries[0].commits[0].summary, "Add a");
+        assert_eq!(entries[0].commits[0].insertions, Some(2));
+        assert!(entries[1].commits.is_empty());
+    }
+
+    #[test]
+    fn test_zero_commit_sessions_skip_running_entries() {
+        let mut running = entry("Acme", "api", 1, &[]);
+        running.entry.end = None;
+        let entries = [
+            entry("Acme", "docs", 1, &[]),
+            entry("Acme", "docs", 3, &[]),
+            entry("Acme", "api", 1, &[("a1", 1, 0)]),
+            running,
+        ];
+        let report = CodeReport::build(&entries, "test");
+
+        assert_eq!(report.zero_commit_sessions.len(), 2);
+        assert_eq!(report.zero_commit_sessions[0].seconds, 3 * 3600);
+        assert_eq!(report.zero_commit_csv().lines().count(), 3);
+    }
+}
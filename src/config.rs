@@ -1,8 +1,11 @@
 //! Configuration management for MatteriaTrack
 
-use crate::error::{ConfigError, Result};
+use crate::error::{ConfigError, Error, Result};
+use crate::profile;
 use crate::theme::MateriaTheme;
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -17,6 +20,18 @@ pub struct Config {
     pub notifications: NotificationConfig,
     pub integrations: IntegrationConfig,
     pub security: SecurityConfig,
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+    #[serde(default)]
+    pub standup: StandupConfig,
+    #[serde(default)]
+    pub meetings: MeetingsConfig,
+    /// Saved `--where` expressions, usable as `--filter <name>` or `@name`
+    #[serde(default)]
+    pub filters: BTreeMap<String, String>,
+    /// Profile this configuration was loaded for; not stored in the file.
+    #[serde(skip)]
+    pub profile: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +72,280 @@ pub struct SecurityConfig {
     pub enable_audit_log: Option<bool>,
     #[serde(default)]
     pub audit_log_path: Option<String>,
+    #[serde(default)]
+    pub database_encryption: DatabaseEncryption,
+}
+
+/// Working hours and contracted time, used to highlight untracked time and
+/// to compute the flextime balance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    /// Start of the working day, `HH:MM`
+    pub work_start: String,
+    /// End of the working day, `HH:MM`
+    pub work_end: String,
+    /// Working weekdays, `mon` … `sun`
+    pub work_days: Vec<String>,
+    /// Individual days off, `YYYY-MM-DD`
+    pub days_off: Vec<NaiveDate>,
+    /// Expected hours per weekday, e.g. `fri = 6` (default: the length of
+    /// the working day on every work day)
+    pub hours: BTreeMap<String, f64>,
+    /// First day the balance counts, usually the contract start
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<NaiveDate>,
+    /// Public holidays, one `YYYY-MM-DD name` per line (empty = none)
+    pub holidays_file: String,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            work_start: "09:00".to_string(),
+            work_end: "17:00".to_string(),
+            work_days: ["mon", "tue", "wed", "thu", "fri"]
+                .iter()
+                .map(|d| d.to_string())
+                .collect(),
+            days_off: Vec::new(),
+            hours: BTreeMap::new(),
+            start_date: None,
+            holidays_file: String::new(),
+        }
+    }
+}
+
+impl ScheduleConfig {
+    /// Start and end of the working day.
+    pub fn working_hours(&self) -> Result<(NaiveTime, NaiveTime)> {
+        let parse = |field: &str, value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| {
+                ConfigError::ParseError(format!(
+                    "schedule.{} must be HH:MM, got '{}'",
+                    field, value
+                ))
+            })
+        };
+        let start = parse("work_start", &self.work_start)?;
+        let end = parse("work_end", &self.work_end)?;
+        if end <= start {
+            return Err(ConfigError::ParseError(
+                "schedule.work_end must be after schedule.work_start".into(),
+            )
+            .into());
+        }
+        Ok((start, end))
+    }
+
+    pub fn is_work_day(&self, day: Weekday) -> bool {
+        self.work_days
+            .iter()
+            .any(|d| d.parse::<Weekday>().map(|w| w == day).unwrap_or(false))
+    }
+
+    /// Whether contracted hours were configured (`hours` or `start_date`);
+    /// without them there is no balance to keep.
+    pub fn has_contract(&self) -> bool {
+        !self.hours.is_empty() || self.start_date.is_some()
+    }
+
+    /// Whether `date` is a working weekday and not a configured day off.
+    pub fn is_working_date(&self, date: NaiveDate) -> bool {
+        self.is_work_day(date.weekday()) && !self.days_off.contains(&date)
+    }
+
+    /// Contracted seconds for `date`, ignoring public holidays.
+    pub fn expected_seconds(&self, date: NaiveDate) -> i64 {
+        if self.start_date.is_some_and(|start| date < start) || self.days_off.contains(&date) {
+            return 0;
+        }
+        let hours = self.hours.iter().find_map(|(day, hours)| {
+            (day.parse::<Weekday>().ok() == Some(date.weekday())).then_some(*hours)
+        });
+        match hours {
+            Some(hours) => (hours * 3600.0).round() as i64,
+            None if self.is_work_day(date.weekday()) => self
+                .working_hours()
+                .map(|(start, end)| (end - start).num_seconds())
+                .unwrap_or(0),
+            None => 0,
+        }
+    }
+
+    /// Public holidays from `holidays_file`, keyed by date.
+    pub fn holidays(&self) -> Result<BTreeMap<NaiveDate, String>> {
+        if self.holidays_file.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        let path = expand_path(&self.holidays_file)?;
+        let text = fs::read_to_string(&path).map_err(|e| {
+            ConfigError::ParseError(format!("schedule.holidays_file {}: {}", path.display(), e))
+        })?;
+        parse_holidays(&text)
+    }
+
+    fn validate(&self) -> Result<()> {
+        self.working_hours()?;
+        for (day, hours) in &self.hours {
+            if day.parse::<Weekday>().is_err() {
+                return Err(ConfigError::ParseError(format!(
+                    "schedule.hours: unknown weekday '{}'",
+                    day
+                ))
+                .into());
+            }
+            if !(0.0..=24.0).contains(hours) {
+                return Err(ConfigError::ParseError(format!(
+                    "schedule.hours.{} must be between 0 and 24, got {}",
+                    day, hours
+                ))
+                .into());
+            }
+        }
+        if let Some(day) = self
+            .work_days
+            .iter()
+            .find(|d| d.parse::<Weekday>().is_err())
+        {
+            return Err(ConfigError::ParseError(format!(
+                "schedule.work_days: unknown weekday '{}'",
+                day
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Parse a holidays file: `YYYY-MM-DD` and an optional name per line,
+/// separated by whitespace or a comma. Blank lines and `#` comments are skipped.
+pub fn parse_holidays(text: &str) -> Result<BTreeMap<NaiveDate, String>> {
+    let mut holidays = BTreeMap::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (date, name) = line
+            .split_once(|c: char| c == ',' || c.is_whitespace())
+            .unwrap_or((line, ""));
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+            ConfigError::ParseError(format!(
+                "holidays line {}: expected YYYY-MM-DD, got '{}'",
+                n + 1,
+                date
+            ))
+        })?;
+        let name = name.trim_start_matches([',', ' ', '\t']).trim();
+        holidays.insert(
+            date,
+            if name.is_empty() { "Holiday" } else { name }.to_string(),
+        );
+    }
+    Ok(holidays)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StandupConfig {
+    /// Template file for `mtrack standup` (empty = built-in layout)
+    pub template: String,
+}
+
+/// Rules for turning calendar meetings into entries with `mtrack import ics`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeetingsConfig {
+    /// Project for meetings no rule matches (empty = skip them)
+    pub project: String,
+    /// Task for meetings no rule matches, and for rules without a task
+    pub task: String,
+    /// Shorter meetings are skipped, e.g. `10m` (empty = keep all)
+    pub min_duration: String,
+    /// Skip all-day events instead of filling the working day
+    pub skip_all_day: bool,
+    /// Skip invitations one of the `me` addresses declined
+    pub skip_declined: bool,
+    /// Your calendar addresses, to find your reply among the attendees
+    pub me: Vec<String>,
+    /// What to do when a meeting overlaps tracked time
+    pub overlap: OverlapPolicy,
+    /// Checked in order; the first whose pattern matches the summary wins
+    pub rules: Vec<MeetingRule>,
+}
+
+impl Default for MeetingsConfig {
+    fn default() -> Self {
+        Self {
+            project: String::new(),
+            task: "Meetings".to_string(),
+            min_duration: String::new(),
+            skip_all_day: true,
+            skip_declined: true,
+            me: Vec::new(),
+            overlap: OverlapPolicy::Skip,
+            rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MeetingRule {
+    /// Regular expression matched against the event summary
+    #[serde(rename = "match")]
+    pub pattern: String,
+    /// Project, may use the pattern's groups (`$client`)
+    #[serde(default)]
+    pub project: String,
+    /// Task, may use the pattern's groups (empty = `meetings.task`)
+    #[serde(default)]
+    pub task: String,
+    /// Added to the entry's notes as `#tag`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Leave matching meetings out, e.g. lunch or focus time
+    #[serde(default)]
+    pub skip: bool,
+}
+
+/// What happens to imported time that overlaps time already tracked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlapPolicy {
+    /// Leave the overlapping meeting out
+    #[default]
+    Skip,
+    /// Keep only the parts of the meeting that are not tracked yet
+    Trim,
+}
+
+impl std::str::FromStr for OverlapPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "skip" => Ok(Self::Skip),
+            "trim" => Ok(Self::Trim),
+            _ => Err(Error::InvalidInput(format!(
+                "Unknown overlap policy '{}' (expected skip or trim)",
+                s
+            ))),
+        }
+    }
+}
+
+/// How the tracking database is stored at rest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseEncryption {
+    /// Plain SQLite file
+    #[default]
+    None,
+    /// GPG symmetric encryption with a passphrase
+    Passphrase,
+    /// GPG public-key encryption to `security.encryption_key`
+    Gpg,
 }
 
 impl Default for Config {
@@ -85,53 +374,140 @@ impl Default for Config {
                 encryption_key: String::new(),
                 enable_audit_log: Some(false),
                 audit_log_path: None,
+                database_encryption: DatabaseEncryption::None,
             },
+            schedule: ScheduleConfig::default(),
+            standup: StandupConfig::default(),
+            meetings: MeetingsConfig::default(),
+            filters: BTreeMap::new(),
+            profile: profile::DEFAULT_PROFILE.to_string(),
         }
     }
 }
 
 impl Config {
+    /// Load the configuration for the active profile.
     pub fn load() -> Result<Self> {
+        Self::load_for_profile(&profile::active())
+    }
+
+    /// Load `config.toml` with the overrides of profile `name` applied on top.
+    pub fn load_for_profile(name: &str) -> Result<Self> {
         let config_path = Self::config_path()?;
 
         if !config_path.exists() {
-            let config = Self::default();
-            config.save()?;
-            return Ok(config);
+            Self::default().write_to(&config_path)?;
         }
 
         let content = fs::read_to_string(&config_path)
             .map_err(|e| ConfigError::ParseError(format!("Failed to read config: {}", e)))?;
 
-        let config: Config =
-            toml::from_str(&content).map_err(|e| ConfigError::ParseError(e.to_string()))?;
-
-        config.validate()?;
-        Ok(config)
+        Self::from_toml(&content, name)
     }
 
+    /// Load a base configuration from `path`, still applying the active profile's overrides.
     pub fn load_from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
         let content = fs::read_to_string(path.as_ref())
             .map_err(|e| ConfigError::NotFound(format!("{}: {}", path.as_ref().display(), e)))?;
 
-        let config: Config =
-            toml::from_str(&content).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        Self::from_toml(&content, &profile::active())
+    }
+
+    fn from_toml(base: &str, profile_name: &str) -> Result<Self> {
+        let mut value: toml::Value =
+            toml::from_str(base).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+
+        if profile_name != profile::DEFAULT_PROFILE {
+            let overlay_path = Self::profile_config_path_for(profile_name)?;
+            let overlay: toml::Value = match fs::read_to_string(&overlay_path) {
+                Ok(content) => toml::from_str(&content).map_err(|e| {
+                    ConfigError::ParseError(format!("{}: {}", overlay_path.display(), e))
+                })?,
+                Err(_) => toml::Value::Table(Default::default()),
+            };
+
+            let has_db_path = overlay
+                .get("database")
+                .and_then(|d| d.get("path"))
+                .is_some();
+            merge_toml(&mut value, overlay);
+
+            if !has_db_path {
+                let db_path = profile::default_db_path(profile_name)?;
+                if let Some(database) = value.get_mut("database").and_then(|d| d.as_table_mut()) {
+                    database.insert(
+                        "path".into(),
+                        toml::Value::String(db_path.to_string_lossy().to_string()),
+                    );
+                }
+            }
+        }
+
+        let mut config: Config = value
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::ParseError(e.to_string()))?;
+        config.profile = profile_name.to_string();
 
         config.validate()?;
         Ok(config)
     }
 
+    /// Save the configuration. For a named profile only the settings that
+    /// differ from the base `config.toml` are written to its override file.
     pub fn save(&self) -> Result<()> {
-        let config_path = Self::config_path()?;
+        if self.profile.is_empty() || self.profile == profile::DEFAULT_PROFILE {
+            return self.write_to(&Self::config_path()?);
+        }
+
+        let base_path = Self::config_path()?;
+        let base: toml::Value = match fs::read_to_string(&base_path) {
+            Ok(content) => {
+                toml::from_str(&content).map_err(|e| ConfigError::ParseError(e.to_string()))?
+            }
+            Err(_) => toml::Value::try_from(Self::default())
+                .map_err(|e| ConfigError::ParseError(e.to_string()))?,
+        };
+        let current =
+            toml::Value::try_from(self).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+
+        let mut overrides =
+            diff_toml(&base, &current).unwrap_or_else(|| toml::Value::Table(Default::default()));
+
+        // The profile's own database location is implied, so don't pin it.
+        let implied_db = profile::default_db_path(&self.profile)?;
+        if self.db_path()? == implied_db {
+            if let Some(table) = overrides.as_table_mut() {
+                if let Some(database) = table.get_mut("database").and_then(|d| d.as_table_mut()) {
+                    database.remove("path");
+                    if database.is_empty() {
+                        table.remove("database");
+                    }
+                }
+            }
+        }
+
+        let path = Self::profile_config_path_for(&self.profile)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string_pretty(&overrides)
+            .map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        fs::write(&path, content)?;
+        Ok(())
+    }
 
-        if let Some(parent) = config_path.parent() {
+    fn write_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
 
         let content =
             toml::to_string_pretty(self).map_err(|e| ConfigError::ParseError(e.to_string()))?;
 
-        fs::write(&config_path, content)?;
+        // Write-then-rename so a concurrent first run never reads a half-written file.
+        let tmp = path.with_extension(format!("toml.{}.tmp", std::process::id()));
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, path)?;
         Ok(())
     }
 
@@ -145,6 +521,25 @@ impl Config {
         Ok(Self::config_dir()?.join(CONFIG_FILE))
     }
 
+    /// The file holding the active profile's settings: `config.toml` for the
+    /// default profile, its override file otherwise.
+    pub fn profile_config_path() -> Result<PathBuf> {
+        Self::profile_config_path_for(&profile::active())
+    }
+
+    fn profile_config_path_for(name: &str) -> Result<PathBuf> {
+        if name == profile::DEFAULT_PROFILE {
+            Self::config_path()
+        } else {
+            Ok(profile::profile_dir(name)?.join(CONFIG_FILE))
+        }
+    }
+
+    /// Directory for per-profile state such as achievements, frecency and calendar events.
+    pub fn profile_dir() -> Result<PathBuf> {
+        profile::profile_dir(&profile::active())
+    }
+
     pub fn data_dir() -> Result<PathBuf> {
         dirs::data_local_dir()
             .map(|p| p.join(APP_DIR))
@@ -161,7 +556,16 @@ impl Config {
             .into());
         }
 
-        Ok(())
+        if self.security.database_encryption == DatabaseEncryption::Gpg
+            && self.security.encryption_key.is_empty()
+        {
+            return Err(ConfigError::MissingField(
+                "encryption_key required for GPG database encryption".into(),
+            )
+            .into());
+        }
+
+        self.schedule.validate()
     }
 
     pub fn theme(&self) -> MateriaTheme {
@@ -179,6 +583,13 @@ impl Config {
         expand_path(&self.tracking.git_repo_path).map(Some)
     }
 
+    pub fn standup_template_path(&self) -> Result<Option<PathBuf>> {
+        if self.standup.template.is_empty() {
+            return Ok(None);
+        }
+        expand_path(&self.standup.template).map(Some)
+    }
+
     pub fn obsidian_path(&self) -> Result<Option<PathBuf>> {
         if self.integrations.obsidian_path.is_empty() {
             return Ok(None);
@@ -197,6 +608,44 @@ pub fn expand_path(path: &str) -> Result<PathBuf> {
     }
 }
 
+/// Recursively overlay `overlay` onto `base`; tables merge, everything else replaces.
+fn merge_toml(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// The parts of `current` that differ from `base`, or `None` if they match.
+fn diff_toml(base: &toml::Value, current: &toml::Value) -> Option<toml::Value> {
+    match (base, current) {
+        (toml::Value::Table(base), toml::Value::Table(current)) => {
+            let mut diff = toml::value::Table::new();
+            for (key, value) in current {
+                let changed = match base.get(key) {
+                    Some(existing) => diff_toml(existing, value),
+                    None => Some(value.clone()),
+                };
+                if let Some(changed) = changed {
+                    diff.insert(key.clone(), changed);
+                }
+            }
+            (!diff.is_empty()).then_some(toml::Value::Table(diff))
+        }
+        (base, current) if base == current => None,
+        (_, current) => Some(current.clone()),
+    }
+}
+
 fn default_db_path() -> String {
     dirs::data_local_dir()
         .map(|p| {
@@ -237,13 +686,7 @@ impl EncryptionManager {
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
             .spawn()
-            .and_then(|mut child| {
-                use std::io::Write;
-                if let Some(ref mut stdin) = child.stdin {
-                    stdin.write_all(data)?;
-                }
-                child.wait_with_output()
-            })
+            .and_then(|child| crate::security::encryption::pipe_through(child, data))
             .map_err(|e| ConfigError::EncryptionError(e.to_string()))?;
 
         if !output.status.success() {
@@ -268,13 +711,7 @@ impl EncryptionManager {
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
             .spawn()
-            .and_then(|mut child| {
-                use std::io::Write;
-                if let Some(ref mut stdin) = child.stdin {
-                    stdin.write_all(data)?;
-                }
-                child.wait_with_output()
-            })
+            .and_then(|child| crate::security::encryption::pipe_through(child, data))
             .map_err(|e| ConfigError::EncryptionError(e.to_string()))?;
 
         if !output.status.success() {
@@ -305,9 +742,99 @@ mod tests {
         assert_eq!(path, PathBuf::from("/absolute/path"));
     }
 
+    #[test]
+    fn test_merge_and_diff_toml() {
+        let base: toml::Value =
+            toml::from_str("[ui]\ntheme = \"fire\"\n[tracking]\nauto_import_git = false\n")
+                .unwrap();
+        let overlay: toml::Value = toml::from_str("[ui]\ntheme = \"ice\"\n").unwrap();
+
+        let mut merged = base.clone();
+        merge_toml(&mut merged, overlay.clone());
+        assert_eq!(merged["ui"]["theme"].as_str(), Some("ice"));
+        assert_eq!(merged["tracking"]["auto_import_git"].as_bool(), Some(false));
+
+        assert_eq!(diff_toml(&base, &merged), Some(overlay));
+        assert_eq!(diff_toml(&base, &base), None);
+    }
+
     #[test]
     fn test_theme_parsing() {
         let config = Config::default();
         assert_eq!(config.theme(), MateriaTheme::Fire);
     }
+
+    #[test]
+    fn test_schedule_defaults_and_validation() {
+        let schedule = ScheduleConfig::default();
+        let (start, end) = schedule.working_hours().unwrap();
+        assert_eq!(start, NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+        assert_eq!(end, NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+        assert!(schedule.is_work_day(Weekday::Fri));
+        assert!(!schedule.is_work_day(Weekday::Sat));
+        let christmas_eve = NaiveDate::from_ymd_opt(2026, 12, 24).unwrap();
+        assert!(schedule.is_working_date(christmas_eve));
+        let with_day_off = ScheduleConfig {
+            days_off: vec![christmas_eve],
+            ..Default::default()
+        };
+        assert!(!with_day_off.is_working_date(christmas_eve));
+        assert!(!schedule.has_contract());
+        assert!(ScheduleConfig {
+            start_date: Some(christmas_eve),
+            ..Default::default()
+        }
+        .has_contract());
+
+        // Older config files without a [schedule] table still load
+        let config: Config = toml::from_str(
+            &toml::to_string(&Config::default())
+                .unwrap()
+                .replace("[schedule]", "[unused]"),
+        )
+        .unwrap();
+        assert_eq!(config.schedule, ScheduleConfig::default());
+
+        let late = ScheduleConfig {
+            work_start: "18:00".into(),
+            ..Default::default()
+        };
+        assert!(late.validate().is_err());
+        let typo = ScheduleConfig {
+            work_days: vec!["mon".into(), "fry".into()],
+            ..Default::default()
+        };
+        assert!(typo.validate().is_err());
+    }
+
+    #[test]
+    fn test_expected_hours_and_holidays() {
+        let mut schedule = ScheduleConfig::default();
+        schedule.hours.insert("fri".into(), 6.0);
+        schedule.start_date = NaiveDate::from_ymd_opt(2026, 10, 1);
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+
+        assert_eq!(schedule.expected_seconds(day(1)), 8 * 3600); // Thursday
+        assert_eq!(schedule.expected_seconds(day(2)), 6 * 3600); // Friday
+        assert_eq!(schedule.expected_seconds(day(3)), 0); // Saturday
+        assert_eq!(
+            schedule.expected_seconds(NaiveDate::from_ymd_opt(2026, 9, 30).unwrap()),
+            0
+        );
+        assert!(schedule.validate().is_ok());
+        schedule.hours.insert("fri".into(), 25.0);
+        assert!(schedule.validate().is_err());
+
+        let holidays = parse_holidays(
+            "# Public holidays\n2026-10-03 German Unity Day\n\n2026-12-25,Christmas\n2026-12-26\n",
+        )
+        .unwrap();
+        assert_eq!(holidays.len(), 3);
+        assert_eq!(holidays[&day(3)], "German Unity Day");
+        assert_eq!(
+            holidays[&NaiveDate::from_ymd_opt(2026, 12, 26).unwrap()],
+            "Holiday"
+        );
+        assert!(parse_holidays("25.12.2026 Christmas").is_err());
+    }
 }
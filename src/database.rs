@@ -1,14 +1,26 @@
 //! Database management for MatteriaTrack
 
+use crate::absence::{Absence, AbsenceKind};
 use crate::error::{DatabaseError, Result};
-use crate::models::{Entry, EntryId, EntryWithDetails, Project, ProjectId, Task, TaskId};
-use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection, OptionalExtension, Row};
+use crate::filter::Filter;
+use crate::models::{
+    CommitWithDetails, Entry, EntryCommit, EntryId, EntryWithDetails, Project, ProjectId, Task,
+    TaskId,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row, TransactionBehavior};
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
-const SCHEMA_VERSION: i32 = 1;
+const SCHEMA_VERSION: i32 = 4;
 
+/// How long a connection waits for another process's lock before failing
+/// with "database is locked". Status bars poll while commands write.
+pub const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Cheap to clone: clones share the same connection.
+#[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
 }
@@ -24,8 +36,12 @@ impl Database {
         let conn =
             Connection::open(path).map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
 
-        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")
+        conn.busy_timeout(BUSY_TIMEOUT)
             .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
+        conn.execute_batch(
+            "PRAGMA journal_mode=WAL; PRAGMA synchronous=NORMAL; PRAGMA foreign_keys=ON;",
+        )
+        .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
 
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
@@ -51,87 +67,29 @@ impl Database {
     }
 
     fn migrate(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER PRIMARY KEY)",
-            [],
-        )
-        .map_err(|e| DatabaseError::MigrationFailed(e.to_string()))?;
+        let mut conn = self.conn.lock().unwrap();
 
-        let current_version: i32 = conn
-            .query_row("SELECT version FROM schema_version LIMIT 1", [], |row| {
-                row.get(0)
-            })
-            .unwrap_or(0);
-
-        if current_version < 1 {
-            conn.execute_batch(
-                r#"
-                CREATE TABLE IF NOT EXISTS projects (
-                    id INTEGER PRIMARY KEY AUTOINCREMENT,
-                    name TEXT NOT NULL UNIQUE,
-                    color TEXT,
-                    created_at TEXT NOT NULL,
-                    updated_at TEXT NOT NULL
-                );
-
-                CREATE TABLE IF NOT EXISTS tasks (
-                    id INTEGER PRIMARY KEY AUTOINCREMENT,
-                    project_id INTEGER NOT NULL,
-                    name TEXT NOT NULL,
-                    git_repo TEXT,
-                    created_at TEXT NOT NULL,
-                    updated_at TEXT NOT NULL,
-                    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
-                    UNIQUE(project_id, name)
-                );
-
-                CREATE TABLE IF NOT EXISTS entries (
-                    id INTEGER PRIMARY KEY AUTOINCREMENT,
-                    project_id INTEGER NOT NULL,
-                    task_id INTEGER NOT NULL,
-                    start_time TEXT NOT NULL,
-                    end_time TEXT,
-                    notes TEXT,
-                    git_commits TEXT,
-                    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
-                    FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE
-                );
-
-                CREATE TABLE IF NOT EXISTS active_tracking (
-                    id INTEGER PRIMARY KEY CHECK (id = 1),
-                    entry_id INTEGER NOT NULL,
-                    FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE
-                );
-
-                CREATE INDEX IF NOT EXISTS idx_entries_project ON entries(project_id);
-                CREATE INDEX IF NOT EXISTS idx_entries_task ON entries(task_id);
-                CREATE INDEX IF NOT EXISTS idx_entries_start ON entries(start_time);
-                CREATE INDEX IF NOT EXISTS idx_tasks_project ON tasks(project_id);
-                "#,
-            )
+        // Take the write lock up front so concurrent first runs apply each
+        // migration exactly once.
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| DatabaseError::MigrationFailed(e.to_string()))?;
+        migrate_schema(&tx)?;
+        tx.commit()
             .map_err(|e| DatabaseError::MigrationFailed(e.to_string()))?;
-        }
-
-        conn.execute(
-            "INSERT OR REPLACE INTO schema_version (version) VALUES (?1)",
-            params![SCHEMA_VERSION],
-        )
-        .map_err(|e| DatabaseError::MigrationFailed(e.to_string()))?;
-
         Ok(())
     }
 
     pub fn create_project(&self, project: &mut Project) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO projects (name, color, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+            "INSERT INTO projects (name, color, created_at, updated_at, uuid) VALUES (?1, ?2, ?3, ?4, ?5)",
             params![
                 project.name,
                 project.color,
                 project.created_at.to_rfc3339(),
-                project.updated_at.to_rfc3339()
+                project.updated_at.to_rfc3339(),
+                new_uuid()
             ],
         )?;
         project.id = conn.last_insert_rowid();
@@ -202,13 +160,14 @@ impl Database {
     pub fn create_task(&self, task: &mut Task) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO tasks (project_id, name, git_repo, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO tasks (project_id, name, git_repo, created_at, updated_at, uuid) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             params![
                 task.project_id,
                 task.name,
                 task.git_repo,
                 task.created_at.to_rfc3339(),
-                task.updated_at.to_rfc3339()
+                task.updated_at.to_rfc3339(),
+                new_uuid()
             ],
         )?;
         task.id = conn.last_insert_rowid();
@@ -287,14 +246,15 @@ impl Database {
         let git_commits = serde_json::to_string(&entry.git_commits)?;
 
         conn.execute(
-            "INSERT INTO entries (project_id, task_id, start_time, end_time, notes, git_commits) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT INTO entries (project_id, task_id, start_time, end_time, notes, git_commits, uuid) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
             params![
                 entry.project_id,
                 entry.task_id,
                 entry.start.to_rfc3339(),
                 entry.end.map(|e| e.to_rfc3339()),
                 entry.notes,
-                git_commits
+                git_commits,
+                new_uuid()
             ],
         )?;
         entry.id = conn.last_insert_rowid();
@@ -317,8 +277,10 @@ impl Database {
         let git_commits = serde_json::to_string(&entry.git_commits)?;
 
         let updated = conn.execute(
-            "UPDATE entries SET start_time = ?1, end_time = ?2, notes = ?3, git_commits = ?4 WHERE id = ?5",
+            "UPDATE entries SET project_id = ?1, task_id = ?2, start_time = ?3, end_time = ?4, notes = ?5, git_commits = ?6 WHERE id = ?7",
             params![
+                entry.project_id,
+                entry.task_id,
                 entry.start.to_rfc3339(),
                 entry.end.map(|e| e.to_rfc3339()),
                 entry.notes,
@@ -344,59 +306,171 @@ impl Database {
     }
 
     pub fn list_entries(&self, since: Option<DateTime<Utc>>) -> Result<Vec<Entry>> {
+        self.list_entries_where(since, None)
+    }
+
+    /// Entries started at or after `since` that match `filter`, newest first.
+    pub fn list_entries_where(
+        &self,
+        since: Option<DateTime<Utc>>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<Entry>> {
         let conn = self.conn.lock().unwrap();
 
-        if let Some(since) = since {
-            let mut stmt = conn.prepare(
-                "SELECT id, project_id, task_id, start_time, end_time, notes, git_commits 
-                 FROM entries WHERE start_time >= ?1 ORDER BY start_time DESC",
-            )?;
-            let entries: Vec<Entry> = stmt
-                .query_map(params![since.to_rfc3339()], row_to_entry)?
-                .collect::<std::result::Result<Vec<_>, _>>()?;
-            Ok(entries)
-        } else {
-            let mut stmt = conn.prepare(
-                "SELECT id, project_id, task_id, start_time, end_time, notes, git_commits 
-                 FROM entries ORDER BY start_time DESC",
-            )?;
-            let entries: Vec<Entry> = stmt
-                .query_map([], row_to_entry)?
-                .collect::<std::result::Result<Vec<_>, _>>()?;
-            Ok(entries)
-        }
+        let (condition, values) = entry_conditions(since, None, filter);
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT e.id, e.project_id, e.task_id, e.start_time, e.end_time, e.notes, e.git_commits
+            FROM entries e
+            LEFT JOIN projects p ON e.project_id = p.id
+            LEFT JOIN tasks t ON e.task_id = t.id
+            WHERE {}
+            ORDER BY e.start_time DESC
+            "#,
+            condition
+        ))?;
+        let entries: Vec<Entry> = stmt
+            .query_map(rusqlite::params_from_iter(values.iter()), row_to_entry)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(entries)
     }
 
     pub fn list_entries_with_details(
         &self,
         since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<EntryWithDetails>> {
+        self.list_entries_with_details_where(since, None)
+    }
+
+    /// Like [`Self::list_entries_with_details`], restricted to entries matching `filter`.
+    pub fn list_entries_with_details_where(
+        &self,
+        since: Option<DateTime<Utc>>,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<EntryWithDetails>> {
+        self.list_entries_with_details_between(since, None, filter)
+    }
+
+    /// Entries started in `[since, until)` that match `filter`, newest first.
+    pub fn list_entries_with_details_between(
+        &self,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        filter: Option<&Filter>,
     ) -> Result<Vec<EntryWithDetails>> {
         let conn = self.conn.lock().unwrap();
 
-        let query = r#"
+        let (condition, values) = entry_conditions(since, until, filter);
+        let mut stmt = conn.prepare(&format!(
+            r#"
             SELECT e.id, e.project_id, e.task_id, e.start_time, e.end_time, e.notes, e.git_commits,
                    p.name as project_name, p.color as project_color, t.name as task_name
             FROM entries e
             JOIN projects p ON e.project_id = p.id
             JOIN tasks t ON e.task_id = t.id
-        "#;
-
-        if let Some(since) = since {
-            let mut stmt = conn.prepare(&format!(
-                "{} WHERE e.start_time >= ?1 ORDER BY e.start_time DESC",
-                query
-            ))?;
-            let entries: Vec<EntryWithDetails> = stmt
-                .query_map(params![since.to_rfc3339()], row_to_entry_with_details)?
-                .collect::<std::result::Result<Vec<_>, _>>()?;
-            Ok(entries)
-        } else {
-            let mut stmt = conn.prepare(&format!("{} ORDER BY e.start_time DESC", query))?;
-            let entries: Vec<EntryWithDetails> = stmt
-                .query_map([], row_to_entry_with_details)?
-                .collect::<std::result::Result<Vec<_>, _>>()?;
-            Ok(entries)
+            WHERE {}
+            ORDER BY e.start_time DESC
+            "#,
+            condition
+        ))?;
+        let mut entries: Vec<EntryWithDetails> = stmt
+            .query_map(
+                rusqlite::params_from_iter(values.iter()),
+                row_to_entry_with_details,
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut commits = load_commits_since(&conn, since)?;
+        for e in &mut entries {
+            if let Some(c) = commits.remove(&e.entry.id) {
+                e.commits = c;
+            }
         }
+
+        Ok(entries)
+    }
+
+    /// Replace the structured commits stored for an entry.
+    pub fn replace_entry_commits(&self, entry_id: EntryId, commits: &[EntryCommit]) -> Result<()> {
+        self.transaction(|tx| {
+            tx.execute(
+                "DELETE FROM entry_commits WHERE entry_id = ?1",
+                params![entry_id],
+            )?;
+            for commit in commits {
+                insert_commit(tx, entry_id, commit)?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn get_entry_commits(&self, entry_id: EntryId) -> Result<Vec<EntryCommit>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM entry_commits c WHERE c.entry_id = ?1 ORDER BY c.committed_at, c.id",
+            COMMIT_COLUMNS
+        ))?;
+        let commits = stmt
+            .query_map(params![entry_id], row_to_commit)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(commits)
+    }
+
+    /// List captured commits, newest first. Commits without a timestamp (migrated
+    /// from the legacy string format) are dated by their entry's start.
+    pub fn list_commits(&self, filter: &CommitFilter) -> Result<Vec<CommitWithDetails>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut query = format!(
+            r#"
+            SELECT {}, p.name, t.name, e.start_time
+            FROM entry_commits c
+            JOIN entries e ON c.entry_id = e.id
+            JOIN projects p ON e.project_id = p.id
+            JOIN tasks t ON e.task_id = t.id
+            WHERE 1 = 1
+            "#,
+            COMMIT_COLUMNS
+        );
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+
+        if let Some(ref project) = filter.project {
+            values.push(project.clone().into());
+            query.push_str(&format!(" AND p.name = ?{} COLLATE NOCASE", values.len()));
+        }
+        if let Some(ref repo) = filter.repo_path {
+            values.push(repo.clone().into());
+            query.push_str(&format!(" AND c.repo_path = ?{}", values.len()));
+        }
+        if let Some(ref author) = filter.author {
+            values.push(author.clone().into());
+            query.push_str(&format!(" AND c.author = ?{} COLLATE NOCASE", values.len()));
+        }
+        if let Some(since) = filter.since {
+            values.push(since.to_rfc3339().into());
+            query.push_str(&format!(
+                " AND COALESCE(c.committed_at, e.start_time) >= ?{}",
+                values.len()
+            ));
+        }
+        if let Some(ref entries) = filter.entries {
+            let condition = entries.to_sql(&mut values);
+            query.push_str(&format!(" AND {}", condition));
+        }
+        query.push_str(" ORDER BY COALESCE(c.committed_at, e.start_time) DESC, c.id DESC");
+
+        let mut stmt = conn.prepare(&query)?;
+        let commits = stmt
+            .query_map(rusqlite::params_from_iter(values.iter()), |row| {
+                Ok(CommitWithDetails {
+                    commit: row_to_commit(row)?,
+                    project_name: row.get(11)?,
+                    task_name: row.get(12)?,
+                    entry_start: parse_datetime(&row.get::<_, String>(13)?),
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(commits)
     }
 
     pub fn set_active_tracking(&self, entry_id: EntryId) -> Result<()> {
@@ -434,6 +508,66 @@ impl Database {
         Ok(())
     }
 
+    /// Run `f` against the raw connection, for checks that need SQL the typed API does not cover.
+    pub fn with_conn<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let conn = self.conn.lock().unwrap();
+        f(&conn)
+    }
+
+    /// Run `f` inside a single transaction, committing only if it succeeds.
+    /// Inside [`Self::immediate`] this becomes a savepoint of the outer transaction.
+    pub fn transaction<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let mut conn = self.conn.lock().unwrap();
+        if !conn.is_autocommit() {
+            let sp = conn.savepoint()?;
+            let value = f(&sp)?;
+            sp.commit()?;
+            return Ok(value);
+        }
+
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let value = f(&tx)?;
+        tx.commit()?;
+        Ok(value)
+    }
+
+    /// Like [`Self::transaction`], but always rolls back: shows what `f` would
+    /// change without changing anything.
+    pub fn rehearse<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let mut conn = self.conn.lock().unwrap();
+        if !conn.is_autocommit() {
+            let mut sp = conn.savepoint()?;
+            let value = f(&sp)?;
+            sp.rollback()?;
+            return Ok(value);
+        }
+
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let value = f(&tx)?;
+        tx.rollback()?;
+        Ok(value)
+    }
+
+    /// Run `f` inside a `BEGIN IMMEDIATE` transaction, rolling back if it fails.
+    ///
+    /// The write lock is taken before `f` runs, so a read-check-write sequence
+    /// made through the regular methods (e.g. "is a timer active? if not,
+    /// start one") cannot interleave with another process doing the same.
+    pub fn immediate<T>(&self, f: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        self.conn.lock().unwrap().execute_batch("BEGIN IMMEDIATE")?;
+
+        match f(self) {
+            Ok(value) => {
+                self.conn.lock().unwrap().execute_batch("COMMIT")?;
+                Ok(value)
+            }
+            Err(e) => {
+                let _ = self.conn.lock().unwrap().execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
     pub fn get_or_create_project(&self, name: &str) -> Result<Project> {
         if let Some(project) = self.get_project_by_name(name)? {
             return Ok(project);
@@ -453,6 +587,692 @@ impl Database {
         self.create_task(&mut task)?;
         Ok(task)
     }
+
+    /// Insert many entries in one transaction, creating projects and tasks by
+    /// name as needed. See [`Self::bulk_insert_with`].
+    pub fn bulk_insert(&self, rows: &[ImportRow]) -> Result<BulkInsertReport> {
+        self.bulk_insert_with(rows, &ImportOptions::default())
+    }
+
+    /// Insert many entries in one transaction, creating projects and tasks by
+    /// name as needed.
+    ///
+    /// Rows are fingerprinted by (project, task, start, end). A row matching any
+    /// existing entry with the same notes is a duplicate and skipped; if only
+    /// entries with different notes match, it is a conflict with the oldest of
+    /// them, resolved by [`ImportOptions::on_conflict`].
+    ///
+    /// Rows that fail validation are skipped and listed in
+    /// [`BulkInsertReport::errors`]; a database error rolls back the whole
+    /// batch, so an import either lands completely or not at all. With
+    /// [`ImportOptions::dry_run`] the batch is always rolled back.
+    pub fn bulk_insert_with(
+        &self,
+        rows: &[ImportRow],
+        options: &ImportOptions,
+    ) -> Result<BulkInsertReport> {
+        let insert = |conn: &Connection| {
+            let mut find_project = conn.prepare("SELECT id FROM projects WHERE name = ?1")?;
+            let mut insert_project = conn.prepare(
+                "INSERT INTO projects (name, color, created_at, updated_at, uuid) VALUES (?1, NULL, ?2, ?2, ?3)",
+            )?;
+            let mut find_task =
+                conn.prepare("SELECT id FROM tasks WHERE project_id = ?1 AND name = ?2")?;
+            let mut insert_task = conn.prepare(
+                "INSERT INTO tasks (project_id, name, git_repo, created_at, updated_at, uuid) VALUES (?1, ?2, NULL, ?3, ?3, ?4)",
+            )?;
+            let mut find_entry = conn.prepare(
+                "SELECT id, notes FROM entries WHERE project_id = ?1 AND task_id = ?2 \
+                 AND start_time = ?3 AND end_time IS ?4 ORDER BY id",
+            )?;
+            let mut insert_entry = conn.prepare(
+                "INSERT INTO entries (project_id, task_id, start_time, end_time, notes, git_commits, uuid) VALUES (?1, ?2, ?3, ?4, ?5, '[]', ?6)",
+            )?;
+            let mut update_notes = conn.prepare("UPDATE entries SET notes = ?2 WHERE id = ?1")?;
+
+            let mut report = BulkInsertReport::default();
+            let mut projects: HashMap<String, ProjectId> = HashMap::new();
+            let mut tasks: HashMap<(ProjectId, String), TaskId> = HashMap::new();
+            let now = Utc::now().to_rfc3339();
+
+            for (index, row) in rows.iter().enumerate() {
+                if let Err(message) = row.validate() {
+                    report.errors.push(RowError { index, message });
+                    continue;
+                }
+                let project_name = row.project.trim();
+                let task_name = row.task.trim();
+
+                let project_id = match projects.get(project_name) {
+                    Some(id) => *id,
+                    None => {
+                        let existing: Option<ProjectId> = find_project
+                            .query_row(params![project_name], |r| r.get(0))
+                            .optional()?;
+                        let id = match existing {
+                            Some(id) => id,
+                            None => {
+                                insert_project.execute(params![project_name, now, new_uuid()])?;
+                                report.projects_created += 1;
+                                conn.last_insert_rowid()
+                            }
+                        };
+                        projects.insert(project_name.to_string(), id);
+                        id
+                    }
+                };
+
+                let task_key = (project_id, task_name.to_string());
+                let task_id = match tasks.get(&task_key) {
+                    Some(id) => *id,
+                    None => {
+                        let existing: Option<TaskId> = find_task
+                            .query_row(params![project_id, task_name], |r| r.get(0))
+                            .optional()?;
+                        let id = match existing {
+                            Some(id) => id,
+                            None => {
+                                insert_task.execute(params![
+                                    project_id,
+                                    task_name,
+                                    now,
+                                    new_uuid()
+                                ])?;
+                                report.tasks_created += 1;
+                                conn.last_insert_rowid()
+                            }
+                        };
+                        tasks.insert(task_key, id);
+                        id
+                    }
+                };
+
+                let start = row.start.to_rfc3339();
+                let end = row.end.map(|e| e.to_rfc3339());
+                let matches = find_entry
+                    .query_map(params![project_id, task_id, start, end], |r| {
+                        Ok((r.get::<_, EntryId>(0)?, r.get::<_, Option<String>>(1)?))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                // A copy kept by an earlier keep-both import counts as a duplicate.
+                let existing = match matches.iter().position(|(_, notes)| *notes == row.notes) {
+                    Some(i) => Some(matches[i].clone()),
+                    None => matches.into_iter().next(),
+                };
+
+                let action = match existing {
+                    None => RowAction::Added,
+                    Some((_, ref notes)) if *notes == row.notes => RowAction::Duplicate,
+                    Some(_) => match options.on_conflict {
+                        OnConflict::Skip => RowAction::ConflictSkipped,
+                        OnConflict::Overwrite => RowAction::Overwritten,
+                        OnConflict::KeepBoth => RowAction::KeptBoth,
+                    },
+                };
+                match action {
+                    RowAction::Added | RowAction::KeptBoth => {
+                        insert_entry.execute(params![
+                            project_id,
+                            task_id,
+                            start,
+                            end,
+                            row.notes,
+                            new_uuid()
+                        ])?;
+                        report.entries_created += 1;
+                    }
+                    RowAction::Overwritten => {
+                        let id = existing.as_ref().map(|(id, _)| *id);
+                        update_notes.execute(params![id, row.notes])?;
+                        report.entries_updated += 1;
+                    }
+                    RowAction::Duplicate => report.duplicates += 1,
+                    RowAction::ConflictSkipped => report.conflicts_skipped += 1,
+                }
+                report.changes.push(RowChange {
+                    index,
+                    action,
+                    previous_notes: existing.and_then(|(_, notes)| notes),
+                });
+            }
+
+            Ok(report)
+        };
+
+        if options.dry_run {
+            self.rehearse(insert)
+        } else {
+            self.transaction(insert)
+        }
+    }
+
+    /// Record an absence, replacing any absence already recorded for its date.
+    pub fn save_absence(&self, absence: &mut Absence) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        absence.id = conn.query_row(
+            "INSERT INTO absences (date, kind, half_day, note, created_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5) \
+             ON CONFLICT(date) DO UPDATE SET kind = excluded.kind, \
+             half_day = excluded.half_day, note = excluded.note \
+             RETURNING id",
+            params![
+                absence.date.to_string(),
+                absence.kind.name(),
+                absence.half_day,
+                absence.note,
+                absence.created_at.to_rfc3339()
+            ],
+            |row| row.get(0),
+        )?;
+        Ok(())
+    }
+
+    /// Absences between `from` and `until` (inclusive), oldest first.
+    pub fn list_absences(
+        &self,
+        from: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> Result<Vec<Absence>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, date, kind, half_day, note, created_at FROM absences \
+             WHERE (?1 IS NULL OR date >= ?1) AND (?2 IS NULL OR date <= ?2) \
+             ORDER BY date",
+        )?;
+        let absences = stmt
+            .query_map(
+                params![from.map(|d| d.to_string()), until.map(|d| d.to_string())],
+                row_to_absence,
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(absences)
+    }
+
+    pub fn delete_absence(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM absences WHERE id = ?1", params![id])?;
+
+        if deleted == 0 {
+            return Err(DatabaseError::NotFound(format!("Absence {}", id)).into());
+        }
+        Ok(())
+    }
+}
+
+/// One entry for [`Database::bulk_insert`], naming its project and task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportRow {
+    pub project: String,
+    pub task: String,
+    pub start: DateTime<Utc>,
+    pub end: Option<DateTime<Utc>>,
+    pub notes: Option<String>,
+}
+
+impl ImportRow {
+    fn validate(&self) -> std::result::Result<(), String> {
+        if self.project.trim().is_empty() {
+            return Err("missing project name".into());
+        }
+        if self.task.trim().is_empty() {
+            return Err("missing task name".into());
+        }
+        if let Some(end) = self.end {
+            if end < self.start {
+                return Err(format!(
+                    "ends before it starts ({} < {})",
+                    end.to_rfc3339(),
+                    self.start.to_rfc3339()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A row skipped by [`Database::bulk_insert`]; `index` is its position in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    pub index: usize,
+    pub message: String,
+}
+
+/// What an import does when a row matches an existing entry but its notes differ.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Keep the existing entry
+    #[default]
+    Skip,
+    /// Replace the existing entry's notes
+    Overwrite,
+    /// Import the row as a second entry
+    KeepBoth,
+}
+
+impl std::str::FromStr for OnConflict {
+    type Err = crate::error::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "keep-both" => Ok(Self::KeepBoth),
+            _ => Err(crate::error::Error::InvalidInput(format!(
+                "Unknown conflict strategy '{}' (expected skip, overwrite or keep-both)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Options for [`Database::bulk_insert_with`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImportOptions {
+    pub on_conflict: OnConflict,
+    pub dry_run: bool,
+}
+
+impl ImportOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_on_conflict(mut self, on_conflict: OnConflict) -> Self {
+        self.on_conflict = on_conflict;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowAction {
+    Added,
+    /// Same fingerprint and notes as an existing entry
+    Duplicate,
+    Overwritten,
+    KeptBoth,
+    ConflictSkipped,
+}
+
+/// What happened to one valid row; `index` is its position in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowChange {
+    pub index: usize,
+    pub action: RowAction,
+    /// Notes of the matching existing entry
+    pub previous_notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BulkInsertReport {
+    pub projects_created: usize,
+    pub tasks_created: usize,
+    pub entries_created: usize,
+    pub entries_updated: usize,
+    pub duplicates: usize,
+    pub conflicts_skipped: usize,
+    pub changes: Vec<RowChange>,
+    pub errors: Vec<RowError>,
+}
+
+/// Filter for [`Database::list_commits`].
+#[derive(Debug, Clone, Default)]
+pub struct CommitFilter {
+    pub project: Option<String>,
+    pub repo_path: Option<String>,
+    pub author: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    /// Only commits captured on entries matching this filter
+    pub entries: Option<Filter>,
+}
+
+impl CommitFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    pub fn with_repo_path(mut self, repo_path: impl Into<String>) -> Self {
+        self.repo_path = Some(repo_path.into());
+        self
+    }
+
+    pub fn with_author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    pub fn with_since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn with_entry_filter(mut self, filter: Filter) -> Self {
+        self.entries = Some(filter);
+        self
+    }
+}
+
+pub(crate) const COMMIT_COLUMNS: &str =
+    "c.id, c.entry_id, c.hash, c.repo_path, c.author, c.author_email, \
+     c.committed_at, c.summary, c.files_changed, c.insertions, c.deletions";
+
+fn migrate_schema(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER PRIMARY KEY)",
+        [],
+    )
+    .map_err(|e| DatabaseError::MigrationFailed(e.to_string()))?;
+
+    let current_version: i32 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
+    if current_version < 1 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS projects (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                color TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS tasks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                project_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                git_repo TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+                UNIQUE(project_id, name)
+            );
+
+            CREATE TABLE IF NOT EXISTS entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                project_id INTEGER NOT NULL,
+                task_id INTEGER NOT NULL,
+                start_time TEXT NOT NULL,
+                end_time TEXT,
+                notes TEXT,
+                git_commits TEXT,
+                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+                FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS active_tracking (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                entry_id INTEGER NOT NULL,
+                FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_entries_project ON entries(project_id);
+            CREATE INDEX IF NOT EXISTS idx_entries_task ON entries(task_id);
+            CREATE INDEX IF NOT EXISTS idx_entries_start ON entries(start_time);
+            CREATE INDEX IF NOT EXISTS idx_tasks_project ON tasks(project_id);
+            "#,
+        )
+        .map_err(|e| DatabaseError::MigrationFailed(e.to_string()))?;
+    }
+
+    if current_version < 2 {
+        migrate_entry_commits(conn).map_err(|e| DatabaseError::MigrationFailed(e.to_string()))?;
+    }
+
+    if current_version < 3 {
+        migrate_sync_ids(conn).map_err(|e| DatabaseError::MigrationFailed(e.to_string()))?;
+    }
+
+    if current_version < 4 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS absences (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                date TEXT NOT NULL UNIQUE,
+                kind TEXT NOT NULL,
+                half_day INTEGER NOT NULL DEFAULT 0,
+                note TEXT,
+                created_at TEXT NOT NULL
+            );
+            "#,
+        )
+        .map_err(|e| DatabaseError::MigrationFailed(e.to_string()))?;
+    }
+
+    if current_version < SCHEMA_VERSION {
+        conn.execute(
+            "INSERT OR REPLACE INTO schema_version (version) VALUES (?1)",
+            params![SCHEMA_VERSION],
+        )
+        .map_err(|e| DatabaseError::MigrationFailed(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Schema v2: move the `"abc1234: message"` strings in `entries.git_commits`
+/// into the structured `entry_commits` table. The legacy column is kept so
+/// older builds can still read the database.
+fn migrate_entry_commits(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS entry_commits (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entry_id INTEGER NOT NULL,
+            hash TEXT NOT NULL,
+            repo_path TEXT,
+            author TEXT,
+            author_email TEXT,
+            committed_at TEXT,
+            summary TEXT NOT NULL,
+            files_changed INTEGER,
+            insertions INTEGER,
+            deletions INTEGER,
+            FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_entry_commits_entry ON entry_commits(entry_id);
+        CREATE INDEX IF NOT EXISTS idx_entry_commits_time ON entry_commits(committed_at);
+        CREATE INDEX IF NOT EXISTS idx_entry_commits_repo ON entry_commits(repo_path);
+        "#,
+    )?;
+
+    let legacy: Vec<(EntryId, Option<String>, Option<String>)> = {
+        let mut stmt = conn.prepare(
+            "SELECT e.id, e.git_commits, t.git_repo FROM entries e \
+             LEFT JOIN tasks t ON e.task_id = t.id \
+             WHERE e.git_commits IS NOT NULL AND e.git_commits != '[]'",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows
+    };
+
+    for (entry_id, git_commits, git_repo) in legacy {
+        let labels: Vec<String> = git_commits
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+
+        for label in labels {
+            let mut commit = EntryCommit::from_legacy(&label);
+            commit.repo_path = git_repo.clone();
+            insert_commit(conn, entry_id, &commit)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Tables whose rows carry a sync UUID.
+pub(crate) const SYNCED_TABLES: &[&str] = &["projects", "tasks", "entries"];
+
+/// Schema v3: stable UUIDs for projects, tasks and entries plus the
+/// bookkeeping tables used by `mtrack sync`.
+fn migrate_sync_ids(conn: &Connection) -> rusqlite::Result<()> {
+    for table in SYNCED_TABLES {
+        let has_uuid: bool = conn
+            .prepare(&format!(
+                "SELECT 1 FROM pragma_table_info('{}') WHERE name = 'uuid'",
+                table
+            ))?
+            .exists([])?;
+        if !has_uuid {
+            conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN uuid TEXT;", table))?;
+        }
+        conn.execute_batch(&format!(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_{0}_uuid ON {0}(uuid);",
+            table
+        ))?;
+    }
+    assign_missing_uuids(conn)?;
+
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS sync_state (
+            uuid TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            hash TEXT,
+            ts INTEGER NOT NULL,
+            device TEXT NOT NULL,
+            deleted INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS sync_cursors (
+            device TEXT PRIMARY KEY,
+            seq INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS sync_aliases (
+            alias TEXT PRIMARY KEY,
+            uuid TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS sync_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        "#,
+    )?;
+
+    Ok(())
+}
+
+/// Give every synced row without a UUID a fresh one.
+pub(crate) fn assign_missing_uuids(conn: &Connection) -> rusqlite::Result<usize> {
+    let mut assigned = 0;
+    for table in SYNCED_TABLES {
+        let ids: Vec<i64> = {
+            let mut stmt = conn.prepare(&format!("SELECT id FROM {} WHERE uuid IS NULL", table))?;
+            let rows = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            rows
+        };
+        for id in ids {
+            conn.execute(
+                &format!("UPDATE {} SET uuid = ?1 WHERE id = ?2", table),
+                params![new_uuid(), id],
+            )?;
+            assigned += 1;
+        }
+    }
+    Ok(assigned)
+}
+
+fn new_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+pub(crate) fn insert_commit(
+    conn: &Connection,
+    entry_id: EntryId,
+    commit: &EntryCommit,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO entry_commits (entry_id, hash, repo_path, author, author_email, committed_at, \
+         summary, files_changed, insertions, deletions) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            entry_id,
+            commit.hash,
+            commit.repo_path,
+            commit.author,
+            commit.author_email,
+            commit.committed_at.map(|t| t.to_rfc3339()),
+            commit.summary,
+            commit.files_changed,
+            commit.insertions,
+            commit.deletions
+        ],
+    )?;
+    Ok(())
+}
+
+/// Load commits grouped by entry, for entries starting at or after `since`.
+/// WHERE clause (and its parameters) shared by the entry listing queries.
+fn entry_conditions(
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    filter: Option<&Filter>,
+) -> (String, Vec<rusqlite::types::Value>) {
+    let mut values = Vec::new();
+    let mut conditions = vec!["1 = 1".to_string()];
+
+    if let Some(since) = since {
+        values.push(since.to_rfc3339().into());
+        conditions.push(format!("e.start_time >= ?{}", values.len()));
+    }
+    if let Some(until) = until {
+        values.push(until.to_rfc3339().into());
+        conditions.push(format!("e.start_time < ?{}", values.len()));
+    }
+    if let Some(filter) = filter {
+        conditions.push(filter.to_sql(&mut values));
+    }
+
+    (conditions.join(" AND "), values)
+}
+
+fn load_commits_since(
+    conn: &Connection,
+    since: Option<DateTime<Utc>>,
+) -> Result<HashMap<EntryId, Vec<EntryCommit>>> {
+    let mut query = format!(
+        "SELECT {} FROM entry_commits c JOIN entries e ON c.entry_id = e.id",
+        COMMIT_COLUMNS
+    );
+    if since.is_some() {
+        query.push_str(" WHERE e.start_time >= ?1");
+    }
+    query.push_str(" ORDER BY c.committed_at, c.id");
+
+    let mut stmt = conn.prepare(&query)?;
+    let commits = match since {
+        Some(since) => stmt.query_map(params![since.to_rfc3339()], row_to_commit)?,
+        None => stmt.query_map([], row_to_commit)?,
+    }
+    .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut grouped: HashMap<EntryId, Vec<EntryCommit>> = HashMap::new();
+    for commit in commits {
+        grouped.entry(commit.entry_id).or_default().push(commit);
+    }
+    Ok(grouped)
 }
 
 fn parse_datetime(s: &str) -> DateTime<Utc> {
@@ -461,7 +1281,24 @@ fn parse_datetime(s: &str) -> DateTime<Utc> {
         .unwrap_or_else(|_| Utc::now())
 }
 
-fn row_to_project(row: &Row) -> rusqlite::Result<Project> {
+pub(crate) fn row_to_absence(row: &Row) -> rusqlite::Result<Absence> {
+    let date: String = row.get(1)?;
+    let kind: String = row.get(2)?;
+    Ok(Absence {
+        id: row.get(0)?,
+        date: date.parse().map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        kind: kind.parse::<AbsenceKind>().map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        half_day: row.get(3)?,
+        note: row.get(4)?,
+        created_at: parse_datetime(&row.get::<_, String>(5)?),
+    })
+}
+
+pub(crate) fn row_to_project(row: &Row) -> rusqlite::Result<Project> {
     Ok(Project {
         id: row.get(0)?,
         name: row.get(1)?,
@@ -471,7 +1308,7 @@ fn row_to_project(row: &Row) -> rusqlite::Result<Project> {
     })
 }
 
-fn row_to_task(row: &Row) -> rusqlite::Result<Task> {
+pub(crate) fn row_to_task(row: &Row) -> rusqlite::Result<Task> {
     Ok(Task {
         id: row.get(0)?,
         project_id: row.get(1)?,
@@ -482,7 +1319,7 @@ fn row_to_task(row: &Row) -> rusqlite::Result<Task> {
     })
 }
 
-fn row_to_entry(row: &Row) -> rusqlite::Result<Entry> {
+pub(crate) fn row_to_entry(row: &Row) -> rusqlite::Result<Entry> {
     let end_time: Option<String> = row.get(4)?;
     let git_commits_str: String = row.get(6)?;
     let git_commits: Vec<String> =
@@ -506,13 +1343,201 @@ fn row_to_entry_with_details(row: &Row) -> rusqlite::Result<EntryWithDetails> {
         project_name: row.get(7)?,
         project_color: row.get(8)?,
         task_name: row.get(9)?,
+        commits: Vec::new(),
+    })
+}
+
+pub(crate) fn row_to_commit(row: &Row) -> rusqlite::Result<EntryCommit> {
+    let committed_at: Option<String> = row.get(6)?;
+    Ok(EntryCommit {
+        id: row.get(0)?,
+        entry_id: row.get(1)?,
+        hash: row.get(2)?,
+        repo_path: row.get(3)?,
+        author: row.get(4)?,
+        author_email: row.get(5)?,
+        committed_at: committed_at.map(|s| parse_datetime(&s)),
+        summary: row.get(7)?,
+        files_changed: row.get(8)?,
+        insertions: row.get(9)?,
+        deletions: row.get(10)?,
     })
 }
 
+/// Seed data for tests across the crate.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::{Database, ImportRow};
+    use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone, Utc};
+
+    /// A `YYYY-MM-DD HH:MM` wall-clock time in the local timezone.
+    pub fn local(datetime: &str) -> DateTime<Utc> {
+        let naive = NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M").unwrap();
+        Local
+            .from_local_datetime(&naive)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    /// `minutes` spent on `project` / `task` from `start`.
+    pub fn row(project: &str, task: &str, start: DateTime<Utc>, minutes: i64) -> ImportRow {
+        ImportRow {
+            project: project.into(),
+            task: task.into(),
+            start,
+            end: Some(start + Duration::minutes(minutes)),
+            notes: None,
+        }
+    }
+
+    impl ImportRow {
+        pub fn with_notes(self, notes: &str) -> Self {
+            Self {
+                notes: Some(notes.into()),
+                ..self
+            }
+        }
+    }
+
+    /// An in-memory database holding `rows`.
+    pub fn seeded(rows: &[ImportRow]) -> Database {
+        let db = Database::open_in_memory().unwrap();
+        db.bulk_insert(rows).unwrap();
+        db
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn import_row(project: &str, task: &str, hours_ago: i64) -> ImportRow {
+        use chrono::Duration;
+        let start = Utc::now() - Duration::hours(hours_ago);
+        ImportRow {
+            project: project.into(),
+            task: task.into(),
+            start,
+            end: Some(start + Duration::minutes(30)),
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_bulk_insert_reuses_names_and_reports_bad_rows() {
+        let db = Database::open_in_memory().unwrap();
+        let mut existing = Project::new("Alpha");
+        db.create_project(&mut existing).unwrap();
+
+        let mut backwards = import_row("Beta", "b", 3);
+        backwards.end = Some(backwards.start - chrono::Duration::minutes(5));
+        let rows = vec![
+            import_row("Alpha", "a", 5),
+            import_row("Beta", "b", 4),
+            backwards,
+            import_row("", "x", 2),
+            import_row("Beta", "b", 1),
+        ];
+
+        let report = db.bulk_insert(&rows).unwrap();
+        assert_eq!(report.projects_created, 1);
+        assert_eq!(report.tasks_created, 2);
+        assert_eq!(report.entries_created, 3);
+        assert_eq!(
+            report.errors.iter().map(|e| e.index).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(db.list_projects().unwrap().len(), 2);
+        assert_eq!(db.list_entries(None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_bulk_insert_skips_duplicates_and_resolves_conflicts() {
+        let db = Database::open_in_memory().unwrap();
+        let rows = vec![import_row("Alpha", "a", 3), import_row("Alpha", "a", 2)];
+        db.bulk_insert(&rows).unwrap();
+
+        let again = db.bulk_insert(&rows).unwrap();
+        assert_eq!(again.entries_created, 0);
+        assert_eq!(again.duplicates, 2);
+
+        let mut edited = rows[0].clone();
+        edited.notes = Some("edited".into());
+        let skipped = db.bulk_insert(&[edited.clone()]).unwrap();
+        assert_eq!(skipped.conflicts_skipped, 1);
+        assert_eq!(skipped.changes[0].action, RowAction::ConflictSkipped);
+
+        let overwrite = ImportOptions::new().with_on_conflict(OnConflict::Overwrite);
+        let report = db.bulk_insert_with(&[edited.clone()], &overwrite).unwrap();
+        assert_eq!(report.entries_updated, 1);
+        assert_eq!(db.list_entries(None).unwrap().len(), 2);
+        assert!(db
+            .list_entries(None)
+            .unwrap()
+            .iter()
+            .any(|e| e.notes.as_deref() == Some("edited")));
+
+        edited.notes = Some("edited again".into());
+        let keep_both = ImportOptions::new().with_on_conflict(OnConflict::KeepBoth);
+        db.bulk_insert_with(&[edited], &keep_both).unwrap();
+        assert_eq!(db.list_entries(None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_keep_both_import_is_idempotent() {
+        let db = Database::open_in_memory().unwrap();
+        let row = import_row("Alpha", "a", 3);
+        db.bulk_insert(std::slice::from_ref(&row)).unwrap();
+
+        let mut edited = row;
+        edited.notes = Some("from the file".into());
+        let keep_both = ImportOptions::new().with_on_conflict(OnConflict::KeepBoth);
+        let first = db.bulk_insert_with(&[edited.clone()], &keep_both).unwrap();
+        assert_eq!(first.changes[0].action, RowAction::KeptBoth);
+
+        let second = db.bulk_insert_with(&[edited], &keep_both).unwrap();
+        assert_eq!(second.entries_created, 0);
+        assert_eq!(second.duplicates, 1);
+        assert_eq!(db.list_entries(None).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_bulk_insert_dry_run_changes_nothing() {
+        let db = Database::open_in_memory().unwrap();
+        let row = import_row("Alpha", "a", 3);
+        let rows = vec![row.clone(), row];
+
+        let report = db
+            .bulk_insert_with(&rows, &ImportOptions::new().with_dry_run(true))
+            .unwrap();
+        assert_eq!(report.projects_created, 1);
+        assert_eq!(report.entries_created, 1);
+        assert_eq!(report.duplicates, 1);
+        assert!(db.list_projects().unwrap().is_empty());
+        assert!(db.list_entries(None).unwrap().is_empty());
+        assert!("keep-both".parse::<OnConflict>().is_ok());
+        assert!("merge".parse::<OnConflict>().is_err());
+    }
+
+    #[test]
+    fn test_bulk_insert_rolls_back_on_error() {
+        let db = Database::open_in_memory().unwrap();
+        db.conn
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "CREATE TRIGGER reject_gamma BEFORE INSERT ON projects
+                 WHEN NEW.name = 'Gamma'
+                 BEGIN SELECT RAISE(ABORT, 'rejected'); END;",
+            )
+            .unwrap();
+
+        let rows = vec![import_row("Alpha", "a", 3), import_row("Gamma", "g", 2)];
+        assert!(db.bulk_insert(&rows).is_err());
+        assert!(db.list_projects().unwrap().is_empty());
+        assert!(db.list_entries(None).unwrap().is_empty());
+    }
+
     #[test]
     fn test_database_creation() {
         let db = Database::open_in_memory().unwrap();
@@ -576,4 +1601,135 @@ mod tests {
         db.clear_active_tracking().unwrap();
         assert!(db.get_active_tracking().unwrap().is_none());
     }
+
+    #[test]
+    fn test_legacy_commit_migration() {
+        let db = Database::open_in_memory().unwrap();
+
+        let mut project = Project::new("TestProject");
+        db.create_project(&mut project).unwrap();
+        let mut task = Task::new(project.id, "TestTask").with_git_repo("/src/app");
+        db.create_task(&mut task).unwrap();
+
+        let mut entry = Entry::new(project.id, task.id);
+        entry.git_commits = vec!["abc1234: Add parser".into(), "def5678: Fix: quotes".into()];
+        db.create_entry(&mut entry).unwrap();
+
+        db.with_conn(|conn| {
+            conn.execute_batch(
+                "DROP TABLE entry_commits; DELETE FROM schema_version; \
+                 INSERT INTO schema_version (version) VALUES (1);",
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        db.migrate().unwrap();
+
+        let commits = db.get_entry_commits(entry.id).unwrap();
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].hash, "abc1234");
+        assert_eq!(commits[1].summary, "Fix: quotes");
+        assert_eq!(commits[0].repo_path.as_deref(), Some("/src/app"));
+
+        // Re-running the migration must not duplicate rows.
+        db.migrate().unwrap();
+        assert_eq!(db.get_entry_commits(entry.id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_list_commits_filters() {
+        let db = Database::open_in_memory().unwrap();
+
+        let mut alpha = Project::new("Alpha");
+        db.create_project(&mut alpha).unwrap();
+        let mut beta = Project::new("Beta");
+        db.create_project(&mut beta).unwrap();
+        let mut alpha_task = Task::new(alpha.id, "Dev");
+        db.create_task(&mut alpha_task).unwrap();
+        let mut beta_task = Task::new(beta.id, "Dev");
+        db.create_task(&mut beta_task).unwrap();
+
+        let now = Utc::now();
+        let mut old = EntryCommit::new("1111111aaaa", "Old work");
+        old.committed_at = Some(now - chrono::Duration::days(10));
+        old.author = Some("Ana".into());
+        let mut recent = EntryCommit::new("2222222bbbb", "Recent work");
+        recent.committed_at = Some(now - chrono::Duration::hours(1));
+        recent.author = Some("Ben".into());
+
+        let mut a = Entry::new(alpha.id, alpha_task.id);
+        db.create_entry(&mut a).unwrap();
+        db.replace_entry_commits(a.id, &[old, recent]).unwrap();
+
+        let mut b = Entry::new(beta.id, beta_task.id);
+        db.create_entry(&mut b).unwrap();
+        db.replace_entry_commits(b.id, &[EntryCommit::new("3333333", "Beta work")])
+            .unwrap();
+
+        assert_eq!(db.list_commits(&CommitFilter::new()).unwrap().len(), 3);
+
+        let alpha_commits = db
+            .list_commits(&CommitFilter::new().with_project("alpha"))
+            .unwrap();
+        assert_eq!(alpha_commits.len(), 2);
+        assert_eq!(alpha_commits[0].commit.summary, "Recent work");
+
+        let since = db
+            .list_commits(
+                &CommitFilter::new()
+                    .with_project("Alpha")
+                    .with_since(now - chrono::Duration::days(1)),
+            )
+            .unwrap();
+        assert_eq!(since.len(), 1);
+
+        let by_author = db
+            .list_commits(&CommitFilter::new().with_author("ana"))
+            .unwrap();
+        assert_eq!(by_author.len(), 1);
+
+        let detailed = db.list_entries_with_details(None).unwrap();
+        let a_details = detailed.iter().find(|e| e.entry.id == a.id).unwrap();
+        assert_eq!(a_details.commits.len(), 2);
+
+        db.delete_entry(a.id).unwrap();
+        assert_eq!(db.list_commits(&CommitFilter::new()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_absence_crud() {
+        let db = Database::open_in_memory().unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2026, 12, d).unwrap();
+
+        let mut christmas = Absence::new(day(25), AbsenceKind::Holiday);
+        db.save_absence(&mut christmas).unwrap();
+        let mut eve = Absence::new(day(24), AbsenceKind::Vacation).with_half_day(true);
+        db.save_absence(&mut eve).unwrap();
+
+        let all = db.list_absences(None, None).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].date, day(24));
+        assert!(all[0].half_day);
+        assert_eq!(db.list_absences(Some(day(25)), None).unwrap().len(), 1);
+
+        // Saving the same date again replaces the record
+        let mut sick = Absence::new(day(24), AbsenceKind::Sick).with_note("flu");
+        db.save_absence(&mut sick).unwrap();
+        assert_eq!(sick.id, eve.id);
+        let eve = &db.list_absences(None, Some(day(24))).unwrap()[0];
+        assert_eq!(eve.kind, AbsenceKind::Sick);
+        assert!(!eve.half_day);
+
+        db.delete_absence(christmas.id).unwrap();
+        assert_eq!(db.list_absences(None, None).unwrap().len(), 1);
+        assert!(db.delete_absence(christmas.id).is_err());
+
+        // An unknown kind is an error, not a silent vacation day
+        db.with_conn(|conn| {
+            conn.execute("UPDATE absences SET kind = 'sabbatical'", [])?;
+            Ok(())
+        })
+        .unwrap();
+        assert!(db.list_absences(None, None).is_err());
+    }
 }
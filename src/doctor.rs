@@ -0,0 +1,943 @@
+//! Database integrity checks and repairs for MatteriaTrack
+//!
+//! Detects anomalies that the regular read paths silently paper over
+//! (orphaned rows, inverted time ranges, unparseable timestamps, stale
+//! active tracking, ...) and optionally repairs them in one transaction.
+
+use crate::database::Database;
+use crate::error::Result;
+use crate::theme::MateriaTheme;
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use colored::Colorize;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Entries starting further than this in the future are reported.
+const FUTURE_GRACE_MINUTES: i64 = 5;
+
+/// Timestamp columns checked for RFC 3339 validity, as (table, column, nullable).
+const TIMESTAMP_COLUMNS: &[(&str, &str, bool)] = &[
+    ("projects", "created_at", false),
+    ("projects", "updated_at", false),
+    ("tasks", "created_at", false),
+    ("tasks", "updated_at", false),
+    ("entries", "start_time", false),
+    ("entries", "end_time", true),
+    ("entry_commits", "committed_at", true),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+
+    fn color(&self) -> (u8, u8, u8) {
+        match self {
+            Self::Info => (100, 200, 255),
+            Self::Warning => (255, 200, 50),
+            Self::Error => (255, 80, 80),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    IntegrityCheck,
+    ForeignKeyViolation,
+    UnparseableTimestamp,
+    InvalidTimeRange,
+    FutureEntry,
+    TaskProjectMismatch,
+    InvalidGitCommits,
+    StaleActiveTracking,
+    UntrackedOpenEntry,
+    DuplicateProject,
+}
+
+/// A repair that can be applied to resolve an [`Issue`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Repair {
+    DeleteRow {
+        table: String,
+        rowid: i64,
+    },
+    /// Recreate a missing project under its old id, so rows pointing at it
+    /// keep their tracked time.
+    RestoreProject {
+        project_id: i64,
+    },
+    /// Recreate a missing task under its old id in `project_id`.
+    RestoreTask {
+        task_id: i64,
+        project_id: i64,
+    },
+    SetTimestamp {
+        table: String,
+        column: String,
+        rowid: i64,
+        value: String,
+    },
+    SwapEntryTimes {
+        entry_id: i64,
+    },
+    SetEntryProject {
+        entry_id: i64,
+        project_id: i64,
+    },
+    ResetGitCommits {
+        entry_id: i64,
+    },
+    ClearActiveTracking,
+    MergeProjects {
+        keep: i64,
+        duplicates: Vec<i64>,
+    },
+}
+
+impl Repair {
+    /// Repairs are applied in this order so later ones see consistent rows.
+    fn priority(&self) -> u8 {
+        match self {
+            Self::DeleteRow { .. } => 0,
+            Self::RestoreProject { .. } => 1,
+            Self::RestoreTask { .. } => 2,
+            Self::SetTimestamp { .. } => 3,
+            Self::SwapEntryTimes { .. } => 4,
+            Self::SetEntryProject { .. } => 5,
+            Self::ResetGitCommits { .. } => 6,
+            Self::MergeProjects { .. } => 7,
+            Self::ClearActiveTracking => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub severity: Severity,
+    pub message: String,
+    pub fixable: bool,
+    #[serde(skip)]
+    pub repair: Option<Repair>,
+}
+
+impl Issue {
+    fn new(kind: IssueKind, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            severity,
+            message: message.into(),
+            fixable: false,
+            repair: None,
+        }
+    }
+
+    fn with_repair(mut self, repair: Repair) -> Self {
+        self.fixable = true;
+        self.repair = Some(repair);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DoctorReport {
+    pub issues: Vec<Issue>,
+    pub fixed: usize,
+}
+
+impl DoctorReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == severity)
+            .count()
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn fixable_count(&self) -> usize {
+        self.issues.iter().filter(|i| i.fixable).count()
+    }
+}
+
+pub struct Doctor<'a> {
+    db: &'a Database,
+}
+
+impl<'a> Doctor<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    pub fn diagnose(&self) -> Result<DoctorReport> {
+        let issues = self.db.with_conn(|conn| collect_issues(conn, Utc::now()))?;
+        Ok(DoctorReport { issues, fixed: 0 })
+    }
+
+    /// Apply every available repair inside a single transaction, then re-run
+    /// the checks so the returned report only lists what is still wrong.
+    pub fn fix(&self) -> Result<DoctorReport> {
+        let fixed = self.db.transaction(|tx| {
+            let issues = collect_issues(tx, Utc::now())?;
+            // Several issues can ask for the same repair, not necessarily in a row.
+            let mut repairs: Vec<Repair> = Vec::new();
+            for repair in issues.into_iter().filter_map(|i| i.repair) {
+                if !repairs.contains(&repair) {
+                    repairs.push(repair);
+                }
+            }
+            repairs.sort_by_key(Repair::priority);
+
+            for repair in &repairs {
+                apply_repair(tx, repair)?;
+            }
+            Ok(repairs.len())
+        })?;
+
+        let mut report = self.diagnose()?;
+        report.fixed = fixed;
+        Ok(report)
+    }
+}
+
+fn collect_issues(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<Issue>> {
+    let mut issues = Vec::new();
+
+    check_integrity(conn, &mut issues)?;
+    check_foreign_keys(conn, &mut issues)?;
+    check_timestamps(conn, &mut issues)?;
+    check_time_ranges(conn, now, &mut issues)?;
+    check_task_projects(conn, &mut issues)?;
+    check_git_commits(conn, &mut issues)?;
+    check_active_tracking(conn, &mut issues)?;
+    check_duplicate_projects(conn, &mut issues)?;
+
+    issues.sort_by_key(|i| std::cmp::Reverse(i.severity));
+    Ok(issues)
+}
+
+fn check_integrity(conn: &Connection, issues: &mut Vec<Issue>) -> Result<()> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let results = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    for result in results.into_iter().filter(|r| r != "ok") {
+        issues.push(Issue::new(
+            IssueKind::IntegrityCheck,
+            Severity::Error,
+            format!("SQLite integrity check: {}", result),
+        ));
+    }
+    Ok(())
+}
+
+fn check_foreign_keys(conn: &Connection, issues: &mut Vec<Issue>) -> Result<()> {
+    let mut stmt = conn.prepare("PRAGMA foreign_key_check")?;
+    let violations = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    for (table, rowid, parent) in violations {
+        let Some(rowid) = rowid else { continue };
+        let issue = Issue::new(
+            IssueKind::ForeignKeyViolation,
+            Severity::Error,
+            format!(
+                "{} row {} references a missing row in {}",
+                table, rowid, parent
+            ),
+        );
+        issues.push(match orphan_repair(conn, &table, &parent, rowid)? {
+            Some(repair) => issue.with_repair(repair),
+            None => issue,
+        });
+    }
+    Ok(())
+}
+
+/// How to resolve a foreign key violation without losing tracked time:
+/// missing projects and tasks are recreated as placeholders, only commit
+/// rows and the active-tracking pointer of a missing entry are dropped.
+fn orphan_repair(
+    conn: &Connection,
+    table: &str,
+    parent: &str,
+    rowid: i64,
+) -> Result<Option<Repair>> {
+    let repair = match (table, parent) {
+        ("tasks" | "entries", "projects") => conn
+            .query_row(
+                &format!("SELECT project_id FROM {} WHERE rowid = ?1", table),
+                params![rowid],
+                |row| row.get(0),
+            )
+            .optional()?
+            .map(|project_id| Repair::RestoreProject { project_id }),
+        ("entries", "tasks") => conn
+            .query_row(
+                "SELECT task_id, project_id FROM entries WHERE rowid = ?1",
+                params![rowid],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .map(|(task_id, project_id)| Repair::RestoreTask {
+                task_id,
+                project_id,
+            }),
+        ("entry_commits", "entries") => Some(Repair::DeleteRow {
+            table: table.to_string(),
+            rowid,
+        }),
+        ("active_tracking", "entries") => Some(Repair::ClearActiveTracking),
+        _ => None,
+    };
+    Ok(repair)
+}
+
+fn check_timestamps(conn: &Connection, issues: &mut Vec<Issue>) -> Result<()> {
+    for (table, column, nullable) in TIMESTAMP_COLUMNS {
+        let query = format!("SELECT rowid, {} FROM {}", column, table);
+        let mut stmt = conn.prepare(&query)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        for (rowid, value) in rows {
+            let Some(value) = value else {
+                if !nullable {
+                    issues.push(Issue::new(
+                        IssueKind::UnparseableTimestamp,
+                        Severity::Error,
+                        format!("{} row {}: {} is NULL", table, rowid, column),
+                    ));
+                }
+                continue;
+            };
+
+            if parse_rfc3339(&value).is_some() {
+                continue;
+            }
+
+            let issue = Issue::new(
+                IssueKind::UnparseableTimestamp,
+                Severity::Error,
+                format!(
+                    "{} row {}: {} '{}' is not a valid RFC 3339 timestamp",
+                    table, rowid, column, value
+                ),
+            );
+
+            issues.push(match parse_lenient(&value) {
+                Some(dt) => issue.with_repair(Repair::SetTimestamp {
+                    table: table.to_string(),
+                    column: column.to_string(),
+                    rowid,
+                    value: dt.to_rfc3339(),
+                }),
+                None => issue,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn check_time_ranges(conn: &Connection, now: DateTime<Utc>, issues: &mut Vec<Issue>) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, start_time, end_time FROM entries")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let horizon = now + Duration::minutes(FUTURE_GRACE_MINUTES);
+
+    for (id, start, end) in rows {
+        let Some(start) = parse_rfc3339(&start) else {
+            continue;
+        };
+
+        if start > horizon {
+            issues.push(Issue::new(
+                IssueKind::FutureEntry,
+                Severity::Warning,
+                format!(
+                    "Entry {} starts in the future ({})",
+                    id,
+                    start.format("%Y-%m-%d %H:%M")
+                ),
+            ));
+        }
+
+        if let Some(end) = end.as_deref().and_then(parse_rfc3339) {
+            if end < start {
+                issues.push(
+                    Issue::new(
+                        IssueKind::InvalidTimeRange,
+                        Severity::Error,
+                        format!(
+                            "Entry {} ends before it starts ({} < {})",
+                            id,
+                            end.format("%Y-%m-%d %H:%M"),
+                            start.format("%Y-%m-%d %H:%M")
+                        ),
+                    )
+                    .with_repair(Repair::SwapEntryTimes { entry_id: id }),
+                );
+            } else if end > horizon {
+                issues.push(Issue::new(
+                    IssueKind::FutureEntry,
+                    Severity::Warning,
+                    format!(
+                        "Entry {} ends in the future ({})",
+                        id,
+                        end.format("%Y-%m-%d %H:%M")
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_task_projects(conn: &Connection, issues: &mut Vec<Issue>) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT e.id, e.project_id, t.project_id, t.name
+         FROM entries e JOIN tasks t ON e.task_id = t.id
+         WHERE e.project_id != t.project_id",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    for (entry_id, entry_project, task_project, task_name) in rows {
+        issues.push(
+            Issue::new(
+                IssueKind::TaskProjectMismatch,
+                Severity::Warning,
+                format!(
+                    "Entry {} is filed under project {} but its task '{}' belongs to project {}",
+                    entry_id, entry_project, task_name, task_project
+                ),
+            )
+            .with_repair(Repair::SetEntryProject {
+                entry_id,
+                project_id: task_project,
+            }),
+        );
+    }
+    Ok(())
+}
+
+fn check_git_commits(conn: &Connection, issues: &mut Vec<Issue>) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, git_commits FROM entries")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    for (entry_id, commits) in rows {
+        let valid = commits
+            .as_deref()
+            .map(|c| serde_json::from_str::<Vec<String>>(c).is_ok())
+            .unwrap_or(false);
+
+        if !valid {
+            issues.push(
+                Issue::new(
+                    IssueKind::InvalidGitCommits,
+                    Severity::Warning,
+                    format!("Entry {} has a malformed git_commits column", entry_id),
+                )
+                .with_repair(Repair::ResetGitCommits { entry_id }),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn check_active_tracking(conn: &Connection, issues: &mut Vec<Issue>) -> Result<()> {
+    let active_id: Option<i64> = conn
+        .query_row(
+            "SELECT entry_id FROM active_tracking WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    if let Some(entry_id) = active_id {
+        let end: Option<Option<String>> = conn
+            .query_row(
+                "SELECT end_time FROM entries WHERE id = ?1",
+                params![entry_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match end {
+            None => issues.push(
+                Issue::new(
+                    IssueKind::StaleActiveTracking,
+                    Severity::Error,
+                    format!("Active tracking points at missing entry {}", entry_id),
+                )
+                .with_repair(Repair::ClearActiveTracking),
+            ),
+            Some(Some(_)) => issues.push(
+                Issue::new(
+                    IssueKind::StaleActiveTracking,
+                    Severity::Error,
+                    format!("Active tracking points at finished entry {}", entry_id),
+                )
+                .with_repair(Repair::ClearActiveTracking),
+            ),
+            Some(None) => {}
+        }
+    }
+
+    let mut stmt = conn.prepare("SELECT id FROM entries WHERE end_time IS NULL")?;
+    let open_entries = stmt
+        .query_map([], |row| row.get::<_, i64>(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    for entry_id in open_entries.into_iter().filter(|id| Some(*id) != active_id) {
+        issues.push(Issue::new(
+            IssueKind::UntrackedOpenEntry,
+            Severity::Warning,
+            format!(
+                "Entry {} has no end time but is not the active tracking session",
+                entry_id
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn check_duplicate_projects(conn: &Connection, issues: &mut Vec<Issue>) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, name FROM projects ORDER BY id")?;
+    let projects = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut groups: HashMap<String, Vec<(i64, String)>> = HashMap::new();
+    for (id, name) in projects {
+        groups
+            .entry(name.to_lowercase())
+            .or_default()
+            .push((id, name));
+    }
+
+    let mut duplicates: Vec<_> = groups.into_values().filter(|g| g.len() > 1).collect();
+    duplicates.sort_by_key(|g| g[0].0);
+
+    for group in duplicates {
+        let names: Vec<&str> = group.iter().map(|(_, n)| n.as_str()).collect();
+        issues.push(
+            Issue::new(
+                IssueKind::DuplicateProject,
+                Severity::Warning,
+                format!(
+                    "Projects differ only by case: {} (would merge into '{}')",
+                    names.join(", "),
+                    group[0].1
+                ),
+            )
+            .with_repair(Repair::MergeProjects {
+                keep: group[0].0,
+                duplicates: group[1..].iter().map(|(id, _)| *id).collect(),
+            }),
+        );
+    }
+    Ok(())
+}
+
+fn apply_repair(conn: &Connection, repair: &Repair) -> Result<()> {
+    match repair {
+        Repair::DeleteRow { table, rowid } => {
+            conn.execute(
+                &format!("DELETE FROM {} WHERE rowid = ?1", table),
+                params![rowid],
+            )?;
+        }
+        Repair::RestoreProject { project_id } => {
+            let now = Utc::now().to_rfc3339();
+            conn.execute(
+                "INSERT OR IGNORE INTO projects (id, name, created_at, updated_at, uuid) \
+                 VALUES (?1, ?2, ?3, ?3, ?4)",
+                params![
+                    project_id,
+                    format!("Recovered project {}", project_id),
+                    now,
+                    uuid::Uuid::new_v4().to_string()
+                ],
+            )?;
+        }
+        Repair::RestoreTask {
+            task_id,
+            project_id,
+        } => {
+            let now = Utc::now().to_rfc3339();
+            conn.execute(
+                "INSERT OR IGNORE INTO tasks (id, project_id, name, created_at, updated_at, uuid) \
+                 VALUES (?1, ?2, ?3, ?4, ?4, ?5)",
+                params![
+                    task_id,
+                    project_id,
+                    format!("Recovered task {}", task_id),
+                    now,
+                    uuid::Uuid::new_v4().to_string()
+                ],
+            )?;
+        }
+        Repair::SetTimestamp {
+            table,
+            column,
+            rowid,
+            value,
+        } => {
+            conn.execute(
+                &format!("UPDATE {} SET {} = ?1 WHERE rowid = ?2", table, column),
+                params![value, rowid],
+            )?;
+        }
+        Repair::SwapEntryTimes { entry_id } => {
+            conn.execute(
+                "UPDATE entries SET start_time = end_time, end_time = start_time WHERE id = ?1",
+                params![entry_id],
+            )?;
+        }
+        Repair::SetEntryProject {
+            entry_id,
+            project_id,
+        } => {
+            conn.execute(
+                "UPDATE entries SET project_id = ?1 WHERE id = ?2",
+                params![project_id, entry_id],
+            )?;
+        }
+        Repair::ResetGitCommits { entry_id } => {
+            conn.execute(
+                "UPDATE entries SET git_commits = '[]' WHERE id = ?1",
+                params![entry_id],
+            )?;
+        }
+        Repair::ClearActiveTracking => {
+            conn.execute("DELETE FROM active_tracking WHERE id = 1", [])?;
+        }
+        Repair::MergeProjects { keep, duplicates } => {
+            for dup in duplicates {
+                merge_project(conn, *keep, *dup)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn merge_project(conn: &Connection, keep: i64, dup: i64) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, name FROM tasks WHERE project_id = ?1")?;
+    let tasks = stmt
+        .query_map(params![dup], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    for (task_id, name) in tasks {
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM tasks WHERE project_id = ?1 AND name = ?2",
+                params![keep, name],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match existing {
+            Some(keep_task) => {
+                conn.execute(
+                    "UPDATE entries SET task_id = ?1 WHERE task_id = ?2",
+                    params![keep_task, task_id],
+                )?;
+                conn.execute("DELETE FROM tasks WHERE id = ?1", params![task_id])?;
+            }
+            None => {
+                conn.execute(
+                    "UPDATE tasks SET project_id = ?1 WHERE id = ?2",
+                    params![keep, task_id],
+                )?;
+            }
+        }
+    }
+
+    conn.execute(
+        "UPDATE entries SET project_id = ?1 WHERE project_id = ?2",
+        params![keep, dup],
+    )?;
+    conn.execute("DELETE FROM projects WHERE id = ?1", params![dup])?;
+    Ok(())
+}
+
+fn parse_rfc3339(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Recover timestamps written by older versions or by hand-edits.
+fn parse_lenient(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(ndt) = NaiveDateTime::parse_from_str(s, format) {
+            return Some(Utc.from_utc_datetime(&ndt));
+        }
+    }
+
+    s.parse::<i64>()
+        .ok()
+        .and_then(|ts| DateTime::from_timestamp(ts, 0))
+}
+
+pub fn format_report(report: &DoctorReport, theme: MateriaTheme) -> String {
+    let (r, g, b) = theme.primary_color();
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "\n{} {} {}\n",
+        theme.icon(),
+        "Database Doctor".truecolor(r, g, b).bold(),
+        theme.materia_icon()
+    ));
+    output.push_str(&"━".repeat(50));
+    output.push('\n');
+
+    if report.fixed > 0 {
+        output.push_str(&format!("\n🔧 Applied {} repairs\n", report.fixed));
+    }
+
+    if report.is_healthy() {
+        output.push_str(&format!(
+            "\n{} No problems found\n",
+            "✓".truecolor(100, 255, 100)
+        ));
+    } else {
+        output.push('\n');
+        for issue in &report.issues {
+            let (ir, ig, ib) = issue.severity.color();
+            let marker = if issue.fixable { " (fixable)" } else { "" };
+            output.push_str(&format!(
+                "  {:<9} {}{}\n",
+                format!("[{}]", issue.severity.label()).truecolor(ir, ig, ib),
+                issue.message,
+                marker.truecolor(100, 100, 100)
+            ));
+        }
+
+        output.push_str(&format!(
+            "\n{} errors, {} warnings, {} info\n",
+            report.count(Severity::Error),
+            report.count(Severity::Warning),
+            report.count(Severity::Info)
+        ));
+
+        let fixable = report.fixable_count();
+        if fixable > 0 && report.fixed == 0 {
+            output.push_str(&format!(
+                "Run 'mtrack doctor --fix' to repair {} of them\n",
+                fixable
+            ));
+        }
+    }
+
+    output.push('\n');
+    output.push_str(&"━".repeat(50));
+    output.push('\n');
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Entry, Project, Task};
+
+    fn seeded_db() -> (Database, Project, Task) {
+        let db = Database::open_in_memory().unwrap();
+        let mut project = Project::new("Acme");
+        db.create_project(&mut project).unwrap();
+        let mut task = Task::new(project.id, "Dev");
+        db.create_task(&mut task).unwrap();
+        (db, project, task)
+    }
+
+    fn has_kind(report: &DoctorReport, kind: IssueKind) -> bool {
+        report.issues.iter().any(|i| i.kind == kind)
+    }
+
+    #[test]
+    fn test_healthy_database() {
+        let (db, project, task) = seeded_db();
+        let mut entry = Entry::new(project.id, task.id);
+        entry.finish();
+        db.create_entry(&mut entry).unwrap();
+
+        let report = Doctor::new(&db).diagnose().unwrap();
+        assert!(report.is_healthy(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn test_detects_and_fixes_inverted_range() {
+        let (db, project, task) = seeded_db();
+        let mut entry = Entry::new(project.id, task.id);
+        entry.end = Some(entry.start - Duration::hours(1));
+        db.create_entry(&mut entry).unwrap();
+
+        let report = Doctor::new(&db).diagnose().unwrap();
+        assert!(has_kind(&report, IssueKind::InvalidTimeRange));
+
+        let report = Doctor::new(&db).fix().unwrap();
+        assert!(report.is_healthy(), "{:?}", report.issues);
+        let fixed = db.get_entry(entry.id).unwrap().unwrap();
+        assert!(fixed.end.unwrap() > fixed.start);
+    }
+
+    #[test]
+    fn test_detects_unparseable_and_stale_active() {
+        let (db, project, task) = seeded_db();
+        let mut entry = Entry::new(project.id, task.id);
+        entry.finish();
+        db.create_entry(&mut entry).unwrap();
+        db.set_active_tracking(entry.id).unwrap();
+
+        db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE entries SET start_time = '2024-01-15 10:30:00' WHERE id = ?1",
+                params![entry.id],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        let report = Doctor::new(&db).diagnose().unwrap();
+        assert!(has_kind(&report, IssueKind::UnparseableTimestamp));
+        assert!(has_kind(&report, IssueKind::StaleActiveTracking));
+
+        let report = Doctor::new(&db).fix().unwrap();
+        assert!(report.is_healthy(), "{:?}", report.issues);
+        assert!(db.get_active_tracking().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_merges_case_duplicate_projects() {
+        let (db, project, task) = seeded_db();
+        let mut dup = Project::new("ACME");
+        db.create_project(&mut dup).unwrap();
+        let mut dup_task = Task::new(dup.id, "Dev");
+        db.create_task(&mut dup_task).unwrap();
+
+        let mut entry = Entry::new(dup.id, dup_task.id);
+        entry.finish();
+        db.create_entry(&mut entry).unwrap();
+
+        let report = Doctor::new(&db).diagnose().unwrap();
+        assert!(has_kind(&report, IssueKind::DuplicateProject));
+
+        Doctor::new(&db).fix().unwrap();
+        assert_eq!(db.list_projects().unwrap().len(), 1);
+        let merged = db.get_entry(entry.id).unwrap().unwrap();
+        assert_eq!(merged.project_id, project.id);
+        assert_eq!(merged.task_id, task.id);
+    }
+
+    #[test]
+    fn test_detects_orphaned_entries() {
+        let (db, project, _task) = seeded_db();
+        db.with_conn(|conn| {
+            conn.execute_batch("PRAGMA foreign_keys=OFF;")?;
+            conn.execute(
+                "INSERT INTO entries (project_id, task_id, start_time, git_commits) VALUES (?1, 999, ?2, '[]')",
+                params![project.id, Utc::now().to_rfc3339()],
+            )?;
+            conn.execute_batch("PRAGMA foreign_keys=ON;")?;
+            Ok(())
+        })
+        .unwrap();
+
+        let report = Doctor::new(&db).diagnose().unwrap();
+        assert!(has_kind(&report, IssueKind::ForeignKeyViolation));
+
+        let report = Doctor::new(&db).fix().unwrap();
+        assert!(!has_kind(&report, IssueKind::ForeignKeyViolation));
+
+        // The entry is kept and filed under a placeholder task
+        let entries = db.list_entries_with_details(None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].task_name, "Recovered task 999");
+        assert_eq!(entries[0].project_name, "Acme");
+    }
+
+    #[test]
+    fn test_shared_repairs_run_once() {
+        let (db, project, _task) = seeded_db();
+        db.with_conn(|conn| {
+            conn.execute_batch("PRAGMA foreign_keys=OFF;")?;
+            for task_id in [999, 998, 999] {
+                conn.execute(
+                    "INSERT INTO entries (project_id, task_id, start_time, git_commits) VALUES (?1, ?2, ?3, '[]')",
+                    params![project.id, task_id, Utc::now().to_rfc3339()],
+                )?;
+            }
+            conn.execute_batch("PRAGMA foreign_keys=ON;")?;
+            Ok(())
+        })
+        .unwrap();
+
+        let report = Doctor::new(&db).fix().unwrap();
+        assert_eq!(report.fixed, 2);
+        assert!(!has_kind(&report, IssueKind::ForeignKeyViolation));
+        assert_eq!(db.list_entries_with_details(None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        assert!(parse_lenient("2024-01-15 10:30:00").is_some());
+        assert!(parse_lenient("1700000000").is_some());
+        assert!(parse_lenient("garbage").is_none());
+    }
+}
@@ -0,0 +1,818 @@
+//! Filter expressions for reading commands
+//!
+//! `--where` takes a small query language that is parsed into a typed AST and
+//! compiled to SQL against `entries e`, `projects p` and `tasks t`:
+//!
+//! ```text
+//! project ~ "acme" and tag = meeting and duration > 30m and weekday in (sat, sun)
+//! ```
+//!
+//! Saved filters from the `[filters]` config table can be referenced as `@name`.
+
+use crate::error::{Error, Result};
+use crate::period::parse_date;
+use chrono::{NaiveDate, Weekday};
+use rusqlite::types::Value;
+use std::collections::BTreeMap;
+
+/// Nesting limit for `@name` references, which also catches cycles.
+const MAX_REFERENCE_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Project,
+    Task,
+    Notes,
+    Tag,
+    Duration,
+    Date,
+    Weekday,
+    Hour,
+    Status,
+    Commits,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Match,
+    NotMatch,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    In,
+    NotIn,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Literal {
+    Text(String),
+    Seconds(i64),
+    Date(NaiveDate),
+    Weekday(Weekday),
+    Number(i64),
+    Active(bool),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare {
+        field: Field,
+        op: Op,
+        values: Vec<Literal>,
+    },
+}
+
+/// A parsed, type-checked filter expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    /// Parse an expression without saved filters.
+    pub fn parse(input: &str) -> Result<Self> {
+        Self::parse_with(input, &BTreeMap::new())
+    }
+
+    /// Parse an expression, resolving `@name` against `saved`.
+    pub fn parse_with(input: &str, saved: &BTreeMap<String, String>) -> Result<Self> {
+        let expr = Parser::new(input, saved, 0)?.parse()?;
+        Ok(Self { expr })
+    }
+
+    /// Build the filter for a command from its `--where` and `--filter` options.
+    pub fn from_args(
+        expr: Option<&str>,
+        named: Option<&str>,
+        saved: &BTreeMap<String, String>,
+    ) -> Result<Option<Self>> {
+        let named = named
+            .map(|name| {
+                saved
+                    .get(name)
+                    .ok_or_else(|| Error::NotFound(format!("Saved filter '{}'", name)))
+                    .and_then(|e| Self::parse_with(e, saved))
+            })
+            .transpose()?;
+        let adhoc = expr.map(|e| Self::parse_with(e, saved)).transpose()?;
+
+        Ok(match (named, adhoc) {
+            (Some(a), Some(b)) => Some(a.and(b)),
+            (a, b) => a.or(b),
+        })
+    }
+
+    /// Entries of the project named `name` (case-insensitive, like `project = name`).
+    pub fn project(name: &str) -> Self {
+        Self {
+            expr: Expr::Compare {
+                field: Field::Project,
+                op: Op::Eq,
+                values: vec![Literal::Text(name.to_string())],
+            },
+        }
+    }
+
+    /// Combine with an optional second filter.
+    pub fn and_maybe(self, other: Option<Filter>) -> Self {
+        match other {
+            Some(other) => self.and(other),
+            None => self,
+        }
+    }
+
+    pub fn and(self, other: Filter) -> Self {
+        Self {
+            expr: Expr::And(Box::new(self.expr), Box::new(other.expr)),
+        }
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Compile to a SQL condition. Placeholders are numbered after the
+    /// values already in `params`, so the result can be appended to a query
+    /// that uses `?N` parameters.
+    pub fn to_sql(&self, params: &mut Vec<Value>) -> String {
+        compile(&self.expr, params)
+    }
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "project" => Some(Self::Project),
+            "task" => Some(Self::Task),
+            "notes" | "note" => Some(Self::Notes),
+            "tag" => Some(Self::Tag),
+            "duration" => Some(Self::Duration),
+            "date" | "day" => Some(Self::Date),
+            "weekday" => Some(Self::Weekday),
+            "hour" => Some(Self::Hour),
+            "status" => Some(Self::Status),
+            "commits" => Some(Self::Commits),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Project => "project",
+            Self::Task => "task",
+            Self::Notes => "notes",
+            Self::Tag => "tag",
+            Self::Duration => "duration",
+            Self::Date => "date",
+            Self::Weekday => "weekday",
+            Self::Hour => "hour",
+            Self::Status => "status",
+            Self::Commits => "commits",
+        }
+    }
+
+    fn allows(self, op: Op) -> bool {
+        use Op::*;
+        match self {
+            Self::Project | Self::Task | Self::Notes => {
+                matches!(op, Eq | Ne | Match | NotMatch | In | NotIn)
+            }
+            Self::Tag | Self::Weekday => matches!(op, Eq | Ne | In | NotIn),
+            Self::Status => matches!(op, Eq | Ne),
+            Self::Duration | Self::Date | Self::Hour | Self::Commits => {
+                !matches!(op, Match | NotMatch)
+            }
+        }
+    }
+
+    fn literal(self, raw: &str) -> Result<Literal> {
+        let invalid = |what: &str| {
+            Error::Parse(format!(
+                "invalid {} '{}' for field '{}'",
+                what,
+                raw,
+                self.name()
+            ))
+        };
+
+        match self {
+            Self::Project | Self::Task | Self::Notes => Ok(Literal::Text(raw.to_string())),
+            Self::Tag => {
+                let tag = raw.trim_start_matches('#').to_lowercase();
+                let valid = !tag.is_empty()
+                    && tag
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+                if valid {
+                    Ok(Literal::Text(tag))
+                } else {
+                    Err(invalid("tag"))
+                }
+            }
+            Self::Duration => parse_duration(raw)
+                .map(Literal::Seconds)
+                .ok_or_else(|| invalid("duration")),
+            Self::Date => parse_date(raw)
+                .map(Literal::Date)
+                .ok_or_else(|| invalid("date")),
+            Self::Weekday => raw
+                .parse::<Weekday>()
+                .map(Literal::Weekday)
+                .map_err(|_| invalid("weekday")),
+            Self::Hour => match raw.parse::<i64>() {
+                Ok(h) if (0..24).contains(&h) => Ok(Literal::Number(h)),
+                _ => Err(invalid("hour")),
+            },
+            Self::Commits => raw
+                .parse::<i64>()
+                .map(Literal::Number)
+                .map_err(|_| invalid("number")),
+            Self::Status => match raw.to_lowercase().as_str() {
+                "active" | "running" => Ok(Literal::Active(true)),
+                "finished" | "done" => Ok(Literal::Active(false)),
+                _ => Err(invalid("status")),
+            },
+        }
+    }
+
+    fn sql(self) -> &'static str {
+        match self {
+            Self::Project => "p.name",
+            Self::Task => "t.name",
+            Self::Notes => "COALESCE(e.notes, '')",
+            Self::Tag => "(' ' || lower(COALESCE(e.notes, '')) || ' ')",
+            Self::Duration => {
+                "(CAST(strftime('%s', COALESCE(e.end_time, 'now')) AS INTEGER) \
+                 - CAST(strftime('%s', e.start_time) AS INTEGER))"
+            }
+            Self::Date => "date(e.start_time, 'localtime')",
+            Self::Weekday => "CAST(strftime('%w', e.start_time, 'localtime') AS INTEGER)",
+            Self::Hour => "CAST(strftime('%H', e.start_time, 'localtime') AS INTEGER)",
+            Self::Status => "e.end_time",
+            Self::Commits => "(SELECT COUNT(*) FROM entry_commits ec WHERE ec.entry_id = e.id)",
+        }
+    }
+}
+
+/// Lowercased `#hashtags` in `notes`, in order of first appearance. A tag
+/// must start the text or follow a character that cannot be part of a tag.
+pub fn extract_tags(notes: &str) -> Vec<String> {
+    let is_tag_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    let mut tags: Vec<String> = Vec::new();
+    let mut prev: Option<char> = None;
+
+    for (i, c) in notes.char_indices() {
+        if c == '#' && !prev.is_some_and(is_tag_char) {
+            let rest = &notes[i + 1..];
+            let len = rest.find(|c: char| !is_tag_char(c)).unwrap_or(rest.len());
+            if len > 0 {
+                let tag = rest[..len].to_lowercase();
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        }
+        prev = Some(c);
+    }
+
+    tags
+}
+
+/// Durations like `90`, `30m`, `1h30m`, `2h`, `45s` or `1:30`. Bare numbers are minutes.
+pub fn parse_duration(s: &str) -> Option<i64> {
+    let s = s.trim().to_lowercase();
+    if s.is_empty() {
+        return None;
+    }
+
+    if let Some((h, m)) = s.split_once(':') {
+        return Some(h.parse::<i64>().ok()? * 3600 + m.parse::<i64>().ok()? * 60);
+    }
+    if let Ok(minutes) = s.parse::<i64>() {
+        return Some(minutes * 60);
+    }
+
+    let mut total = 0;
+    let mut number = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let value: i64 = number.parse().ok()?;
+        number.clear();
+        total += match c {
+            'd' => value * 86400,
+            'h' => value * 3600,
+            'm' => value * 60,
+            's' => value,
+            _ => return None,
+        };
+    }
+
+    number.is_empty().then_some(total)
+}
+
+fn compile(expr: &Expr, params: &mut Vec<Value>) -> String {
+    match expr {
+        Expr::And(a, b) => format!("({} AND {})", compile(a, params), compile(b, params)),
+        Expr::Or(a, b) => format!("({} OR {})", compile(a, params), compile(b, params)),
+        Expr::Not(inner) => format!("(NOT {})", compile(inner, params)),
+        Expr::Compare { field, op, values } => compile_compare(*field, *op, values, params),
+    }
+}
+
+fn compile_compare(field: Field, op: Op, values: &[Literal], params: &mut Vec<Value>) -> String {
+    let column = field.sql();
+
+    if let Op::In | Op::NotIn = op {
+        let alternatives: Vec<String> = values
+            .iter()
+            .map(|v| compile_compare(field, Op::Eq, std::slice::from_ref(v), params))
+            .collect();
+        let any = format!("({})", alternatives.join(" OR "));
+        return if op == Op::NotIn {
+            format!("(NOT {})", any)
+        } else {
+            any
+        };
+    }
+
+    let mut bind = |value: Value| {
+        params.push(value);
+        format!("?{}", params.len())
+    };
+
+    match (field, &values[0]) {
+        (Field::Status, Literal::Active(active)) => {
+            let is_null = *active == (op == Op::Eq);
+            format!("{} IS {}NULL", column, if is_null { "" } else { "NOT " })
+        }
+        (Field::Tag, Literal::Text(tag)) => {
+            let pattern = bind(Value::Text(format!("*[^a-z0-9_-]#{}[^a-z0-9_-]*", tag)));
+            let not = if op == Op::Ne { "NOT " } else { "" };
+            format!("{} {}GLOB {}", column, not, pattern)
+        }
+        (_, Literal::Text(text)) => match op {
+            Op::Match | Op::NotMatch => {
+                let pattern = bind(Value::Text(format!("%{}%", escape_like(text))));
+                let not = if op == Op::NotMatch { "NOT " } else { "" };
+                format!("{} {}LIKE {} ESCAPE '\\'", column, not, pattern)
+            }
+            _ => format!(
+                "{} {} {} COLLATE NOCASE",
+                column,
+                sql_op(op),
+                bind(Value::Text(text.clone()))
+            ),
+        },
+        (_, literal) => {
+            let value = match literal {
+                Literal::Seconds(n) | Literal::Number(n) => Value::Integer(*n),
+                Literal::Date(d) => Value::Text(d.format("%Y-%m-%d").to_string()),
+                Literal::Weekday(w) => Value::Integer(w.num_days_from_sunday() as i64),
+                Literal::Text(_) | Literal::Active(_) => unreachable!("handled above"),
+            };
+            format!("{} {} {}", column, sql_op(op), bind(value))
+        }
+    }
+}
+
+impl Op {
+    fn symbol(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Match => "~",
+            Op::NotMatch => "!~",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::In => "in",
+            Op::NotIn => "not in",
+        }
+    }
+}
+
+fn sql_op(op: Op) -> &'static str {
+    match op {
+        Op::Eq => "=",
+        Op::Ne => "<>",
+        Op::Gt => ">",
+        Op::Ge => ">=",
+        Op::Lt => "<",
+        Op::Le => "<=",
+        Op::Match | Op::NotMatch | Op::In | Op::NotIn => unreachable!("compiled separately"),
+    }
+}
+
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Reference(String),
+    Op(Op),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => value.extend(chars.next()),
+                        Some(q) if q == c => break,
+                        Some(ch) => value.push(ch),
+                        None => return Err(Error::Parse("unterminated string".into())),
+                    }
+                }
+                tokens.push(Token::Quoted(value));
+            }
+            '=' | '!' | '~' | '<' | '>' => {
+                chars.next();
+                let next = chars.peek().copied();
+                let op = match (c, next) {
+                    ('!', Some('=')) => Op::Ne,
+                    ('!', Some('~')) => Op::NotMatch,
+                    ('>', Some('=')) => Op::Ge,
+                    ('<', Some('=')) => Op::Le,
+                    ('=', Some('=')) => Op::Eq,
+                    ('=', _) => Op::Eq,
+                    ('~', _) => Op::Match,
+                    ('>', _) => Op::Gt,
+                    ('<', _) => Op::Lt,
+                    _ => return Err(Error::Parse(format!("unexpected '{}'", c))),
+                };
+                if matches!(op, Op::Ne | Op::NotMatch | Op::Ge | Op::Le) || next == Some('=') {
+                    chars.next();
+                }
+                tokens.push(Token::Op(op));
+            }
+            '@' => {
+                chars.next();
+                let name = take_word(&mut chars);
+                if name.is_empty() {
+                    return Err(Error::Parse("expected a filter name after '@'".into()));
+                }
+                tokens.push(Token::Reference(name));
+            }
+            _ => {
+                let word = take_word(&mut chars);
+                if word.is_empty() {
+                    return Err(Error::Parse(format!("unexpected '{}'", c)));
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn take_word(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut word = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.' | '#' | '/') {
+            word.push(c);
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    word
+}
+
+/// Recursive-descent parser: `or` binds loosest, then `and`, then `not`.
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    saved: &'a BTreeMap<String, String>,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &str, saved: &'a BTreeMap<String, String>, depth: usize) -> Result<Self> {
+        Ok(Self {
+            tokens: tokenize(input)?,
+            pos: 0,
+            saved,
+            depth,
+        })
+    }
+
+    fn parse(mut self) -> Result<Expr> {
+        if self.tokens.is_empty() {
+            return Err(Error::Parse("empty filter expression".into()));
+        }
+        let expr = self.parse_or()?;
+        match self.peek() {
+            None => Ok(expr),
+            Some(token) => Err(Error::Parse(format!("unexpected {:?}", token))),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("or") {
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut left = self.parse_unary()?;
+        while self.eat_keyword("and") {
+            left = Expr::And(Box::new(left), Box::new(self.parse_unary()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+
+        match self.next() {
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err(Error::Parse("expected ')'".into())),
+                }
+            }
+            Some(Token::Reference(name)) => self.resolve(&name),
+            Some(Token::Word(name)) => self.parse_comparison(&name),
+            Some(token) => Err(Error::Parse(format!("expected a field, got {:?}", token))),
+            None => Err(Error::Parse("unexpected end of expression".into())),
+        }
+    }
+
+    fn resolve(&self, name: &str) -> Result<Expr> {
+        if self.depth >= MAX_REFERENCE_DEPTH {
+            return Err(Error::Parse(format!(
+                "saved filter '@{}' is nested too deeply (is it recursive?)",
+                name
+            )));
+        }
+        let source = self
+            .saved
+            .get(name)
+            .ok_or_else(|| Error::NotFound(format!("Saved filter '{}'", name)))?;
+        Parser::new(source, self.saved, self.depth + 1)?.parse()
+    }
+
+    fn parse_comparison(&mut self, name: &str) -> Result<Expr> {
+        let field = Field::from_name(name).ok_or_else(|| {
+            Error::Parse(format!(
+                "unknown field '{}' (expected project, task, notes, tag, duration, date, \
+                 weekday, hour, status or commits)",
+                name
+            ))
+        })?;
+
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("in") => Op::In,
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case("not") && self.eat_keyword("in") => {
+                Op::NotIn
+            }
+            _ => {
+                return Err(Error::Parse(format!(
+                    "expected an operator after '{}'",
+                    name
+                )))
+            }
+        };
+        if !field.allows(op) {
+            return Err(Error::Parse(format!(
+                "operator '{}' is not supported for '{}'",
+                op.symbol(),
+                field.name()
+            )));
+        }
+
+        let values = if let Op::In | Op::NotIn = op {
+            self.parse_list(field)?
+        } else {
+            vec![self.parse_value(field)?]
+        };
+
+        Ok(Expr::Compare { field, op, values })
+    }
+
+    fn parse_list(&mut self, field: Field) -> Result<Vec<Literal>> {
+        if self.next() != Some(Token::LParen) {
+            return Err(Error::Parse("expected '(' after 'in'".into()));
+        }
+        let mut values = vec![self.parse_value(field)?];
+        loop {
+            match self.next() {
+                Some(Token::Comma) => values.push(self.parse_value(field)?),
+                Some(Token::RParen) => return Ok(values),
+                _ => return Err(Error::Parse("expected ',' or ')' in list".into())),
+            }
+        }
+    }
+
+    fn parse_value(&mut self, field: Field) -> Result<Literal> {
+        match self.next() {
+            Some(Token::Word(raw)) | Some(Token::Quoted(raw)) => field.literal(&raw),
+            _ => Err(Error::Parse(format!(
+                "expected a value for '{}'",
+                field.name()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::models::{Entry, Project};
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_extract_tags() {
+        assert_eq!(
+            extract_tags("#Meeting with acme#not-a-tag, then #review #meeting"),
+            vec!["meeting", "review"]
+        );
+        assert!(extract_tags("issue # 12").is_empty());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30m"), Some(1800));
+        assert_eq!(parse_duration("1h30m"), Some(5400));
+        assert_eq!(parse_duration("90"), Some(5400));
+        assert_eq!(parse_duration("1:15"), Some(4500));
+        assert_eq!(parse_duration("2x"), None);
+        assert_eq!(parse_duration("1h30"), None);
+    }
+
+    #[test]
+    fn test_precedence_and_types() {
+        let filter = Filter::parse("project ~ acme or not tag = meeting and hour >= 9").unwrap();
+        match filter.expr() {
+            Expr::Or(left, right) => {
+                assert!(matches!(
+                    **left,
+                    Expr::Compare {
+                        field: Field::Project,
+                        op: Op::Match,
+                        ..
+                    }
+                ));
+                assert!(matches!(**right, Expr::And(..)));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let filter = Filter::parse("weekday in (sat, sun)").unwrap();
+        assert_eq!(
+            filter.expr(),
+            &Expr::Compare {
+                field: Field::Weekday,
+                op: Op::In,
+                values: vec![
+                    Literal::Weekday(Weekday::Sat),
+                    Literal::Weekday(Weekday::Sun)
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Filter::parse("").is_err());
+        assert!(Filter::parse("colour = red").is_err());
+        assert!(Filter::parse("duration ~ 5m").is_err());
+        assert!(Filter::parse("duration > soon").is_err());
+        assert!(Filter::parse("project = \"acme").is_err());
+        assert!(Filter::parse("(project = a").is_err());
+        assert!(Filter::parse("project = a b").is_err());
+    }
+
+    #[test]
+    fn test_saved_filters() {
+        let mut saved = BTreeMap::new();
+        saved.insert("client".to_string(), "project ~ acme".to_string());
+        saved.insert("loop".to_string(), "@loop".to_string());
+
+        assert!(Filter::parse_with("@client and duration > 1h", &saved).is_ok());
+        assert!(Filter::parse_with("@missing", &saved).is_err());
+        assert!(Filter::parse_with("@loop", &saved).is_err());
+
+        let combined = Filter::from_args(Some("hour < 12"), Some("client"), &saved)
+            .unwrap()
+            .unwrap();
+        assert!(matches!(combined.expr(), Expr::And(..)));
+    }
+
+    #[test]
+    fn test_compiled_sql_matches_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path().join("test.db")).unwrap();
+
+        let mut acme = Project::new("Acme Corp");
+        db.create_project(&mut acme).unwrap();
+        let mut home = Project::new("Home");
+        db.create_project(&mut home).unwrap();
+        let call = db.get_or_create_task(acme.id, "Call").unwrap();
+        let chores = db.get_or_create_task(home.id, "Chores").unwrap();
+
+        // Saturday 2024-06-01 and Monday 2024-06-03, noon UTC.
+        let sat = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let mon = Utc.with_ymd_and_hms(2024, 6, 3, 12, 0, 0).unwrap();
+        for (project, task, start, minutes, notes) in [
+            (acme.id, call.id, sat, 45, "Weekly sync #meeting"),
+            (acme.id, call.id, mon, 20, "Quick #meeting, ad hoc"),
+            (acme.id, call.id, mon, 90, "#meetings are not meetings"),
+            (home.id, chores.id, sat, 60, "#meeting with myself"),
+        ] {
+            let mut entry = Entry::new(project, task)
+                .with_start(start)
+                .with_notes(notes);
+            entry.end = Some(start + Duration::minutes(minutes));
+            db.create_entry(&mut entry).unwrap();
+        }
+
+        let count = |expr: &str| {
+            db.list_entries_with_details_where(None, Some(&Filter::parse(expr).unwrap()))
+                .unwrap()
+                .len()
+        };
+
+        assert_eq!(count("project ~ \"acme\""), 3);
+        assert_eq!(count("project = 'acme corp'"), 3);
+        assert_eq!(count("tag = meeting"), 3);
+        assert_eq!(count("tag = meeting and duration > 30m"), 2);
+        assert_eq!(
+            count(
+                r#"project ~ "acme" and tag = meeting and duration > 30m and weekday in (sat, sun)"#
+            ),
+            1
+        );
+        assert_eq!(count("date = 2024-06-03"), 2);
+        assert_eq!(count("not project ~ acme"), 1);
+        assert_eq!(count("status = finished and commits = 0"), 4);
+        assert_eq!(count("notes ~ '100%'"), 0);
+    }
+}
@@ -54,7 +54,7 @@ impl FuzzyFinder {
             })
             .collect();
 
-        results.sort_by(|a, b| b.score.cmp(&a.score));
+        results.sort_by_key(|r| std::cmp::Reverse(r.score));
         results
     }
 
@@ -75,7 +75,7 @@ impl FuzzyFinder {
             })
             .collect();
 
-        results.sort_by(|a, b| b.score.cmp(&a.score));
+        results.sort_by_key(|r| std::cmp::Reverse(r.score));
         results
     }
 
@@ -194,7 +194,7 @@ impl FrecencyScorer {
     }
 
     fn frecency_path() -> Result<std::path::PathBuf> {
-        crate::config::Config::config_dir().map(|d| d.join("frecency.json"))
+        crate::config::Config::profile_dir().map(|d| d.join("frecency.json"))
     }
 }
 
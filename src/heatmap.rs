@@ -0,0 +1,447 @@
+//! Activity heatmap for MatteriaTrack
+//!
+//! A GitHub-style grid of tracked time per day: one column per week, one row
+//! per weekday, shaded along the theme's gradient. Shared by `mtrack heatmap`
+//! and the dashboard's heatmap tab.
+
+use crate::models::EntryWithDetails;
+use crate::theme::MateriaTheme;
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Columns in the default trailing view, as on GitHub.
+pub const DEFAULT_WEEKS: usize = 53;
+
+/// Shading levels including "nothing tracked".
+pub const LEVELS: usize = 5;
+
+const EMPTY_COLOR: (u8, u8, u8) = (60, 60, 60);
+const ASCII_CELLS: [char; LEVELS] = ['.', '-', '+', '*', '#'];
+pub const WEEKDAY_LABELS: [&str; 7] = ["Mon", "", "Wed", "", "Fri", "", ""];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Heatmap {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Seconds tracked per local day; days without time are absent
+    pub days: BTreeMap<NaiveDate, i64>,
+    pub max_seconds: i64,
+    pub total_seconds: i64,
+    pub active_days: usize,
+    pub longest_streak: Streak,
+    pub current_streak: usize,
+    /// Days off that bridge a streak instead of breaking it
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub absent_days: BTreeSet<NaiveDate>,
+    #[serde(skip)]
+    today: NaiveDate,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Streak {
+    pub days: usize,
+    pub start: Option<NaiveDate>,
+    pub end: Option<NaiveDate>,
+}
+
+impl Heatmap {
+    /// January 1st to December 31st of `year`.
+    pub fn year_range(year: i32) -> Option<(NaiveDate, NaiveDate)> {
+        Some((
+            NaiveDate::from_ymd_opt(year, 1, 1)?,
+            NaiveDate::from_ymd_opt(year, 12, 31)?,
+        ))
+    }
+
+    /// The last `weeks` whole weeks up to `today`, starting on a Monday.
+    pub fn trailing_range(today: NaiveDate, weeks: usize) -> (NaiveDate, NaiveDate) {
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let weeks = weeks.max(1) as i64;
+        (monday - Duration::weeks(weeks - 1), today)
+    }
+
+    /// Sum `entries` into days between `from` and `to`, by local start date.
+    pub fn build(
+        entries: &[EntryWithDetails],
+        from: NaiveDate,
+        to: NaiveDate,
+        today: NaiveDate,
+    ) -> Self {
+        let mut days: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+        for e in entries {
+            let date = e.entry.start.with_timezone(&Local).date_naive();
+            let secs = e.entry.duration().num_seconds();
+            if date >= from && date <= to && secs > 0 {
+                *days.entry(date).or_insert(0) += secs;
+            }
+        }
+
+        let absent_days = BTreeSet::new();
+        let longest_streak = longest_streak(&days, &absent_days);
+        let current_streak = current_streak(&days, &absent_days, today.min(to), today <= to);
+
+        Self {
+            from,
+            to,
+            max_seconds: days.values().copied().max().unwrap_or(0),
+            total_seconds: days.values().sum(),
+            active_days: days.len(),
+            days,
+            longest_streak,
+            current_streak,
+            absent_days,
+            today,
+        }
+    }
+
+    /// Let recorded absences bridge streaks.
+    pub fn with_absences(mut self, absent: impl IntoIterator<Item = NaiveDate>) -> Self {
+        self.absent_days = absent
+            .into_iter()
+            .filter(|d| (self.from..=self.to).contains(d))
+            .collect();
+        self.longest_streak = longest_streak(&self.days, &self.absent_days);
+        self.current_streak = current_streak(
+            &self.days,
+            &self.absent_days,
+            self.today.min(self.to),
+            self.today <= self.to,
+        );
+        self
+    }
+
+    /// Shading level 0..LEVELS for a day, in quarters of the busiest day.
+    pub fn level(&self, seconds: i64) -> usize {
+        if seconds <= 0 || self.max_seconds <= 0 {
+            return 0;
+        }
+        let quarters = (seconds * 4 + self.max_seconds - 1) / self.max_seconds;
+        quarters.clamp(1, 4) as usize
+    }
+
+    pub fn seconds_on(&self, date: NaiveDate) -> i64 {
+        self.days.get(&date).copied().unwrap_or(0)
+    }
+
+    /// Week columns from the Monday on or before `from`; days outside the
+    /// range are `None`.
+    pub fn weeks(&self) -> Vec<[Option<NaiveDate>; 7]> {
+        let mut monday =
+            self.from - Duration::days(self.from.weekday().num_days_from_monday() as i64);
+        let mut weeks = Vec::new();
+        while monday <= self.to {
+            let mut column = [None; 7];
+            for (i, slot) in column.iter_mut().enumerate() {
+                let day = monday + Duration::days(i as i64);
+                if day >= self.from && day <= self.to {
+                    *slot = Some(day);
+                }
+            }
+            weeks.push(column);
+            monday += Duration::weeks(1);
+        }
+        weeks
+    }
+
+    /// Month names placed over the week in which each month starts, two
+    /// characters per column.
+    pub fn month_header(&self) -> String {
+        let mut header = String::new();
+        for (col, week) in self.weeks().iter().enumerate() {
+            let starts_month = week
+                .iter()
+                .flatten()
+                .find(|d| d.day() == 1 || **d == self.from);
+            if let Some(day) = starts_month {
+                let pos = col * 2;
+                if header.chars().count() <= pos {
+                    let pad = pos - header.chars().count();
+                    header.push_str(&" ".repeat(pad));
+                    header.push_str(&day.format("%b").to_string());
+                }
+            }
+        }
+        header
+    }
+
+    /// Render for the terminal; `ascii` swaps coloured squares for characters.
+    pub fn render(&self, theme: MateriaTheme, ascii: bool) -> String {
+        let (r, g, b) = theme.primary_color();
+        let colors = gradient(theme);
+        let weeks = self.weeks();
+        let mut output = String::new();
+
+        let title = format!(
+            "Activity {} – {}",
+            self.from.format("%Y-%m-%d"),
+            self.to.format("%Y-%m-%d")
+        );
+        if ascii {
+            output.push_str(&format!("\n{}\n", title));
+        } else {
+            output.push_str(&format!(
+                "\n{} {} {}\n",
+                theme.icon(),
+                title.truecolor(r, g, b).bold(),
+                theme.materia_icon()
+            ));
+        }
+        output.push('\n');
+        output.push_str(&format!("    {}\n", self.month_header()));
+
+        for (row, label) in WEEKDAY_LABELS.iter().enumerate() {
+            output.push_str(&format!("{:<4}", label));
+            for week in &weeks {
+                match week[row] {
+                    Some(day) => {
+                        let level = self.level(self.seconds_on(day));
+                        output.push_str(&cell(level, &colors, ascii));
+                    }
+                    None => output.push(' '),
+                }
+                output.push(' ');
+            }
+            output.push('\n');
+        }
+
+        output.push('\n');
+        output.push_str("    Less ");
+        for level in 0..LEVELS {
+            output.push_str(&cell(level, &colors, ascii));
+            output.push(' ');
+        }
+        output.push_str("More");
+        if self.max_seconds > 0 {
+            output.push_str(&format!(
+                "   (darkest: up to {} a day)",
+                format_hours(self.max_seconds)
+            ));
+        }
+        output.push_str("\n\n");
+
+        let longest = match (self.longest_streak.start, self.longest_streak.end) {
+            (Some(start), Some(end)) => format!(
+                "{} ({} → {})",
+                days(self.longest_streak.days),
+                start.format("%Y-%m-%d"),
+                end.format("%Y-%m-%d")
+            ),
+            _ => days(0),
+        };
+        output.push_str(&format!(
+            "    Current streak: {}   Longest streak: {}\n",
+            days(self.current_streak),
+            longest
+        ));
+        output.push_str(&format!(
+            "    {} tracked on {}\n",
+            format_hours(self.total_seconds),
+            days(self.active_days)
+        ));
+
+        output
+    }
+}
+
+/// Colours for each level: grey for empty days, then towards the primary colour.
+pub fn gradient(theme: MateriaTheme) -> [(u8, u8, u8); LEVELS] {
+    let (r, g, b) = theme.primary_color();
+    let (er, eg, eb) = EMPTY_COLOR;
+    let mix = |from: u8, to: u8, t: f64| (from as f64 + (to as f64 - from as f64) * t) as u8;
+
+    let mut colors = [EMPTY_COLOR; LEVELS];
+    for (level, color) in colors.iter_mut().enumerate().skip(1) {
+        let t = 0.25 + 0.75 * (level - 1) as f64 / (LEVELS - 2) as f64;
+        *color = (mix(er, r, t), mix(eg, g, t), mix(eb, b, t));
+    }
+    colors
+}
+
+fn cell(level: usize, colors: &[(u8, u8, u8); LEVELS], ascii: bool) -> String {
+    if ascii {
+        ASCII_CELLS[level].to_string()
+    } else {
+        let (r, g, b) = colors[level];
+        "■".truecolor(r, g, b).to_string()
+    }
+}
+
+fn longest_streak(days: &BTreeMap<NaiveDate, i64>, absent: &BTreeSet<NaiveDate>) -> Streak {
+    let mut best = Streak::default();
+    let mut run: Option<(NaiveDate, NaiveDate, usize)> = None;
+
+    for &date in days.keys() {
+        run = match run {
+            Some((start, end, len))
+                if (end + Duration::days(1))
+                    .iter_days()
+                    .take_while(|d| *d < date)
+                    .all(|d| absent.contains(&d)) =>
+            {
+                Some((start, date, len + 1))
+            }
+            _ => Some((date, date, 1)),
+        };
+        if let Some((start, end, len)) = run {
+            if len > best.days {
+                best = Streak {
+                    days: len,
+                    start: Some(start),
+                    end: Some(end),
+                };
+            }
+        }
+    }
+    best
+}
+
+/// Consecutive tracked days ending at `anchor`, skipping over absences. When
+/// `anchor` is today and nothing has been tracked yet, the streak may still
+/// end yesterday.
+fn current_streak(
+    days: &BTreeMap<NaiveDate, i64>,
+    absent: &BTreeSet<NaiveDate>,
+    anchor: NaiveDate,
+    is_today: bool,
+) -> usize {
+    let mut day = anchor;
+    if is_today && !days.contains_key(&day) {
+        day -= Duration::days(1);
+    }
+
+    let mut streak = 0;
+    loop {
+        if days.contains_key(&day) {
+            streak += 1;
+        } else if !absent.contains(&day) {
+            break;
+        }
+        day -= Duration::days(1);
+    }
+    streak
+}
+
+pub(crate) fn days(n: usize) -> String {
+    if n == 1 {
+        "1 day".to_string()
+    } else {
+        format!("{} days", n)
+    }
+}
+
+fn format_hours(seconds: i64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Entry;
+    use chrono::{TimeZone, Utc};
+
+    fn date(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, m, d).unwrap()
+    }
+
+    fn entry(day: NaiveDate, hours: i64) -> EntryWithDetails {
+        let start = Local
+            .from_local_datetime(&day.and_hms_opt(9, 0, 0).unwrap())
+            .unwrap()
+            .with_timezone(&Utc);
+        EntryWithDetails {
+            entry: Entry {
+                start,
+                end: Some(start + Duration::hours(hours)),
+                ..Entry::new(1, 1)
+            },
+            project_name: "Acme".into(),
+            task_name: "api".into(),
+            project_color: None,
+            commits: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_streaks() {
+        let entries: Vec<_> = [
+            (date(3, 2), 1),
+            (date(3, 3), 2),
+            (date(3, 4), 8),
+            (date(3, 10), 1),
+            (date(3, 11), 1),
+        ]
+        .iter()
+        .map(|(d, h)| entry(*d, *h))
+        .collect();
+
+        let (from, to) = Heatmap::year_range(2026).unwrap();
+        let map = Heatmap::build(&entries, from, to, date(3, 12));
+        assert_eq!(map.longest_streak.days, 3);
+        assert_eq!(map.longest_streak.start, Some(date(3, 2)));
+        // Nothing tracked today yet, but yesterday continues the streak
+        assert_eq!(map.current_streak, 2);
+        assert_eq!(map.active_days, 5);
+        assert_eq!(map.total_seconds, 13 * 3600);
+
+        let later = Heatmap::build(&entries, from, to, date(3, 14));
+        assert_eq!(later.current_streak, 0);
+
+        // A week of vacation joins both runs
+        let bridged = Heatmap::build(&entries, from, to, date(3, 12))
+            .with_absences((5..=9).map(|d| date(3, d)));
+        assert_eq!(bridged.longest_streak.days, 5);
+        assert_eq!(bridged.longest_streak.end, Some(date(3, 11)));
+        assert_eq!(bridged.current_streak, 5);
+    }
+
+    #[test]
+    fn test_levels() {
+        let entries = vec![entry(date(1, 5), 8), entry(date(1, 6), 1)];
+        let (from, to) = Heatmap::year_range(2026).unwrap();
+        let map = Heatmap::build(&entries, from, to, date(12, 31));
+        assert_eq!(map.level(0), 0);
+        assert_eq!(map.level(3600), 1);
+        assert_eq!(map.level(4 * 3600), 2);
+        assert_eq!(map.level(8 * 3600), 4);
+    }
+
+    #[test]
+    fn test_grid_shape() {
+        let (from, to) = Heatmap::trailing_range(date(10, 18), DEFAULT_WEEKS);
+        assert_eq!(from.weekday(), chrono::Weekday::Mon);
+        let map = Heatmap::build(&[], from, to, date(10, 18));
+        let weeks = map.weeks();
+        assert_eq!(weeks.len(), DEFAULT_WEEKS);
+        assert!(weeks.iter().all(|w| w.len() == 7));
+
+        // 2026 starts on a Thursday, so the first column is partly empty
+        let (from, to) = Heatmap::year_range(2026).unwrap();
+        let year = Heatmap::build(&[], from, to, date(10, 18));
+        let weeks = year.weeks();
+        assert_eq!(weeks.len(), 53);
+        assert_eq!(weeks[0][0], None);
+        assert_eq!(weeks[0][3], Some(date(1, 1)));
+        assert!(year.month_header().starts_with("Jan"));
+    }
+
+    #[test]
+    fn test_ascii_render() {
+        let entries = vec![entry(date(1, 5), 8)];
+        let (from, to) = Heatmap::year_range(2026).unwrap();
+        let text =
+            Heatmap::build(&entries, from, to, date(12, 31)).render(MateriaTheme::Fire, true);
+        assert!(text.contains("Less . - + * # More"));
+        assert!(text.contains("Longest streak: 1 day ("));
+        assert!(text
+            .lines()
+            .any(|l| l.starts_with("Mon ") && l.contains('#')));
+    }
+}
@@ -0,0 +1,256 @@
+//! Shared import pipeline for MatteriaTrack
+//!
+//! Every importer (Zeit, JSON, calendar meetings) turns its source into
+//! [`ImportRow`]s and hands them to [`import_rows`], which inserts them in one
+//! transaction and reports per-row outcomes.
+
+use crate::database::{Database, ImportOptions, ImportRow, RowAction};
+use crate::error::Result;
+use chrono::{DateTime, Local, Utc};
+use colored::Colorize;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportResult {
+    pub projects_imported: usize,
+    pub tasks_imported: usize,
+    pub entries_imported: usize,
+    pub entries_updated: usize,
+    pub duplicates: usize,
+    pub conflicts_skipped: usize,
+    /// Nothing was written; the counts are what an import would do
+    pub dry_run: bool,
+    /// One change per readable row, in source order
+    pub changes: Vec<ImportChange>,
+    pub errors: Vec<String>,
+}
+
+/// What an import does (or would do) with one source row.
+#[derive(Debug, Clone)]
+pub struct ImportChange {
+    /// Row number in the source, from 1
+    pub row: usize,
+    pub action: RowAction,
+    pub entry: ImportRow,
+    pub previous_notes: Option<String>,
+}
+
+impl ImportResult {
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} {} projects, {} tasks, {} entries",
+            if self.dry_run {
+                "Would import"
+            } else {
+                "Imported"
+            },
+            self.projects_imported,
+            self.tasks_imported,
+            self.entries_imported
+        );
+
+        let mut notes = Vec::new();
+        if self.entries_updated > 0 {
+            notes.push(format!("{} updated", self.entries_updated));
+        }
+        if self.duplicates > 0 {
+            notes.push(format!("{} already imported", self.duplicates));
+        }
+        if self.conflicts_skipped > 0 {
+            notes.push(format!(
+                "{} conflicts kept as they were",
+                self.conflicts_skipped
+            ));
+        }
+        if !self.errors.is_empty() {
+            notes.push(format!("{} rows skipped", self.errors.len()));
+        }
+        if !notes.is_empty() {
+            summary.push_str(&format!(" ({})", notes.join(", ")));
+        }
+        summary
+    }
+
+    /// Diff-style preview: `+` new, `~` notes replaced, `!` conflict kept, `=` already there.
+    pub fn format_diff(&self) -> String {
+        let mut output = String::new();
+        for change in &self.changes {
+            let e = &change.entry;
+            let line = format!(
+                "row {:<4} {} → {}  {}/{}",
+                change.row,
+                e.start.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                e.end.map_or("…".to_string(), |t| t
+                    .with_timezone(&Local)
+                    .format("%H:%M")
+                    .to_string()),
+                e.project.trim(),
+                e.task.trim()
+            );
+            let notes = |n: &Option<String>| format!("{:?}", n.as_deref().unwrap_or(""));
+            let formatted = match change.action {
+                RowAction::Added => format!("+ {}  {}", line, notes(&e.notes)).green(),
+                RowAction::KeptBoth => {
+                    format!("+ {}  {} (kept both)", line, notes(&e.notes)).green()
+                }
+                RowAction::Overwritten => format!(
+                    "~ {}  notes {} → {}",
+                    line,
+                    notes(&change.previous_notes),
+                    notes(&e.notes)
+                )
+                .yellow(),
+                RowAction::ConflictSkipped => format!(
+                    "! {}  keeps {}, not {}",
+                    line,
+                    notes(&change.previous_notes),
+                    notes(&e.notes)
+                )
+                .red(),
+                RowAction::Duplicate => format!("= {}  already imported", line).dimmed(),
+            };
+            output.push_str(&format!("{}\n", formatted));
+        }
+        output
+    }
+}
+
+/// Bulk-insert the readable rows and report the rest, numbering rows from 1
+/// in source order.
+pub fn import_rows(
+    rows: Vec<std::result::Result<ImportRow, String>>,
+    target_db: &Database,
+    options: &ImportOptions,
+) -> Result<ImportResult> {
+    let mut result = ImportResult::default();
+    let mut valid = Vec::new();
+    let mut source_rows = Vec::new();
+    let mut errors = Vec::new();
+
+    for (i, row) in rows.into_iter().enumerate() {
+        match row {
+            Ok(row) => {
+                valid.push(row);
+                source_rows.push(i + 1);
+            }
+            Err(message) => errors.push((i + 1, message)),
+        }
+    }
+
+    let report = target_db.bulk_insert_with(&valid, options)?;
+    result.projects_imported = report.projects_created;
+    result.tasks_imported = report.tasks_created;
+    result.entries_imported = report.entries_created;
+    result.entries_updated = report.entries_updated;
+    result.duplicates = report.duplicates;
+    result.conflicts_skipped = report.conflicts_skipped;
+    result.dry_run = options.dry_run;
+    result.changes = report
+        .changes
+        .into_iter()
+        .map(|change| ImportChange {
+            row: source_rows[change.index],
+            action: change.action,
+            entry: valid[change.index].clone(),
+            previous_notes: change.previous_notes,
+        })
+        .collect();
+    errors.extend(
+        report
+            .errors
+            .into_iter()
+            .map(|error| (source_rows[error.index], error.message)),
+    );
+    errors.sort_by_key(|(row, _)| *row);
+    result.errors = errors
+        .into_iter()
+        .map(|(row, message)| format!("row {}: {}", row, message))
+        .collect();
+
+    Ok(result)
+}
+
+/// Import a JSON array of entries in one transaction, skipping entries
+/// imported before. Rows that do not match the expected shape are listed in
+/// [`ImportResult::errors`].
+pub fn import_from_json<P: AsRef<Path>>(
+    path: P,
+    target_db: &Database,
+    options: &ImportOptions,
+) -> Result<ImportResult> {
+    let content = std::fs::read_to_string(path)?;
+    let values: Vec<serde_json::Value> = serde_json::from_str(&content)?;
+
+    let rows = values
+        .into_iter()
+        .map(|value| {
+            serde_json::from_value::<JsonEntry>(value)
+                .map(|je| ImportRow {
+                    project: je.project,
+                    task: je.task.unwrap_or_else(|| "default".to_string()),
+                    start: je.start,
+                    end: je.end,
+                    notes: je.notes,
+                })
+                .map_err(|e| e.to_string())
+        })
+        .collect();
+
+    import_rows(rows, target_db, options)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct JsonEntry {
+    project: String,
+    task: Option<String>,
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+    notes: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_result_summary() {
+        let result = ImportResult {
+            projects_imported: 3,
+            tasks_imported: 10,
+            entries_imported: 50,
+            ..Default::default()
+        };
+
+        let summary = result.summary();
+        assert!(summary.contains("3 projects"));
+        assert!(summary.contains("10 tasks"));
+        assert!(summary.contains("50 entries"));
+    }
+
+    #[test]
+    fn test_import_from_json_reports_bad_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("entries.json");
+        std::fs::write(
+            &path,
+            r#"[
+                {"project": "Alpha", "task": "a", "start": "2024-01-15T10:00:00Z", "end": "2024-01-15T11:00:00Z"},
+                {"project": "Alpha", "start": "not a date"},
+                {"project": "Alpha", "task": "a", "start": "2024-01-15T12:00:00Z", "end": "2024-01-15T11:00:00Z"},
+                {"project": "Beta", "start": "2024-01-16T09:00:00Z", "notes": "no task"}
+            ]"#,
+        )
+        .unwrap();
+
+        let db = Database::open_in_memory().unwrap();
+        let result = import_from_json(&path, &db, &ImportOptions::default()).unwrap();
+
+        assert_eq!(result.projects_imported, 2);
+        assert_eq!(result.tasks_imported, 2);
+        assert_eq!(result.entries_imported, 2);
+        assert_eq!(result.errors.len(), 2);
+        assert!(result.errors[0].starts_with("row 2:"));
+        assert!(result.errors[1].starts_with("row 3:"));
+        assert!(result.summary().contains("2 rows skipped"));
+    }
+}
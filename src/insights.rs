@@ -0,0 +1,644 @@
+//! Productivity insights for MatteriaTrack
+//!
+//! Looks at how time is spent rather than how much: when work happens, how
+//! long sessions last, how often the project changes within a day, the
+//! longest stretches of focused work and whether focus is getting more or
+//! less fragmented from week to week.
+
+use crate::models::EntryWithDetails;
+use crate::stats::format_seconds;
+use crate::theme::MateriaTheme;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Timelike, Utc};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Blocks at least this long count as deep work.
+pub const DEEP_WORK_MINUTES: i64 = 90;
+/// Entries on the same project at most this far apart form one block.
+const BLOCK_GAP_MINUTES: i64 = 5;
+/// Time in blocks shorter than this counts as fragmented.
+const SHORT_BLOCK_MINUTES: i64 = 30;
+const TOP_BLOCKS: usize = 5;
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+/// Session length buckets as (label, upper bound in minutes).
+const BUCKETS: [(&str, i64); 6] = [
+    ("< 15m", 15),
+    ("15–30m", 30),
+    ("30m–1h", 60),
+    ("1–2h", 120),
+    ("2–4h", 240),
+    ("4h+", i64::MAX),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Insights {
+    pub period: String,
+    pub entry_count: usize,
+    pub total_seconds: i64,
+    pub histogram: Histogram,
+    pub sessions: Sessions,
+    pub context_switches: ContextSwitches,
+    pub deep_work: DeepWork,
+    pub weeks: Vec<WeekFocus>,
+    /// Change in fragmentation (percentage points) from the first to the last week
+    pub fragmentation_trend: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Histogram {
+    /// Seconds per weekday (Monday first) and local hour of day
+    pub cells: Vec<Vec<i64>>,
+    pub by_hour: Vec<i64>,
+    pub by_weekday: Vec<i64>,
+    pub peak_hour: Option<u32>,
+    pub peak_weekday: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Sessions {
+    pub count: usize,
+    pub median_seconds: i64,
+    pub mean_seconds: i64,
+    pub longest_seconds: i64,
+    pub distribution: Vec<Bucket>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Bucket {
+    pub label: String,
+    pub count: usize,
+    pub total_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextSwitches {
+    pub total: usize,
+    pub active_days: usize,
+    pub per_day: f64,
+    pub days: Vec<DaySwitches>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DaySwitches {
+    pub date: NaiveDate,
+    pub switches: usize,
+    pub projects: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeepWork {
+    pub threshold_minutes: i64,
+    pub blocks: usize,
+    pub total_seconds: i64,
+    pub share_percent: f64,
+    pub longest: Vec<Block>,
+}
+
+/// Back-to-back entries on one project.
+#[derive(Debug, Clone, Serialize)]
+pub struct Block {
+    pub project: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Tracked time, without the gaps between entries
+    pub seconds: i64,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WeekFocus {
+    pub week: String,
+    pub tracked_seconds: i64,
+    pub blocks: usize,
+    pub switches: usize,
+    pub active_days: usize,
+    pub median_block_seconds: i64,
+    /// Share of tracked time spent in blocks shorter than 30 minutes
+    pub fragmentation: f64,
+}
+
+impl Insights {
+    pub fn build(entries: &[EntryWithDetails], period: &str) -> Self {
+        let mut sorted: Vec<&EntryWithDetails> = entries.iter().collect();
+        sorted.sort_by_key(|e| e.entry.start);
+
+        let blocks = blocks(&sorted);
+        let total_seconds = sorted.iter().map(|e| seconds(e)).sum();
+        let weeks = weeks(&sorted, &blocks);
+        let fragmentation_trend = match (weeks.first(), weeks.last()) {
+            (Some(first), Some(last)) if weeks.len() > 1 => {
+                Some(last.fragmentation - first.fragmentation)
+            }
+            _ => None,
+        };
+
+        Self {
+            period: period.to_string(),
+            entry_count: sorted.len(),
+            total_seconds,
+            histogram: histogram(&sorted),
+            sessions: sessions(&sorted),
+            context_switches: context_switches(&sorted),
+            deep_work: deep_work(&blocks, total_seconds),
+            weeks,
+            fragmentation_trend,
+        }
+    }
+
+    pub fn format_pretty(&self, theme: MateriaTheme) -> String {
+        let (r, g, b) = theme.primary_color();
+        let mut output = String::new();
+        let section = |title: &str| format!("\n{}\n", title.bold());
+
+        output.push_str(&format!(
+            "\n{} {} {}\n",
+            theme.icon(),
+            format!("Insights: {}", self.period)
+                .truecolor(r, g, b)
+                .bold(),
+            theme.materia_icon()
+        ));
+        output.push_str(&"━".repeat(60));
+        output.push('\n');
+        output.push_str(&format!(
+            "\n⏱️  Total Time: {}   📊 Entries: {}\n",
+            format_seconds(self.total_seconds).truecolor(r, g, b).bold(),
+            self.entry_count
+        ));
+
+        if self.entry_count == 0 {
+            output.push_str("\n  No entries in this period.\n\n");
+            return output;
+        }
+
+        // Hour × weekday grid, shaded in quarters of the busiest cell
+        output.push_str(&section("🕘 When you work"));
+        let max = self
+            .histogram
+            .cells
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0);
+        output.push_str("       ");
+        for hour in (0..24).step_by(3) {
+            output.push_str(&format!("{:<6}", format!("{:02}", hour)));
+        }
+        output.push('\n');
+        for (day, row) in self.histogram.cells.iter().enumerate() {
+            output.push_str(&format!("   {} ", WEEKDAYS[day]));
+            for &cell in row {
+                output.push_str(&match shade_level(cell, max) {
+                    0 => "· ".dimmed().to_string(),
+                    1 => "░░".truecolor(r, g, b).to_string(),
+                    2 => "▒▒".truecolor(r, g, b).to_string(),
+                    3 => "▓▓".truecolor(r, g, b).to_string(),
+                    _ => "██".truecolor(r, g, b).to_string(),
+                });
+            }
+            output.push_str(&format!(
+                " {}\n",
+                format_seconds(self.histogram.by_weekday[day]).dimmed()
+            ));
+        }
+        if let (Some(hour), Some(day)) = (self.histogram.peak_hour, &self.histogram.peak_weekday) {
+            output.push_str(&format!(
+                "   Peak hour: {}   Busiest weekday: {}\n",
+                format!("{:02}:00", hour).truecolor(r, g, b).bold(),
+                day.truecolor(r, g, b).bold()
+            ));
+        }
+
+        output.push_str(&section("⏳ Session lengths"));
+        output.push_str(&format!(
+            "   {} sessions, median {}, mean {}, longest {}\n",
+            self.sessions.count,
+            format_seconds(self.sessions.median_seconds)
+                .truecolor(r, g, b)
+                .bold(),
+            format_seconds(self.sessions.mean_seconds),
+            format_seconds(self.sessions.longest_seconds)
+        ));
+        let most = self
+            .sessions
+            .distribution
+            .iter()
+            .map(|b| b.count)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        for bucket in &self.sessions.distribution {
+            let width = bucket.count * 30 / most;
+            output.push_str(&format!(
+                "   {:>7} {:>4} {}\n",
+                bucket.label,
+                bucket.count,
+                "█".repeat(width).truecolor(r, g, b)
+            ));
+        }
+
+        output.push_str(&section("🔀 Context switches"));
+        let switches = &self.context_switches;
+        output.push_str(&format!(
+            "   {:.1} per day ({} over {} active days)\n",
+            switches.per_day, switches.total, switches.active_days
+        ));
+        if let Some(day) = switches.days.iter().max_by_key(|d| (d.switches, d.date)) {
+            if day.switches > 0 {
+                output.push_str(&format!(
+                    "   Most scattered: {} with {} across {} projects\n",
+                    day.date.format("%a %Y-%m-%d"),
+                    plural(day.switches, "switch", "switches"),
+                    day.projects
+                ));
+            }
+        }
+
+        output.push_str(&section("🧠 Deep work"));
+        let deep = &self.deep_work;
+        output.push_str(&format!(
+            "   {} of {}m or more, {} ({:.0}% of tracked time)\n",
+            plural(deep.blocks, "block", "blocks"),
+            deep.threshold_minutes,
+            format_seconds(deep.total_seconds).truecolor(r, g, b).bold(),
+            deep.share_percent
+        ));
+        output.push_str(&"   Longest blocks:\n".dimmed().to_string());
+        for block in &deep.longest {
+            let start = block.start.with_timezone(&Local);
+            output.push_str(&format!(
+                "     {} {}–{}  {:>8}  {}\n",
+                start.format("%a %Y-%m-%d"),
+                start.format("%H:%M"),
+                block.end.with_timezone(&Local).format("%H:%M"),
+                format_seconds(block.seconds),
+                block.project.bold()
+            ));
+        }
+
+        output.push_str(&section("🧩 Fragmentation by week"));
+        output.push_str(
+            &format!(
+                "   {:<9} {:>9} {:>7} {:>10} {:>7}\n",
+                "Week", "Tracked", "Blocks", "Switch/day", "Short"
+            )
+            .dimmed()
+            .to_string(),
+        );
+        for week in &self.weeks {
+            let per_day = week.switches as f64 / week.active_days.max(1) as f64;
+            output.push_str(&format!(
+                "   {:<9} {:>9} {:>7} {:>10.1} {:>6.0}% {}\n",
+                week.week,
+                format_seconds(week.tracked_seconds),
+                week.blocks,
+                per_day,
+                week.fragmentation,
+                "▮"
+                    .repeat((week.fragmentation / 10.0).round() as usize)
+                    .truecolor(r, g, b)
+            ));
+        }
+        if let Some(trend) = self.fragmentation_trend {
+            let text = if trend.abs() < 5.0 {
+                "steady".dimmed().to_string()
+            } else if trend < 0.0 {
+                format!("▼ {:.0} points, more focused", -trend)
+                    .truecolor(r, g, b)
+                    .to_string()
+            } else {
+                format!("▲ {:.0} points, more fragmented", trend)
+                    .truecolor(150, 150, 150)
+                    .to_string()
+            };
+            output.push_str(&format!("   Trend: {}\n", text));
+        }
+
+        output.push('\n');
+        output.push_str(&"━".repeat(60));
+        output.push('\n');
+        output
+    }
+}
+
+fn plural(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
+
+fn seconds(entry: &EntryWithDetails) -> i64 {
+    entry.entry.duration().num_seconds().max(0)
+}
+
+fn end(entry: &EntryWithDetails) -> DateTime<Utc> {
+    entry.entry.end.unwrap_or_else(Utc::now)
+}
+
+fn local_day(t: DateTime<Utc>) -> NaiveDate {
+    t.with_timezone(&Local).date_naive()
+}
+
+fn median(values: &mut [i64]) -> i64 {
+    if values.is_empty() {
+        return 0;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2
+    } else {
+        values[mid]
+    }
+}
+
+/// 0 for nothing, then 1–4 by quarters of `max`.
+fn shade_level(seconds: i64, max: i64) -> usize {
+    if seconds <= 0 || max <= 0 {
+        0
+    } else {
+        ((seconds * 4 + max - 1) / max).clamp(1, 4) as usize
+    }
+}
+
+/// Spread each entry over the local hours it covers.
+fn histogram(entries: &[&EntryWithDetails]) -> Histogram {
+    let mut cells = vec![vec![0i64; 24]; 7];
+    for entry in entries {
+        let mut t = entry.entry.start.with_timezone(&Local);
+        let end = end(entry).with_timezone(&Local);
+        while t < end {
+            let next_hour = (t + Duration::hours(1))
+                .with_minute(0)
+                .and_then(|n| n.with_second(0))
+                .and_then(|n| n.with_nanosecond(0))
+                .unwrap_or(end);
+            let slice_end = next_hour.min(end);
+            cells[t.weekday().num_days_from_monday() as usize][t.hour() as usize] +=
+                (slice_end - t).num_seconds();
+            t = slice_end;
+        }
+    }
+
+    let by_hour: Vec<i64> = (0..24).map(|h| cells.iter().map(|d| d[h]).sum()).collect();
+    let by_weekday: Vec<i64> = cells.iter().map(|d| d.iter().sum()).collect();
+    let peak = |values: &[i64]| {
+        values
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| **s > 0)
+            .max_by_key(|(i, s)| (**s, std::cmp::Reverse(*i)))
+            .map(|(i, _)| i)
+    };
+
+    Histogram {
+        peak_hour: peak(&by_hour).map(|h| h as u32),
+        peak_weekday: peak(&by_weekday).map(|d| WEEKDAYS[d].to_string()),
+        cells,
+        by_hour,
+        by_weekday,
+    }
+}
+
+fn sessions(entries: &[&EntryWithDetails]) -> Sessions {
+    let mut lengths: Vec<i64> = entries.iter().map(|e| seconds(e)).collect();
+    let total: i64 = lengths.iter().sum();
+
+    let mut distribution: Vec<Bucket> = BUCKETS
+        .iter()
+        .map(|(label, _)| Bucket {
+            label: label.to_string(),
+            count: 0,
+            total_seconds: 0,
+        })
+        .collect();
+    for &length in &lengths {
+        let i = BUCKETS
+            .iter()
+            .position(|(_, max)| length < max.saturating_mul(60))
+            .unwrap_or(BUCKETS.len() - 1);
+        distribution[i].count += 1;
+        distribution[i].total_seconds += length;
+    }
+
+    Sessions {
+        count: lengths.len(),
+        mean_seconds: total / (lengths.len().max(1) as i64),
+        longest_seconds: lengths.iter().copied().max().unwrap_or(0),
+        median_seconds: median(&mut lengths),
+        distribution,
+    }
+}
+
+/// Project changes between consecutive entries of the same local day.
+fn context_switches(entries: &[&EntryWithDetails]) -> ContextSwitches {
+    let mut by_day: BTreeMap<NaiveDate, Vec<&EntryWithDetails>> = BTreeMap::new();
+    for entry in entries {
+        by_day
+            .entry(local_day(entry.entry.start))
+            .or_default()
+            .push(entry);
+    }
+
+    let days: Vec<DaySwitches> = by_day
+        .into_iter()
+        .map(|(date, day)| {
+            let mut projects: Vec<&str> = day.iter().map(|e| e.project_name.as_str()).collect();
+            let switches = projects.windows(2).filter(|w| w[0] != w[1]).count();
+            projects.sort_unstable();
+            projects.dedup();
+            DaySwitches {
+                date,
+                switches,
+                projects: projects.len(),
+            }
+        })
+        .collect();
+
+    let total = days.iter().map(|d| d.switches).sum();
+    ContextSwitches {
+        total,
+        active_days: days.len(),
+        per_day: total as f64 / days.len().max(1) as f64,
+        days,
+    }
+}
+
+/// Merge consecutive entries on the same project separated by short gaps.
+fn blocks(entries: &[&EntryWithDetails]) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::new();
+    for entry in entries {
+        match blocks.last_mut() {
+            Some(block)
+                if block.project == entry.project_name
+                    && entry.entry.start - block.end <= Duration::minutes(BLOCK_GAP_MINUTES) =>
+            {
+                block.end = block.end.max(end(entry));
+                block.seconds += seconds(entry);
+                block.entries += 1;
+            }
+            _ => blocks.push(Block {
+                project: entry.project_name.clone(),
+                start: entry.entry.start,
+                end: end(entry),
+                seconds: seconds(entry),
+                entries: 1,
+            }),
+        }
+    }
+    blocks
+}
+
+fn deep_work(blocks: &[Block], total_seconds: i64) -> DeepWork {
+    let deep: Vec<&Block> = blocks
+        .iter()
+        .filter(|b| b.seconds >= DEEP_WORK_MINUTES * 60)
+        .collect();
+    let deep_seconds: i64 = deep.iter().map(|b| b.seconds).sum();
+
+    let mut longest = blocks.to_vec();
+    longest.sort_by_key(|b| (std::cmp::Reverse(b.seconds), b.start));
+    longest.truncate(TOP_BLOCKS);
+
+    DeepWork {
+        threshold_minutes: DEEP_WORK_MINUTES,
+        blocks: deep.len(),
+        total_seconds: deep_seconds,
+        share_percent: percent(deep_seconds, total_seconds),
+        longest,
+    }
+}
+
+fn weeks(entries: &[&EntryWithDetails], blocks: &[Block]) -> Vec<WeekFocus> {
+    let week_of = |t: DateTime<Utc>| local_day(t).format("%G-W%V").to_string();
+    let switches = context_switches(entries);
+
+    let mut weeks: BTreeMap<String, (Vec<i64>, usize, usize)> = BTreeMap::new();
+    for block in blocks {
+        weeks
+            .entry(week_of(block.start))
+            .or_default()
+            .0
+            .push(block.seconds);
+    }
+    for day in &switches.days {
+        let week = weeks
+            .entry(day.date.format("%G-W%V").to_string())
+            .or_default();
+        week.1 += day.switches;
+        week.2 += 1;
+    }
+
+    weeks
+        .into_iter()
+        .map(|(week, (mut lengths, switches, active_days))| {
+            let tracked: i64 = lengths.iter().sum();
+            let short: i64 = lengths
+                .iter()
+                .filter(|s| **s < SHORT_BLOCK_MINUTES * 60)
+                .sum();
+            WeekFocus {
+                week,
+                tracked_seconds: tracked,
+                blocks: lengths.len(),
+                switches,
+                active_days,
+                fragmentation: percent(short, tracked),
+                median_block_seconds: median(&mut lengths),
+            }
+        })
+        .collect()
+}
+
+fn percent(part: i64, whole: i64) -> f64 {
+    if whole > 0 {
+        part as f64 / whole as f64 * 100.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::fixtures::{local, row, seeded};
+
+    fn entries() -> Vec<EntryWithDetails> {
+        let db = seeded(&[
+            // Monday of ISO week 41: one long block split by a short break,
+            // then two short hops to another project and back
+            row("Acme", "work", local("2026-10-05 09:00"), 60),
+            row("Acme", "work", local("2026-10-05 10:03"), 60),
+            row("Beta", "work", local("2026-10-05 13:00"), 10),
+            row("Acme", "work", local("2026-10-05 13:15"), 20),
+            // Monday of week 42: scattered short entries
+            row("Beta", "work", local("2026-10-12 09:30"), 20),
+            row("Acme", "work", local("2026-10-12 10:00"), 20),
+        ]);
+        db.list_entries_with_details_between(None, None, None)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_histogram_splits_entries_across_hours() {
+        let insights = Insights::build(&entries(), "test");
+        let monday = &insights.histogram.cells[0];
+        assert_eq!(monday[9], (60 + 20) * 60);
+        assert_eq!(monday[10], (57 + 20) * 60);
+        assert_eq!(monday[11], 3 * 60);
+        assert_eq!(insights.histogram.by_weekday[0], insights.total_seconds);
+        assert_eq!(insights.histogram.peak_hour, Some(9));
+        assert_eq!(insights.histogram.peak_weekday.as_deref(), Some("Mon"));
+    }
+
+    #[test]
+    fn test_sessions_and_context_switches() {
+        let insights = Insights::build(&entries(), "test");
+        assert_eq!(insights.sessions.count, 6);
+        assert_eq!(insights.sessions.median_seconds, 20 * 60);
+        assert_eq!(insights.sessions.longest_seconds, 60 * 60);
+        let counts: Vec<usize> = insights
+            .sessions
+            .distribution
+            .iter()
+            .map(|b| b.count)
+            .collect();
+        assert_eq!(counts, vec![1, 3, 0, 2, 0, 0]);
+
+        let switches = &insights.context_switches;
+        assert_eq!(switches.days[0].switches, 2);
+        assert_eq!(switches.days[0].projects, 2);
+        assert_eq!(switches.days[1].switches, 1);
+        assert_eq!(switches.total, 3);
+        assert!((switches.per_day - 1.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_deep_work_blocks_and_fragmentation_trend() {
+        let insights = Insights::build(&entries(), "test");
+        let deep = &insights.deep_work;
+        assert_eq!(deep.blocks, 1);
+        assert_eq!(deep.longest[0].project, "Acme");
+        assert_eq!(deep.longest[0].seconds, 120 * 60);
+        assert_eq!(deep.longest[0].entries, 2);
+
+        assert_eq!(insights.weeks.len(), 2);
+        assert_eq!(insights.weeks[0].week, "2026-W41");
+        assert_eq!(insights.weeks[0].blocks, 3);
+        assert!((insights.weeks[0].fragmentation - 30.0 / 150.0 * 100.0).abs() < 1e-9);
+        assert!((insights.weeks[1].fragmentation - 100.0).abs() < 1e-9);
+        assert!(insights.fragmentation_trend.unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_empty_period() {
+        let insights = Insights::build(&[], "empty");
+        assert_eq!(insights.sessions.count, 0);
+        assert_eq!(insights.sessions.median_seconds, 0);
+        assert!(insights.histogram.peak_hour.is_none());
+        assert!(insights.fragmentation_trend.is_none());
+        assert!(insights
+            .format_pretty(MateriaTheme::Fire)
+            .contains("No entries"));
+    }
+}
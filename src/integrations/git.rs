@@ -4,7 +4,7 @@
 
 use crate::config::Config;
 use crate::error::Result;
-use crate::models::Entry;
+use crate::models::{Entry, EntryCommit};
 use chrono::{DateTime, Utc};
 use git2::{Repository, Sort};
 use std::path::{Path, PathBuf};
@@ -68,6 +68,7 @@ impl GitIntegration {
                 }
 
                 if commit_time <= until_ts && commit_time >= since_ts {
+                    let stats = diff_stats(&repo, &commit);
                     let git_commit = GitCommit {
                         hash: commit.id().to_string(),
                         short_hash: commit.id().to_string()[..7].to_string(),
@@ -78,6 +79,9 @@ impl GitIntegration {
                         timestamp: DateTime::from_timestamp(commit_time, 0)
                             .unwrap_or_else(Utc::now),
                         repo_path: repo_path.to_path_buf(),
+                        files_changed: stats.as_ref().map(|s| s.files_changed()),
+                        insertions: stats.as_ref().map(|s| s.insertions()),
+                        deletions: stats.as_ref().map(|s| s.deletions()),
                     };
                     commits.push(git_commit);
                 }
@@ -102,7 +106,7 @@ impl GitIntegration {
             }
         }
 
-        all_commits.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        all_commits.sort_by_key(|c| c.timestamp);
         Ok(all_commits)
     }
 
@@ -204,6 +208,9 @@ pub struct GitCommit {
     pub author_email: String,
     pub timestamp: DateTime<Utc>,
     pub repo_path: PathBuf,
+    pub files_changed: Option<usize>,
+    pub insertions: Option<usize>,
+    pub deletions: Option<usize>,
 }
 
 impl GitCommit {
@@ -211,6 +218,22 @@ impl GitCommit {
         format!("{}: {}", self.short_hash, truncate(&self.message, 50))
     }
 
+    pub fn to_entry_commit(&self) -> EntryCommit {
+        EntryCommit {
+            id: 0,
+            entry_id: 0,
+            hash: self.hash.clone(),
+            repo_path: Some(self.repo_path.display().to_string()),
+            author: Some(self.author.clone()),
+            author_email: Some(self.author_email.clone()),
+            committed_at: Some(self.timestamp),
+            summary: self.message.clone(),
+            files_changed: self.files_changed.map(|n| n as i64),
+            insertions: self.insertions.map(|n| n as i64),
+            deletions: self.deletions.map(|n| n as i64),
+        }
+    }
+
     pub fn format_full(&self) -> String {
         format!(
             "[{}] {} - {} <{}>\n{}",
@@ -231,6 +254,16 @@ pub struct RepoInfo {
     pub remote_url: Option<String>,
 }
 
+/// Diff a commit against its first parent (or the empty tree for root commits).
+fn diff_stats(repo: &Repository, commit: &git2::Commit) -> Option<git2::DiffStats> {
+    let tree = commit.tree().ok()?;
+    let parent_tree = commit.parent(0).ok().and_then(|p| p.tree().ok());
+    repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+        .ok()?
+        .stats()
+        .ok()
+}
+
 fn truncate(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
@@ -12,6 +12,7 @@ pub mod obsidian;
 pub mod zeit;
 
 use crate::config::Config;
+use crate::database::ImportRow;
 use crate::error::Result;
 use crate::models::Entry;
 use std::path::PathBuf;
@@ -33,7 +34,8 @@ pub trait TimeExporter: Integration {
 }
 
 pub trait TimeImporter: Integration {
-    fn import_entries(&self, config: &Config) -> Result<Vec<Entry>>;
+    /// Entries to import, named by project and task for [`crate::Database::bulk_insert`].
+    fn import_entries(&self, config: &Config) -> Result<Vec<ImportRow>>;
 }
 
 #[derive(Debug, Clone)]
@@ -409,6 +409,7 @@ mod tests {
             project_name: "TestProject".to_string(),
             task_name: "TestTask".to_string(),
             project_color: None,
+            commits: Vec::new(),
         };
 
         let block = obs.format_time_block(&entry);
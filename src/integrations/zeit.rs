@@ -3,23 +3,26 @@
 //! Imports time entries from Zeit time tracker SQLite database.
 
 use crate::config::Config;
-use crate::database::Database;
+use crate::database::{Database, ImportOptions, ImportRow};
 use crate::error::{ConfigError, Result};
-use crate::models::{Entry, Project, Task};
+use crate::import::{import_rows, ImportResult};
 use chrono::{DateTime, TimeZone, Utc};
 use rusqlite::Connection;
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 use super::{Integration, TimeImporter};
 
 pub struct ZeitImporter {
     db_path: Option<PathBuf>,
+    options: ImportOptions,
 }
 
 impl ZeitImporter {
     pub fn new() -> Self {
-        Self { db_path: None }
+        Self {
+            db_path: None,
+            options: ImportOptions::default(),
+        }
     }
 
     pub fn with_db_path(mut self, path: PathBuf) -> Self {
@@ -27,6 +30,11 @@ impl ZeitImporter {
         self
     }
 
+    pub fn with_options(mut self, options: ImportOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     pub fn default_zeit_path() -> Option<PathBuf> {
         dirs::data_local_dir().map(|d| d.join("zeit").join("zeit.db"))
     }
@@ -42,6 +50,9 @@ impl ZeitImporter {
             })
     }
 
+    /// Import every Zeit entry in one transaction, skipping entries imported
+    /// before. Rows that cannot be read are listed in [`ImportResult::errors`];
+    /// a database error imports nothing.
     pub fn import_to_database(&self, target_db: &Database) -> Result<ImportResult> {
         let zeit_path = self.get_db_path()?;
 
@@ -53,69 +64,18 @@ impl ZeitImporter {
         }
 
         let zeit_conn = Connection::open(&zeit_path)?;
-
-        let mut project_map: HashMap<String, Project> = HashMap::new();
-        let mut task_map: HashMap<(i64, String), Task> = HashMap::new();
-
-        let mut result = ImportResult::default();
-
-        let zeit_projects = self.read_zeit_projects(&zeit_conn)?;
-        for zp in zeit_projects {
-            let mut project = Project::new(&zp.name);
-            target_db.create_project(&mut project)?;
-            project_map.insert(zp.name.clone(), project);
-            result.projects_imported += 1;
-        }
-
-        let zeit_entries = self.read_zeit_entries(&zeit_conn)?;
-
-        for ze in zeit_entries {
-            let project = project_map.entry(ze.project.clone()).or_insert_with(|| {
-                let mut p = Project::new(&ze.project);
-                let _ = target_db.create_project(&mut p);
-                result.projects_imported += 1;
-                p
-            });
-
-            let task_key = (project.id, ze.task.clone());
-            let task = task_map.entry(task_key.clone()).or_insert_with(|| {
-                let mut t = Task::new(project.id, &ze.task);
-                let _ = target_db.create_task(&mut t);
-                result.tasks_imported += 1;
-                t
-            });
-
-            let mut entry = Entry {
-                id: 0,
-                project_id: project.id,
-                task_id: task.id,
-                start: ze.start,
-                end: ze.end,
-                notes: ze.notes,
-                git_commits: Vec::new(),
-            };
-
-            target_db.create_entry(&mut entry)?;
-            result.entries_imported += 1;
-        }
-
-        Ok(result)
-    }
-
-    fn read_zeit_projects(&self, conn: &Connection) -> Result<Vec<ZeitProject>> {
-        let mut stmt = conn.prepare(
-            "SELECT DISTINCT project FROM entries WHERE project IS NOT NULL AND project != ''",
-        )?;
-
-        let projects = stmt
-            .query_map([], |row| Ok(ZeitProject { name: row.get(0)? }))?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        Ok(projects)
+        import_rows(
+            self.read_zeit_entries(&zeit_conn)?,
+            target_db,
+            &self.options,
+        )
     }
 
-    fn read_zeit_entries(&self, conn: &Connection) -> Result<Vec<ZeitEntry>> {
+    /// Read all Zeit entries in order; unreadable rows become `Err` with a reason.
+    fn read_zeit_entries(
+        &self,
+        conn: &Connection,
+    ) -> Result<Vec<std::result::Result<ImportRow, String>>> {
         let has_task_column = conn.prepare("SELECT task FROM entries LIMIT 1").is_ok();
 
         let query = if has_task_column {
@@ -133,25 +93,35 @@ impl ZeitImporter {
         let entries = stmt
             .query_map([], |row| {
                 let project: String = row.get(0)?;
-                let task: String = row
+                let task = row
                     .get::<_, Option<String>>(1)?
+                    .filter(|t| !t.trim().is_empty())
                     .unwrap_or_else(|| "default".to_string());
-                let start_str: String = row.get(2)?;
+                let start_str: Option<String> = row.get(2)?;
                 let end_str: Option<String> = row.get(3)?;
                 let notes: Option<String> = row.get(4)?;
 
-                let start = parse_zeit_datetime(&start_str);
-                let end = end_str.as_ref().map(|s| parse_zeit_datetime(s));
-
-                Ok(ZeitEntry {
+                let start_str = start_str.unwrap_or_default();
+                let Some(start) = parse_zeit_datetime(&start_str) else {
+                    return Ok(Err(format!("invalid start time '{}'", start_str)));
+                };
+                let end = match end_str.filter(|s| !s.is_empty()) {
+                    Some(s) => match parse_zeit_datetime(&s) {
+                        Some(end) => Some(end),
+                        None => return Ok(Err(format!("invalid finish time '{}'", s))),
+                    },
+                    None => None,
+                };
+
+                Ok(Ok(ImportRow {
                     project,
                     task,
                     start,
                     end,
                     notes,
-                })
+                }))
             })?
-            .filter_map(|r| r.ok())
+            .map(|r| r.unwrap_or_else(|e| Err(e.to_string())))
             .collect();
 
         Ok(entries)
@@ -191,8 +161,8 @@ impl ZeitImporter {
             },
         )?;
 
-        let oldest = date_range.0.map(|s| parse_zeit_datetime(&s));
-        let newest = date_range.1.map(|s| parse_zeit_datetime(&s));
+        let oldest = date_range.0.and_then(|s| parse_zeit_datetime(&s));
+        let newest = date_range.1.and_then(|s| parse_zeit_datetime(&s));
 
         Ok(ImportPreview {
             source_path: zeit_path,
@@ -235,57 +205,15 @@ impl Integration for ZeitImporter {
 }
 
 impl TimeImporter for ZeitImporter {
-    fn import_entries(&self, _config: &Config) -> Result<Vec<Entry>> {
+    fn import_entries(&self, _config: &Config) -> Result<Vec<ImportRow>> {
         let zeit_path = self.get_db_path()?;
         let conn = Connection::open(&zeit_path)?;
 
-        let zeit_entries = self.read_zeit_entries(&conn)?;
-
-        let entries: Vec<Entry> = zeit_entries
+        Ok(self
+            .read_zeit_entries(&conn)?
             .into_iter()
-            .map(|ze| Entry {
-                id: 0,
-                project_id: 0,
-                task_id: 0,
-                start: ze.start,
-                end: ze.end,
-                notes: ze.notes,
-                git_commits: Vec::new(),
-            })
-            .collect();
-
-        Ok(entries)
-    }
-}
-
-#[derive(Debug, Clone)]
-struct ZeitProject {
-    name: String,
-}
-
-#[derive(Debug, Clone)]
-struct ZeitEntry {
-    project: String,
-    task: String,
-    start: DateTime<Utc>,
-    end: Option<DateTime<Utc>>,
-    notes: Option<String>,
-}
-
-#[derive(Debug, Clone, Default)]
-pub struct ImportResult {
-    pub projects_imported: usize,
-    pub tasks_imported: usize,
-    pub entries_imported: usize,
-    pub errors: Vec<String>,
-}
-
-impl ImportResult {
-    pub fn summary(&self) -> String {
-        format!(
-            "Imported {} projects, {} tasks, {} entries",
-            self.projects_imported, self.tasks_imported, self.entries_imported
-        )
+            .filter_map(|row| row.ok())
+            .collect())
     }
 }
 
@@ -317,7 +245,7 @@ impl ImportPreview {
     }
 }
 
-fn parse_zeit_datetime(s: &str) -> DateTime<Utc> {
+fn parse_zeit_datetime(s: &str) -> Option<DateTime<Utc>> {
     DateTime::parse_from_rfc3339(s)
         .map(|dt| dt.with_timezone(&Utc))
         .or_else(|_| {
@@ -328,58 +256,7 @@ fn parse_zeit_datetime(s: &str) -> DateTime<Utc> {
             chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")
                 .map(|ndt| Utc.from_utc_datetime(&ndt))
         })
-        .unwrap_or_else(|_| Utc::now())
-}
-
-pub fn import_from_json<P: AsRef<Path>>(path: P, target_db: &Database) -> Result<ImportResult> {
-    let content = std::fs::read_to_string(path)?;
-    let entries: Vec<JsonEntry> = serde_json::from_str(&content)?;
-
-    let mut result = ImportResult::default();
-    let mut project_map: HashMap<String, Project> = HashMap::new();
-    let mut task_map: HashMap<(i64, String), Task> = HashMap::new();
-
-    for je in entries {
-        let project = project_map.entry(je.project.clone()).or_insert_with(|| {
-            let mut p = Project::new(&je.project);
-            let _ = target_db.create_project(&mut p);
-            result.projects_imported += 1;
-            p
-        });
-
-        let task_name = je.task.as_deref().unwrap_or("default");
-        let task_key = (project.id, task_name.to_string());
-        let task = task_map.entry(task_key).or_insert_with(|| {
-            let mut t = Task::new(project.id, task_name);
-            let _ = target_db.create_task(&mut t);
-            result.tasks_imported += 1;
-            t
-        });
-
-        let mut entry = Entry {
-            id: 0,
-            project_id: project.id,
-            task_id: task.id,
-            start: je.start,
-            end: je.end,
-            notes: je.notes,
-            git_commits: Vec::new(),
-        };
-
-        target_db.create_entry(&mut entry)?;
-        result.entries_imported += 1;
-    }
-
-    Ok(result)
-}
-
-#[derive(Debug, serde::Deserialize)]
-struct JsonEntry {
-    project: String,
-    task: Option<String>,
-    start: DateTime<Utc>,
-    end: Option<DateTime<Utc>>,
-    notes: Option<String>,
+        .ok()
 }
 
 #[cfg(test)]
@@ -394,25 +271,39 @@ mod tests {
 
     #[test]
     fn test_parse_zeit_datetime() {
-        let dt = parse_zeit_datetime("2024-01-15T10:30:00+00:00");
+        let dt = parse_zeit_datetime("2024-01-15T10:30:00+00:00").unwrap();
         assert_eq!(dt.format("%Y-%m-%d").to_string(), "2024-01-15");
 
-        let dt2 = parse_zeit_datetime("2024-01-15 10:30:00");
+        let dt2 = parse_zeit_datetime("2024-01-15 10:30:00").unwrap();
         assert_eq!(dt2.format("%Y-%m-%d").to_string(), "2024-01-15");
+
+        assert!(parse_zeit_datetime("yesterday-ish").is_none());
     }
 
     #[test]
-    fn test_import_result_summary() {
-        let result = ImportResult {
-            projects_imported: 3,
-            tasks_imported: 10,
-            entries_imported: 50,
-            errors: Vec::new(),
-        };
-
-        let summary = result.summary();
-        assert!(summary.contains("3 projects"));
-        assert!(summary.contains("10 tasks"));
-        assert!(summary.contains("50 entries"));
+    fn test_import_zeit_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let zeit_path = dir.path().join("zeit.db");
+        let zeit = Connection::open(&zeit_path).unwrap();
+        zeit.execute_batch(
+            "CREATE TABLE entries (project TEXT, task TEXT, start TEXT, finish TEXT, note TEXT);
+             INSERT INTO entries VALUES ('Alpha', 'a', '2024-01-15 10:00:00', '2024-01-15 11:00:00', NULL);
+             INSERT INTO entries VALUES ('Alpha', NULL, '2024-01-15T12:00:00+00:00', NULL, 'open');
+             INSERT INTO entries VALUES ('Alpha', 'a', 'garbage', NULL, NULL);",
+        )
+        .unwrap();
+        drop(zeit);
+
+        let db = Database::open_in_memory().unwrap();
+        let result = ZeitImporter::new()
+            .with_db_path(zeit_path)
+            .import_to_database(&db)
+            .unwrap();
+
+        assert_eq!(result.projects_imported, 1);
+        assert_eq!(result.tasks_imported, 2);
+        assert_eq!(result.entries_imported, 2);
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].contains("invalid start time"));
     }
 }
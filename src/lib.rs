@@ -4,20 +4,38 @@
 
 #![allow(dead_code)]
 
+pub mod absence;
 pub mod achievements;
+pub mod backup;
+pub mod balance;
 pub mod calendar;
 pub mod cli;
+pub mod code_activity;
 pub mod config;
 pub mod database;
+pub mod doctor;
 pub mod error;
+pub mod filter;
 pub mod fuzzy;
+pub mod heatmap;
+pub mod import;
+pub mod insights;
 pub mod integrations;
+pub mod meetings;
+pub mod metrics;
 pub mod models;
 pub mod notifications;
+pub mod period;
+pub mod profile;
+pub mod report;
 pub mod security;
+pub mod standup;
 pub mod stats;
+pub mod sync;
 pub mod theme;
 pub mod themes;
+pub mod timeline;
+pub mod timesheet;
 pub mod tracking;
 pub mod ui;
 
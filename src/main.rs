@@ -2,34 +2,55 @@
 
 #![allow(dead_code)]
 
+mod absence;
 mod achievements;
+mod backup;
+mod balance;
 mod calendar;
 mod cli;
+mod code_activity;
 mod config;
 mod database;
+mod doctor;
 mod error;
+mod filter;
 mod fuzzy;
+mod heatmap;
+mod import;
+mod insights;
 mod integrations;
+mod meetings;
+mod metrics;
 mod models;
 mod notifications;
+mod period;
+mod profile;
+mod report;
 mod security;
+mod standup;
 mod stats;
+mod sync;
 mod theme;
 mod themes;
+mod timeline;
+mod timesheet;
 mod tracking;
 mod ui;
 
-use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Utc};
 use clap::CommandFactory;
 use clap_complete::Shell;
 use cli::{
-    Cli, Commands, ConfigCommands, OutputFormat, ProjectCommands, TaskCommands, ThemeCommands,
+    AbsenceCommands, Cli, Commands, ConfigCommands, DbCommands, FilterArgs, ImportSource,
+    OutputFormat, ProfileCommands, ProjectCommands, ReportCommands, TaskCommands, ThemeCommands,
 };
 use colored::Colorize;
-use config::Config;
-use database::Database;
+use config::{Config, DatabaseEncryption};
+use database::{Database, ImportOptions};
 use error::Result;
 use models::Project;
+use period::Period;
+use security::export::{self, ExportFormat, ExportOptions, SecureExporter};
 use stats::StatsEngine;
 use tracking::TrackingEngine;
 
@@ -60,13 +81,34 @@ async fn run() -> Result<()> {
         return Ok(());
     }
 
+    if let Commands::Profile { command } = &cli.command {
+        return run_profile_command(command, cli.format);
+    }
+
+    profile::activate(cli.profile.as_deref())?;
+
     let config = if let Some(ref path) = cli.config {
         Config::load_from_path(path)?
     } else {
         Config::load()?
     };
 
+    if let Commands::Db { command } = &cli.command {
+        return run_db_command(command, config);
+    }
+
+    // Declared before the database handles so it is dropped, and re-sealed, after them.
     let db_path = config.db_path()?;
+    let unlocked = match config.security.database_encryption {
+        DatabaseEncryption::None => None,
+        _ => Some(security::at_rest::UnlockedDatabase::unlock(
+            &db_path,
+            security::at_rest::configured_storage(&config)?,
+        )?),
+    };
+    let db_path = unlocked
+        .as_ref()
+        .map_or(db_path, |u| u.path().to_path_buf());
     let db = Database::open(&db_path)?;
     let theme = config.theme();
     let engine = TrackingEngine::new(db, config.clone());
@@ -180,13 +222,17 @@ async fn run() -> Result<()> {
                         if let Some(ref notes) = entry.notes {
                             println!("  Notes: {}", notes);
                         }
+                        print_daily_delta(&engine, &config, theme);
                     }
                 }
             } else {
                 match cli.format {
                     OutputFormat::Json => println!("null"),
                     OutputFormat::Statusbar => println!("{} idle", theme.icon()),
-                    _ => print_info("No active tracking session"),
+                    _ => {
+                        print_info("No active tracking session");
+                        print_daily_delta(&engine, &config, theme);
+                    }
                 }
             }
         }
@@ -196,8 +242,10 @@ async fn run() -> Result<()> {
             since,
             total,
             limit,
+            filter,
         } => {
             let since_dt = since.as_ref().and_then(|s| parse_datetime(s));
+            let filter = entry_filter(&filter, &config)?;
 
             if only_projects_and_tasks {
                 let projects = engine.db().list_projects()?;
@@ -226,7 +274,9 @@ async fn run() -> Result<()> {
                     }
                 }
             } else {
-                let entries = engine.db().list_entries_with_details(since_dt)?;
+                let entries = engine
+                    .db()
+                    .list_entries_with_details_where(since_dt, filter.as_ref())?;
                 let entries: Vec<_> = entries.into_iter().take(limit).collect();
 
                 match cli.format {
@@ -250,6 +300,40 @@ async fn run() -> Result<()> {
             }
         }
 
+        Commands::Commits {
+            project,
+            since,
+            author,
+            limit,
+            filter: entries,
+        } => {
+            let mut filter = database::CommitFilter::new();
+            if let Some(entries) = entry_filter(&entries, &config)? {
+                filter = filter.with_entry_filter(entries);
+            }
+            if let Some(p) = project {
+                filter = filter.with_project(p);
+            }
+            if let Some(a) = author {
+                filter = filter.with_author(a);
+            }
+            if let Some(dt) = since.as_ref().and_then(|s| parse_datetime(s)) {
+                filter = filter.with_since(dt);
+            }
+
+            let commits = engine.db().list_commits(&filter)?;
+            let commits: Vec<_> = commits.into_iter().take(limit).collect();
+
+            match cli.format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&commits)?);
+                }
+                _ => {
+                    println!("{}", stats::format_commit_table(&commits, theme));
+                }
+            }
+        }
+
         Commands::Project { command } => match command {
             ProjectCommands::Add { name, color } => {
                 let mut project = Project::new(&name);
@@ -396,31 +480,483 @@ async fn run() -> Result<()> {
             week,
             month,
             since,
-            by_project: _,
-            by_task: _,
+            until,
+            period,
+            compare,
+            by_project,
+            by_task,
+            group_by,
+            commits,
+            filter,
         } => {
-            let db2 = Database::open(&db_path)?;
-            let stats_engine = StatsEngine::new(db2, theme);
+            let stats_engine = StatsEngine::new(engine.db().clone(), theme)
+                .with_filter(entry_filter(&filter, &config)?);
 
-            let (stats, title) = if today {
-                (stats_engine.today_stats()?, "Today's Stats")
+            let (period, title) = if today {
+                (Period::named("today")?, "Today's Stats".to_string())
             } else if week {
-                (stats_engine.week_stats()?, "This Week's Stats")
+                (Period::named("this-week")?, "This Week's Stats".to_string())
             } else if month {
-                (stats_engine.month_stats()?, "This Month's Stats")
-            } else if let Some(ref s) = since {
-                let dt = parse_datetime(s);
-                (stats_engine.calculate_stats(dt)?, "Stats")
+                (
+                    Period::named("this-month")?,
+                    "This Month's Stats".to_string(),
+                )
+            } else if let Some(ref name) = period {
+                let period = Period::named(name)?;
+                let title = format!("Stats: {}", period.label);
+                (period, title)
+            } else if since.is_some() || until.is_some() {
+                let period = Period::range(since.as_deref(), until.as_deref())?;
+                let title = format!("Stats: {}", period.label);
+                (period, title)
             } else {
-                (stats_engine.week_stats()?, "This Week's Stats")
+                (Period::named("this-week")?, "This Week's Stats".to_string())
             };
 
-            match cli.format {
-                OutputFormat::Json => {
-                    println!("{}", stats_engine.format_stats_json(&stats)?);
+            let keys = match (group_by, by_project, by_task) {
+                (Some(spec), _, _) => Some(stats::GroupKey::parse_list(&spec)?),
+                (None, true, true) => Some(stats::DEFAULT_GROUPING.to_vec()),
+                (None, true, false) => Some(vec![stats::GroupKey::Project]),
+                (None, false, true) => Some(vec![stats::GroupKey::Task]),
+                (None, false, false) if commits => Some(stats::DEFAULT_GROUPING.to_vec()),
+                (None, false, false) => None,
+            };
+            let title = match keys {
+                Some(ref keys) => {
+                    let dimensions: Vec<_> = keys.iter().map(|k| k.name()).collect();
+                    format!("{} by {}", title, dimensions.join(" × "))
                 }
+                None => title,
+            };
+
+            if let Some(compare) = compare {
+                let compare: period::CompareTo = compare.parse()?;
+                let keys = keys.unwrap_or_else(|| stats::DEFAULT_GROUPING.to_vec());
+                let comparison = stats_engine.compare(&period, compare, &keys)?;
+                match cli.format {
+                    OutputFormat::Json => {
+                        println!("{}", serde_json::to_string_pretty(&comparison)?);
+                    }
+                    OutputFormat::Csv => print!("{}", comparison.to_csv()),
+                    _ => println!("{}", stats_engine.format_comparison(&comparison, &title)),
+                }
+                return Ok(());
+            }
+
+            match keys {
+                None => {
+                    let stats = stats_engine.period_stats(&period)?;
+                    match cli.format {
+                        OutputFormat::Json => {
+                            println!("{}", stats_engine.format_stats_json(&stats)?);
+                        }
+                        OutputFormat::Csv => {
+                            print!("{}", stats::GroupedStats::from_time_stats(&stats).to_csv());
+                        }
+                        _ => {
+                            println!("{}", stats_engine.format_stats(&stats, &title));
+                            print_days_off(&engine, &period)?;
+                        }
+                    }
+                }
+                Some(keys) => {
+                    let grouped = if commits {
+                        stats_engine.group_with_code(&period, &keys)?
+                    } else {
+                        stats_engine.group(&period, &keys)?
+                    };
+                    match cli.format {
+                        OutputFormat::Json => {
+                            println!("{}", serde_json::to_string_pretty(&grouped)?);
+                        }
+                        OutputFormat::Csv => print!("{}", grouped.to_csv()),
+                        _ => {
+                            println!("{}", stats_engine.format_grouped(&grouped, &title));
+                            print_days_off(&engine, &period)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        Commands::Timesheet {
+            week,
+            by_project,
+            round,
+            decimal,
+            output,
+            filter,
+        } => {
+            let period = match week {
+                Some(ref spec) => Period::week(spec)?,
+                None => Period::week("today")?,
+            };
+            let stats_engine = StatsEngine::new(engine.db().clone(), theme)
+                .with_filter(entry_filter(&filter, &config)?);
+            let sheet = timesheet::Timesheet::build(&stats_engine, &period, by_project, round)?;
+
+            let content = match cli.format {
+                OutputFormat::Json => serde_json::to_string_pretty(&sheet)? + "\n",
+                OutputFormat::Csv => sheet.to_csv(decimal),
+                OutputFormat::Markdown => sheet.to_markdown(decimal),
+                OutputFormat::Html => sheet.to_html(theme, decimal),
+                _ => sheet.format_pretty(theme, decimal),
+            };
+
+            if let Some(path) = output {
+                std::fs::write(&path, &content)?;
+                print_success(&format!("Timesheet written to: {}", path));
+            } else {
+                print!("{}", content);
+            }
+        }
+
+        Commands::Standup { template, filter } => {
+            let today = Local::now().date_naive();
+            let previous_day = standup::previous_working_day(today, &config.schedule);
+            let filter = entry_filter(&filter, &config)?;
+            let entries = engine.db().list_entries_with_details_between(
+                Some(period::local_midnight(previous_day)),
+                Some(period::local_midnight(
+                    previous_day + chrono::Duration::days(1),
+                )),
+                filter.as_ref(),
+            )?;
+
+            let running = engine
+                .get_status()?
+                .map(|(entry, project, task)| standup::RunningTask {
+                    project: project.name,
+                    task: task.name,
+                    since: entry.start.with_timezone(&Local),
+                    seconds: entry.duration().num_seconds(),
+                    notes: entry.notes,
+                });
+            let mut event_store =
+                calendar::EventStore::new(Config::profile_dir()?.join("events.json"));
+            event_store.load()?;
+            let events = event_store.get_by_date(today);
+
+            let report = standup::Standup::build(&entries, today, previous_day, running, &events);
+
+            let template = match template
+                .map(|t| config::expand_path(&t))
+                .transpose()?
+                .or(config.standup_template_path()?)
+            {
+                Some(path) => Some(std::fs::read_to_string(&path).map_err(|e| {
+                    error::Error::NotFound(format!("standup template {}: {}", path.display(), e))
+                })?),
+                None => None,
+            };
+            let markdown = matches!(cli.format, OutputFormat::Markdown);
+            match cli.format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
                 _ => {
-                    println!("{}", stats_engine.format_stats(&stats, title));
+                    let default = if markdown {
+                        standup::MARKDOWN_TEMPLATE
+                    } else {
+                        standup::PLAIN_TEMPLATE
+                    };
+                    print!(
+                        "{}",
+                        report.render(template.as_deref().unwrap_or(default), markdown)
+                    );
+                }
+            }
+        }
+
+        Commands::Timeline {
+            date,
+            week,
+            days,
+            filter,
+        } => {
+            let date = match date {
+                Some(ref spec) => period::parse_date(spec).ok_or_else(|| {
+                    error::Error::InvalidInput(format!(
+                        "Invalid date '{}' (expected YYYY-MM-DD, today, yesterday or -3d)",
+                        spec
+                    ))
+                })?,
+                None => Local::now().date_naive(),
+            };
+            let days: Vec<NaiveDate> = if week {
+                Period::week(&date.to_string())?.days()
+            } else {
+                let n = days.unwrap_or(1).max(1) as i64;
+                (0..n)
+                    .rev()
+                    .map(|i| date - chrono::Duration::days(i))
+                    .collect()
+            };
+
+            let stats_engine = StatsEngine::new(engine.db().clone(), theme)
+                .with_filter(entry_filter(&filter, &config)?);
+            let timeline = stats_engine.timeline(&days, &config.schedule)?;
+
+            match cli.format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&timeline)?),
+                _ => print!("{}", timeline.format_pretty(theme)),
+            }
+        }
+
+        Commands::Absence { command } => match command {
+            AbsenceCommands::Add {
+                kind,
+                date,
+                until,
+                half,
+                note,
+            } => {
+                let kind: absence::AbsenceKind = kind.parse()?;
+                let first = match date {
+                    Some(ref spec) => parse_day(spec)?,
+                    None => Local::now().date_naive(),
+                };
+                let days: Vec<NaiveDate> = match until {
+                    Some(ref spec) => {
+                        let last = parse_day(spec)?;
+                        let calendar = balance::WorkCalendar::load(&config.schedule)?;
+                        first
+                            .iter_days()
+                            .take_while(|d| *d <= last)
+                            .filter(|d| calendar.expected_seconds(*d) > 0)
+                            .collect()
+                    }
+                    None => vec![first],
+                };
+                if days.is_empty() {
+                    return Err(error::Error::InvalidInput(format!(
+                        "No working days between {} and {}",
+                        first,
+                        until.unwrap_or_default()
+                    )));
+                }
+
+                for day in &days {
+                    let mut record = absence::Absence::new(*day, kind).with_half_day(half);
+                    if let Some(ref note) = note {
+                        record = record.with_note(note.clone());
+                    }
+                    engine.db().save_absence(&mut record)?;
+                }
+                let what = if half { "Half day" } else { "Day" };
+                match days.as_slice() {
+                    [day] => print_success(&format!("{} off recorded: {} ({})", what, day, kind)),
+                    _ => print_success(&format!(
+                        "{} {} days off recorded: {} – {}",
+                        days.len(),
+                        kind,
+                        days[0],
+                        days[days.len() - 1]
+                    )),
+                }
+            }
+
+            AbsenceCommands::List { from, until } => {
+                let from = from.as_deref().map(parse_day).transpose()?;
+                let until = until.as_deref().map(parse_day).transpose()?;
+                let absences = engine.db().list_absences(from, until)?;
+                match cli.format {
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&absences)?),
+                    _ => print!("{}", absence::format_list(&absences, theme)),
+                }
+            }
+
+            AbsenceCommands::Remove { id } => {
+                engine.db().delete_absence(id)?;
+                print_success(&format!("Removed absence #{}", id));
+            }
+        },
+
+        Commands::Balance {
+            from,
+            until,
+            by,
+            filter,
+        } => {
+            let view: balance::BalanceView = by.parse()?;
+            if !config.schedule.has_contract() {
+                print_info(
+                    "No contracted hours yet: set `hours` or `start_date` under [schedule] \
+                     (`mtrack config edit`) to keep a balance",
+                );
+                return Ok(());
+            }
+            let today = Local::now().date_naive();
+            let from = match (from, config.schedule.start_date) {
+                (Some(ref spec), _) => parse_day(spec)?,
+                (None, Some(start)) => start,
+                (None, None) => NaiveDate::from_ymd_opt(today.year(), 1, 1).unwrap(),
+            };
+            let until = match until {
+                Some(ref spec) => parse_day(spec)?,
+                None => today,
+            };
+            if until < from {
+                return Err(error::Error::InvalidInput(format!(
+                    "--until {} is before --from {}",
+                    until, from
+                )));
+            }
+
+            let calendar = balance::WorkCalendar::load(&config.schedule)?;
+            let stats_engine = StatsEngine::new(engine.db().clone(), theme)
+                .with_filter(entry_filter(&filter, &config)?);
+            let balance = stats_engine.balance(from, until, &calendar)?;
+
+            match cli.format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&balance)?),
+                _ => print!("{}", balance.format_pretty(theme, view)),
+            }
+        }
+
+        Commands::Heatmap {
+            year,
+            project,
+            ascii,
+            filter,
+        } => {
+            let today = Local::now().date_naive();
+            let (from, to) = match year {
+                Some(year) => {
+                    let year = year.unwrap_or_else(|| today.year());
+                    heatmap::Heatmap::year_range(year).ok_or_else(|| {
+                        error::Error::InvalidInput(format!("Invalid year {}", year))
+                    })?
+                }
+                None => heatmap::Heatmap::trailing_range(today, heatmap::DEFAULT_WEEKS),
+            };
+
+            let mut filter = entry_filter(&filter, &config)?;
+            if let Some(ref name) = project {
+                filter = Some(filter::Filter::project(name).and_maybe(filter));
+            }
+            let entries = engine.db().list_entries_with_details_between(
+                Some(period::local_midnight(from)),
+                Some(period::local_midnight(to + chrono::Duration::days(1))),
+                filter.as_ref(),
+            )?;
+            let absences = engine.db().list_absences(Some(from), Some(to))?;
+            let map = heatmap::Heatmap::build(&entries, from, to, today)
+                .with_absences(absences.iter().map(|a| a.date));
+
+            match cli.format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&map)?),
+                OutputFormat::Plain => print!("{}", map.render(theme, true)),
+                _ => print!("{}", map.render(theme, ascii)),
+            }
+        }
+
+        Commands::Insights {
+            weeks,
+            period,
+            filter,
+        } => {
+            let period = match period {
+                Some(ref name) => Period::named(name)?,
+                None => Period::last_weeks(weeks),
+            };
+            let stats_engine = StatsEngine::new(engine.db().clone(), theme)
+                .with_filter(entry_filter(&filter, &config)?);
+            let insights = stats_engine.insights(&period)?;
+
+            match cli.format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&insights)?),
+                _ => print!("{}", insights.format_pretty(theme)),
+            }
+        }
+
+        Commands::Report { command } => match command {
+            ReportCommands::Html {
+                from,
+                until,
+                period,
+                output,
+                filter,
+            } => {
+                let period = match period {
+                    Some(ref name) => Period::named(name)?,
+                    None if from.is_some() || until.is_some() => {
+                        Period::range(from.as_deref(), until.as_deref())?
+                    }
+                    None => Period::named("this-month")?,
+                };
+                let filter = entry_filter(&filter, &config)?;
+                let entries = engine.db().list_entries_with_details_between(
+                    period.from,
+                    period.until,
+                    filter.as_ref(),
+                )?;
+                let html = report::HtmlReport::build(entries, &period).to_html(theme);
+
+                if let Some(path) = output {
+                    std::fs::write(&path, &html)?;
+                    print_success(&format!("Report written to: {}", path));
+                } else {
+                    print!("{}", html);
+                }
+            }
+
+            ReportCommands::Code {
+                from,
+                until,
+                period,
+                zero_commits,
+                filter,
+            } => {
+                let period = match period {
+                    Some(ref name) => Period::named(name)?,
+                    None if from.is_some() || until.is_some() => {
+                        Period::range(from.as_deref(), until.as_deref())?
+                    }
+                    None => Period::named("this-month")?,
+                };
+                let filter = entry_filter(&filter, &config)?;
+                let mut entries = engine.db().list_entries_with_details_between(
+                    period.from,
+                    period.until,
+                    filter.as_ref(),
+                )?;
+                code_activity::fill_missing_commits(&mut entries, engine.db(), &config)?;
+                let report = code_activity::CodeReport::build(&entries, &period.label);
+
+                match cli.format {
+                    OutputFormat::Json if zero_commits => println!(
+                        "{}",
+                        serde_json::to_string_pretty(&report.zero_commit_sessions)?
+                    ),
+                    OutputFormat::Json => {
+                        println!("{}", serde_json::to_string_pretty(&report)?);
+                    }
+                    OutputFormat::Csv if zero_commits => print!("{}", report.zero_commit_csv()),
+                    OutputFormat::Csv => print!("{}", report.to_csv()),
+                    _ => println!("{}", report.format_pretty(theme, zero_commits)),
+                }
+            }
+        },
+
+        Commands::Metrics {
+            textfile,
+            interval,
+            filter,
+        } => {
+            let stats_engine = StatsEngine::new(engine.db().clone(), theme)
+                .with_filter(entry_filter(&filter, &config)?);
+            let Some(path) = textfile else {
+                let metrics = metrics::Metrics::collect(&stats_engine, engine.get_status()?)?;
+                print!("{}", metrics.to_prometheus_text());
+                return Ok(());
+            };
+            let path = config::expand_path(&path)?;
+
+            loop {
+                let metrics = metrics::Metrics::collect(&stats_engine, engine.get_status()?)?;
+                metrics::write_textfile(&path, &metrics.to_prometheus_text())?;
+                match interval {
+                    Some(secs) => std::thread::sleep(std::time::Duration::from_secs(secs.max(1))),
+                    None => break,
                 }
             }
         }
@@ -429,7 +965,10 @@ async fn run() -> Result<()> {
             format,
             short,
             icon,
+            show_profile,
         } => {
+            let label = show_profile.then(|| format!("[{}] ", config.profile));
+
             if let Some((entry, proj, tsk)) = engine.get_status()? {
                 let secs = entry.duration().num_seconds();
 
@@ -438,7 +977,11 @@ async fn run() -> Result<()> {
                 let output = match format.as_str() {
                     "polybar" => integration.format_polybar(&proj.name, &tsk.name, secs),
                     "waybar" => {
-                        let json = integration.format_waybar(&proj.name, &tsk.name, secs);
+                        let mut json = integration.format_waybar(&proj.name, &tsk.name, secs);
+                        if let Some(ref label) = label {
+                            json.text = format!("{}{}", label, json.text);
+                            json.tooltip = format!("Profile: {}\n{}", config.profile, json.tooltip);
+                        }
                         serde_json::to_string(&json)?
                     }
                     "i3blocks" | "i3" => integration.format_i3blocks(&proj.name, &tsk.name, secs),
@@ -453,23 +996,26 @@ async fn run() -> Result<()> {
                     ),
                 };
 
-                println!("{}", output);
+                match label {
+                    Some(label) if format != "waybar" => println!("{}{}", label, output),
+                    _ => println!("{}", output),
+                }
             } else {
+                let label = label.unwrap_or_default();
                 match format.as_str() {
                     "waybar" => {
-                        println!("{{ \"text\": \"💎 idle\", \"class\": \"idle\" }}");
+                        println!("{{ \"text\": \"💎 {}idle\", \"class\": \"idle\" }}", label);
                     }
                     _ => {
                         let icon = icon.as_deref().unwrap_or("💎");
-                        println!("{} idle", icon);
+                        println!("{} {}idle", icon, label);
                     }
                 }
             }
         }
 
         Commands::Dashboard => {
-            let db2 = Database::open(&db_path)?;
-            ui::run_dashboard(db2, theme)?;
+            ui::run_dashboard(engine.db().clone(), theme)?;
         }
 
         Commands::Config { command } => match command {
@@ -479,6 +1025,7 @@ async fn run() -> Result<()> {
                 }
                 _ => {
                     println!("\n{} Configuration\n", theme.icon());
+                    println!("Profile: {}", config.profile);
                     println!("Database: {}", config.database.path);
                     println!("Theme: {}", config.ui.theme);
                     println!("Auto-import Git: {}", config.tracking.auto_import_git);
@@ -487,19 +1034,22 @@ async fn run() -> Result<()> {
             },
 
             ConfigCommands::Path => {
-                println!("{}", Config::config_path()?.display());
+                println!("{}", Config::profile_config_path()?.display());
             }
 
             ConfigCommands::Edit => {
                 let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vim".to_string());
-                let config_path = Config::config_path()?;
+                let config_path = Config::profile_config_path()?;
                 std::process::Command::new(&editor)
                     .arg(&config_path)
                     .status()?;
             }
 
             ConfigCommands::Reset { force: _ } => {
-                let default_config = Config::default();
+                let default_config = Config {
+                    profile: config.profile.clone(),
+                    ..Config::default()
+                };
                 default_config.save()?;
                 print_success("Configuration reset to defaults");
             }
@@ -539,24 +1089,115 @@ async fn run() -> Result<()> {
             );
         }
 
-        Commands::Import { zeit, json } => {
-            if let Some(path) = zeit {
+        Commands::Import {
+            source,
+            zeit,
+            json,
+            full,
+            dry_run,
+            on_conflict,
+        } => {
+            if let Some(ImportSource::Ics {
+                file,
+                dry_run,
+                from,
+                until,
+                overlap,
+            }) = source
+            {
+                let period = Period::range(from.as_deref(), until.as_deref())?;
+                let until = period.until.map_or_else(Utc::now, |u| u.min(Utc::now()));
+                let mut rules = meetings::MeetingRules::new(&config.meetings, &config.schedule)?;
+                if let Some(policy) = overlap {
+                    rules = rules.with_overlap(policy.parse()?);
+                }
+
+                let path = config::expand_path(&file)?;
+                let calendar = calendar::IcsFile::parse(&std::fs::read_to_string(&path)?)?;
+                let tracked = engine.db().list_entries_with_details_between(
+                    period.from.map(|from| from - chrono::Duration::days(1)),
+                    None,
+                    None,
+                )?;
+                let plan = rules.plan(&calendar, period.from, until, &tracked, &Local);
+
+                let rows = plan.rows.iter().cloned().map(Ok).collect();
+                let result = import::import_rows(
+                    rows,
+                    engine.db(),
+                    &ImportOptions::new().with_dry_run(dry_run),
+                )?;
+                print_import_result(&result);
+                if dry_run {
+                    for skipped in &plan.skipped {
+                        println!(
+                            "{}",
+                            format!(
+                                "- {}  {}  ({})",
+                                skipped.start.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                                skipped.summary,
+                                skipped.reason.label()
+                            )
+                            .dimmed()
+                        );
+                    }
+                }
+                if let Some(summary) = plan.skipped_summary() {
+                    print_info(&summary);
+                }
+                for skipped in &calendar.skipped {
+                    print_info(&format!(
+                        "Skipped '{}': {}",
+                        skipped.summary, skipped.reason
+                    ));
+                }
+                return Ok(());
+            }
+
+            let options = ImportOptions::new()
+                .with_on_conflict(on_conflict.parse()?)
+                .with_dry_run(dry_run);
+
+            if let Some(path) = full {
+                let text = std::fs::read_to_string(config::expand_path(&path)?)?;
+                let backup = backup::Backup::from_json(&text)?;
+                let report = backup.restore(engine.db())?;
+
+                let mut events =
+                    calendar::EventStore::new(Config::profile_dir()?.join("events.json"));
+                events.load()?;
+                events.replace_all(backup.calendar_events.clone())?;
+                backup.achievements.save()?;
+
+                print_success(&format!(
+                    "Restored {}, {} calendar events",
+                    report.summary(),
+                    backup.calendar_events.len()
+                ));
+                security::SecurityManager::new(config.clone())?.log_action(
+                    security::AuditAction::DataImported {
+                        source: "backup".into(),
+                        entry_count: report.entries,
+                    },
+                )?;
+            } else if let Some(path) = zeit {
                 let importer = integrations::zeit::ZeitImporter::new()
-                    .with_db_path(std::path::PathBuf::from(&path));
+                    .with_db_path(std::path::PathBuf::from(&path))
+                    .with_options(options);
 
                 match importer.preview() {
                     Ok(preview) => {
                         print_info(&format!("Preview:\n{}", preview.display()));
                         let result = importer.import_to_database(engine.db())?;
-                        print_success(&result.summary());
+                        print_import_result(&result);
                     }
                     Err(e) => {
                         print_error(&format!("Failed to preview Zeit database: {}", e));
                     }
                 }
             } else if let Some(path) = json {
-                let result = integrations::zeit::import_from_json(&path, engine.db())?;
-                print_success(&result.summary());
+                let result = import::import_from_json(&path, engine.db(), &options)?;
+                print_import_result(&result);
             } else if let Some(default_path) = integrations::zeit::ZeitImporter::default_zeit_path()
             {
                 if default_path.exists() {
@@ -576,36 +1217,123 @@ async fn run() -> Result<()> {
             export_format,
             output,
             since,
+            projects,
+            encrypt,
+            password,
+            sanitize,
+            commits,
+            full,
+            filter,
         } => {
-            let since_dt = since.as_ref().and_then(|s| parse_datetime(s));
-            let entries = engine.db().list_entries_with_details(since_dt)?;
-
-            let content = match export_format.as_str() {
-                "csv" => {
-                    let mut csv =
-                        String::from("date,project,task,start,end,duration_seconds,notes\n");
-                    for e in &entries {
-                        csv.push_str(&format!(
-                            "{},{},{},{},{},{},{}\n",
-                            e.entry.start_local().format("%Y-%m-%d"),
-                            e.project_name,
-                            e.task_name,
-                            e.entry.start.to_rfc3339(),
-                            e.entry.end.map_or(String::new(), |t| t.to_rfc3339()),
-                            e.entry.duration().num_seconds(),
-                            e.entry.notes.as_deref().unwrap_or("")
-                        ));
-                    }
-                    csv
+            if full {
+                let mut events =
+                    calendar::EventStore::new(Config::profile_dir()?.join("events.json"));
+                events.load()?;
+                let backup = backup::Backup::capture(
+                    engine.db(),
+                    events.get_all().to_vec(),
+                    achievements::AchievementProgress::load()?,
+                )?;
+                let json = backup.to_json()?;
+
+                if let Some(path) = output {
+                    let path = config::expand_path(&path)?;
+                    std::fs::write(&path, json)?;
+                    print_success(&format!(
+                        "Backed up {} entries to {}",
+                        backup.entries.len(),
+                        path.display()
+                    ));
+                } else {
+                    println!("{}", json);
                 }
-                _ => serde_json::to_string_pretty(&entries)?,
+                security::SecurityManager::new(config.clone())?.log_action(
+                    security::AuditAction::DataExported {
+                        format: "backup".into(),
+                        entry_count: backup.entries.len(),
+                    },
+                )?;
+                return Ok(());
+            }
+
+            let format: ExportFormat = export_format.parse()?;
+            let mut options = ExportOptions::new().with_format(format);
+            if !projects.is_empty() {
+                options = options.with_projects(projects);
+            }
+            if let Some(recipient) = encrypt {
+                options = options.with_encryption(recipient);
+            }
+            if password {
+                let password = std::env::var(export::PASSWORD_ENV).map_err(|_| {
+                    error::Error::Config(error::ConfigError::MissingField(format!(
+                        "set {} to the archive password",
+                        export::PASSWORD_ENV
+                    )))
+                })?;
+                options = options.with_password(password);
+            }
+            if sanitize {
+                options = options.with_sanitization();
+            }
+            if commits {
+                options = options.with_code_columns();
+            }
+
+            let since_dt = since.as_ref().and_then(|s| parse_datetime(s));
+            let filter = entry_filter(&filter, &config)?;
+            let entries = engine
+                .db()
+                .list_entries_with_details_where(since_dt, filter.as_ref())?;
+            let exporter = SecureExporter::new(options);
+
+            let entry_count = if let Some(path) = output {
+                let result = exporter.export_entries(&entries, config::expand_path(&path)?)?;
+                print_success(&result.summary());
+                result.entry_count
+            } else {
+                println!("{}", exporter.render(&entries)?);
+                exporter.filter_entries(&entries).len()
             };
 
-            if let Some(path) = output {
-                std::fs::write(&path, &content)?;
-                print_success(&format!("Exported to: {}", path));
+            security::SecurityManager::new(config.clone())?.log_action(
+                security::AuditAction::DataExported {
+                    format: format.extension().to_string(),
+                    entry_count,
+                },
+            )?;
+        }
+
+        Commands::Doctor { fix } => {
+            let doctor = doctor::Doctor::new(engine.db());
+            let report = if fix {
+                doctor.fix()?
             } else {
-                println!("{}", content);
+                doctor.diagnose()?
+            };
+
+            match cli.format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                _ => {
+                    println!("{}", doctor::format_report(&report, theme));
+                }
+            }
+        }
+
+        Commands::Sync { dir } => {
+            let dir = config::expand_path(&dir)?;
+            let device = sync::local_device_id()?;
+            let report = sync::Syncer::new(engine.db(), device).sync(&dir)?;
+
+            match cli.format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                _ => {
+                    println!("{}", sync::format_report(&report, theme));
+                }
             }
         }
 
@@ -672,15 +1400,60 @@ async fn run() -> Result<()> {
             theme: theme_name,
             add,
             date,
+            import,
+            export,
+            until,
         } => {
             use calendar::{Calendar, CalendarEvent, CalendarEventType, CalendarTui, EventStore};
             use chrono::NaiveDate;
 
             // Get events file path
-            let events_path = Config::config_dir()?.join("events.json");
+            let events_path = Config::profile_dir()?.join("events.json");
             let mut event_store = EventStore::new(events_path);
             event_store.load()?;
 
+            if let Some(path) = import {
+                let path = config::expand_path(&path)?;
+                let horizon = match until {
+                    Some(until) => NaiveDate::parse_from_str(&until, "%Y-%m-%d").map_err(|_| {
+                        error::Error::InvalidInput(
+                            "Invalid date format. Use YYYY-MM-DD".to_string(),
+                        )
+                    })?,
+                    None => Local::now().date_naive() + chrono::Duration::days(365),
+                };
+                let file = calendar::IcsFile::parse(&std::fs::read_to_string(&path)?)?;
+                let report = event_store
+                    .upsert_events(file.calendar_events(horizon, &Local), &file.uids())?;
+
+                print_success(&format!(
+                    "Imported {} events from {} ({} updated, {} unchanged, {} removed)",
+                    report.added,
+                    path.display(),
+                    report.updated,
+                    report.unchanged,
+                    report.removed
+                ));
+                for skipped in &file.skipped {
+                    print_info(&format!(
+                        "Skipped '{}': {}",
+                        skipped.summary, skipped.reason
+                    ));
+                }
+                return Ok(());
+            }
+
+            if let Some(path) = export {
+                let path = config::expand_path(&path)?;
+                std::fs::write(&path, calendar::ics::events_to_ics(event_store.get_all()))?;
+                print_success(&format!(
+                    "Exported {} events to {}",
+                    event_store.get_all().len(),
+                    path.display()
+                ));
+                return Ok(());
+            }
+
             // If add flag is set, add event and return
             if let Some(event_title) = add {
                 let event_date = if let Some(date_str) = date {
@@ -728,7 +1501,9 @@ async fn run() -> Result<()> {
             };
 
             // Create calendar with events
-            let calendar = Calendar::new().with_events(event_store.get_all().to_vec());
+            let calendar = Calendar::new()
+                .with_events(event_store.get_all().to_vec())
+                .with_absences(engine.db().list_absences(None, None)?);
             let mut tui = CalendarTui::new(calendar, calendar_theme, Some(event_store));
 
             // Run the TUI
@@ -736,6 +1511,8 @@ async fn run() -> Result<()> {
         }
 
         Commands::Completions { .. } => unreachable!("handled before config is loaded"),
+        Commands::Db { .. } => unreachable!("handled before the database is opened"),
+        Commands::Profile { .. } => unreachable!("handled before config is loaded"),
     }
 
     Ok(())
@@ -762,6 +1539,214 @@ fn generate_completions(shell: Shell, out_dir: &str) -> Result<()> {
     Ok(())
 }
 
+fn run_profile_command(command: &ProfileCommands, format: OutputFormat) -> Result<()> {
+    match command {
+        ProfileCommands::List => {
+            let profiles = profile::list()?;
+            match format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&profiles)?);
+                }
+                _ => {
+                    let theme = Config::load_for_profile(profile::DEFAULT_PROFILE)?.theme();
+                    let (r, g, b) = theme.primary_color();
+                    println!("\n{} Profiles\n", theme.icon());
+                    for p in &profiles {
+                        let marker = if p.active { "●" } else { "○" };
+                        println!(
+                            "  {} {:<16} {}",
+                            marker.truecolor(r, g, b),
+                            p.name,
+                            p.database.dimmed()
+                        );
+                    }
+                    println!();
+                }
+            }
+        }
+
+        ProfileCommands::Create { name, db } => {
+            let dir = profile::create(name, db.as_deref())?;
+            print_success(&format!("Created profile '{}' ({})", name, dir.display()));
+        }
+
+        ProfileCommands::Switch { name } => {
+            profile::switch(name)?;
+            print_success(&format!("Switched to profile '{}'", name));
+            if std::env::var(profile::PROFILE_ENV).is_ok() {
+                print_info(&format!(
+                    "{} is set and still takes precedence",
+                    profile::PROFILE_ENV
+                ));
+            }
+        }
+
+        ProfileCommands::Remove { name, purge } => {
+            profile::remove(name, *purge)?;
+            print_success(&format!("Removed profile '{}'", name));
+        }
+    }
+
+    Ok(())
+}
+
+fn run_db_command(command: &DbCommands, mut config: Config) -> Result<()> {
+    use security::at_rest;
+
+    let db_path = config.db_path()?;
+    let current = config.security.database_encryption;
+
+    let (mode, action) = match command {
+        DbCommands::Status => {
+            let location = match current {
+                DatabaseEncryption::None => db_path,
+                _ => at_rest::encrypted_path(&db_path),
+            };
+            print_info(&format!(
+                "Database encryption: {} ({})",
+                format!("{:?}", current).to_lowercase(),
+                location.display()
+            ));
+            return Ok(());
+        }
+
+        DbCommands::Encrypt { recipient } => {
+            if current != DatabaseEncryption::None {
+                return Err(error::Error::InvalidInput(
+                    "database is already encrypted; use `mtrack db rekey`".into(),
+                ));
+            }
+            let (mode, key) = encryption_target(recipient.as_deref());
+            let storage = at_rest::storage_for(mode, &key, at_rest::PASSPHRASE_ENV)?;
+            let path = at_rest::encrypt_database(&db_path, storage.as_ref())?;
+            if mode == DatabaseEncryption::Gpg {
+                config.security.encryption_key = key;
+            }
+            print_success(&format!("Database encrypted: {}", path.display()));
+            (mode, security::AuditAction::EncryptionEnabled)
+        }
+
+        DbCommands::Decrypt => {
+            let storage = at_rest::configured_storage(&config)?;
+            at_rest::decrypt_database(&db_path, storage.as_ref())?;
+            print_success(&format!("Database decrypted: {}", db_path.display()));
+            (
+                DatabaseEncryption::None,
+                security::AuditAction::EncryptionDisabled,
+            )
+        }
+
+        DbCommands::Rekey { recipient } => {
+            let old = at_rest::configured_storage(&config)?;
+            let (mode, key) = encryption_target(recipient.as_deref());
+            let new = at_rest::storage_for(mode, &key, at_rest::NEW_PASSPHRASE_ENV)?;
+            at_rest::rekey_database(&db_path, old.as_ref(), new.as_ref())?;
+            if mode == DatabaseEncryption::Gpg {
+                config.security.encryption_key = key;
+            }
+            print_success("Database re-encrypted with the new key");
+            (mode, security::AuditAction::EncryptionEnabled)
+        }
+    };
+
+    config.security.database_encryption = mode;
+    config.save()?;
+    security::SecurityManager::new(config)?.log_action(action)?;
+    Ok(())
+}
+
+fn encryption_target(recipient: Option<&str>) -> (DatabaseEncryption, String) {
+    match recipient {
+        Some(r) => (DatabaseEncryption::Gpg, r.to_string()),
+        None => (DatabaseEncryption::Passphrase, String::new()),
+    }
+}
+
+/// The import summary, with the diff on `--dry-run` and the rows left out.
+fn print_import_result(result: &import::ImportResult) {
+    if result.dry_run {
+        print!("{}", result.format_diff());
+    }
+    print_success(&result.summary());
+    for error in &result.errors {
+        print_error(&format!("Skipped {}", error));
+    }
+    if result.conflicts_skipped > 0 && !result.dry_run {
+        print_info("Use --on-conflict overwrite or keep-both to import conflicting rows");
+    }
+}
+
+fn parse_day(spec: &str) -> Result<NaiveDate> {
+    period::parse_date(spec).ok_or_else(|| {
+        error::Error::InvalidInput(format!(
+            "Invalid date '{}' (expected YYYY-MM-DD, today, yesterday or -3d)",
+            spec
+        ))
+    })
+}
+
+/// `Days off: 2 days (vacation 2)` below the stats of a bounded period.
+fn print_days_off(engine: &TrackingEngine, period: &Period) -> Result<()> {
+    let days = period.days();
+    let (Some(first), Some(last)) = (days.first(), days.last()) else {
+        return Ok(());
+    };
+    let absences = engine.db().list_absences(Some(*first), Some(*last))?;
+    if !absences.is_empty() {
+        println!(
+            "🏖️  Days off: {}\n",
+            absence::AbsenceSummary::build(&absences).formatted()
+        );
+    }
+    Ok(())
+}
+
+/// `Today: 5h 10m of 8h 0m (-2h 50m)` below the status, if a contract is
+/// configured. Best effort: a broken `[schedule]` (say, an unreadable
+/// holidays file) is mentioned but never fails `status`.
+fn print_daily_delta(engine: &TrackingEngine, config: &Config, theme: theme::MateriaTheme) {
+    if !config.schedule.has_contract() {
+        return;
+    }
+    match daily_delta(engine, config, theme) {
+        Ok(Some(line)) => println!("{}", line),
+        Ok(None) => {}
+        Err(e) => print_info(&format!("Today's balance skipped: {}", e)),
+    }
+}
+
+fn daily_delta(
+    engine: &TrackingEngine,
+    config: &Config,
+    theme: theme::MateriaTheme,
+) -> Result<Option<String>> {
+    let today = Local::now().date_naive();
+    let calendar = balance::WorkCalendar::load(&config.schedule)?;
+    let day = StatsEngine::new(engine.db().clone(), theme)
+        .balance(today, today, &calendar)?
+        .days
+        .remove(0);
+    if day.expected_seconds == 0 && day.tracked_seconds == 0 {
+        return Ok(None);
+    }
+    let delta = balance::format_delta(day.delta_seconds);
+    Ok(Some(format!(
+        "  Today: {} of {} ({})",
+        stats::format_seconds(day.tracked_seconds),
+        stats::format_seconds(day.expected_seconds),
+        if day.delta_seconds < 0 {
+            delta.red()
+        } else {
+            delta.green()
+        }
+    )))
+}
+
+/// Build the entry filter from `--where` / `--filter`, resolving saved filters from the config.
+fn entry_filter(args: &FilterArgs, config: &Config) -> Result<Option<filter::Filter>> {
+    filter::Filter::from_args(args.expr.as_deref(), args.name.as_deref(), &config.filters)
+}
+
 fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
     if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
         return Some(dt.with_timezone(&Utc));
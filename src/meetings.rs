@@ -0,0 +1,659 @@
+//! Calendar meetings as time entries
+//!
+//! `mtrack import ics` turns the finished occurrences of an `.ics` file into
+//! entries, mapped to projects and tasks by the `[meetings]` rules.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use regex::Regex;
+use std::collections::BTreeMap;
+
+use crate::calendar::ics::{IcsFile, IcsTime};
+use crate::config::{MeetingsConfig, OverlapPolicy, ScheduleConfig};
+use crate::database::ImportRow;
+use crate::error::{ConfigError, Result};
+use crate::filter::parse_duration;
+use crate::models::EntryWithDetails;
+
+/// Multi-day all-day events fill at most this many days.
+const MAX_ALL_DAY_DAYS: i64 = 366;
+
+/// Why a meeting did not become an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SkipReason {
+    AllDay,
+    Declined,
+    Unmatched,
+    Ignored,
+    TooShort,
+    Overlap,
+}
+
+impl SkipReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::AllDay => "all-day",
+            Self::Declined => "declined",
+            Self::Unmatched => "no matching rule",
+            Self::Ignored => "skipped by a rule",
+            Self::TooShort => "too short",
+            Self::Overlap => "overlapping tracked time",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedMeeting {
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub reason: SkipReason,
+}
+
+/// The entries a calendar would add, and the meetings left out.
+#[derive(Debug, Clone, Default)]
+pub struct MeetingPlan {
+    pub rows: Vec<ImportRow>,
+    pub skipped: Vec<SkippedMeeting>,
+}
+
+impl MeetingPlan {
+    /// `3 meetings skipped (2 declined, 1 too short)`, if any were.
+    pub fn skipped_summary(&self) -> Option<String> {
+        if self.skipped.is_empty() {
+            return None;
+        }
+        let mut counts: BTreeMap<SkipReason, usize> = BTreeMap::new();
+        for skipped in &self.skipped {
+            *counts.entry(skipped.reason).or_default() += 1;
+        }
+        let counts: Vec<String> = counts
+            .iter()
+            .map(|(reason, n)| format!("{} {}", n, reason.label()))
+            .collect();
+        Some(format!(
+            "{} meetings skipped ({})",
+            self.skipped.len(),
+            counts.join(", ")
+        ))
+    }
+}
+
+struct Rule {
+    pattern: Regex,
+    project: String,
+    task: String,
+    tags: Vec<String>,
+    skip: bool,
+}
+
+/// Where a meeting goes.
+struct Target {
+    project: String,
+    task: String,
+    notes: String,
+}
+
+struct Candidate {
+    summary: String,
+    spans: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+    target: Target,
+}
+
+/// The compiled `[meetings]` configuration.
+pub struct MeetingRules {
+    rules: Vec<Rule>,
+    project: String,
+    task: String,
+    min_duration: Duration,
+    skip_declined: bool,
+    me: Vec<String>,
+    overlap: OverlapPolicy,
+    /// Working hours and days for all-day events; `None` skips them
+    working_day: Option<(NaiveTime, NaiveTime, ScheduleConfig)>,
+}
+
+impl MeetingRules {
+    pub fn new(config: &MeetingsConfig, schedule: &ScheduleConfig) -> Result<Self> {
+        let min_duration = if config.min_duration.trim().is_empty() {
+            0
+        } else {
+            parse_duration(&config.min_duration).ok_or_else(|| {
+                ConfigError::ParseError(format!(
+                    "meetings.min_duration must be a duration like 10m, got '{}'",
+                    config.min_duration
+                ))
+            })?
+        };
+
+        let rules = config
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let pattern = Regex::new(&rule.pattern).map_err(|e| {
+                    ConfigError::ParseError(format!("meetings.rules[{}].match: {}", i, e))
+                })?;
+                let project = if rule.project.is_empty() {
+                    config.project.clone()
+                } else {
+                    rule.project.clone()
+                };
+                if project.is_empty() && !rule.skip {
+                    return Err(ConfigError::MissingField(format!(
+                        "meetings.rules[{}].project",
+                        i
+                    ))
+                    .into());
+                }
+                Ok(Rule {
+                    pattern,
+                    project,
+                    task: if rule.task.is_empty() {
+                        config.task.clone()
+                    } else {
+                        rule.task.clone()
+                    },
+                    tags: rule
+                        .tags
+                        .iter()
+                        .map(|t| t.trim_start_matches('#').to_string())
+                        .collect(),
+                    skip: rule.skip,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let working_day = if config.skip_all_day {
+            None
+        } else {
+            let (start, end) = schedule.working_hours()?;
+            Some((start, end, schedule.clone()))
+        };
+
+        Ok(Self {
+            rules,
+            project: config.project.clone(),
+            task: config.task.clone(),
+            min_duration: Duration::seconds(min_duration),
+            skip_declined: config.skip_declined,
+            me: config.me.clone(),
+            overlap: config.overlap,
+            working_day,
+        })
+    }
+
+    pub fn with_overlap(mut self, overlap: OverlapPolicy) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Entries for the meetings that ended by `until` (and started at or after
+    /// `from`), without double-counting time in `tracked` or each other.
+    ///
+    /// Entries this import created before are recognised by their project,
+    /// task and notes, so planning the same file again yields the same rows.
+    pub fn plan<Tz: TimeZone>(
+        &self,
+        file: &IcsFile,
+        from: Option<DateTime<Utc>>,
+        until: DateTime<Utc>,
+        tracked: &[EntryWithDetails],
+        local: &Tz,
+    ) -> MeetingPlan {
+        let mut plan = MeetingPlan::default();
+        let in_range = |(start, end): &(DateTime<Utc>, DateTime<Utc>)| {
+            *end <= until && from.map_or(true, |f| *start >= f)
+        };
+
+        let mut candidates = Vec::new();
+        for (event, occurrence) in file.occurrences(until.with_timezone(local).date_naive(), local)
+        {
+            let start = occurrence.start.to_utc(local);
+            let end = occurrence.end.as_ref().map_or(start, |e| e.to_utc(local));
+            let mut skip = |reason| {
+                plan.skipped.push(SkippedMeeting {
+                    summary: event.summary.clone(),
+                    start,
+                    reason,
+                })
+            };
+
+            let mut spans = match (&occurrence.start, &self.working_day) {
+                (IcsTime::Date(first), Some((work_start, work_end, schedule))) => {
+                    let days = (end - start).num_days().clamp(1, MAX_ALL_DAY_DAYS);
+                    working_days(*first, days, *work_start, *work_end, schedule, local)
+                }
+                (IcsTime::Date(_), None) => {
+                    let end = end.max(start + Duration::days(1));
+                    if in_range(&(start, end)) {
+                        skip(SkipReason::AllDay);
+                    }
+                    continue;
+                }
+                (IcsTime::DateTime(..), _) => vec![(start, end)],
+            };
+            spans.retain(in_range);
+            if spans.is_empty() {
+                continue;
+            }
+
+            if self.skip_declined && event.declined_by(&self.me) {
+                skip(SkipReason::Declined);
+                continue;
+            }
+            let target = match self.target(&event.summary) {
+                Ok(target) => target,
+                Err(reason) => {
+                    skip(reason);
+                    continue;
+                }
+            };
+            spans.retain(|(s, e)| *e - *s >= self.min_duration && e > s);
+            if spans.is_empty() {
+                skip(SkipReason::TooShort);
+                continue;
+            }
+            candidates.push(Candidate {
+                summary: event.summary.clone(),
+                spans,
+                target,
+            });
+        }
+        candidates.sort_by_key(|c| c.spans[0].0);
+
+        let now = Utc::now();
+        let mut accepted: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+        for candidate in candidates {
+            let (first, last) = (
+                candidate.spans[0].0,
+                candidate.spans[candidate.spans.len() - 1].1,
+            );
+            let target = &candidate.target;
+            let busy: Vec<(DateTime<Utc>, DateTime<Utc>)> = tracked
+                .iter()
+                .map(|e| (e, e.entry.start, e.entry.end.unwrap_or(now)))
+                .filter(|(e, start, end)| {
+                    let imported_before = e.project_name == target.project
+                        && e.task_name == target.task
+                        && e.entry.notes.as_deref() == Some(target.notes.as_str())
+                        && *start >= first
+                        && *end <= last;
+                    !imported_before
+                })
+                .map(|(_, start, end)| (start, end))
+                .chain(accepted.iter().copied())
+                .collect();
+
+            let pieces: Vec<(DateTime<Utc>, DateTime<Utc>)> = match self.overlap {
+                OverlapPolicy::Skip => {
+                    if candidate
+                        .spans
+                        .iter()
+                        .any(|span| free_time(*span, &busy) != [*span])
+                    {
+                        Vec::new()
+                    } else {
+                        candidate.spans.clone()
+                    }
+                }
+                OverlapPolicy::Trim => candidate
+                    .spans
+                    .iter()
+                    .flat_map(|span| free_time(*span, &busy))
+                    .filter(|(s, e)| *e - *s >= self.min_duration)
+                    .collect(),
+            };
+            if pieces.is_empty() {
+                plan.skipped.push(SkippedMeeting {
+                    summary: candidate.summary,
+                    start: first,
+                    reason: SkipReason::Overlap,
+                });
+                continue;
+            }
+
+            accepted.extend(pieces.iter().copied());
+            plan.rows
+                .extend(pieces.into_iter().map(|(start, end)| ImportRow {
+                    project: target.project.clone(),
+                    task: target.task.clone(),
+                    start,
+                    end: Some(end),
+                    notes: Some(target.notes.clone()),
+                }));
+        }
+        plan.skipped.sort_by_key(|s| s.start);
+        plan
+    }
+
+    /// The first matching rule's project and task, else the defaults.
+    fn target(&self, summary: &str) -> std::result::Result<Target, SkipReason> {
+        let title = if summary.trim().is_empty() {
+            "(untitled)"
+        } else {
+            summary.trim()
+        };
+        for rule in &self.rules {
+            let Some(captures) = rule.pattern.captures(summary) else {
+                continue;
+            };
+            if rule.skip {
+                return Err(SkipReason::Ignored);
+            }
+            let expand = |template: &str| {
+                let mut out = String::new();
+                captures.expand(template, &mut out);
+                out.trim().to_string()
+            };
+            // An optional group that didn't take part expands to nothing.
+            let (project, task) = (expand(&rule.project), expand(&rule.task));
+            if project.is_empty() || task.is_empty() {
+                return Err(SkipReason::Unmatched);
+            }
+            let mut notes = title.to_string();
+            for tag in &rule.tags {
+                notes.push_str(&format!(" #{}", tag));
+            }
+            return Ok(Target {
+                project,
+                task,
+                notes,
+            });
+        }
+
+        if self.project.is_empty() {
+            return Err(SkipReason::Unmatched);
+        }
+        Ok(Target {
+            project: self.project.clone(),
+            task: self.task.clone(),
+            notes: title.to_string(),
+        })
+    }
+}
+
+/// The working hours of each working day from `first` on.
+fn working_days<Tz: TimeZone>(
+    first: NaiveDate,
+    days: i64,
+    start: NaiveTime,
+    end: NaiveTime,
+    schedule: &ScheduleConfig,
+    local: &Tz,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let at = |day: NaiveDate, time: NaiveTime| {
+        local
+            .from_local_datetime(&day.and_time(time))
+            .earliest()
+            .map(|t| t.with_timezone(&Utc))
+    };
+    (0..days)
+        .map(|n| first + Duration::days(n))
+        .filter(|day| schedule.is_work_day(day.weekday()))
+        .filter_map(|day| Some((at(day, start)?, at(day, end)?)))
+        .collect()
+}
+
+/// The parts of `span` not covered by any of `busy`.
+fn free_time(
+    span: (DateTime<Utc>, DateTime<Utc>),
+    busy: &[(DateTime<Utc>, DateTime<Utc>)],
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut free = vec![span];
+    for &(busy_start, busy_end) in busy {
+        free = free
+            .into_iter()
+            .flat_map(|(start, end)| {
+                if busy_end <= start || busy_start >= end {
+                    return vec![(start, end)];
+                }
+                let mut left = Vec::new();
+                if busy_start > start {
+                    left.push((start, busy_start));
+                }
+                if busy_end < end {
+                    left.push((busy_end, end));
+                }
+                left
+            })
+            .collect();
+    }
+    free
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MeetingRule;
+    use crate::models::Entry;
+
+    fn calendar(events: &[&str]) -> IcsFile {
+        let mut ics = String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:test\r\n");
+        for (i, event) in events.iter().enumerate() {
+            ics.push_str(&format!(
+                "BEGIN:VEVENT\r\nUID:{}\r\n{}\r\nEND:VEVENT\r\n",
+                i,
+                event.replace('|', "\r\n")
+            ));
+        }
+        ics.push_str("END:VCALENDAR\r\n");
+        IcsFile::parse(&ics).unwrap()
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 5, hour, minute, 0).unwrap()
+    }
+
+    fn config() -> MeetingsConfig {
+        MeetingsConfig {
+            min_duration: "10m".into(),
+            me: vec!["me@example.com".into()],
+            rules: vec![
+                MeetingRule {
+                    pattern: "^Lunch".into(),
+                    project: String::new(),
+                    task: String::new(),
+                    tags: Vec::new(),
+                    skip: true,
+                },
+                MeetingRule {
+                    pattern: r"^(?P<client>\w+) sync".into(),
+                    project: "$client".into(),
+                    task: "Sync".into(),
+                    tags: vec!["#meeting".into(), "client".into()],
+                    skip: false,
+                },
+            ],
+            ..MeetingsConfig::default()
+        }
+    }
+
+    fn tracked(
+        project: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        notes: &str,
+    ) -> EntryWithDetails {
+        EntryWithDetails {
+            entry: Entry {
+                id: 1,
+                project_id: 1,
+                task_id: 1,
+                start,
+                end: Some(end),
+                notes: Some(notes.into()),
+                git_commits: Vec::new(),
+            },
+            project_name: project.into(),
+            task_name: "Sync".into(),
+            project_color: None,
+            commits: Vec::new(),
+        }
+    }
+
+    fn plan(config: &MeetingsConfig, file: &IcsFile, tracked: &[EntryWithDetails]) -> MeetingPlan {
+        MeetingRules::new(config, &ScheduleConfig::default())
+            .unwrap()
+            .plan(
+                file,
+                None,
+                Utc.with_ymd_and_hms(2026, 10, 6, 0, 0, 0).unwrap(),
+                tracked,
+                &Utc,
+            )
+    }
+
+    #[test]
+    fn test_rules_map_summaries_and_skip_reasons() {
+        let file = calendar(&[
+            "SUMMARY:Acme sync|DTSTART:20261005T090000Z|DTEND:20261005T093000Z",
+            "SUMMARY:Lunch|DTSTART:20261005T120000Z|DTEND:20261005T130000Z",
+            "SUMMARY:Random chat|DTSTART:20261005T140000Z|DTEND:20261005T150000Z",
+            "SUMMARY:Globex sync|DTSTART:20261005T150000Z|DTEND:20261005T150500Z",
+            "SUMMARY:Initech sync|DTSTART:20261005T160000Z|DTEND:20261005T170000Z|ATTENDEE;PARTSTAT=DECLINED:mailto:ME@example.com",
+            "SUMMARY:Offsite|DTSTART;VALUE=DATE:20261005",
+            "SUMMARY:Acme sync|DTSTART:20261006T090000Z|DTEND:20261006T093000Z",
+        ]);
+        let plan = plan(&config(), &file, &[]);
+
+        assert_eq!(plan.rows.len(), 1);
+        assert_eq!(plan.rows[0].project, "Acme");
+        assert_eq!(plan.rows[0].task, "Sync");
+        assert_eq!(
+            plan.rows[0].notes.as_deref(),
+            Some("Acme sync #meeting #client")
+        );
+
+        let reasons: Vec<(&str, SkipReason)> = plan
+            .skipped
+            .iter()
+            .map(|s| (s.summary.as_str(), s.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                ("Offsite", SkipReason::AllDay),
+                ("Lunch", SkipReason::Ignored),
+                ("Random chat", SkipReason::Unmatched),
+                ("Globex sync", SkipReason::TooShort),
+                ("Initech sync", SkipReason::Declined),
+            ]
+        );
+        assert_eq!(
+            plan.skipped_summary().unwrap(),
+            "5 meetings skipped (1 all-day, 1 declined, 1 no matching rule, 1 skipped by a rule, 1 too short)"
+        );
+    }
+
+    #[test]
+    fn test_rule_with_empty_expansion_is_unmatched() {
+        let mut config = config();
+        config.rules[1].pattern = r"sync(?: with (?P<client>\w+))?$".into();
+        let rules = MeetingRules::new(&config, &ScheduleConfig::default()).unwrap();
+
+        assert_eq!(rules.target("sync with Acme").unwrap().project, "Acme");
+        assert_eq!(rules.target("Team sync").err(), Some(SkipReason::Unmatched));
+    }
+
+    #[test]
+    fn test_overlap_skip_or_trim() {
+        let file = calendar(&[
+            "SUMMARY:Acme sync|DTSTART:20261005T090000Z|DTEND:20261005T110000Z",
+            "SUMMARY:Acme sync 2|DTSTART:20261005T103000Z|DTEND:20261005T120000Z",
+        ]);
+        let worked = [tracked("Acme", at(9, 30), at(10, 0), "coding")];
+
+        let skipped = plan(&config(), &file, &worked);
+        assert_eq!(skipped.rows.len(), 1);
+        assert_eq!(skipped.rows[0].start, at(10, 30));
+        assert_eq!(skipped.skipped.len(), 1);
+        assert_eq!(skipped.skipped[0].summary, "Acme sync");
+        assert_eq!(skipped.skipped[0].reason, SkipReason::Overlap);
+
+        let trim = MeetingsConfig {
+            overlap: OverlapPolicy::Trim,
+            ..config()
+        };
+        let trimmed: Vec<(DateTime<Utc>, Option<DateTime<Utc>>)> = plan(&trim, &file, &worked)
+            .rows
+            .iter()
+            .map(|r| (r.start, r.end))
+            .collect();
+        assert_eq!(
+            trimmed,
+            vec![
+                (at(9, 0), Some(at(9, 30))),
+                (at(10, 0), Some(at(11, 0))),
+                (at(11, 0), Some(at(12, 0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_planning_again_ignores_own_earlier_import() {
+        let file = calendar(&["SUMMARY:Acme sync|DTSTART:20261005T090000Z|DTEND:20261005T110000Z"]);
+        let trim = MeetingsConfig {
+            overlap: OverlapPolicy::Trim,
+            ..config()
+        };
+        let mut worked = vec![tracked("Other", at(10, 0), at(10, 30), "coding")];
+        let first = plan(&trim, &file, &worked);
+        assert_eq!(first.rows.len(), 2);
+
+        for row in &first.rows {
+            worked.push(tracked(
+                &row.project,
+                row.start,
+                row.end.unwrap(),
+                row.notes.as_deref().unwrap(),
+            ));
+        }
+        let again = plan(&trim, &file, &worked);
+        assert_eq!(again.rows, first.rows);
+    }
+
+    #[test]
+    fn test_only_finished_meetings_in_range_and_all_day_working_hours() {
+        let file = calendar(&[
+            "SUMMARY:Acme sync|DTSTART:20261001T180000Z|DTEND:20261001T183000Z|RRULE:FREQ=DAILY;COUNT=10",
+            "SUMMARY:Workshop|DTSTART;VALUE=DATE:20261002|DTEND;VALUE=DATE:20261005",
+        ]);
+        let config = MeetingsConfig {
+            project: "Internal".into(),
+            skip_all_day: false,
+            ..config()
+        };
+        let rules = MeetingRules::new(&config, &ScheduleConfig::default()).unwrap();
+        let plan = rules.plan(
+            &file,
+            Some(Utc.with_ymd_and_hms(2026, 10, 2, 0, 0, 0).unwrap()),
+            at(9, 15),
+            &[],
+            &Utc,
+        );
+
+        let rows: Vec<(&str, DateTime<Utc>)> = plan
+            .rows
+            .iter()
+            .map(|r| (r.project.as_str(), r.start))
+            .collect();
+        // The workshop only fills Friday's working hours; the sync on the 1st
+        // is before the range and the one on the 5th has not happened yet.
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "Internal",
+                    Utc.with_ymd_and_hms(2026, 10, 2, 9, 0, 0).unwrap()
+                ),
+                ("Acme", Utc.with_ymd_and_hms(2026, 10, 2, 18, 0, 0).unwrap()),
+                ("Acme", Utc.with_ymd_and_hms(2026, 10, 3, 18, 0, 0).unwrap()),
+                ("Acme", Utc.with_ymd_and_hms(2026, 10, 4, 18, 0, 0).unwrap()),
+            ]
+        );
+        assert_eq!(
+            plan.rows[0].end,
+            Some(Utc.with_ymd_and_hms(2026, 10, 2, 17, 0, 0).unwrap())
+        );
+        assert!(plan.skipped.is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+//! Prometheus metrics export for MatteriaTrack
+//!
+//! Renders the running timer, today's and this week's time per project and
+//! task, and all-time entry counts in the Prometheus text format that
+//! node_exporter's textfile collector reads. Counter families are declared
+//! under their `_total` sample names.
+
+use crate::error::Result;
+use crate::models::{Entry, Project, Task};
+use crate::period::Period;
+use crate::stats::{GroupNode, GroupedStats, StatsEngine, DEFAULT_GROUPING};
+use chrono::{DateTime, Utc};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+const PREFIX: &str = "materiatrack";
+
+/// A snapshot of everything exported.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    /// Project, task and elapsed seconds of the running timer
+    pub active: Option<(String, String, i64)>,
+    pub today: GroupedStats,
+    pub week: GroupedStats,
+    pub all_time: GroupedStats,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl Metrics {
+    pub fn collect(stats: &StatsEngine, status: Option<(Entry, Project, Task)>) -> Result<Self> {
+        Ok(Self {
+            active: status.map(|(entry, project, task)| {
+                (project.name, task.name, entry.duration().num_seconds())
+            }),
+            today: stats.group(&Period::named("today")?, &DEFAULT_GROUPING)?,
+            week: stats.group(&Period::named("this-week")?, &DEFAULT_GROUPING)?,
+            all_time: stats.group(&Period::all(), &DEFAULT_GROUPING)?,
+            generated_at: Utc::now(),
+        })
+    }
+
+    /// The snapshot as Prometheus text, ending with OpenMetrics' `# EOF`
+    /// (a plain comment to the Prometheus parser).
+    pub fn to_prometheus_text(&self) -> String {
+        let mut out = String::new();
+
+        family(
+            &mut out,
+            "tracking_active",
+            "gauge",
+            "Whether a timer is running (1) or not (0).",
+        );
+        family_sample(&mut out, "tracking_active", &self.active_labels(), 1, 0);
+
+        family(
+            &mut out,
+            "tracking_elapsed_seconds",
+            "gauge",
+            "Seconds the running timer has been active.",
+        );
+        let elapsed = self.active.as_ref().map_or(0, |(_, _, secs)| *secs);
+        family_sample(
+            &mut out,
+            "tracking_elapsed_seconds",
+            &self.active_labels(),
+            elapsed,
+            0,
+        );
+
+        family(
+            &mut out,
+            "today_seconds",
+            "gauge",
+            "Seconds tracked today, including the running timer.",
+        );
+        per_task(&mut out, "today_seconds", &self.today, |n| n.total_seconds);
+
+        family(
+            &mut out,
+            "week_seconds",
+            "gauge",
+            "Seconds tracked this week, including the running timer.",
+        );
+        per_task(&mut out, "week_seconds", &self.week, |n| n.total_seconds);
+
+        family(
+            &mut out,
+            "entries_total",
+            "counter",
+            "Time entries recorded, all time.",
+        );
+        per_task(&mut out, "entries_total", &self.all_time, |n| {
+            n.entry_count as i64
+        });
+
+        family(
+            &mut out,
+            "tracked_seconds_total",
+            "counter",
+            "Seconds tracked, all time.",
+        );
+        per_task(&mut out, "tracked_seconds_total", &self.all_time, |n| {
+            n.total_seconds
+        });
+
+        family(
+            &mut out,
+            "generated_timestamp_seconds",
+            "gauge",
+            "When this snapshot was written.",
+        );
+        let _ = writeln!(
+            out,
+            "{}_generated_timestamp_seconds {}",
+            PREFIX,
+            self.generated_at.timestamp()
+        );
+
+        out.push_str("# EOF\n");
+        out
+    }
+
+    fn active_labels(&self) -> Option<String> {
+        self.active
+            .as_ref()
+            .map(|(project, task, _)| labels(project, task))
+    }
+}
+
+/// Write `content` next to `path` and rename it into place, so a collector
+/// never reads a half-written file. The temporary name doesn't end in
+/// `.prom`, which keeps the textfile collector from picking it up.
+pub fn write_textfile(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let mut tmp = path.as_os_str().to_os_string();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    fs::write(&tmp, content)?;
+    if let Err(e) = fs::rename(&tmp, path) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# TYPE {}_{} {}", PREFIX, name, kind);
+    let _ = writeln!(out, "# HELP {}_{} {}", PREFIX, name, help);
+}
+
+/// One sample: with `labels` and `value` when present, otherwise bare with `idle`.
+fn family_sample(out: &mut String, name: &str, labels: &Option<String>, value: i64, idle: i64) {
+    let _ = match labels {
+        Some(labels) => writeln!(out, "{}_{}{{{}}} {}", PREFIX, name, labels, value),
+        None => writeln!(out, "{}_{} {}", PREFIX, name, idle),
+    };
+}
+
+fn per_task(out: &mut String, name: &str, stats: &GroupedStats, value: impl Fn(&GroupNode) -> i64) {
+    for project in &stats.groups {
+        for task in &project.groups {
+            let _ = writeln!(
+                out,
+                "{}_{}{{{}}} {}",
+                PREFIX,
+                name,
+                labels(&project.key, &task.key),
+                value(task)
+            );
+        }
+    }
+}
+
+fn labels(project: &str, task: &str) -> String {
+    format!(
+        "project=\"{}\",task=\"{}\"",
+        escape_label(project),
+        escape_label(task)
+    )
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::fixtures::{row, seeded};
+    use crate::theme::MateriaTheme;
+    use chrono::Duration;
+
+    fn engine() -> StatsEngine {
+        // Half-hour entries ending now and 400 days ago
+        let now = Utc::now() - Duration::minutes(30);
+        let ago = now - Duration::days(400);
+        let db = seeded(&[
+            row("Acme", "api", now, 30),
+            row("Acme", "api", ago, 30),
+            row("Say \"hi\"", "docs", ago, 30),
+        ]);
+        StatsEngine::new(db, MateriaTheme::Fire)
+    }
+
+    #[test]
+    fn test_idle_snapshot() {
+        let metrics = Metrics::collect(&engine(), None).unwrap();
+        let text = metrics.to_prometheus_text();
+
+        assert!(text.contains("materiatrack_tracking_active 0\n"));
+        assert!(text.contains("materiatrack_tracking_elapsed_seconds 0\n"));
+        assert!(text.contains("materiatrack_entries_total{project=\"Acme\",task=\"api\"} 2\n"));
+        assert!(text.contains(
+            "materiatrack_tracked_seconds_total{project=\"Say \\\"hi\\\"\",task=\"docs\"} 1800\n"
+        ));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_every_family_is_typed_once() {
+        let text = Metrics::collect(&engine(), None)
+            .unwrap()
+            .to_prometheus_text();
+        let types: Vec<&str> = text.lines().filter(|l| l.starts_with("# TYPE")).collect();
+        assert_eq!(types.len(), 7);
+        assert!(types.contains(&"# TYPE materiatrack_entries_total counter"));
+    }
+
+    #[test]
+    fn test_samples_are_typed_for_the_textfile_collector() {
+        // node_exporter parses the Prometheus 0.0.4 format: a sample is only
+        // typed when a preceding `# TYPE` line names it exactly.
+        let text = Metrics::collect(&engine(), None)
+            .unwrap()
+            .to_prometheus_text();
+        let mut typed = std::collections::HashMap::new();
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let (name, kind) = rest.split_once(' ').unwrap();
+                typed.insert(name, kind);
+            } else if !line.starts_with('#') {
+                let name = line.split(['{', ' ']).next().unwrap();
+                let kind = typed
+                    .get(name)
+                    .unwrap_or_else(|| panic!("untyped: {}", line));
+                assert_eq!(*kind == "counter", name.ends_with("_total"), "{}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_textfile_replaces_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics").join("mtrack.prom");
+        write_textfile(&path, "first\n").unwrap();
+        write_textfile(&path, "second\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second\n");
+        let files: Vec<_> = fs::read_dir(path.parent().unwrap()).unwrap().collect();
+        assert_eq!(files.len(), 1);
+    }
+}
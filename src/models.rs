@@ -7,7 +7,7 @@ pub type ProjectId = i64;
 pub type TaskId = i64;
 pub type EntryId = i64;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Project {
     pub id: ProjectId,
     pub name: String,
@@ -38,7 +38,7 @@ impl Project {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Task {
     pub id: TaskId,
     pub project_id: ProjectId,
@@ -67,7 +67,7 @@ impl Task {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Entry {
     pub id: EntryId,
     pub project_id: ProjectId,
@@ -148,12 +148,85 @@ impl Entry {
     }
 }
 
+/// A git commit captured during an entry, stored in `entry_commits`.
+///
+/// Commits migrated from the legacy `"abc1234: message"` strings only carry a
+/// short hash and summary, so the remaining metadata is optional.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryCommit {
+    pub id: i64,
+    pub entry_id: EntryId,
+    pub hash: String,
+    pub repo_path: Option<String>,
+    pub author: Option<String>,
+    pub author_email: Option<String>,
+    pub committed_at: Option<DateTime<Utc>>,
+    pub summary: String,
+    pub files_changed: Option<i64>,
+    pub insertions: Option<i64>,
+    pub deletions: Option<i64>,
+}
+
+impl EntryCommit {
+    pub fn new(hash: impl Into<String>, summary: impl Into<String>) -> Self {
+        Self {
+            id: 0,
+            entry_id: 0,
+            hash: hash.into(),
+            repo_path: None,
+            author: None,
+            author_email: None,
+            committed_at: None,
+            summary: summary.into(),
+            files_changed: None,
+            insertions: None,
+            deletions: None,
+        }
+    }
+
+    /// Parse a legacy `"abc1234: message"` string as stored in `entries.git_commits`.
+    pub fn from_legacy(s: &str) -> Self {
+        match s.split_once(": ") {
+            Some((hash, summary))
+                if !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                Self::new(hash, summary)
+            }
+            _ => Self::new("", s),
+        }
+    }
+
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+
+    /// Compact `"abc1234: message"` label, as kept in `Entry::git_commits`.
+    pub fn label(&self) -> String {
+        if self.hash.is_empty() {
+            self.summary.clone()
+        } else {
+            format!("{}: {}", self.short_hash(), self.summary)
+        }
+    }
+}
+
+/// A captured commit together with the entry it belongs to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitWithDetails {
+    pub commit: EntryCommit,
+    pub project_name: String,
+    pub task_name: String,
+    pub entry_start: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntryWithDetails {
     pub entry: Entry,
     pub project_name: String,
     pub task_name: String,
     pub project_color: Option<String>,
+    #[serde(default)]
+    pub commits: Vec<EntryCommit>,
 }
 
 impl EntryWithDetails {
@@ -163,6 +236,7 @@ impl EntryWithDetails {
             project_name: project.name.clone(),
             task_name: task.name.clone(),
             project_color: project.color.clone(),
+            commits: Vec::new(),
         }
     }
 }
@@ -370,4 +444,16 @@ mod tests {
         assert!(finished.is_some());
         assert!(!state.is_tracking());
     }
+
+    #[test]
+    fn test_entry_commit_from_legacy() {
+        let commit = EntryCommit::from_legacy("abc1234: Fix parser: handle quotes");
+        assert_eq!(commit.hash, "abc1234");
+        assert_eq!(commit.summary, "Fix parser: handle quotes");
+        assert_eq!(commit.label(), "abc1234: Fix parser: handle quotes");
+
+        let commit = EntryCommit::from_legacy("not a commit");
+        assert!(commit.hash.is_empty());
+        assert_eq!(commit.summary, "not a commit");
+    }
 }
@@ -0,0 +1,498 @@
+//! Reporting periods for MatteriaTrack
+//!
+//! A [`Period`] is a half-open `[from, until)` range of local time used by
+//! reading commands. Named periods (`last-week`, `ytd`, `2026-09`, ...) know
+//! their calendar step, so "the previous period" of a month is the previous
+//! month rather than the previous 30 days.
+
+use crate::error::{Error, Result};
+use chrono::{
+    DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Utc,
+};
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Names accepted by [`Period::named`], for help and error messages.
+pub const PERIOD_NAMES: &str = "today, yesterday, this-week, last-week, this-month, last-month, \
+                                this-year, last-year, ytd, YYYY-MM or YYYY";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Period {
+    pub label: String,
+    pub from: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    step: Step,
+}
+
+/// How far [`Period::baseline`] moves a period back for `previous`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Span(Duration),
+    Months(u32),
+}
+
+/// The baseline for `--compare`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompareTo {
+    Previous,
+    SameLastYear,
+}
+
+impl FromStr for CompareTo {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "previous" | "prev" => Ok(Self::Previous),
+            "same-last-year" | "last-year" | "yoy" => Ok(Self::SameLastYear),
+            _ => Err(Error::InvalidInput(format!(
+                "Unknown comparison '{}' (expected previous or same-last-year)",
+                s
+            ))),
+        }
+    }
+}
+
+impl Period {
+    /// Everything, from the first entry on.
+    pub fn all() -> Self {
+        Self {
+            label: "All Time".into(),
+            from: None,
+            until: None,
+            step: Step::Span(Duration::zero()),
+        }
+    }
+
+    /// From `since` (or the beginning) until now.
+    pub fn since(since: Option<DateTime<Utc>>) -> Self {
+        match since {
+            Some(from) => Self {
+                label: format!("Since {}", from.with_timezone(&Local).format("%Y-%m-%d")),
+                from: Some(from),
+                until: None,
+                step: Step::Span(Utc::now() - from),
+            },
+            None => Self::all(),
+        }
+    }
+
+    /// A named period relative to today, see [`PERIOD_NAMES`].
+    pub fn named(name: &str) -> Result<Self> {
+        Self::named_on(name, Local::now().date_naive()).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "Unknown period '{}' (expected {})",
+                name, PERIOD_NAMES
+            ))
+        })
+    }
+
+    fn named_on(name: &str, today: NaiveDate) -> Option<Self> {
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let first_of_month = today.with_day(1)?;
+        let first_of_year = NaiveDate::from_ymd_opt(today.year(), 1, 1)?;
+        let tomorrow = today + Duration::days(1);
+        let day = Step::Span(Duration::days(1));
+        let week = Step::Span(Duration::weeks(1));
+
+        let (label, from, until, step) = match name.to_lowercase().as_str() {
+            "today" => ("Today".to_string(), today, tomorrow, day),
+            "yesterday" => ("Yesterday".into(), today - Duration::days(1), today, day),
+            "this-week" | "week" => ("This Week".into(), monday, tomorrow, week),
+            "last-week" => (
+                "Last Week".into(),
+                monday - Duration::weeks(1),
+                monday,
+                week,
+            ),
+            "this-month" | "month" => (
+                "This Month".into(),
+                first_of_month,
+                tomorrow,
+                Step::Months(1),
+            ),
+            "last-month" => (
+                "Last Month".into(),
+                first_of_month.checked_sub_months(Months::new(1))?,
+                first_of_month,
+                Step::Months(1),
+            ),
+            "this-year" | "year" => (
+                "This Year".into(),
+                first_of_year,
+                first_of_year.checked_add_months(Months::new(12))?,
+                Step::Months(12),
+            ),
+            "ytd" => (
+                "Year to Date".into(),
+                first_of_year,
+                tomorrow,
+                Step::Months(12),
+            ),
+            "last-year" => (
+                "Last Year".into(),
+                first_of_year.checked_sub_months(Months::new(12))?,
+                first_of_year,
+                Step::Months(12),
+            ),
+            other => {
+                if let Ok(first) = NaiveDate::parse_from_str(&format!("{}-01", other), "%Y-%m-%d") {
+                    let next = first.checked_add_months(Months::new(1))?;
+                    (
+                        first.format("%B %Y").to_string(),
+                        first,
+                        next,
+                        Step::Months(1),
+                    )
+                } else if other.len() == 4 && other.chars().all(|c| c.is_ascii_digit()) {
+                    let first = NaiveDate::from_ymd_opt(other.parse().ok()?, 1, 1)?;
+                    let next = first.checked_add_months(Months::new(12))?;
+                    (other.to_string(), first, next, Step::Months(12))
+                } else {
+                    return None;
+                }
+            }
+        };
+
+        Some(Self {
+            label,
+            from: Some(local_midnight(from)),
+            until: Some(local_midnight(until)),
+            step,
+        })
+    }
+
+    /// The ISO week `2026-W41`, the week containing a date, or a named
+    /// period like `last-week` snapped to its whole week.
+    pub fn week(spec: &str) -> Result<Self> {
+        let monday = if let Some((year, week)) = spec.to_uppercase().split_once("-W") {
+            year.parse()
+                .ok()
+                .zip(week.parse().ok())
+                .and_then(|(y, w)| NaiveDate::from_isoywd_opt(y, w, chrono::Weekday::Mon))
+        } else if let Some(date) = parse_date(spec) {
+            Some(date - Duration::days(date.weekday().num_days_from_monday() as i64))
+        } else {
+            Self::named(spec)?
+                .from
+                .map(|f| f.with_timezone(&Local).date_naive())
+                .map(|d| d - Duration::days(d.weekday().num_days_from_monday() as i64))
+        };
+
+        monday.map(Self::iso_week).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "Invalid week '{}' (expected 2026-W41, a date or last-week)",
+                spec
+            ))
+        })
+    }
+
+    /// The whole ISO week starting on `monday`.
+    pub fn iso_week(monday: NaiveDate) -> Self {
+        Self {
+            label: monday.format("%G-W%V").to_string(),
+            from: Some(local_midnight(monday)),
+            until: Some(local_midnight(monday + Duration::weeks(1))),
+            step: Step::Span(Duration::weeks(1)),
+        }
+    }
+
+    /// The current ISO week so far and the `weeks - 1` whole weeks before it.
+    pub fn last_weeks(weeks: u32) -> Self {
+        let today = Local::now().date_naive();
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let weeks = weeks.max(1);
+        Self {
+            label: match weeks {
+                1 => "This Week".to_string(),
+                n => format!("Last {} weeks", n),
+            },
+            from: Some(local_midnight(monday - Duration::weeks(weeks as i64 - 1))),
+            until: Some(local_midnight(today + Duration::days(1))),
+            step: Step::Span(Duration::weeks(weeks as i64)),
+        }
+    }
+
+    /// Local dates covered by the period; empty when it is open-ended.
+    pub fn days(&self) -> Vec<NaiveDate> {
+        let (Some(from), Some(until)) = (self.from, self.until) else {
+            return Vec::new();
+        };
+        let first = from.with_timezone(&Local).date_naive();
+        let last = (until - Duration::seconds(1))
+            .with_timezone(&Local)
+            .date_naive();
+        first.iter_days().take_while(|d| *d <= last).collect()
+    }
+
+    /// A custom range. Dates are inclusive, so `--until 2026-09-30` covers
+    /// the whole day; RFC 3339 timestamps are used as given.
+    pub fn range(from: Option<&str>, until: Option<&str>) -> Result<Self> {
+        let bound = |s: &str, end: bool| {
+            parse_bound(s, end).ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "Invalid date '{}' (expected YYYY-MM-DD, today, yesterday, -7d or RFC 3339)",
+                    s
+                ))
+            })
+        };
+        let from = from.map(|s| bound(s, false)).transpose()?;
+        let until = until.map(|s| bound(s, true)).transpose()?;
+
+        if let (Some(from), Some(until)) = (from, until) {
+            if until <= from {
+                return Err(Error::InvalidInput(
+                    "--until must be after --from".to_string(),
+                ));
+            }
+        }
+
+        let step = match (from, until) {
+            (Some(from), Some(until)) => month_span(from, until)
+                .map(Step::Months)
+                .unwrap_or(Step::Span(until - from)),
+            (Some(from), None) => Step::Span(Utc::now() - from),
+            _ => Step::Span(Duration::zero()),
+        };
+
+        let day = |t: DateTime<Utc>| t.with_timezone(&Local).format("%Y-%m-%d").to_string();
+        let last_day = |t: DateTime<Utc>| day(t - Duration::seconds(1));
+        let label = match (from, until) {
+            (Some(f), Some(u)) => format!("{} – {}", day(f), last_day(u)),
+            (Some(f), None) => format!("Since {}", day(f)),
+            (None, Some(u)) => format!("Until {}", last_day(u)),
+            (None, None) => "All Time".into(),
+        };
+
+        Ok(Self {
+            label,
+            from,
+            until,
+            step,
+        })
+    }
+
+    /// The period to compare against.
+    pub fn baseline(&self, compare: CompareTo) -> Result<Self> {
+        let Some(from) = self.from else {
+            return Err(Error::InvalidInput(
+                "Comparisons need a period with a start (use --from or --period)".into(),
+            ));
+        };
+        let until = self.until.unwrap_or_else(Utc::now);
+
+        let (from, until, label) = match compare {
+            CompareTo::Previous => match self.step {
+                Step::Months(n) => (
+                    shift_months(from, n)?,
+                    shift_months(until, n)?,
+                    "Previous Period",
+                ),
+                Step::Span(span) => (shift(from, span), shift(until, span), "Previous Period"),
+            },
+            CompareTo::SameLastYear => (
+                shift_months(from, 12)?,
+                shift_months(until, 12)?,
+                "Same Period Last Year",
+            ),
+        };
+
+        let day = |t: DateTime<Utc>| t.with_timezone(&Local).format("%Y-%m-%d").to_string();
+        Ok(Self {
+            label: format!(
+                "{} ({} – {})",
+                label,
+                day(from),
+                day(until - Duration::seconds(1))
+            ),
+            from: Some(from),
+            until: Some(until),
+            step: self.step,
+        })
+    }
+
+    pub fn contains(&self, t: DateTime<Utc>) -> bool {
+        self.from.map_or(true, |f| t >= f) && self.until.map_or(true, |u| t < u)
+    }
+}
+
+/// Dates like `2026-09-01`, `today`, `yesterday` or `-7d`.
+pub fn parse_date(s: &str) -> Option<NaiveDate> {
+    let today = Local::now().date_naive();
+    match s.to_lowercase().as_str() {
+        "today" => Some(today),
+        "yesterday" => Some(today - Duration::days(1)),
+        other => {
+            if let Some(days) = other.strip_prefix('-').and_then(|d| d.strip_suffix('d')) {
+                return days.parse::<i64>().ok().map(|d| today - Duration::days(d));
+            }
+            NaiveDate::parse_from_str(other, "%Y-%m-%d").ok()
+        }
+    }
+}
+
+/// A range bound: RFC 3339 as given, or a date's local midnight (the next
+/// midnight for an inclusive `end`).
+fn parse_bound(s: &str, end: bool) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    let date = parse_date(s)?;
+    Some(local_midnight(if end {
+        date + Duration::days(1)
+    } else {
+        date
+    }))
+}
+
+/// The UTC instant of local midnight starting `date`.
+pub fn local_midnight(date: NaiveDate) -> DateTime<Utc> {
+    to_utc(date.and_hms_opt(0, 0, 0).unwrap())
+}
+
+fn to_utc(local: NaiveDateTime) -> DateTime<Utc> {
+    Local
+        .from_local_datetime(&local)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&local))
+        .with_timezone(&Utc)
+}
+
+/// Move back by `span` in local wall-clock time, so midnights stay midnights
+/// across DST changes.
+fn shift(t: DateTime<Utc>, span: Duration) -> DateTime<Utc> {
+    to_utc(t.with_timezone(&Local).naive_local() - span)
+}
+
+fn shift_months(t: DateTime<Utc>, months: u32) -> Result<DateTime<Utc>> {
+    t.with_timezone(&Local)
+        .naive_local()
+        .checked_sub_months(Months::new(months))
+        .map(to_utc)
+        .ok_or_else(|| Error::InvalidInput("Date out of range".into()))
+}
+
+/// Whole months between two local month starts, if both are month starts.
+fn month_span(from: DateTime<Utc>, until: DateTime<Utc>) -> Option<u32> {
+    let from = from.with_timezone(&Local).naive_local();
+    let until = until.with_timezone(&Local).naive_local();
+    let midnight = chrono::NaiveTime::MIN;
+    if from.day() != 1 || until.day() != 1 || from.time() != midnight || until.time() != midnight {
+        return None;
+    }
+    let months = (until.year() - from.year()) * 12 + until.month() as i32 - from.month() as i32;
+    u32::try_from(months).ok().filter(|m| *m > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn local_date(t: Option<DateTime<Utc>>) -> NaiveDate {
+        t.unwrap().with_timezone(&Local).date_naive()
+    }
+
+    #[test]
+    fn test_named_periods() {
+        // A Wednesday
+        let today = date(2026, 10, 14);
+
+        let week = Period::named_on("last-week", today).unwrap();
+        assert_eq!(local_date(week.from), date(2026, 10, 5));
+        assert_eq!(local_date(week.until), date(2026, 10, 12));
+
+        let month = Period::named_on("last-month", today).unwrap();
+        assert_eq!(local_date(month.from), date(2026, 9, 1));
+        assert_eq!(local_date(month.until), date(2026, 10, 1));
+
+        let ytd = Period::named_on("ytd", today).unwrap();
+        assert_eq!(local_date(ytd.from), date(2026, 1, 1));
+        assert_eq!(local_date(ytd.until), date(2026, 10, 15));
+
+        let september = Period::named_on("2026-09", today).unwrap();
+        assert_eq!(september.label, "September 2026");
+        assert_eq!(september, month.clone().relabel(&september.label));
+
+        assert!(Period::named_on("fortnight", today).is_none());
+    }
+
+    #[test]
+    fn test_baselines() {
+        let today = date(2026, 10, 14);
+
+        let month = Period::named_on("last-month", today).unwrap();
+        let previous = month.baseline(CompareTo::Previous).unwrap();
+        assert_eq!(local_date(previous.from), date(2026, 8, 1));
+        assert_eq!(local_date(previous.until), date(2026, 9, 1));
+
+        // Week to date compares with the same weekdays of last week
+        let week = Period::named_on("this-week", today).unwrap();
+        let previous = week.baseline(CompareTo::Previous).unwrap();
+        assert_eq!(local_date(previous.from), date(2026, 10, 5));
+        assert_eq!(local_date(previous.until), date(2026, 10, 8));
+
+        let ytd = Period::named_on("ytd", today).unwrap();
+        let last_year = ytd.baseline(CompareTo::SameLastYear).unwrap();
+        assert_eq!(local_date(last_year.from), date(2025, 1, 1));
+        assert_eq!(local_date(last_year.until), date(2025, 10, 15));
+
+        assert!(Period::all().baseline(CompareTo::Previous).is_err());
+    }
+
+    #[test]
+    fn test_custom_range() {
+        let range = Period::range(Some("2026-09-01"), Some("2026-09-30")).unwrap();
+        assert_eq!(local_date(range.until), date(2026, 10, 1));
+        assert_eq!(range.label, "2026-09-01 – 2026-09-30");
+        // A whole month steps back by a month, not by 30 days
+        let previous = range.baseline(CompareTo::Previous).unwrap();
+        assert_eq!(local_date(previous.from), date(2026, 8, 1));
+
+        let days = Period::range(Some("2026-09-10"), Some("2026-09-12")).unwrap();
+        let previous = days.baseline(CompareTo::Previous).unwrap();
+        assert_eq!(local_date(previous.from), date(2026, 9, 7));
+        assert_eq!(local_date(previous.until), date(2026, 9, 10));
+
+        assert!(Period::range(Some("2026-09-12"), Some("2026-09-10")).is_err());
+        assert!(Period::range(Some("soon"), None).is_err());
+    }
+
+    #[test]
+    fn test_iso_week() {
+        let week = Period::week("2026-W41").unwrap();
+        assert_eq!(week.label, "2026-W41");
+        let days = week.days();
+        assert_eq!(days.len(), 7);
+        assert_eq!(days[0], date(2026, 10, 5));
+        assert_eq!(days[6], date(2026, 10, 11));
+
+        assert_eq!(Period::week("2026-10-08").unwrap(), week);
+        assert!(Period::week("2026-W60").is_err());
+    }
+
+    #[test]
+    fn test_compare_to_from_str() {
+        assert_eq!(
+            "previous".parse::<CompareTo>().unwrap(),
+            CompareTo::Previous
+        );
+        assert_eq!(
+            "same-last-year".parse::<CompareTo>().unwrap(),
+            CompareTo::SameLastYear
+        );
+        assert!("later".parse::<CompareTo>().is_err());
+    }
+
+    impl Period {
+        fn relabel(mut self, label: &str) -> Self {
+            self.label = label.to_string();
+            self
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Named profiles for MatteriaTrack
+//!
+//! A profile keeps its own database, config overrides, achievements, frecency
+//! and calendar files. The `default` profile is the classic layout directly in
+//! the config directory; every other profile lives in `profiles/<name>/`.
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+pub const DEFAULT_PROFILE: &str = "default";
+pub const PROFILE_ENV: &str = "MTRACK_PROFILE";
+
+const PROFILES_DIR: &str = "profiles";
+const CURRENT_PROFILE_FILE: &str = "current_profile";
+
+static ACTIVE: OnceLock<String> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub active: bool,
+    pub database: String,
+}
+
+/// Pick the profile for this process: `--profile`, then `MTRACK_PROFILE`,
+/// then the one chosen with `mtrack profile switch`, then `default`.
+pub fn activate(flag: Option<&str>) -> Result<String> {
+    let name = match flag {
+        Some(name) => name.to_string(),
+        None => resolve()?,
+    };
+
+    validate_name(&name)?;
+    if !exists(&name)? {
+        return Err(Error::NotFound(format!(
+            "Profile '{}' (create it with `mtrack profile create {}`)",
+            name, name
+        )));
+    }
+
+    let _ = ACTIVE.set(name.clone());
+    Ok(name)
+}
+
+/// The active profile, resolving it on the fly if [`activate`] was not called.
+pub fn active() -> String {
+    ACTIVE
+        .get()
+        .cloned()
+        .unwrap_or_else(|| resolve().unwrap_or_else(|_| DEFAULT_PROFILE.to_string()))
+}
+
+fn resolve() -> Result<String> {
+    if let Ok(name) = std::env::var(PROFILE_ENV) {
+        if !name.trim().is_empty() {
+            return Ok(name.trim().to_string());
+        }
+    }
+
+    let current = Config::config_dir()?.join(CURRENT_PROFILE_FILE);
+    match fs::read_to_string(current) {
+        Ok(name) if !name.trim().is_empty() => Ok(name.trim().to_string()),
+        _ => Ok(DEFAULT_PROFILE.to_string()),
+    }
+}
+
+/// Directory holding a profile's config overrides and per-profile state files.
+pub fn profile_dir(name: &str) -> Result<PathBuf> {
+    let root = Config::config_dir()?;
+    if name == DEFAULT_PROFILE {
+        Ok(root)
+    } else {
+        Ok(root.join(PROFILES_DIR).join(name))
+    }
+}
+
+/// Default database location for a profile without an explicit `database.path`.
+pub fn default_db_path(name: &str) -> Result<PathBuf> {
+    Ok(Config::data_dir()?
+        .join(PROFILES_DIR)
+        .join(name)
+        .join("materiatrack.db"))
+}
+
+pub fn exists(name: &str) -> Result<bool> {
+    Ok(name == DEFAULT_PROFILE || profile_dir(name)?.is_dir())
+}
+
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidInput(format!(
+            "Invalid profile name '{}': use letters, digits, '-' and '_'",
+            name
+        )))
+    }
+}
+
+pub fn list() -> Result<Vec<ProfileInfo>> {
+    let mut names = vec![DEFAULT_PROFILE.to_string()];
+
+    let dir = Config::config_dir()?.join(PROFILES_DIR);
+    if dir.is_dir() {
+        let mut others: Vec<String> = fs::read_dir(&dir)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| e.file_name().to_str().map(String::from))
+            .filter(|n| validate_name(n).is_ok() && n != DEFAULT_PROFILE)
+            .collect();
+        others.sort();
+        names.extend(others);
+    }
+
+    let active = active();
+    names
+        .into_iter()
+        .map(|name| {
+            let database = Config::load_for_profile(&name)
+                .and_then(|c| c.db_path())
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|e| format!("<{}>", e));
+            Ok(ProfileInfo {
+                active: name == active,
+                name,
+                database,
+            })
+        })
+        .collect()
+}
+
+/// Create a profile, optionally pinning its database to `db_path`.
+pub fn create(name: &str, db_path: Option<&str>) -> Result<PathBuf> {
+    validate_name(name)?;
+    if exists(name)? {
+        return Err(Error::InvalidInput(format!(
+            "Profile '{}' already exists",
+            name
+        )));
+    }
+
+    let dir = profile_dir(name)?;
+    fs::create_dir_all(&dir)?;
+
+    let overrides = match db_path {
+        Some(path) => format!("[database]\npath = {:?}\n", path),
+        None => String::new(),
+    };
+    fs::write(dir.join("config.toml"), overrides)?;
+    Ok(dir)
+}
+
+/// Make `name` the profile used when neither `--profile` nor `MTRACK_PROFILE` is set.
+pub fn switch(name: &str) -> Result<()> {
+    validate_name(name)?;
+    if !exists(name)? {
+        return Err(Error::NotFound(format!("Profile '{}'", name)));
+    }
+
+    let current = Config::config_dir()?.join(CURRENT_PROFILE_FILE);
+    if name == DEFAULT_PROFILE {
+        if current.exists() {
+            fs::remove_file(current)?;
+        }
+    } else {
+        fs::write(current, name)?;
+    }
+    Ok(())
+}
+
+/// Remove a profile's config and state files. The database is only deleted
+/// with `purge`, and only when it sits in the profile's default location.
+pub fn remove(name: &str, purge: bool) -> Result<()> {
+    validate_name(name)?;
+    if name == DEFAULT_PROFILE {
+        return Err(Error::InvalidInput(
+            "The default profile cannot be removed".into(),
+        ));
+    }
+    if !exists(name)? {
+        return Err(Error::NotFound(format!("Profile '{}'", name)));
+    }
+    if resolve()? == name || active() == name {
+        return Err(Error::InvalidInput(format!(
+            "Profile '{}' is active; switch to another profile first",
+            name
+        )));
+    }
+
+    if purge {
+        let default_db = default_db_path(name)?;
+        let configured = Config::load_for_profile(name)?.db_path()?;
+        if configured == default_db {
+            if let Some(data_dir) = default_db.parent() {
+                if data_dir.exists() {
+                    fs::remove_dir_all(data_dir)?;
+                }
+            }
+        }
+    }
+
+    fs::remove_dir_all(profile_dir(name)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("work").is_ok());
+        assert!(validate_name("free-lance_2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name("with space").is_err());
+    }
+
+    #[test]
+    fn test_default_profile_uses_config_dir() {
+        assert_eq!(
+            profile_dir(DEFAULT_PROFILE).unwrap(),
+            Config::config_dir().unwrap()
+        );
+        assert!(profile_dir("work")
+            .unwrap()
+            .ends_with(PathBuf::from("profiles").join("work")));
+    }
+}
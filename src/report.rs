@@ -0,0 +1,525 @@
+//! Shareable reports for MatteriaTrack
+//!
+//! `mtrack report html` writes a single offline HTML file: summary figures,
+//! inline SVG charts drawn here (time per project, a daily stacked area and
+//! a donut of task shares) and a sortable table of every entry. Projects
+//! keep their configured colours; nothing is loaded from the network.
+
+use crate::models::EntryWithDetails;
+use crate::period::Period;
+use crate::stats::format_seconds;
+use crate::theme::MateriaTheme;
+use crate::timesheet::html_escape;
+use chrono::{Local, NaiveDate};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Colours for projects without a valid `#rrggbb` colour of their own.
+pub(crate) const PALETTE: [&str; 10] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+    "#9c755f", "#bab0ac",
+];
+/// Donut slices before the rest is folded into "Other".
+const MAX_SLICES: usize = 8;
+const CHART_WIDTH: f64 = 720.0;
+
+pub struct HtmlReport {
+    period: Period,
+    entries: Vec<EntryWithDetails>,
+    /// Projects by total time, with their colour
+    projects: Vec<ProjectTotal>,
+    /// (label, colour, opacity, seconds), largest first
+    slices: Vec<(String, String, f64, i64)>,
+    days: Vec<NaiveDate>,
+    total_seconds: i64,
+}
+
+struct ProjectTotal {
+    name: String,
+    color: String,
+    seconds: i64,
+    /// Seconds per entry of `days`
+    daily: Vec<i64>,
+}
+
+impl HtmlReport {
+    pub fn build(mut entries: Vec<EntryWithDetails>, period: &Period) -> Self {
+        entries.sort_by_key(|e| e.entry.start);
+        let day_of = |e: &EntryWithDetails| e.entry.start.with_timezone(&Local).date_naive();
+
+        let mut days = period.days();
+        if days.is_empty() {
+            if let (Some(first), Some(last)) = (entries.first(), entries.last()) {
+                days = day_of(first)
+                    .iter_days()
+                    .take_while(|d| *d <= day_of(last))
+                    .collect();
+            }
+        }
+        let day_index: HashMap<NaiveDate, usize> =
+            days.iter().enumerate().map(|(i, d)| (*d, i)).collect();
+
+        let mut projects: Vec<ProjectTotal> = Vec::new();
+        let mut tasks: Vec<(String, String, i64)> = Vec::new();
+        for entry in &entries {
+            let seconds = entry.entry.duration().num_seconds().max(0);
+            let project = match projects.iter().position(|p| p.name == entry.project_name) {
+                Some(i) => &mut projects[i],
+                None => {
+                    let color = valid_color(entry.project_color.as_deref())
+                        .unwrap_or(PALETTE[projects.len() % PALETTE.len()])
+                        .to_string();
+                    projects.push(ProjectTotal {
+                        name: entry.project_name.clone(),
+                        color,
+                        seconds: 0,
+                        daily: vec![0; days.len()],
+                    });
+                    projects.last_mut().expect("just pushed")
+                }
+            };
+            project.seconds += seconds;
+            if let Some(&i) = day_index.get(&day_of(entry)) {
+                project.daily[i] += seconds;
+            }
+
+            match tasks
+                .iter_mut()
+                .find(|(p, t, _)| *p == entry.project_name && *t == entry.task_name)
+            {
+                Some(task) => task.2 += seconds,
+                None => tasks.push((entry.project_name.clone(), entry.task_name.clone(), seconds)),
+            }
+        }
+        projects.sort_by(|a, b| b.seconds.cmp(&a.seconds).then(a.name.cmp(&b.name)));
+        tasks.sort_by(|a, b| b.2.cmp(&a.2).then((&a.0, &a.1).cmp(&(&b.0, &b.1))));
+
+        // Tasks share their project's colour, lighter for each further task
+        let mut shade: HashMap<&str, usize> = HashMap::new();
+        let mut slices: Vec<(String, String, f64, i64)> = Vec::new();
+        let mut other = 0;
+        for (i, (project, task, seconds)) in tasks.iter().enumerate() {
+            if i >= MAX_SLICES {
+                other += seconds;
+                continue;
+            }
+            let color = projects
+                .iter()
+                .find(|p| p.name == *project)
+                .map(|p| p.color.clone())
+                .unwrap_or_default();
+            let n = shade.entry(project.as_str()).or_insert(0);
+            slices.push((
+                format!("{} / {}", project, task),
+                color,
+                [1.0, 0.7, 0.45][*n % 3],
+                *seconds,
+            ));
+            *n += 1;
+        }
+        if other > 0 {
+            slices.push(("Other".to_string(), "#cccccc".to_string(), 1.0, other));
+        }
+
+        Self {
+            period: period.clone(),
+            total_seconds: projects.iter().map(|p| p.seconds).sum(),
+            entries,
+            projects,
+            slices,
+            days,
+        }
+    }
+
+    pub fn to_html(&self, theme: MateriaTheme) -> String {
+        let (r, g, b) = theme.primary_color();
+        let (sr, sg, sb) = theme.secondary_color();
+        let title = format!("Time Report: {}", self.period.label);
+        let mut html = String::new();
+
+        html.push_str(&format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{title}</title>\n<style>\n\
+             body {{ font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 60rem; padding: 0 1rem; color: #222; }}\n\
+             h1 {{ color: rgb({r}, {g}, {b}); margin-bottom: 0.2rem; }}\n\
+             h2 {{ border-bottom: 2px solid rgb({sr}, {sg}, {sb}); padding-bottom: 0.2rem; margin-top: 2.5rem; }}\n\
+             .muted {{ color: #777; }}\n\
+             .cards {{ display: flex; flex-wrap: wrap; gap: 1rem; margin: 1.5rem 0; }}\n\
+             .card {{ flex: 1 1 8rem; border: 1px solid #ddd; border-radius: 6px; padding: 0.75rem 1rem; }}\n\
+             .card strong {{ display: block; font-size: 1.4rem; color: rgb({r}, {g}, {b}); }}\n\
+             svg {{ max-width: 100%; height: auto; font-size: 12px; }}\n\
+             svg text {{ fill: #444; }}\n\
+             .legend {{ list-style: none; padding: 0; }}\n\
+             .legend li {{ margin: 0.2rem 0; }}\n\
+             .swatch {{ display: inline-block; width: 0.8rem; height: 0.8rem; border-radius: 2px; margin-right: 0.4rem; vertical-align: middle; }}\n\
+             .donut {{ display: flex; flex-wrap: wrap; align-items: center; gap: 2rem; }}\n\
+             table {{ border-collapse: collapse; width: 100%; }}\n\
+             th, td {{ padding: 0.35rem 0.6rem; border-bottom: 1px solid #ddd; text-align: left; vertical-align: top; }}\n\
+             thead th {{ background: rgb({r}, {g}, {b}); color: #fff; cursor: pointer; user-select: none; }}\n\
+             thead th[aria-sort=ascending]::after {{ content: \" ▲\"; }}\n\
+             thead th[aria-sort=descending]::after {{ content: \" ▼\"; }}\n\
+             td.num, th.num {{ text-align: right; font-variant-numeric: tabular-nums; }}\n\
+             </style>\n</head>\n<body>\n<h1>{title}</h1>\n<p class=\"muted\">Generated {generated} by MatteriaTrack</p>\n",
+            title = html_escape(&title),
+            generated = Local::now().format("%Y-%m-%d %H:%M"),
+        ));
+
+        let active_days = self
+            .days
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.projects.iter().any(|p| p.daily[*i] > 0))
+            .count();
+        html.push_str("<div class=\"cards\">\n");
+        for (label, value) in [
+            ("Total time", format_seconds(self.total_seconds)),
+            ("Entries", self.entries.len().to_string()),
+            ("Projects", self.projects.len().to_string()),
+            ("Active days", active_days.to_string()),
+            (
+                "Per active day",
+                format_seconds(self.total_seconds / active_days.max(1) as i64),
+            ),
+        ] {
+            html.push_str(&format!(
+                "<div class=\"card\">{}<strong>{}</strong></div>\n",
+                label, value
+            ));
+        }
+        html.push_str("</div>\n");
+
+        if self.entries.is_empty() {
+            html.push_str("<p>No entries in this period.</p>\n</body>\n</html>\n");
+            return html;
+        }
+
+        html.push_str("<h2>Time per project</h2>\n");
+        html.push_str(&self.project_bars());
+        html.push_str("<h2>Daily</h2>\n");
+        html.push_str(&self.daily_area());
+        html.push_str("<h2>Task share</h2>\n");
+        html.push_str(&self.task_donut());
+        html.push_str("<h2>Entries</h2>\n");
+        html.push_str(&self.entries_table());
+        html.push_str(SORT_SCRIPT);
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Horizontal bars, one per project.
+    fn project_bars(&self) -> String {
+        let row = 28.0;
+        let label_width = 170.0;
+        let bar_width = CHART_WIDTH - label_width - 90.0;
+        let max = self.projects.first().map(|p| p.seconds).unwrap_or(0).max(1) as f64;
+        let height = row * self.projects.len() as f64 + 8.0;
+
+        let mut svg = format!(
+            "<svg viewBox=\"0 0 {} {}\" role=\"img\" aria-label=\"Time per project\">\n",
+            CHART_WIDTH, height
+        );
+        for (i, project) in self.projects.iter().enumerate() {
+            let y = 4.0 + i as f64 * row;
+            let width = (project.seconds as f64 / max * bar_width).max(1.0);
+            let name = html_escape(&project.name);
+            let time = format_seconds(project.seconds);
+            let _ = writeln!(
+                svg,
+                "<text x=\"{lx}\" y=\"{ty}\" text-anchor=\"end\">{name}</text>\
+                 <rect x=\"{x}\" y=\"{y}\" width=\"{width:.1}\" height=\"20\" rx=\"3\" fill=\"{color}\">\
+                 <title>{name}: {time}</title></rect>\
+                 <text x=\"{vx:.1}\" y=\"{ty}\">{time} ({pct:.0}%)</text>",
+                lx = label_width - 8.0,
+                ty = y + 14.0,
+                x = label_width,
+                color = project.color,
+                vx = label_width + width + 6.0,
+                pct = percent(project.seconds, self.total_seconds),
+            );
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Hours per day, stacked by project.
+    fn daily_area(&self) -> String {
+        let (left, right, top, bottom) = (44.0, 12.0, 10.0, 28.0);
+        let height = 260.0;
+        let plot_w = CHART_WIDTH - left - right;
+        let plot_h = height - top - bottom;
+        let n = self.days.len();
+
+        let totals: Vec<i64> = (0..n)
+            .map(|i| self.projects.iter().map(|p| p.daily[i]).sum())
+            .collect();
+        let max_hours = (totals.iter().copied().max().unwrap_or(0) as f64 / 3600.0)
+            .ceil()
+            .max(1.0);
+        let step_hours = (max_hours / 4.0).ceil();
+        let x = |i: usize| {
+            if n > 1 {
+                left + plot_w * i as f64 / (n - 1) as f64
+            } else {
+                left + plot_w / 2.0
+            }
+        };
+        let y = |seconds: i64| top + plot_h - seconds as f64 / 3600.0 / max_hours * plot_h;
+
+        let mut svg = format!(
+            "<svg viewBox=\"0 0 {} {}\" role=\"img\" aria-label=\"Hours per day by project\">\n",
+            CHART_WIDTH, height
+        );
+        let mut hours = 0.0;
+        while hours <= max_hours {
+            let gy = top + plot_h - hours / max_hours * plot_h;
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{left}\" y1=\"{gy:.1}\" x2=\"{x2}\" y2=\"{gy:.1}\" stroke=\"#e5e5e5\"/>\
+                 <text x=\"{tx}\" y=\"{ty:.1}\" text-anchor=\"end\">{hours}h</text>",
+                x2 = left + plot_w,
+                tx = left - 6.0,
+                ty = gy + 4.0,
+            );
+            hours += step_hours;
+        }
+
+        // Each layer runs along its top edge, then back along the layer below
+        let mut below = vec![0i64; n];
+        for project in self.projects.iter() {
+            let above: Vec<i64> = below
+                .iter()
+                .zip(&project.daily)
+                .map(|(b, d)| b + d)
+                .collect();
+            let mut points: Vec<String> = Vec::new();
+            if n == 1 {
+                points.push(format!("{:.1},{:.1}", left, y(above[0])));
+                points.push(format!("{:.1},{:.1}", left + plot_w, y(above[0])));
+                points.push(format!("{:.1},{:.1}", left + plot_w, y(below[0])));
+                points.push(format!("{:.1},{:.1}", left, y(below[0])));
+            } else {
+                points.extend((0..n).map(|i| format!("{:.1},{:.1}", x(i), y(above[i]))));
+                points.extend(
+                    (0..n)
+                        .rev()
+                        .map(|i| format!("{:.1},{:.1}", x(i), y(below[i]))),
+                );
+            }
+            let _ = writeln!(
+                svg,
+                "<polygon points=\"{}\" fill=\"{}\" fill-opacity=\"0.85\"><title>{}: {}</title></polygon>",
+                points.join(" "),
+                project.color,
+                html_escape(&project.name),
+                format_seconds(project.seconds)
+            );
+            below = above;
+        }
+
+        let every = ((n + 7) / 8).max(1);
+        for (i, day) in self.days.iter().enumerate().step_by(every) {
+            let _ = writeln!(
+                svg,
+                "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+                x(i),
+                height - 8.0,
+                day.format("%d %b")
+            );
+        }
+        svg.push_str("</svg>\n");
+
+        svg.push_str("<ul class=\"legend\">\n");
+        for project in &self.projects {
+            let _ = writeln!(
+                svg,
+                "<li><span class=\"swatch\" style=\"background: {}\"></span>{}</li>",
+                project.color,
+                html_escape(&project.name)
+            );
+        }
+        svg.push_str("</ul>\n");
+        svg
+    }
+
+    /// A ring of dashed circle strokes, one dash per task.
+    fn task_donut(&self) -> String {
+        let radius = 70.0;
+        let circumference = 2.0 * std::f64::consts::PI * radius;
+        let mut svg = String::from(
+            "<div class=\"donut\">\n<svg viewBox=\"0 0 220 220\" width=\"220\" role=\"img\" \
+             aria-label=\"Share of time per task\">\n",
+        );
+        let mut offset = 0.0;
+        for (label, color, opacity, seconds) in &self.slices {
+            let length = *seconds as f64 / self.total_seconds.max(1) as f64 * circumference;
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"110\" cy=\"110\" r=\"{radius}\" fill=\"none\" stroke=\"{color}\" \
+                 stroke-opacity=\"{opacity}\" stroke-width=\"38\" \
+                 stroke-dasharray=\"{length:.2} {circumference:.2}\" stroke-dashoffset=\"{dash:.2}\" \
+                 transform=\"rotate(-90 110 110)\"><title>{label}: {time}</title></circle>",
+                dash = -offset,
+                label = html_escape(label),
+                time = format_seconds(*seconds),
+            );
+            offset += length;
+        }
+        let _ = writeln!(
+            svg,
+            "<text x=\"110\" y=\"115\" text-anchor=\"middle\" font-size=\"16\">{}</text>\n</svg>",
+            format_seconds(self.total_seconds)
+        );
+
+        svg.push_str("<ul class=\"legend\">\n");
+        for (label, color, opacity, seconds) in &self.slices {
+            let _ = writeln!(
+                svg,
+                "<li><span class=\"swatch\" style=\"background: {}; opacity: {}\"></span>{} \
+                 <span class=\"muted\">{} ({:.0}%)</span></li>",
+                color,
+                opacity,
+                html_escape(label),
+                format_seconds(*seconds),
+                percent(*seconds, self.total_seconds)
+            );
+        }
+        svg.push_str("</ul>\n</div>\n");
+        svg
+    }
+
+    fn entries_table(&self) -> String {
+        let mut html = String::from(
+            "<table class=\"sortable\">\n<thead>\n<tr><th>Date</th><th>Start</th><th>End</th>\
+             <th>Project</th><th>Task</th><th class=\"num\">Duration</th><th>Notes</th></tr>\n\
+             </thead>\n<tbody>\n",
+        );
+        for e in &self.entries {
+            let start = e.entry.start.with_timezone(&Local);
+            let end = match e.entry.end {
+                Some(end) => end.with_timezone(&Local).format("%H:%M").to_string(),
+                None => "running".to_string(),
+            };
+            let seconds = e.entry.duration().num_seconds().max(0);
+            let color = self
+                .projects
+                .iter()
+                .find(|p| p.name == e.project_name)
+                .map(|p| p.color.as_str())
+                .unwrap_or_default();
+            let _ = writeln!(
+                html,
+                "<tr><td data-sort=\"{ts}\">{date}</td><td>{start}</td><td>{end}</td>\
+                 <td><span class=\"swatch\" style=\"background: {color}\"></span>{project}</td>\
+                 <td>{task}</td><td class=\"num\" data-sort=\"{seconds}\">{duration}</td><td>{notes}</td></tr>",
+                ts = e.entry.start.timestamp(),
+                date = start.format("%Y-%m-%d"),
+                start = start.format("%H:%M"),
+                project = html_escape(&e.project_name),
+                task = html_escape(&e.task_name),
+                duration = format_seconds(seconds),
+                notes = html_escape(e.entry.notes.as_deref().unwrap_or("")),
+            );
+        }
+        html.push_str("</tbody>\n</table>\n");
+        html
+    }
+}
+
+/// Click a column header to sort by it; click again to reverse.
+const SORT_SCRIPT: &str = "<script>\n\
+document.querySelectorAll('table.sortable th').forEach(function (th, col) {\n\
+  th.addEventListener('click', function () {\n\
+    var table = th.closest('table'), body = table.tBodies[0];\n\
+    var asc = th.getAttribute('aria-sort') !== 'ascending';\n\
+    table.querySelectorAll('th').forEach(function (h) { h.removeAttribute('aria-sort'); });\n\
+    th.setAttribute('aria-sort', asc ? 'ascending' : 'descending');\n\
+    var key = function (row) {\n\
+      var cell = row.cells[col], v = cell.getAttribute('data-sort');\n\
+      return v === null ? cell.textContent.toLowerCase() : parseFloat(v);\n\
+    };\n\
+    Array.prototype.slice.call(body.rows)\n\
+      .sort(function (a, b) { var x = key(a), y = key(b); return (x < y ? -1 : x > y ? 1 : 0) * (asc ? 1 : -1); })\n\
+      .forEach(function (row) { body.appendChild(row); });\n\
+  });\n\
+});\n\
+</script>\n";
+
+/// `#rgb` or `#rrggbb`, as stored by `project add --color`.
+pub(crate) fn valid_color(color: Option<&str>) -> Option<&str> {
+    color.filter(|c| {
+        let hex = c.strip_prefix('#').unwrap_or("");
+        (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|ch| ch.is_ascii_hexdigit())
+    })
+}
+
+fn percent(part: i64, whole: i64) -> f64 {
+    if whole > 0 {
+        part as f64 / whole as f64 * 100.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::fixtures::{local, row, seeded};
+
+    fn report() -> HtmlReport {
+        let notes = "<b>notes</b> & more";
+        let db = seeded(&[
+            row("Acme", "api", local("2026-09-01 09:00"), 120).with_notes(notes),
+            row("Acme", "ui", local("2026-09-02 09:00"), 60).with_notes(notes),
+            row("Beta <Ltd>", "docs", local("2026-09-02 14:00"), 30).with_notes(notes),
+        ]);
+        for (name, color) in [("Acme", "#3498db"), ("Beta <Ltd>", "red\" onload=\"x")] {
+            let mut project = db.get_project_by_name(name).unwrap().unwrap();
+            project.color = Some(color.to_string());
+            db.update_project(&project).unwrap();
+        }
+        let period = Period::named("2026-09").unwrap();
+        let entries = db
+            .list_entries_with_details_between(period.from, period.until, None)
+            .unwrap();
+        HtmlReport::build(entries, &period)
+    }
+
+    #[test]
+    fn test_report_totals_and_colors() {
+        let report = report();
+        assert_eq!(report.days.len(), 30);
+        assert_eq!(report.total_seconds, 210 * 60);
+        assert_eq!(report.projects[0].name, "Acme");
+        assert_eq!(report.projects[0].color, "#3498db");
+        assert_eq!(report.projects[0].daily[..2], [7200, 3600]);
+        // An invalid colour falls back to the palette
+        assert_eq!(report.projects[1].color, PALETTE[1]);
+        assert_eq!(report.slices.len(), 3);
+        assert_eq!(report.slices[1].2, 0.7);
+    }
+
+    #[test]
+    fn test_html_is_self_contained_and_escaped() {
+        let html = report().to_html(MateriaTheme::Fire);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert_eq!(html.matches("<svg").count(), 3);
+        assert_eq!(html.matches("<polygon").count(), 2);
+        assert_eq!(html.matches("stroke-dasharray").count(), 3);
+        assert_eq!(html.matches("<tr><td data-sort").count(), 3);
+        assert!(html.contains("fill=\"#3498db\""));
+        assert!(html.contains("Beta &lt;Ltd&gt;"));
+        assert!(html.contains("&lt;b&gt;notes&lt;/b&gt; &amp; more"));
+        assert!(!html.contains("onload"));
+        assert!(!html.contains("http://") && !html.contains("https://"));
+        assert!(!html.contains("<script src") && !html.contains("<link"));
+    }
+
+    #[test]
+    fn test_empty_report() {
+        let report = HtmlReport::build(Vec::new(), &Period::named("2026-09").unwrap());
+        let html = report.to_html(MateriaTheme::Ice);
+        assert!(html.contains("No entries in this period."));
+        assert!(!html.contains("<svg"));
+    }
+}
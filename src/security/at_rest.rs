@@ -0,0 +1,408 @@
+//! Encryption at rest for the tracking database
+//!
+//! When enabled, only `<db>.gpg` is kept on disk. Each command decrypts it to a
+//! private working copy, runs against that, and seals it again on exit. The
+//! plaintext copy and its SQLite side files are wiped with
+//! [`SecurityManager::secure_delete_file`].
+//!
+//! An exclusive lock on `<db>.gpg.lock` is held from unlock to seal, so
+//! concurrent commands take turns instead of sealing over each other's writes.
+
+use crate::config::{Config, DatabaseEncryption};
+use crate::error::{ConfigError, Error, Result};
+use crate::security::encryption::{GpgEncryption, PasswordEncryption};
+use crate::security::{ensure_secure_directory, set_secure_permissions};
+use crate::security::{SecureStorage, SecureString, SecurityManager};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable holding the database passphrase, for non-interactive use.
+pub const PASSPHRASE_ENV: &str = "MTRACK_DB_PASSPHRASE";
+
+/// Environment variable holding the new passphrase during `mtrack db rekey`.
+pub const NEW_PASSPHRASE_ENV: &str = "MTRACK_DB_NEW_PASSPHRASE";
+
+/// SQLite files that may sit next to a database and contain plaintext.
+const SIDE_FILE_SUFFIXES: &[&str] = &["-wal", "-shm", "-journal"];
+
+/// How long a command waits for another one to seal the database.
+const UNLOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn encrypted_path(db_path: &Path) -> PathBuf {
+    let mut name = db_path.as_os_str().to_os_string();
+    name.push(".gpg");
+    PathBuf::from(name)
+}
+
+/// Build the storage backend for `mode`. Passphrases are read from
+/// `passphrase_env` when set, otherwise GPG prompts through pinentry.
+pub fn storage_for(
+    mode: DatabaseEncryption,
+    recipient: &str,
+    passphrase_env: &str,
+) -> Result<Box<dyn SecureStorage>> {
+    match mode {
+        DatabaseEncryption::None => Err(Error::InvalidInput(
+            "database encryption is not enabled".into(),
+        )),
+        DatabaseEncryption::Passphrase => {
+            let mut storage = PasswordEncryption::new();
+            if let Ok(passphrase) = std::env::var(passphrase_env) {
+                storage = storage.with_passphrase(SecureString::new(passphrase));
+            }
+            Ok(Box::new(storage))
+        }
+        DatabaseEncryption::Gpg => Ok(Box::new(GpgEncryption::new(recipient)?)),
+    }
+}
+
+/// Storage for the database as currently configured.
+pub fn configured_storage(config: &Config) -> Result<Box<dyn SecureStorage>> {
+    storage_for(
+        config.security.database_encryption,
+        &config.security.encryption_key,
+        PASSPHRASE_ENV,
+    )
+}
+
+/// A decrypted working copy of the database, sealed again when dropped.
+pub struct UnlockedDatabase {
+    encrypted_path: PathBuf,
+    working_path: PathBuf,
+    storage: Box<dyn SecureStorage>,
+    digest: Vec<u8>,
+    sealed: bool,
+    /// Dropped after `Drop::drop` has sealed, releasing the lock last.
+    _lock: UnlockLock,
+}
+
+impl UnlockedDatabase {
+    pub fn unlock(db_path: &Path, storage: Box<dyn SecureStorage>) -> Result<Self> {
+        let encrypted_path = encrypted_path(db_path);
+        if !encrypted_path.exists() {
+            return Err(Error::NotFound(format!(
+                "Encrypted database {} (run `mtrack db encrypt` first)",
+                encrypted_path.display()
+            )));
+        }
+
+        let lock = UnlockLock::acquire(&encrypted_path)?;
+        let plaintext = storage.decrypt(&fs::read(&encrypted_path)?)?;
+        let working_path = working_path_for(db_path)?;
+        write_private(&working_path, &plaintext)?;
+
+        Ok(Self {
+            encrypted_path,
+            working_path,
+            storage,
+            digest: Sha256::digest(&plaintext).to_vec(),
+            sealed: false,
+            _lock: lock,
+        })
+    }
+
+    /// Path of the plaintext working copy to open with [`crate::database::Database`].
+    pub fn path(&self) -> &Path {
+        &self.working_path
+    }
+
+    /// Re-encrypt the working copy if it changed and wipe the plaintext.
+    ///
+    /// Every connection to [`Self::path`] must be closed first so SQLite has
+    /// checkpointed its WAL into the main file.
+    pub fn seal(mut self) -> Result<()> {
+        self.seal_inner()
+    }
+
+    fn seal_inner(&mut self) -> Result<()> {
+        if self.sealed {
+            return Ok(());
+        }
+
+        let plaintext = fs::read(&self.working_path)?;
+        if Sha256::digest(&plaintext).as_slice() != self.digest.as_slice() {
+            let ciphertext = self.storage.encrypt(&plaintext)?;
+            write_atomic(&self.encrypted_path, &ciphertext)?;
+        }
+
+        wipe_database_files(&self.working_path)?;
+        self.sealed = true;
+        Ok(())
+    }
+}
+
+impl Drop for UnlockedDatabase {
+    fn drop(&mut self) {
+        // On failure the plaintext copy is left in place rather than losing
+        // the changes it holds.
+        if let Err(e) = self.seal_inner() {
+            eprintln!(
+                "Failed to re-encrypt database, plaintext left at {}: {}",
+                self.working_path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// An exclusive SQLite lock on `<db>.gpg.lock`. The OS releases it if the
+/// process dies, so a crash never leaves the database locked.
+struct UnlockLock {
+    _conn: rusqlite::Connection,
+}
+
+impl UnlockLock {
+    fn acquire(encrypted_path: &Path) -> Result<Self> {
+        let mut path = encrypted_path.as_os_str().to_os_string();
+        path.push(".lock");
+        let conn = rusqlite::Connection::open(PathBuf::from(path))?;
+        conn.busy_timeout(UNLOCK_TIMEOUT)?;
+        match conn.execute_batch("BEGIN EXCLUSIVE") {
+            Ok(()) => Ok(Self { _conn: conn }),
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if e.code == rusqlite::ErrorCode::DatabaseBusy =>
+            {
+                Err(Error::InvalidInput(format!(
+                    "{} is still unlocked by another mtrack process",
+                    encrypted_path.display()
+                )))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Encrypt a plaintext database in place, leaving only `<db>.gpg`.
+pub fn encrypt_database(db_path: &Path, storage: &dyn SecureStorage) -> Result<PathBuf> {
+    let encrypted = encrypted_path(db_path);
+    if encrypted.exists() {
+        return Err(Error::InvalidInput(format!(
+            "{} already exists; use `mtrack db rekey` to change the key",
+            encrypted.display()
+        )));
+    }
+    if !db_path.exists() {
+        return Err(Error::NotFound(format!("Database {}", db_path.display())));
+    }
+
+    checkpoint(db_path)?;
+    let ciphertext = storage.encrypt(&fs::read(db_path)?)?;
+    write_atomic(&encrypted, &ciphertext)?;
+    wipe_database_files(db_path)?;
+
+    Ok(encrypted)
+}
+
+/// Decrypt `<db>.gpg` back to a plain database file.
+pub fn decrypt_database(db_path: &Path, storage: &dyn SecureStorage) -> Result<()> {
+    let encrypted = encrypted_path(db_path);
+    if db_path.exists() {
+        return Err(Error::InvalidInput(format!(
+            "{} already exists; refusing to overwrite it",
+            db_path.display()
+        )));
+    }
+
+    let _lock = UnlockLock::acquire(&encrypted)?;
+    let plaintext = storage.decrypt(&fs::read(&encrypted)?)?;
+    write_private(db_path, &plaintext)?;
+    fs::remove_file(&encrypted)?;
+    Ok(())
+}
+
+/// Re-encrypt `<db>.gpg` with a different key without writing plaintext to disk.
+pub fn rekey_database(
+    db_path: &Path,
+    current: &dyn SecureStorage,
+    new: &dyn SecureStorage,
+) -> Result<()> {
+    let encrypted = encrypted_path(db_path);
+    let _lock = UnlockLock::acquire(&encrypted)?;
+    let plaintext = current.decrypt(&fs::read(&encrypted)?)?;
+    let ciphertext = new.encrypt(&plaintext)?;
+    write_atomic(&encrypted, &ciphertext)
+}
+
+/// Working copies go to the per-user runtime directory (usually tmpfs) when
+/// there is one, so plaintext never reaches persistent storage.
+fn working_path_for(db_path: &Path) -> Result<PathBuf> {
+    let file_name = db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "materiatrack.db".to_string());
+    let name = format!("{}.{}.unlocked", file_name, std::process::id());
+
+    match dirs::runtime_dir() {
+        Some(runtime) => {
+            let dir = runtime.join("materiatrack");
+            ensure_secure_directory(&dir)?;
+            Ok(dir.join(name))
+        }
+        None => Ok(db_path.with_file_name(name)),
+    }
+}
+
+fn checkpoint(db_path: &Path) -> Result<()> {
+    let conn = rusqlite::Connection::open(db_path)?;
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+    Ok(())
+}
+
+fn wipe_database_files(db_path: &Path) -> Result<()> {
+    for suffix in SIDE_FILE_SUFFIXES {
+        let mut side = db_path.as_os_str().to_os_string();
+        side.push(suffix);
+        SecurityManager::secure_delete_file(PathBuf::from(side))?;
+    }
+    SecurityManager::secure_delete_file(db_path)
+}
+
+fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, b"")?;
+    set_secure_permissions(path)?;
+    fs::write(path, data)?;
+    Ok(())
+}
+
+fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_os_string();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    write_private(&tmp, data)?;
+    fs::rename(&tmp, path).map_err(|e| {
+        Error::Config(ConfigError::EncryptionError(format!(
+            "Failed to replace {}: {}",
+            path.display(),
+            e
+        )))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::models::Project;
+
+    /// Reversible stand-in so the lifecycle can be tested without GPG.
+    struct XorStorage(u8);
+
+    impl SecureStorage for XorStorage {
+        fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+            Ok(data.iter().map(|b| b ^ self.0).collect())
+        }
+
+        fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+            self.encrypt(data)
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_encrypted_path() {
+        assert_eq!(
+            encrypted_path(Path::new("/data/materiatrack.db")),
+            PathBuf::from("/data/materiatrack.db.gpg")
+        );
+    }
+
+    #[test]
+    fn test_encrypt_unlock_seal_decrypt() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("track.db");
+
+        {
+            let db = Database::open(&db_path).unwrap();
+            db.create_project(&mut Project::new("Before")).unwrap();
+        }
+
+        encrypt_database(&db_path, &XorStorage(0x5a)).unwrap();
+        assert!(!db_path.exists());
+        let sealed = fs::read(encrypted_path(&db_path)).unwrap();
+        assert!(!sealed.starts_with(b"SQLite format 3"));
+
+        let unlocked = UnlockedDatabase::unlock(&db_path, Box::new(XorStorage(0x5a))).unwrap();
+        let working = unlocked.path().to_path_buf();
+        {
+            let db = Database::open(&working).unwrap();
+            assert_eq!(db.list_projects().unwrap().len(), 1);
+            db.create_project(&mut Project::new("After")).unwrap();
+        }
+        unlocked.seal().unwrap();
+        assert!(!working.exists());
+
+        rekey_database(&db_path, &XorStorage(0x5a), &XorStorage(0x33)).unwrap();
+        decrypt_database(&db_path, &XorStorage(0x33)).unwrap();
+        assert!(!encrypted_path(&db_path).exists());
+
+        let db = Database::open(&db_path).unwrap();
+        assert_eq!(db.list_projects().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_overlapping_unlocks_keep_both_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("track.db");
+        Database::open(&db_path).unwrap();
+        encrypt_database(&db_path, &XorStorage(7)).unwrap();
+
+        let first = UnlockedDatabase::unlock(&db_path, Box::new(XorStorage(7))).unwrap();
+        std::thread::scope(|s| {
+            let second = s.spawn(|| {
+                // Waits until the first copy is sealed, then sees its write.
+                let unlocked = UnlockedDatabase::unlock(&db_path, Box::new(XorStorage(7)))?;
+                let seen = {
+                    let db = Database::open(unlocked.path())?;
+                    db.create_project(&mut Project::new("Second"))?;
+                    db.list_projects()?.len()
+                };
+                unlocked.seal()?;
+                Ok::<_, Error>(seen)
+            });
+
+            {
+                let db = Database::open(first.path()).unwrap();
+                db.create_project(&mut Project::new("First")).unwrap();
+            }
+            std::thread::sleep(Duration::from_millis(200));
+            first.seal().unwrap();
+            assert_eq!(second.join().unwrap().unwrap(), 2);
+        });
+
+        decrypt_database(&db_path, &XorStorage(7)).unwrap();
+        let db = Database::open(&db_path).unwrap();
+        assert_eq!(db.list_projects().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_unchanged_database_is_not_rewritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("track.db");
+        Database::open(&db_path).unwrap();
+        encrypt_database(&db_path, &XorStorage(1)).unwrap();
+
+        let before = fs::metadata(encrypted_path(&db_path))
+            .unwrap()
+            .modified()
+            .unwrap();
+        let unlocked = UnlockedDatabase::unlock(&db_path, Box::new(XorStorage(1))).unwrap();
+        let working = unlocked.path().to_path_buf();
+        drop(unlocked);
+
+        assert!(!working.exists());
+        let after = fs::metadata(encrypted_path(&db_path))
+            .unwrap()
+            .modified()
+            .unwrap();
+        assert_eq!(before, after);
+    }
+}
@@ -3,10 +3,10 @@
 //! Provides transparent encryption/decryption using GPG.
 
 use crate::error::{ConfigError, Result};
-use crate::security::SecureStorage;
+use crate::security::{SecureStorage, SecureString, SecurityManager};
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, Output, Stdio};
 
 const GPG_BINARY: &str = "gpg2";
 const GPG_FALLBACK: &str = "gpg";
@@ -88,10 +88,8 @@ impl GpgEncryption {
                             key_info.can_encrypt = caps.contains('e') || caps.contains('E');
                         }
                     }
-                    "uid" => {
-                        if key_info.user_id.is_empty() {
-                            key_info.user_id = parts.get(9).unwrap_or(&"").to_string();
-                        }
+                    "uid" if key_info.user_id.is_empty() => {
+                        key_info.user_id = parts.get(9).unwrap_or(&"").to_string();
                     }
                     _ => {}
                 }
@@ -159,7 +157,7 @@ impl GpgEncryption {
     }
 
     fn run_gpg_pipe(&self, args: &[&str], input: &[u8]) -> Result<Vec<u8>> {
-        let mut child = Command::new(&self.gpg_binary)
+        let child = Command::new(&self.gpg_binary)
             .args(args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -172,16 +170,7 @@ impl GpgEncryption {
                 )))
             })?;
 
-        if let Some(ref mut stdin) = child.stdin {
-            stdin.write_all(input).map_err(|e| {
-                crate::error::Error::Config(ConfigError::EncryptionError(format!(
-                    "Failed to write to GPG stdin: {}",
-                    e
-                )))
-            })?;
-        }
-
-        let output = child.wait_with_output().map_err(|e| {
+        let output = pipe_through(child, input).map_err(|e| {
             crate::error::Error::Config(ConfigError::EncryptionError(format!(
                 "GPG process failed: {}",
                 e
@@ -241,6 +230,24 @@ impl KeyInfo {
     }
 }
 
+/// Write `input` to the child's stdin from a separate thread while its output
+/// is collected, so a large payload can't fill both pipes and deadlock.
+pub(crate) fn pipe_through(mut child: Child, input: &[u8]) -> std::io::Result<Output> {
+    let stdin = child.stdin.take();
+    std::thread::scope(|s| {
+        let writer = s.spawn(move || match stdin {
+            Some(mut stdin) => stdin.write_all(input),
+            None => Ok(()),
+        });
+        let output = child.wait_with_output()?;
+        match writer.join().expect("GPG stdin writer panicked") {
+            // GPG stopped reading early; its exit status and stderr say why.
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e),
+            _ => Ok(output),
+        }
+    })
+}
+
 pub fn is_gpg_available() -> bool {
     find_gpg_binary().is_some()
 }
@@ -327,12 +334,14 @@ pub fn list_secret_keys() -> Result<Vec<KeyInfo>> {
 
 pub struct PasswordEncryption {
     cipher: String,
+    passphrase: Option<SecureString>,
 }
 
 impl PasswordEncryption {
     pub fn new() -> Self {
         Self {
             cipher: "AES256".to_string(),
+            passphrase: None,
         }
     }
 
@@ -340,37 +349,59 @@ impl PasswordEncryption {
         self.cipher = cipher.into();
         self
     }
-}
 
-impl Default for PasswordEncryption {
-    fn default() -> Self {
-        Self::new()
+    /// Use a fixed passphrase instead of letting GPG prompt through pinentry.
+    pub fn with_passphrase(mut self, passphrase: SecureString) -> Self {
+        self.passphrase = Some(passphrase);
+        self
     }
-}
 
-impl SecureStorage for PasswordEncryption {
-    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+    fn run_gpg(&self, args: &[&str], data: &[u8]) -> Result<Vec<u8>> {
         let gpg = find_gpg_binary().ok_or_else(|| {
             crate::error::Error::Config(ConfigError::InvalidPath("GPG not found".into()))
         })?;
 
-        let mut child = Command::new(&gpg)
-            .args(["--symmetric", "--cipher-algo", &self.cipher, "--armor"])
+        // The passphrase goes through a private temp file so it never shows up
+        // in the process list; the file is wiped as soon as GPG has exited.
+        let passphrase_file = match &self.passphrase {
+            Some(passphrase) => {
+                let mut file = tempfile::NamedTempFile::new()?;
+                file.write_all(passphrase.as_str().as_bytes())?;
+                file.flush()?;
+                Some(file)
+            }
+            None => None,
+        };
+        let passphrase_path = passphrase_file
+            .as_ref()
+            .map(|f| f.path().to_string_lossy().to_string());
+
+        let mut full_args: Vec<&str> = Vec::new();
+        if let Some(ref path) = passphrase_path {
+            full_args.extend([
+                "--batch",
+                "--yes",
+                "--pinentry-mode",
+                "loopback",
+                "--passphrase-file",
+                path,
+            ]);
+        }
+        full_args.extend(args);
+
+        let output = Command::new(&gpg)
+            .args(&full_args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
-            .map_err(|e| {
-                crate::error::Error::Config(ConfigError::EncryptionError(e.to_string()))
-            })?;
+            .and_then(|child| pipe_through(child, data));
 
-        if let Some(ref mut stdin) = child.stdin {
-            stdin.write_all(data).map_err(|e| {
-                crate::error::Error::Config(ConfigError::EncryptionError(e.to_string()))
-            })?;
+        if let Some(file) = passphrase_file {
+            SecurityManager::secure_delete_file(file.path())?;
         }
 
-        let output = child.wait_with_output().map_err(|e| {
+        let output = output.map_err(|e| {
             crate::error::Error::Config(ConfigError::EncryptionError(e.to_string()))
         })?;
 
@@ -383,40 +414,24 @@ impl SecureStorage for PasswordEncryption {
 
         Ok(output.stdout)
     }
+}
 
-    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
-        let gpg = find_gpg_binary().ok_or_else(|| {
-            crate::error::Error::Config(ConfigError::InvalidPath("GPG not found".into()))
-        })?;
-
-        let mut child = Command::new(&gpg)
-            .args(["--decrypt"])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| {
-                crate::error::Error::Config(ConfigError::EncryptionError(e.to_string()))
-            })?;
-
-        if let Some(ref mut stdin) = child.stdin {
-            stdin.write_all(data).map_err(|e| {
-                crate::error::Error::Config(ConfigError::EncryptionError(e.to_string()))
-            })?;
-        }
-
-        let output = child.wait_with_output().map_err(|e| {
-            crate::error::Error::Config(ConfigError::EncryptionError(e.to_string()))
-        })?;
+impl Default for PasswordEncryption {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(crate::error::Error::Config(ConfigError::EncryptionError(
-                stderr.to_string(),
-            )));
-        }
+impl SecureStorage for PasswordEncryption {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.run_gpg(
+            &["--symmetric", "--cipher-algo", &self.cipher, "--armor"],
+            data,
+        )
+    }
 
-        Ok(output.stdout)
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.run_gpg(&["--decrypt"], data)
     }
 
     fn is_available(&self) -> bool {
@@ -461,4 +476,42 @@ mod tests {
         let enc = PasswordEncryption::new();
         assert_eq!(enc.cipher, "AES256");
     }
+
+    #[test]
+    fn test_password_encryption_roundtrip() {
+        if !is_gpg_available() {
+            return;
+        }
+
+        let enc = PasswordEncryption::new().with_passphrase(SecureString::new("correct horse"));
+        let ciphertext = enc.encrypt(b"materia").unwrap();
+        assert_ne!(ciphertext, b"materia");
+        assert_eq!(enc.decrypt(&ciphertext).unwrap(), b"materia");
+
+        let wrong = PasswordEncryption::new().with_passphrase(SecureString::new("wrong"));
+        assert!(wrong.decrypt(&ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_password_encryption_streams_large_payloads() {
+        if !is_gpg_available() {
+            return;
+        }
+
+        // Several MB, well past the pipe buffers, and hard to compress.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let payload: Vec<u8> = (0..4 * 1024 * 1024)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+
+        let enc = PasswordEncryption::new().with_passphrase(SecureString::new("correct horse"));
+        let ciphertext = enc.encrypt(&payload).unwrap();
+        assert!(ciphertext.len() > payload.len() / 2);
+        assert_eq!(enc.decrypt(&ciphertext).unwrap(), payload);
+    }
 }
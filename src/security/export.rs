@@ -2,27 +2,36 @@
 //!
 //! Provides encrypted exports and data sanitization.
 
-use crate::error::{ConfigError, Result};
+use crate::calendar::ics;
+use crate::code_activity::CodeActivity;
+use crate::error::{ConfigError, Error, Result};
 use crate::models::EntryWithDetails;
-use crate::security::encryption::{find_gpg_binary, GpgEncryption};
-use crate::security::SecureStorage;
+use crate::security::encryption::{find_gpg_binary, pipe_through, GpgEncryption};
+use crate::security::{SecureStorage, SecureString};
+use crate::timesheet::markdown_cell;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::io::{Cursor, Read, Write};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use zip::write::SimpleFileOptions;
-use zip::ZipWriter;
+use zip::{AesMode, ZipWriter};
+
+/// Environment variable holding the password for `--password` archives.
+pub const PASSWORD_ENV: &str = "MTRACK_EXPORT_PASSWORD";
 
 #[derive(Debug, Clone)]
 pub struct ExportOptions {
     pub encrypt: bool,
     pub recipient: Option<String>,
     pub sanitize: bool,
-    pub password_protect: bool,
+    pub password: Option<SecureString>,
     pub format: ExportFormat,
     pub since: Option<DateTime<Utc>>,
     pub projects: Option<Vec<String>>,
+    /// Add commits/insertions/deletions columns to CSV
+    pub code_columns: bool,
 }
 
 impl Default for ExportOptions {
@@ -31,10 +40,11 @@ impl Default for ExportOptions {
             encrypt: false,
             recipient: None,
             sanitize: false,
-            password_protect: false,
+            password: None,
             format: ExportFormat::Json,
             since: None,
             projects: None,
+            code_columns: false,
         }
     }
 }
@@ -55,8 +65,9 @@ impl ExportOptions {
         self
     }
 
-    pub fn with_password(mut self) -> Self {
-        self.password_protect = true;
+    /// Wrap the export in a zip archive encrypted with AES-256.
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(SecureString::new(password));
         self
     }
 
@@ -74,6 +85,16 @@ impl ExportOptions {
         self.projects = Some(projects);
         self
     }
+
+    pub fn with_code_columns(mut self) -> Self {
+        self.code_columns = true;
+        self
+    }
+
+    /// Whether the output is a zip archive rather than a text file.
+    pub fn is_archive(&self) -> bool {
+        self.format == ExportFormat::Zip || self.password.is_some()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -81,6 +102,10 @@ pub enum ExportFormat {
     Json,
     Csv,
     Markdown,
+    /// JSON, CSV and Markdown together in one archive
+    Zip,
+    /// iCalendar, one VEVENT per finished entry
+    Ics,
 }
 
 impl ExportFormat {
@@ -89,14 +114,26 @@ impl ExportFormat {
             Self::Json => "json",
             Self::Csv => "csv",
             Self::Markdown => "md",
+            Self::Zip => "zip",
+            Self::Ics => "ics",
         }
     }
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
 
-    pub fn parse(s: &str) -> Self {
+    fn from_str(s: &str) -> Result<Self> {
         match s.to_lowercase().as_str() {
-            "csv" => Self::Csv,
-            "md" | "markdown" => Self::Markdown,
-            _ => Self::Json,
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            "md" | "markdown" => Ok(Self::Markdown),
+            "zip" => Ok(Self::Zip),
+            "ics" | "ical" => Ok(Self::Ics),
+            _ => Err(Error::InvalidInput(format!(
+                "Unknown export format '{}' (expected json, csv, md, zip or ics)",
+                s
+            ))),
         }
     }
 }
@@ -142,19 +179,26 @@ impl SecureExporter {
 
         let filtered = self.filter_entries(entries);
 
-        let data = if self.options.sanitize {
-            self.format_sanitized(&filtered)?
+        let (data, path) = if self.options.is_archive() {
+            let path = if output_path.extension().is_some() {
+                output_path.to_path_buf()
+            } else {
+                output_path.with_extension("zip")
+            };
+            (self.create_archive(&filtered)?, path)
         } else {
-            self.format_full(&filtered)?
+            (
+                self.format_entries(&filtered, self.options.format)?
+                    .into_bytes(),
+                output_path.to_path_buf(),
+            )
         };
 
         let final_path = if self.options.encrypt {
-            self.encrypt_and_write(data.as_bytes(), output_path)?
-        } else if self.options.password_protect {
-            self.create_protected_archive(data.as_bytes(), output_path)?
+            self.encrypt_and_write(&data, &path)?
         } else {
-            fs::write(output_path, &data)?;
-            output_path.to_path_buf()
+            fs::write(&path, &data)?;
+            path
         };
 
         Ok(ExportResult {
@@ -162,11 +206,22 @@ impl SecureExporter {
             entry_count: filtered.len(),
             encrypted: self.options.encrypt,
             sanitized: self.options.sanitize,
+            password_protected: self.options.password.is_some(),
             format: self.options.format,
         })
     }
 
-    fn filter_entries<'a>(&self, entries: &'a [EntryWithDetails]) -> Vec<&'a EntryWithDetails> {
+    /// The export as text, for printing. Archives and encrypted exports need a file.
+    pub fn render(&self, entries: &[EntryWithDetails]) -> Result<String> {
+        if self.options.is_archive() || self.options.encrypt {
+            return Err(Error::InvalidInput(
+                "zip, encrypted and password-protected exports need --output".into(),
+            ));
+        }
+        self.format_entries(&self.filter_entries(entries), self.options.format)
+    }
+
+    pub fn filter_entries<'a>(&self, entries: &'a [EntryWithDetails]) -> Vec<&'a EntryWithDetails> {
         entries
             .iter()
             .filter(|e| {
@@ -177,7 +232,10 @@ impl SecureExporter {
                 }
 
                 if let Some(ref projects) = self.options.projects {
-                    if !projects.contains(&e.project_name) {
+                    if !projects
+                        .iter()
+                        .any(|p| p.eq_ignore_ascii_case(&e.project_name))
+                    {
                         return false;
                     }
                 }
@@ -187,33 +245,40 @@ impl SecureExporter {
             .collect()
     }
 
-    fn format_full(&self, entries: &[&EntryWithDetails]) -> Result<String> {
-        match self.options.format {
-            ExportFormat::Json => serde_json::to_string_pretty(entries)
-                .map_err(|e| crate::error::Error::Parse(e.to_string())),
-            ExportFormat::Csv => self.format_csv(entries, false),
-            ExportFormat::Markdown => self.format_markdown(entries, false),
-        }
-    }
-
-    fn format_sanitized(&self, entries: &[&EntryWithDetails]) -> Result<String> {
-        let sanitized: Vec<SanitizedEntry> =
-            entries.iter().map(|e| SanitizedEntry::from(*e)).collect();
-
-        match self.options.format {
-            ExportFormat::Json => serde_json::to_string_pretty(&sanitized)
-                .map_err(|e| crate::error::Error::Parse(e.to_string())),
-            ExportFormat::Csv => self.format_csv(entries, true),
-            ExportFormat::Markdown => self.format_markdown(entries, true),
+    fn format_entries(
+        &self,
+        entries: &[&EntryWithDetails],
+        format: ExportFormat,
+    ) -> Result<String> {
+        let sanitize = self.options.sanitize;
+        match format {
+            ExportFormat::Json if sanitize => {
+                let sanitized: Vec<SanitizedEntry> =
+                    entries.iter().map(|e| SanitizedEntry::from(*e)).collect();
+                serde_json::to_string_pretty(&sanitized).map_err(|e| Error::Parse(e.to_string()))
+            }
+            ExportFormat::Json => {
+                serde_json::to_string_pretty(entries).map_err(|e| Error::Parse(e.to_string()))
+            }
+            ExportFormat::Csv => self.format_csv(entries, sanitize),
+            ExportFormat::Markdown => self.format_markdown(entries, sanitize),
+            ExportFormat::Ics => Ok(ics::entries_to_ics(entries, sanitize)),
+            ExportFormat::Zip => Err(Error::InvalidInput(
+                "zip is an archive, not a text format".into(),
+            )),
         }
     }
 
     fn format_csv(&self, entries: &[&EntryWithDetails], sanitize: bool) -> Result<String> {
         let mut csv = if sanitize {
-            String::from("id,project,task,start,end,duration_seconds\n")
+            String::from("id,project,task,start,end,duration_seconds")
         } else {
-            String::from("id,project,task,start,end,duration_seconds,notes,git_commits\n")
+            String::from("id,project,task,start,end,duration_seconds,notes,git_commits")
         };
+        if self.options.code_columns {
+            csv.push_str(",commits,insertions,deletions");
+        }
+        csv.push('\n');
 
         for e in entries {
             let end_str = e.entry.end.map_or(String::new(), |t| t.to_rfc3339());
@@ -221,7 +286,7 @@ impl SecureExporter {
 
             if sanitize {
                 csv.push_str(&format!(
-                    "{},{},{},{},{},{}\n",
+                    "{},{},{},{},{},{}",
                     e.entry.id,
                     escape_csv(&e.project_name),
                     escape_csv(&e.task_name),
@@ -233,7 +298,7 @@ impl SecureExporter {
                 let notes = e.entry.notes.as_deref().unwrap_or("");
                 let commits = e.entry.git_commits.join("; ");
                 csv.push_str(&format!(
-                    "{},{},{},{},{},{},{},{}\n",
+                    "{},{},{},{},{},{},{},{}",
                     e.entry.id,
                     escape_csv(&e.project_name),
                     escape_csv(&e.task_name),
@@ -244,6 +309,14 @@ impl SecureExporter {
                     escape_csv(&commits)
                 ));
             }
+            if self.options.code_columns {
+                let code = CodeActivity::from_entries([*e]);
+                csv.push_str(&format!(
+                    ",{},{},{}",
+                    code.commits, code.insertions, code.deletions
+                ));
+            }
+            csv.push('\n');
         }
 
         Ok(csv)
@@ -269,7 +342,10 @@ impl SecureExporter {
             let duration = format_duration(e.entry.duration().num_seconds());
             md.push_str(&format!(
                 "| {} | {} | {} | {} |\n",
-                date, e.project_name, e.task_name, duration
+                date,
+                markdown_cell(&e.project_name),
+                markdown_cell(&e.task_name),
+                duration
             ));
         }
 
@@ -301,7 +377,7 @@ impl SecureExporter {
 
     fn encrypt_and_write(&self, data: &[u8], output_path: &Path) -> Result<PathBuf> {
         let recipient = self.options.recipient.as_ref().ok_or_else(|| {
-            crate::error::Error::Config(ConfigError::MissingField(
+            Error::Config(ConfigError::MissingField(
                 "GPG recipient required for encryption".into(),
             ))
         })?;
@@ -309,34 +385,41 @@ impl SecureExporter {
         let gpg = GpgEncryption::new(recipient)?;
         let encrypted = gpg.encrypt(data)?;
 
-        let encrypted_path =
-            output_path.with_extension(format!("{}.gpg", self.options.format.extension()));
+        let mut encrypted_path = output_path.as_os_str().to_os_string();
+        encrypted_path.push(".gpg");
+        let encrypted_path = PathBuf::from(encrypted_path);
 
         fs::write(&encrypted_path, encrypted)?;
 
         Ok(encrypted_path)
     }
 
-    fn create_protected_archive(&self, data: &[u8], output_path: &Path) -> Result<PathBuf> {
-        let archive_path = output_path.with_extension("zip");
-        let file = File::create(&archive_path)?;
-        let mut zip = ZipWriter::new(file);
-
-        let filename = format!("export.{}", self.options.format.extension());
+    /// A zip holding `export.<ext>`, or every text format for [`ExportFormat::Zip`].
+    fn create_archive(&self, entries: &[&EntryWithDetails]) -> Result<Vec<u8>> {
+        let formats = match self.options.format {
+            ExportFormat::Zip => vec![
+                ExportFormat::Json,
+                ExportFormat::Csv,
+                ExportFormat::Markdown,
+            ],
+            format => vec![format],
+        };
 
-        let options =
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let mut options =
             SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        if let Some(ref password) = self.options.password {
+            options = options.with_aes_encryption(AesMode::Aes256, password.as_str());
+        }
 
-        zip.start_file(&filename, options).map_err(|e| {
-            crate::error::Error::Io(std::io::Error::new(std::io::ErrorKind::Other, e))
-        })?;
-
-        zip.write_all(data)?;
-        zip.finish().map_err(|e| {
-            crate::error::Error::Io(std::io::Error::new(std::io::ErrorKind::Other, e))
-        })?;
+        for format in formats {
+            let data = self.format_entries(entries, format)?;
+            zip.start_file(format!("export.{}", format.extension()), options)
+                .map_err(zip_error)?;
+            zip.write_all(data.as_bytes())?;
+        }
 
-        Ok(archive_path)
+        Ok(zip.finish().map_err(zip_error)?.into_inner())
     }
 }
 
@@ -346,6 +429,7 @@ pub struct ExportResult {
     pub entry_count: usize,
     pub encrypted: bool,
     pub sanitized: bool,
+    pub password_protected: bool,
     pub format: ExportFormat,
 }
 
@@ -356,6 +440,9 @@ impl ExportResult {
         if self.encrypted {
             parts.push("encrypted with GPG".into());
         }
+        if self.password_protected {
+            parts.push("password-protected".into());
+        }
         if self.sanitized {
             parts.push("sanitized".into());
         }
@@ -377,6 +464,10 @@ pub fn verify_gpg_recipient(recipient: &str) -> Result<bool> {
     Ok(output.status.success())
 }
 
+fn zip_error(e: zip::result::ZipError) -> Error {
+    Error::Io(std::io::Error::new(std::io::ErrorKind::Other, e))
+}
+
 fn escape_csv(s: &str) -> String {
     if s.contains(',') || s.contains('"') || s.contains('\n') {
         format!("\"{}\"", s.replace('"', "\"\""))
@@ -411,12 +502,7 @@ pub fn decrypt_import<P: AsRef<Path>>(encrypted_path: P) -> Result<Vec<u8>> {
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()
-        .and_then(|mut child| {
-            if let Some(ref mut stdin) = child.stdin {
-                stdin.write_all(&encrypted_data)?;
-            }
-            child.wait_with_output()
-        })
+        .and_then(|child| pipe_through(child, &encrypted_data))
         .map_err(|e| crate::error::Error::Config(ConfigError::EncryptionError(e.to_string())))?;
 
     if !output.status.success() {
@@ -461,6 +547,129 @@ mod tests {
         assert_eq!(ExportFormat::Markdown.extension(), "md");
     }
 
+    #[test]
+    fn test_export_format_from_str() {
+        assert_eq!(
+            "md".parse::<ExportFormat>().unwrap(),
+            ExportFormat::Markdown
+        );
+        assert_eq!("ZIP".parse::<ExportFormat>().unwrap(), ExportFormat::Zip);
+        assert_eq!("ics".parse::<ExportFormat>().unwrap(), ExportFormat::Ics);
+        assert!("xml".parse::<ExportFormat>().is_err());
+    }
+
+    fn entry(project: &str, notes: &str) -> EntryWithDetails {
+        use crate::models::Entry;
+
+        EntryWithDetails {
+            entry: Entry {
+                id: 1,
+                project_id: 1,
+                task_id: 1,
+                start: Utc::now(),
+                end: Some(Utc::now()),
+                notes: Some(notes.into()),
+                git_commits: Vec::new(),
+            },
+            project_name: project.into(),
+            task_name: "Task".into(),
+            project_color: None,
+            commits: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_csv_escapes_notes_and_filters_projects() {
+        let entries = [
+            entry("Acme", "fixed \"login\", again"),
+            entry("Other", "hidden"),
+        ];
+        let exporter = SecureExporter::new(
+            ExportOptions::new()
+                .with_format(ExportFormat::Csv)
+                .with_projects(vec!["Acme".into()])
+                .with_code_columns(),
+        );
+
+        let csv = exporter.render(&entries).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("notes,git_commits,commits,insertions,deletions"));
+        assert!(lines[1].contains(",\"fixed \"\"login\"\", again\","));
+        assert!(lines[1].ends_with(",0,0,0"));
+    }
+
+    #[test]
+    fn test_render_markdown_escapes_pipes_and_matches_projects_case_insensitively() {
+        let entries = [entry("Acme | Co", "notes"), entry("Other", "hidden")];
+        let md = SecureExporter::new(
+            ExportOptions::new()
+                .with_format(ExportFormat::Markdown)
+                .with_projects(vec!["acme | co".into()]),
+        )
+        .render(&entries)
+        .unwrap();
+        assert!(md.contains("| Acme \\| Co | Task |"));
+        assert!(!md.contains("Other"));
+    }
+
+    #[test]
+    fn test_render_ics_skips_running_entries_and_sanitizes() {
+        let mut running = entry("Acme", "still going");
+        running.entry.end = None;
+        let entries = [entry("Acme", "secret, notes"), running];
+
+        let ics = SecureExporter::new(ExportOptions::new().with_format(ExportFormat::Ics))
+            .render(&entries)
+            .unwrap();
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains("SUMMARY:Acme / Task\r\n"));
+        assert!(ics.contains("DESCRIPTION:secret\\, notes\r\n"));
+
+        let sanitized = SecureExporter::new(
+            ExportOptions::new()
+                .with_format(ExportFormat::Ics)
+                .with_sanitization(),
+        )
+        .render(&entries)
+        .unwrap();
+        assert!(!sanitized.contains("DESCRIPTION"));
+    }
+
+    #[test]
+    fn test_password_archive_holds_every_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let exporter = SecureExporter::new(
+            ExportOptions::new()
+                .with_format(ExportFormat::Zip)
+                .with_password("hunter2")
+                .with_sanitization(),
+        );
+        assert!(exporter.render(&[entry("Acme", "secret")]).is_err());
+
+        let result = exporter
+            .export_entries(&[entry("Acme", "secret")], dir.path().join("out"))
+            .unwrap();
+        assert_eq!(result.path, dir.path().join("out.zip"));
+        assert!(result.summary().contains("password-protected"));
+        let named = exporter
+            .export_entries(&[entry("Acme", "secret")], dir.path().join("out.v2"))
+            .unwrap();
+        assert_eq!(named.path, dir.path().join("out.v2"));
+
+        let mut archive = zip::ZipArchive::new(File::open(&result.path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 3);
+        assert!(archive.by_name("export.json").is_err());
+        let mut json = String::new();
+        archive
+            .by_name_decrypt("export.json", b"hunter2")
+            .unwrap()
+            .read_to_string(&mut json)
+            .unwrap();
+        assert!(json.contains("Acme"));
+        assert!(!json.contains("secret"));
+    }
+
     #[test]
     fn test_escape_csv() {
         assert_eq!(escape_csv("simple"), "simple");
@@ -492,6 +701,7 @@ mod tests {
             project_name: "Project".into(),
             task_name: "Task".into(),
             project_color: None,
+            commits: Vec::new(),
         };
 
         let sanitized = SanitizedEntry::from(&entry);
@@ -1,11 +1,12 @@
 //! Security module for MatteriaTrack
 //!
 //! Provides:
-//! - GPG-based database encryption
+//! - GPG-based database encryption, including at rest
 //! - Audit logging with tamper detection
 //! - Secure export with encryption and sanitization
 //! - Zero telemetry enforcement
 
+pub mod at_rest;
 pub mod audit;
 pub mod encryption;
 pub mod export;
@@ -293,6 +294,7 @@ pub fn check_file_permissions<P: AsRef<Path>>(path: P) -> Result<bool> {
     Ok(is_secure)
 }
 
+#[derive(Clone)]
 pub struct SecureString {
     inner: String,
 }
@@ -0,0 +1,412 @@
+//! Standup reports for MatteriaTrack
+//!
+//! Compiles the previous working day's tasks, with their notes and captured
+//! commits, what is running or planned today and any `#blocker` notes into a
+//! "yesterday / today / blockers" summary. The layout is a template with
+//! `{placeholders}`, so teams can bring their own.
+
+use crate::calendar::{CalendarEvent, CalendarEventType};
+use crate::config::ScheduleConfig;
+use crate::filter::extract_tags;
+use crate::models::EntryWithDetails;
+use crate::stats::format_seconds;
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use serde::Serialize;
+
+/// Notes tagged with one of these are listed as blockers.
+pub const BLOCKER_TAGS: [&str; 2] = ["blocker", "blocked"];
+/// How far back to look for the previous working day.
+const MAX_LOOKBACK_DAYS: i64 = 14;
+
+pub const MARKDOWN_TEMPLATE: &str = "## Standup {date}\n\n\
+**Yesterday** ({previous_day}, {total})\n{yesterday}\n\n\
+**Today**\n{today}\n\n\
+**Blockers**\n{blockers}\n";
+
+pub const PLAIN_TEMPLATE: &str = "Standup {date}\n\n\
+Yesterday ({previous_day}, {total}):\n{yesterday}\n\n\
+Today:\n{today}\n\n\
+Blockers:\n{blockers}\n";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Standup {
+    pub date: NaiveDate,
+    pub previous_day: NaiveDate,
+    pub total_seconds: i64,
+    pub yesterday: Vec<StandupTask>,
+    pub running: Option<RunningTask>,
+    /// Today's calendar events
+    pub planned: Vec<String>,
+    pub blockers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StandupTask {
+    pub project: String,
+    pub task: String,
+    pub seconds: i64,
+    pub notes: Vec<String>,
+    pub commits: Vec<StandupCommit>,
+}
+
+/// A captured commit; `hash` is the short hash and may be empty for commits
+/// recorded without one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StandupCommit {
+    pub hash: String,
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningTask {
+    pub project: String,
+    pub task: String,
+    pub since: DateTime<Local>,
+    pub seconds: i64,
+    #[serde(skip)]
+    pub notes: Option<String>,
+}
+
+/// The last working day before `today`, skipping non-working weekdays and
+/// configured days off.
+pub fn previous_working_day(today: NaiveDate, schedule: &ScheduleConfig) -> NaiveDate {
+    (1..=MAX_LOOKBACK_DAYS)
+        .map(|n| today - Duration::days(n))
+        .find(|d| schedule.is_working_date(*d))
+        .unwrap_or(today - Duration::days(1))
+}
+
+impl Standup {
+    /// Summarise `entries` from `previous_day`, in the order they were started.
+    pub fn build(
+        entries: &[EntryWithDetails],
+        date: NaiveDate,
+        previous_day: NaiveDate,
+        running: Option<RunningTask>,
+        events: &[&CalendarEvent],
+    ) -> Self {
+        let mut sorted: Vec<&EntryWithDetails> = entries.iter().collect();
+        sorted.sort_by_key(|e| e.entry.start);
+
+        let mut tasks: Vec<StandupTask> = Vec::new();
+        let mut blockers: Vec<String> = Vec::new();
+        for e in sorted {
+            let i = match tasks
+                .iter()
+                .position(|t| t.project == e.project_name && t.task == e.task_name)
+            {
+                Some(i) => i,
+                None => {
+                    tasks.push(StandupTask {
+                        project: e.project_name.clone(),
+                        task: e.task_name.clone(),
+                        seconds: 0,
+                        notes: Vec::new(),
+                        commits: Vec::new(),
+                    });
+                    tasks.len() - 1
+                }
+            };
+            let task = &mut tasks[i];
+            task.seconds += e.entry.duration().num_seconds().max(0);
+
+            if let Some(notes) = e.entry.notes.as_deref().map(str::trim) {
+                if let Some(blocker) = blocker(notes) {
+                    push_unique(&mut blockers, blocker);
+                } else if !notes.is_empty() {
+                    push_unique(&mut task.notes, notes.to_string());
+                }
+            }
+            for commit in &e.commits {
+                let commit = StandupCommit {
+                    hash: commit.short_hash().to_string(),
+                    summary: commit.summary.clone(),
+                };
+                if !task.commits.contains(&commit) {
+                    task.commits.push(commit);
+                }
+            }
+        }
+
+        if let Some(notes) = running.as_ref().and_then(|r| r.notes.as_deref()) {
+            if let Some(blocker) = blocker(notes) {
+                push_unique(&mut blockers, blocker);
+            }
+        }
+
+        let mut planned: Vec<&&CalendarEvent> = events
+            .iter()
+            .filter(|e| e.date == date && e.event_type != CalendarEventType::TrackingSession)
+            .collect();
+        planned.sort_by(|a, b| a.time.cmp(&b.time));
+
+        Self {
+            date,
+            previous_day,
+            total_seconds: tasks.iter().map(|t| t.seconds).sum(),
+            yesterday: tasks,
+            running,
+            planned: planned
+                .into_iter()
+                .map(|e| match &e.time {
+                    Some(time) => format!("{} {}", time, e.title),
+                    None => e.title.clone(),
+                })
+                .collect(),
+            blockers,
+        }
+    }
+
+    /// Fill `template`, formatting the lists as Markdown or plain text.
+    pub fn render(&self, template: &str, markdown: bool) -> String {
+        let bullet = |text: &str| format!("- {}", text);
+        let strong = |text: &str| {
+            if markdown {
+                format!("**{}**", text)
+            } else {
+                text.to_string()
+            }
+        };
+
+        let mut yesterday: Vec<String> = Vec::new();
+        for task in &self.yesterday {
+            let mut line = format!(
+                "{} ({})",
+                strong(&format!("{} / {}", task.project, task.task)),
+                format_seconds(task.seconds)
+            );
+            if !task.notes.is_empty() {
+                line.push_str(&format!(": {}", task.notes.join("; ")));
+            }
+            yesterday.push(bullet(&line));
+            for commit in &task.commits {
+                yesterday.push(match (markdown, commit.hash.is_empty()) {
+                    (true, false) => format!("  - `{}` {}", commit.hash, commit.summary),
+                    (true, true) => format!("  - {}", commit.summary),
+                    (false, false) => format!("    {} {}", commit.hash, commit.summary),
+                    (false, true) => format!("    {}", commit.summary),
+                });
+            }
+        }
+        if yesterday.is_empty() {
+            yesterday.push(bullet("Nothing tracked"));
+        }
+
+        let running = self.running.as_ref().map(|r| {
+            format!(
+                "{} (running since {}, {})",
+                strong(&format!("{} / {}", r.project, r.task)),
+                r.since.format("%H:%M"),
+                format_seconds(r.seconds)
+            )
+        });
+        let mut today: Vec<String> = running.iter().map(|r| bullet(r)).collect();
+        today.extend(self.planned.iter().map(|p| bullet(p)));
+        if today.is_empty() {
+            today.push(bullet("Nothing planned yet"));
+        }
+
+        let mut blockers: Vec<String> = self.blockers.iter().map(|b| bullet(b)).collect();
+        if blockers.is_empty() {
+            blockers.push(bullet("None"));
+        }
+
+        let values = [
+            ("date", self.date.format("%a %Y-%m-%d").to_string()),
+            (
+                "previous_day",
+                self.previous_day.format("%a %Y-%m-%d").to_string(),
+            ),
+            ("total", format_seconds(self.total_seconds)),
+            ("yesterday", yesterday.join("\n")),
+            ("today", today.join("\n")),
+            ("blockers", blockers.join("\n")),
+            ("running", running.unwrap_or_default()),
+        ];
+        fill(template, &values)
+    }
+}
+
+/// Replace each known `{name}` in `template` in a single pass, so braces in
+/// the substituted values (say, a note mentioning `{today}`) stay as they are.
+/// Unknown placeholders are left untouched.
+fn fill(template: &str, values: &[(&str, String)]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        output.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after.find('}').and_then(|close| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &after[..close])
+                .map(|(_, value)| (close, value))
+        });
+        match value {
+            Some((close, value)) => {
+                output.push_str(value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                output.push('{');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// The note without its blocker tags, if it has one.
+fn blocker(notes: &str) -> Option<String> {
+    let tags = extract_tags(notes);
+    if !tags.iter().any(|t| BLOCKER_TAGS.contains(&t.as_str())) {
+        return None;
+    }
+    let text: Vec<&str> = notes
+        .split_whitespace()
+        .filter(|word| {
+            !BLOCKER_TAGS
+                .iter()
+                .any(|tag| word.eq_ignore_ascii_case(&format!("#{}", tag)))
+        })
+        .collect();
+    Some(if text.is_empty() {
+        notes.to_string()
+    } else {
+        text.join(" ")
+    })
+}
+
+fn push_unique(list: &mut Vec<String>, item: String) {
+    if !list.contains(&item) {
+        list.push(item);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::fixtures::{local, row, seeded};
+    use crate::models::EntryCommit;
+    use chrono::TimeZone;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    fn standup() -> Standup {
+        // Friday 16 October
+        let db = seeded(&[
+            row("Acme", "api", local("2026-10-16 09:00"), 120).with_notes("Pagination"),
+            row("Acme", "review", local("2026-10-16 13:00"), 60)
+                .with_notes("Waiting on staging access #blocker"),
+            row("Acme", "api", local("2026-10-16 15:00"), 60).with_notes("Pagination"),
+        ]);
+        let mut entries = db
+            .list_entries_with_details_between(None, None, None)
+            .unwrap();
+        let api = entries.iter_mut().find(|e| e.task_name == "api").unwrap();
+        api.commits.push(EntryCommit {
+            id: 1,
+            entry_id: api.entry.id,
+            hash: "0123456789abcdef".into(),
+            repo_path: None,
+            author: None,
+            author_email: None,
+            committed_at: None,
+            summary: "Add cursor pagination".into(),
+            files_changed: None,
+            insertions: None,
+            deletions: None,
+        });
+
+        let running = RunningTask {
+            project: "Acme".into(),
+            task: "ui".into(),
+            since: Local.with_ymd_and_hms(2026, 10, 19, 9, 15, 0).unwrap(),
+            seconds: 45 * 60,
+            notes: None,
+        };
+        let planning = CalendarEvent::new("Sprint planning", date(19)).with_time("10:00");
+        let other_day = CalendarEvent::new("Retro", date(20));
+        Standup::build(
+            &entries,
+            date(19),
+            date(16),
+            Some(running),
+            &[&planning, &other_day],
+        )
+    }
+
+    #[test]
+    fn test_previous_working_day_skips_weekends_and_days_off() {
+        let mut schedule = ScheduleConfig::default();
+        // Monday 19 October goes back to Friday
+        assert_eq!(previous_working_day(date(19), &schedule), date(16));
+        assert_eq!(previous_working_day(date(20), &schedule), date(19));
+        schedule.days_off.push(date(16));
+        assert_eq!(previous_working_day(date(19), &schedule), date(15));
+    }
+
+    #[test]
+    fn test_build_groups_tasks_and_collects_blockers() {
+        let standup = standup();
+        assert_eq!(standup.total_seconds, 4 * 3600);
+        assert_eq!(standup.yesterday.len(), 2);
+        let api = &standup.yesterday[0];
+        assert_eq!((api.task.as_str(), api.seconds), ("api", 3 * 3600));
+        assert_eq!(api.notes, vec!["Pagination"]);
+        assert_eq!(
+            api.commits,
+            vec![StandupCommit {
+                hash: "0123456".into(),
+                summary: "Add cursor pagination".into(),
+            }]
+        );
+        assert!(standup.yesterday[1].notes.is_empty());
+        assert_eq!(standup.blockers, vec!["Waiting on staging access"]);
+        assert_eq!(standup.planned, vec!["10:00 Sprint planning"]);
+    }
+
+    #[test]
+    fn test_render_markdown_plain_and_custom_template() {
+        let standup = standup();
+        let markdown = standup.render(MARKDOWN_TEMPLATE, true);
+        assert!(markdown.starts_with("## Standup Mon 2026-10-19"));
+        assert!(markdown.contains("**Yesterday** (Fri 2026-10-16, 4h 0m)"));
+        assert!(markdown
+            .contains("- **Acme / api** (3h 0m): Pagination\n  - `0123456` Add cursor pagination"));
+        assert!(markdown
+            .contains("- **Acme / ui** (running since 09:15, 45m)\n- 10:00 Sprint planning"));
+        assert!(markdown.contains("**Blockers**\n- Waiting on staging access"));
+
+        let plain = standup.render(PLAIN_TEMPLATE, false);
+        assert!(plain.contains("- Acme / review (1h 0m)\n"));
+        assert!(plain.contains("    0123456 Add cursor pagination"));
+        assert!(!plain.contains("**"));
+
+        let custom = standup.render("{previous_day}: {total}\nNow: {running}\n{unknown}", false);
+        assert_eq!(
+            custom,
+            "Fri 2026-10-16: 4h 0m\nNow: Acme / ui (running since 09:15, 45m)\n{unknown}"
+        );
+    }
+
+    #[test]
+    fn test_render_keeps_placeholders_in_notes_and_hashless_commits() {
+        let mut standup = standup();
+        standup.blockers = vec!["See {today} and {blockers}".into()];
+        standup.yesterday[1].commits.push(StandupCommit {
+            hash: String::new(),
+            summary: "Imported without a hash".into(),
+        });
+
+        let markdown = standup.render("{blockers}\n{yesterday}", true);
+        assert!(markdown.starts_with("- See {today} and {blockers}\n"));
+        assert!(markdown.contains("\n  - Imported without a hash"));
+        assert!(!markdown.contains("``"));
+        let plain = standup.render("{yesterday}", false);
+        assert!(plain.ends_with("\n    Imported without a hash"));
+    }
+}
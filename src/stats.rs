@@ -1,123 +1,704 @@
 //! Statistics and reporting for MatteriaTrack
 
+use crate::balance::{Balance, WorkCalendar};
+use crate::code_activity::CodeActivity;
+use crate::config::ScheduleConfig;
 use crate::database::Database;
-use crate::error::Result;
-use crate::models::{Entry, ProjectStats, TaskStats, TimeStats};
+use crate::error::{Error, Result};
+use crate::filter::{extract_tags, Filter};
+use crate::insights::Insights;
+use crate::models::{EntryWithDetails, ProjectStats, TaskStats, TimeStats};
+use crate::period::{local_midnight, CompareTo, Period};
 use crate::theme::MateriaTheme;
-use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Utc};
+use crate::timeline::Timeline;
+use chrono::{DateTime, Datelike, Local, NaiveDate, Timelike, Utc};
 use colored::Colorize;
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
-type TaskData = (String, i64, usize);
-type ProjectData = (String, i64, usize, HashMap<i64, TaskData>);
+/// Grouping behind the classic `mtrack stats` view: projects, then their tasks.
+pub const DEFAULT_GROUPING: [GroupKey; 2] = [GroupKey::Project, GroupKey::Task];
 
-pub struct StatsEngine {
-    db: Database,
-    theme: MateriaTheme,
+/// Most dimensions a grouping may nest.
+pub const MAX_GROUP_LEVELS: usize = 2;
+
+/// A dimension entries can be grouped by. Time dimensions use the local start
+/// of each entry; `tag` counts an entry once for every `#tag` in its notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupKey {
+    Project,
+    Task,
+    Day,
+    Week,
+    Month,
+    Weekday,
+    Hour,
+    Tag,
 }
 
-impl StatsEngine {
-    pub fn new(db: Database, theme: MateriaTheme) -> Self {
-        Self { db, theme }
+impl GroupKey {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Project => "project",
+            Self::Task => "task",
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::Weekday => "weekday",
+            Self::Hour => "hour",
+            Self::Tag => "tag",
+        }
     }
 
-    pub fn calculate_stats(&self, since: Option<DateTime<Utc>>) -> Result<TimeStats> {
-        let entries = self.db.list_entries(since)?;
-        self.compute_stats(&entries)
+    /// Parse `project`, `project,week` or `project×week`.
+    pub fn parse_list(spec: &str) -> Result<Vec<Self>> {
+        let keys = spec
+            .split([',', '×', '*'])
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(Self::from_str)
+            .collect::<Result<Vec<_>>>()?;
+
+        if keys.is_empty() {
+            return Err(Error::InvalidInput("Empty grouping".into()));
+        }
+        if keys.len() > MAX_GROUP_LEVELS {
+            return Err(Error::InvalidInput(format!(
+                "At most {} grouping levels are supported, got '{}'",
+                MAX_GROUP_LEVELS, spec
+            )));
+        }
+        if keys.iter().enumerate().any(|(i, k)| keys[..i].contains(k)) {
+            return Err(Error::InvalidInput(format!(
+                "Duplicate grouping in '{}'",
+                spec
+            )));
+        }
+        Ok(keys)
     }
 
-    pub fn today_stats(&self) -> Result<TimeStats> {
-        let today = Local::now().date_naive().and_hms_opt(0, 0, 0).unwrap();
-        let since = Local
-            .from_local_datetime(&today)
-            .unwrap()
-            .with_timezone(&Utc);
-        self.calculate_stats(Some(since))
+    /// Projects, tasks and tags are ranked by time; the rest read chronologically.
+    fn ranked(self) -> bool {
+        matches!(self, Self::Project | Self::Task | Self::Tag)
     }
 
-    pub fn week_stats(&self) -> Result<TimeStats> {
-        let today = Local::now().date_naive();
-        let days_since_monday = today.weekday().num_days_from_monday();
-        let monday = today - Duration::days(days_since_monday as i64);
-        let monday_midnight = monday.and_hms_opt(0, 0, 0).unwrap();
-        let since = Local
-            .from_local_datetime(&monday_midnight)
-            .unwrap()
-            .with_timezone(&Utc);
-        self.calculate_stats(Some(since))
+    /// The buckets `entry` falls into as `(sort key, label)` pairs.
+    fn buckets(self, entry: &EntryWithDetails, under_project: bool) -> Vec<(String, String)> {
+        let start = entry.entry.start.with_timezone(&Local);
+        let bucket = |sort: String, label: String| vec![(sort, label)];
+
+        match self {
+            Self::Project => bucket(
+                entry.entry.project_id.to_string(),
+                entry.project_name.clone(),
+            ),
+            Self::Task => {
+                let label = if under_project {
+                    entry.task_name.clone()
+                } else {
+                    format!("{}/{}", entry.project_name, entry.task_name)
+                };
+                bucket(entry.entry.task_id.to_string(), label)
+            }
+            Self::Day => {
+                let day = start.format("%Y-%m-%d").to_string();
+                bucket(day.clone(), day)
+            }
+            Self::Week => {
+                let week = start.format("%G-W%V").to_string();
+                bucket(week.clone(), week)
+            }
+            Self::Month => {
+                let month = start.format("%Y-%m").to_string();
+                bucket(month.clone(), month)
+            }
+            Self::Weekday => bucket(
+                start.weekday().num_days_from_monday().to_string(),
+                start.format("%a").to_string(),
+            ),
+            Self::Hour => bucket(
+                format!("{:02}", start.hour()),
+                format!("{:02}:00", start.hour()),
+            ),
+            Self::Tag => {
+                let tags = extract_tags(entry.entry.notes.as_deref().unwrap_or(""));
+                if tags.is_empty() {
+                    bucket(String::new(), "(untagged)".into())
+                } else {
+                    tags.into_iter()
+                        .map(|t| (t.clone(), format!("#{}", t)))
+                        .collect()
+                }
+            }
+        }
     }
+}
 
-    pub fn month_stats(&self) -> Result<TimeStats> {
-        let today = Local::now().date_naive();
-        let first_of_month = NaiveDate::from_ymd_opt(today.year(), today.month(), 1).unwrap();
-        let first_midnight = first_of_month.and_hms_opt(0, 0, 0).unwrap();
-        let since = Local
-            .from_local_datetime(&first_midnight)
-            .unwrap()
-            .with_timezone(&Utc);
-        self.calculate_stats(Some(since))
+impl FromStr for GroupKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "project" | "projects" => Ok(Self::Project),
+            "task" | "tasks" => Ok(Self::Task),
+            "day" | "date" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            "weekday" => Ok(Self::Weekday),
+            "hour" => Ok(Self::Hour),
+            "tag" | "tags" => Ok(Self::Tag),
+            _ => Err(Error::InvalidInput(format!(
+                "Unknown grouping '{}' (expected project, task, day, week, month, weekday, hour or tag)",
+                s
+            ))),
+        }
     }
+}
 
-    fn compute_stats(&self, entries: &[Entry]) -> Result<TimeStats> {
-        let mut total_seconds: i64 = 0;
-        let mut project_map: HashMap<i64, ProjectData> = HashMap::new();
+/// Time grouped along one or two [`GroupKey`]s.
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupedStats {
+    pub dimensions: Vec<GroupKey>,
+    pub total_seconds: i64,
+    pub entry_count: usize,
+    /// Commits captured by the entries, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<CodeActivity>,
+    pub groups: Vec<GroupNode>,
+}
 
-        for entry in entries {
-            let duration = entry.duration().num_seconds();
-            total_seconds += duration;
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupNode {
+    pub key: String,
+    pub total_seconds: i64,
+    pub entry_count: usize,
+    /// Share of the overall total
+    pub percentage: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<CodeActivity>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<GroupNode>,
+    #[serde(skip)]
+    id: Option<i64>,
+}
 
-            let project = self.db.get_project(entry.project_id)?;
-            let task = self.db.get_task(entry.task_id)?;
+impl GroupNode {
+    pub fn total_formatted(&self) -> String {
+        format_seconds(self.total_seconds)
+    }
+}
 
-            if let (Some(p), Some(t)) = (project, task) {
-                let project_entry = project_map
-                    .entry(p.id)
-                    .or_insert_with(|| (p.name.clone(), 0, 0, HashMap::new()));
+impl GroupedStats {
+    /// Group `entries` along `keys`, outermost first.
+    pub fn build(entries: &[EntryWithDetails], keys: &[GroupKey]) -> Self {
+        Self::assemble(entries, keys, false)
+    }
 
-                project_entry.1 += duration;
-                project_entry.2 += 1;
+    /// Like [`GroupedStats::build`], with commit counts and changed lines per group.
+    pub fn build_with_code(entries: &[EntryWithDetails], keys: &[GroupKey]) -> Self {
+        Self::assemble(entries, keys, true)
+    }
 
-                let task_entry = project_entry
-                    .3
-                    .entry(t.id)
-                    .or_insert_with(|| (t.name.clone(), 0, 0));
+    fn assemble(entries: &[EntryWithDetails], keys: &[GroupKey], code: bool) -> Self {
+        let timed: Vec<(&EntryWithDetails, i64)> = entries
+            .iter()
+            .map(|e| (e, e.entry.duration().num_seconds()))
+            .collect();
+        let total_seconds = timed.iter().map(|(_, s)| s).sum();
 
-                task_entry.1 += duration;
-                task_entry.2 += 1;
-            }
+        Self {
+            dimensions: keys.to_vec(),
+            total_seconds,
+            entry_count: entries.len(),
+            code: code.then(|| CodeActivity::from_entries(entries)),
+            groups: group_level(&timed, keys, false, total_seconds, code),
+        }
+    }
+
+    /// The classic project → task view of `stats` as a grouping.
+    pub fn from_time_stats(stats: &TimeStats) -> Self {
+        let total = stats.total_seconds;
+        let mut projects = stats.projects.clone();
+        projects.sort_by_key(|p| std::cmp::Reverse(p.total_seconds));
+
+        let groups = projects
+            .into_iter()
+            .map(|p| {
+                let mut tasks = p.tasks.clone();
+                tasks.sort_by_key(|t| std::cmp::Reverse(t.total_seconds));
+                GroupNode {
+                    key: p.project_name.clone(),
+                    total_seconds: p.total_seconds,
+                    entry_count: p.entry_count,
+                    percentage: percentage(p.total_seconds, total),
+                    id: Some(p.project_id),
+                    code: None,
+                    groups: tasks
+                        .into_iter()
+                        .map(|t| GroupNode {
+                            key: t.task_name,
+                            total_seconds: t.total_seconds,
+                            entry_count: t.entry_count,
+                            percentage: percentage(t.total_seconds, total),
+                            id: Some(t.task_id),
+                            code: None,
+                            groups: Vec::new(),
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+
+        Self {
+            dimensions: DEFAULT_GROUPING.to_vec(),
+            total_seconds: total,
+            entry_count: stats.entry_count,
+            code: None,
+            groups,
         }
+    }
 
-        let projects: Vec<ProjectStats> = project_map
+    /// The project → task grouping as [`TimeStats`].
+    fn into_time_stats(self) -> TimeStats {
+        let projects = self
+            .groups
             .into_iter()
-            .map(|(id, (name, secs, count, tasks_map))| {
-                let tasks: Vec<TaskStats> = tasks_map
+            .map(|p| ProjectStats {
+                project_id: p.id.unwrap_or_default(),
+                project_name: p.key,
+                total_seconds: p.total_seconds,
+                entry_count: p.entry_count,
+                tasks: p
+                    .groups
                     .into_iter()
-                    .map(|(tid, (tname, tsecs, tcount))| TaskStats {
-                        task_id: tid,
-                        task_name: tname,
-                        total_seconds: tsecs,
-                        entry_count: tcount,
+                    .map(|t| TaskStats {
+                        task_id: t.id.unwrap_or_default(),
+                        task_name: t.key,
+                        total_seconds: t.total_seconds,
+                        entry_count: t.entry_count,
                     })
-                    .collect();
-
-                ProjectStats {
-                    project_id: id,
-                    project_name: name,
-                    total_seconds: secs,
-                    entry_count: count,
-                    tasks,
-                }
+                    .collect(),
             })
             .collect();
 
-        Ok(TimeStats {
-            total_seconds,
-            entry_count: entries.len(),
+        TimeStats {
+            total_seconds: self.total_seconds,
+            entry_count: self.entry_count,
             projects,
+        }
+    }
+
+    /// One CSV row per innermost group, with a column for every dimension.
+    pub fn to_csv(&self) -> String {
+        let mut csv = self
+            .dimensions
+            .iter()
+            .map(|d| d.name())
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push_str(",seconds,hours,entries,percent");
+        if self.code.is_some() {
+            csv.push_str(",commits,insertions,deletions,commits_per_hour");
+        }
+        csv.push('\n');
+
+        fn rows(nodes: &[GroupNode], path: &mut Vec<String>, csv: &mut String) {
+            for node in nodes {
+                path.push(csv_field(&node.key));
+                if node.groups.is_empty() {
+                    csv.push_str(&format!(
+                        "{},{},{:.2},{},{:.1}",
+                        path.join(","),
+                        node.total_seconds,
+                        node.total_seconds as f64 / 3600.0,
+                        node.entry_count,
+                        node.percentage
+                    ));
+                    if let Some(code) = node.code {
+                        csv.push_str(&format!(
+                            ",{},{},{},{:.2}",
+                            code.commits, code.insertions, code.deletions, code.commits_per_hour
+                        ));
+                    }
+                    csv.push('\n');
+                } else {
+                    rows(&node.groups, path, csv);
+                }
+                path.pop();
+            }
+        }
+
+        rows(&self.groups, &mut Vec::new(), &mut csv);
+        csv
+    }
+}
+
+fn group_level(
+    entries: &[(&EntryWithDetails, i64)],
+    keys: &[GroupKey],
+    under_project: bool,
+    grand_total: i64,
+    code: bool,
+) -> Vec<GroupNode> {
+    let Some((&key, rest)) = keys.split_first() else {
+        return Vec::new();
+    };
+
+    type Bucket<'a> = (String, Vec<(&'a EntryWithDetails, i64)>);
+    let mut buckets: BTreeMap<String, Bucket> = BTreeMap::new();
+    for &(entry, secs) in entries {
+        for (sort, label) in key.buckets(entry, under_project) {
+            buckets
+                .entry(sort)
+                .or_insert_with(|| (label, Vec::new()))
+                .1
+                .push((entry, secs));
+        }
+    }
+
+    let mut nodes: Vec<GroupNode> = buckets
+        .into_iter()
+        .map(|(sort, (label, members))| {
+            let total_seconds = members.iter().map(|(_, s)| s).sum();
+            GroupNode {
+                key: label,
+                total_seconds,
+                entry_count: members.len(),
+                percentage: percentage(total_seconds, grand_total),
+                code: code.then(|| CodeActivity::from_entries(members.iter().map(|(e, _)| *e))),
+                id: matches!(key, GroupKey::Project | GroupKey::Task)
+                    .then(|| sort.parse().ok())
+                    .flatten(),
+                groups: group_level(
+                    &members,
+                    rest,
+                    under_project || key == GroupKey::Project,
+                    grand_total,
+                    code,
+                ),
+            }
         })
+        .collect();
+
+    if key.ranked() {
+        nodes.sort_by(|a, b| {
+            b.total_seconds
+                .cmp(&a.total_seconds)
+                .then_with(|| a.key.cmp(&b.key))
+        });
+    }
+    nodes
+}
+
+/// `  · 3 commits +40/-12` after a group, when code activity was requested.
+fn code_suffix(code: Option<CodeActivity>) -> String {
+    match code {
+        Some(c) => format!(
+            "  · {} commit{} {}",
+            c.commits,
+            if c.commits == 1 { "" } else { "s" },
+            c.lines_formatted()
+        )
+        .dimmed()
+        .to_string(),
+        None => String::new(),
+    }
+}
+
+fn percentage(seconds: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        (seconds as f64 / total as f64) * 100.0
+    }
+}
+
+pub(crate) fn format_seconds(seconds: i64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Change of one group between a period and its baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Delta {
+    pub current_seconds: i64,
+    pub previous_seconds: i64,
+    pub delta_seconds: i64,
+    /// `None` when the baseline is empty
+    pub change_percent: Option<f64>,
+}
+
+impl Delta {
+    fn new(current_seconds: i64, previous_seconds: i64) -> Self {
+        let delta_seconds = current_seconds - previous_seconds;
+        Self {
+            current_seconds,
+            previous_seconds,
+            delta_seconds,
+            change_percent: (previous_seconds != 0)
+                .then(|| delta_seconds as f64 / previous_seconds as f64 * 100.0),
+        }
+    }
+
+    /// `+1h 5m (+20.8%)`, `-30m (-50.0%)` or `+2h (new)`.
+    pub fn formatted(&self) -> String {
+        let sign = if self.delta_seconds < 0 { "-" } else { "+" };
+        let change = match self.change_percent {
+            Some(p) => format!("{:+.1}%", p),
+            None if self.current_seconds == 0 => "–".to_string(),
+            None => "new".to_string(),
+        };
+        format!(
+            "{}{} ({})",
+            sign,
+            format_seconds(self.delta_seconds.abs()),
+            change
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeltaNode {
+    pub key: String,
+    #[serde(flatten)]
+    pub delta: Delta,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<DeltaNode>,
+}
+
+/// A grouping compared against the same grouping over a baseline period.
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    pub dimensions: Vec<GroupKey>,
+    pub period: Period,
+    pub baseline: Period,
+    pub total: Delta,
+    pub groups: Vec<DeltaNode>,
+}
+
+impl Comparison {
+    pub fn build(
+        current: &GroupedStats,
+        previous: &GroupedStats,
+        period: Period,
+        baseline: Period,
+    ) -> Self {
+        Self {
+            dimensions: current.dimensions.clone(),
+            period,
+            baseline,
+            total: Delta::new(current.total_seconds, previous.total_seconds),
+            groups: merge_deltas(&current.groups, &previous.groups),
+        }
+    }
+
+    /// One CSV row per innermost group.
+    pub fn to_csv(&self) -> String {
+        let mut csv = self
+            .dimensions
+            .iter()
+            .map(|d| d.name())
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push_str(",current_seconds,previous_seconds,delta_seconds,change_percent\n");
+
+        fn rows(nodes: &[DeltaNode], path: &mut Vec<String>, csv: &mut String) {
+            for node in nodes {
+                path.push(csv_field(&node.key));
+                if node.groups.is_empty() {
+                    let d = node.delta;
+                    csv.push_str(&format!(
+                        "{},{},{},{},{}\n",
+                        path.join(","),
+                        d.current_seconds,
+                        d.previous_seconds,
+                        d.delta_seconds,
+                        d.change_percent
+                            .map_or(String::new(), |p| format!("{:.1}", p))
+                    ));
+                } else {
+                    rows(&node.groups, path, csv);
+                }
+                path.pop();
+            }
+        }
+
+        rows(&self.groups, &mut Vec::new(), &mut csv);
+        csv
+    }
+}
+
+/// Pair groups by key; groups missing on one side count as zero there.
+fn merge_deltas(current: &[GroupNode], previous: &[GroupNode]) -> Vec<DeltaNode> {
+    let mut nodes: Vec<DeltaNode> = current
+        .iter()
+        .map(|c| {
+            let p = previous.iter().find(|p| p.key == c.key);
+            DeltaNode {
+                key: c.key.clone(),
+                delta: Delta::new(c.total_seconds, p.map_or(0, |p| p.total_seconds)),
+                groups: merge_deltas(&c.groups, p.map_or(&[], |p| &p.groups)),
+            }
+        })
+        .collect();
+
+    nodes.extend(
+        previous
+            .iter()
+            .filter(|p| !current.iter().any(|c| c.key == p.key))
+            .map(|p| DeltaNode {
+                key: p.key.clone(),
+                delta: Delta::new(0, p.total_seconds),
+                groups: merge_deltas(&[], &p.groups),
+            }),
+    );
+    nodes
+}
+
+pub struct StatsEngine {
+    db: Database,
+    theme: MateriaTheme,
+    filter: Option<Filter>,
+}
+
+impl StatsEngine {
+    pub fn new(db: Database, theme: MateriaTheme) -> Self {
+        Self {
+            db,
+            theme,
+            filter: None,
+        }
+    }
+
+    /// Only count entries matching `filter`.
+    pub fn with_filter(mut self, filter: Option<Filter>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn calculate_stats(&self, since: Option<DateTime<Utc>>) -> Result<TimeStats> {
+        self.period_stats(&Period::since(since))
+    }
+
+    pub fn period_stats(&self, period: &Period) -> Result<TimeStats> {
+        Ok(self.group(period, &DEFAULT_GROUPING)?.into_time_stats())
+    }
+
+    /// Group matching entries started within `period` along `keys`, outermost first.
+    pub fn group(&self, period: &Period, keys: &[GroupKey]) -> Result<GroupedStats> {
+        let entries = self.db.list_entries_with_details_between(
+            period.from,
+            period.until,
+            self.filter.as_ref(),
+        )?;
+        Ok(GroupedStats::build(&entries, keys))
+    }
+
+    /// [`StatsEngine::group`] with commit counts and changed lines per group.
+    pub fn group_with_code(&self, period: &Period, keys: &[GroupKey]) -> Result<GroupedStats> {
+        let entries = self.db.list_entries_with_details_between(
+            period.from,
+            period.until,
+            self.filter.as_ref(),
+        )?;
+        Ok(GroupedStats::build_with_code(&entries, keys))
+    }
+
+    /// Group `period` and its baseline the same way and compute the deltas.
+    pub fn compare(
+        &self,
+        period: &Period,
+        compare: CompareTo,
+        keys: &[GroupKey],
+    ) -> Result<Comparison> {
+        let baseline = period.baseline(compare)?;
+        let current = self.group(period, keys)?;
+        let previous = self.group(&baseline, keys)?;
+        Ok(Comparison::build(
+            &current,
+            &previous,
+            period.clone(),
+            baseline,
+        ))
+    }
+
+    /// Work-pattern insights for matching entries started within `period`.
+    pub fn insights(&self, period: &Period) -> Result<Insights> {
+        let entries = self.db.list_entries_with_details_between(
+            period.from,
+            period.until,
+            self.filter.as_ref(),
+        )?;
+        Ok(Insights::build(&entries, &period.label))
+    }
+
+    /// Matching entries laid out on an hour axis, one row per day of `days`.
+    pub fn timeline(&self, days: &[NaiveDate], schedule: &ScheduleConfig) -> Result<Timeline> {
+        let (Some(first), Some(last)) = (days.first(), days.last()) else {
+            return Timeline::build(&[], days, schedule);
+        };
+        // Start a day early to catch entries running past midnight
+        let entries = self.db.list_entries_with_details_between(
+            Some(local_midnight(*first - chrono::Duration::days(1))),
+            Some(local_midnight(*last + chrono::Duration::days(1))),
+            self.filter.as_ref(),
+        )?;
+        Timeline::build(&entries, days, schedule)
+    }
+
+    /// Tracked against expected time from `from` to `until`, both inclusive,
+    /// with recorded absences credited.
+    pub fn balance(
+        &self,
+        from: NaiveDate,
+        until: NaiveDate,
+        calendar: &WorkCalendar,
+    ) -> Result<Balance> {
+        let entries = self.db.list_entries_with_details_between(
+            Some(local_midnight(from)),
+            Some(local_midnight(until + chrono::Duration::days(1))),
+            self.filter.as_ref(),
+        )?;
+        let calendar = calendar
+            .clone()
+            .with_absences(self.db.list_absences(Some(from), Some(until))?);
+        Ok(Balance::build(&entries, from, until, &calendar))
+    }
+
+    pub fn today_stats(&self) -> Result<TimeStats> {
+        self.period_stats(&Period::named("today")?)
+    }
+
+    pub fn week_stats(&self) -> Result<TimeStats> {
+        self.period_stats(&Period::named("this-week")?)
+    }
+
+    pub fn month_stats(&self) -> Result<TimeStats> {
+        self.period_stats(&Period::named("this-month")?)
     }
 
     pub fn format_stats(&self, stats: &TimeStats, title: &str) -> String {
+        self.format_grouped(&GroupedStats::from_time_stats(stats), title)
+    }
+
+    /// Render a grouping as a table: outer groups with a share bar, inner
+    /// groups indented below them.
+    pub fn format_grouped(&self, stats: &GroupedStats, title: &str) -> String {
         let (r, g, b) = self.theme.primary_color();
         let mut output = String::new();
 
@@ -132,40 +713,48 @@ impl StatsEngine {
 
         output.push_str(&format!(
             "\n⏱️  Total Time: {}\n",
-            stats.total_formatted().truecolor(r, g, b).bold()
+            format_seconds(stats.total_seconds)
+                .truecolor(r, g, b)
+                .bold()
         ));
-        output.push_str(&format!("📊 Total Entries: {}\n\n", stats.entry_count));
+        output.push_str(&format!("📊 Total Entries: {}\n", stats.entry_count));
+        if let Some(code) = stats.code {
+            output.push_str(&format!(
+                "🔀 Commits: {} ({}, {:.2}/h)\n",
+                code.commits,
+                code.lines_formatted(),
+                code.commits_per_hour
+            ));
+        }
+        output.push('\n');
 
-        if !stats.projects.is_empty() {
-            output.push_str(&format!("{}\n", "Projects:".bold()));
+        if let Some(outer) = stats.dimensions.first() {
+            if !stats.groups.is_empty() {
+                output.push_str(&format!("{}\n", format!("{}:", heading(*outer)).bold()));
+            }
+        }
 
-            let mut sorted_projects = stats.projects.clone();
-            sorted_projects.sort_by(|a, b| b.total_seconds.cmp(&a.total_seconds));
+        for group in &stats.groups {
+            let bar = progress_bar(group.percentage, 20);
 
-            for project in &sorted_projects {
-                let percentage = project.percentage_of(stats.total_seconds);
-                let bar = progress_bar(percentage, 20);
+            output.push_str(&format!(
+                "  {} {} {} ({:.1}%){}\n",
+                "".truecolor(r, g, b),
+                group.key.bold(),
+                group.total_formatted(),
+                group.percentage,
+                code_suffix(group.code)
+            ));
+            output.push_str(&format!("     {}\n", bar.truecolor(r, g, b)));
 
+            for inner in &group.groups {
                 output.push_str(&format!(
-                    "  {} {} {} ({:.1}%)\n",
-                    "".truecolor(r, g, b),
-                    project.project_name.bold(),
-                    project.total_formatted(),
-                    percentage
+                    "       {} {} ({}){}\n",
+                    "",
+                    inner.key,
+                    inner.total_formatted(),
+                    code_suffix(inner.code)
                 ));
-                output.push_str(&format!("     {}\n", bar.truecolor(r, g, b)));
-
-                let mut sorted_tasks = project.tasks.clone();
-                sorted_tasks.sort_by(|a, b| b.total_seconds.cmp(&a.total_seconds));
-
-                for task in &sorted_tasks {
-                    output.push_str(&format!(
-                        "       {} {} ({})\n",
-                        "",
-                        task.task_name,
-                        task.total_formatted()
-                    ));
-                }
             }
         }
 
@@ -176,54 +765,90 @@ impl StatsEngine {
         output
     }
 
-    pub fn format_stats_json(&self, stats: &TimeStats) -> Result<String> {
-        Ok(serde_json::to_string_pretty(stats)?)
-    }
-
-    pub fn format_daily_breakdown(&self, since: DateTime<Utc>) -> Result<String> {
-        let entries = self.db.list_entries(Some(since))?;
+    /// Render a comparison: each group's time with its change against the baseline.
+    pub fn format_comparison(&self, cmp: &Comparison, title: &str) -> String {
         let (r, g, b) = self.theme.primary_color();
-
-        let mut daily: HashMap<NaiveDate, i64> = HashMap::new();
-
-        for entry in &entries {
-            let date = entry.start.with_timezone(&Local).date_naive();
-            let duration = entry.duration().num_seconds();
-            *daily.entry(date).or_insert(0) += duration;
-        }
-
-        let mut dates: Vec<_> = daily.into_iter().collect();
-        dates.sort_by(|a, b| b.0.cmp(&a.0));
+        let change = |d: &Delta| {
+            let text = d.formatted();
+            if d.delta_seconds > 0 {
+                format!("▲ {}", text).truecolor(r, g, b).to_string()
+            } else if d.delta_seconds < 0 {
+                format!("▼ {}", text).truecolor(150, 150, 150).to_string()
+            } else {
+                format!("= {}", text).dimmed().to_string()
+            }
+        };
 
         let mut output = String::new();
         output.push_str(&format!(
-            "\n{} {} Daily Breakdown {}\n",
+            "\n{} {} {}\n",
             self.theme.icon(),
-            "📅".truecolor(r, g, b),
+            title.truecolor(r, g, b).bold(),
             self.theme.materia_icon()
         ));
+        output.push_str(&format!(
+            "   {} vs {}\n",
+            cmp.period.label,
+            cmp.baseline.label.dimmed()
+        ));
         output.push_str(&"━".repeat(50));
         output.push('\n');
 
-        let max_seconds = dates.iter().map(|(_, s)| *s).max().unwrap_or(1);
+        output.push_str(&format!(
+            "\n⏱️  Total Time: {} (was {}) {}\n\n",
+            format_seconds(cmp.total.current_seconds)
+                .truecolor(r, g, b)
+                .bold(),
+            format_seconds(cmp.total.previous_seconds),
+            change(&cmp.total)
+        ));
 
-        for (date, seconds) in dates {
-            let hours = seconds / 3600;
-            let minutes = (seconds % 3600) / 60;
-            let bar_width = ((seconds as f64 / max_seconds as f64) * 30.0) as usize;
-            let bar = "█".repeat(bar_width);
+        if let Some(outer) = cmp.dimensions.first() {
+            if !cmp.groups.is_empty() {
+                output.push_str(&format!("{}\n", format!("{}:", heading(*outer)).bold()));
+            }
+        }
 
+        for group in &cmp.groups {
             output.push_str(&format!(
-                "  {} {:>2}h {:>2}m {}\n",
-                date.format("%Y-%m-%d"),
-                hours,
-                minutes,
-                bar.truecolor(r, g, b)
+                "  {} {} {}  {}\n",
+                "".truecolor(r, g, b),
+                group.key.bold(),
+                format_seconds(group.delta.current_seconds),
+                change(&group.delta)
             ));
+            for inner in &group.groups {
+                output.push_str(&format!(
+                    "       {} {}  {}\n",
+                    inner.key,
+                    format_seconds(inner.delta.current_seconds),
+                    change(&inner.delta)
+                ));
+            }
         }
 
         output.push('\n');
-        Ok(output)
+        output.push_str(&"━".repeat(50));
+        output.push('\n');
+
+        output
+    }
+
+    pub fn format_stats_json(&self, stats: &TimeStats) -> Result<String> {
+        Ok(serde_json::to_string_pretty(stats)?)
+    }
+}
+
+fn heading(key: GroupKey) -> &'static str {
+    match key {
+        GroupKey::Project => "Projects",
+        GroupKey::Task => "Tasks",
+        GroupKey::Day => "Days",
+        GroupKey::Week => "Weeks",
+        GroupKey::Month => "Months",
+        GroupKey::Weekday => "Weekdays",
+        GroupKey::Hour => "Hours",
+        GroupKey::Tag => "Tags",
     }
 }
 
@@ -311,9 +936,235 @@ pub fn format_project_table(
     output
 }
 
+pub fn format_commit_table(
+    commits: &[crate::models::CommitWithDetails],
+    theme: MateriaTheme,
+) -> String {
+    let (r, g, b) = theme.primary_color();
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "\n{} Captured Commits {}\n",
+        theme.icon(),
+        theme.materia_icon()
+    ));
+    output.push_str(&"━".repeat(70));
+    output.push('\n');
+
+    if commits.is_empty() {
+        output.push_str("  No commits captured yet.\n");
+    }
+
+    for c in commits {
+        let date = c
+            .commit
+            .committed_at
+            .unwrap_or(c.entry_start)
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M");
+        let hash = if c.commit.hash.is_empty() {
+            "-------"
+        } else {
+            c.commit.short_hash()
+        };
+
+        output.push_str(&format!(
+            "{} {} {} {}\n",
+            date.to_string().truecolor(150, 150, 150),
+            hash.truecolor(255, 200, 50),
+            format!("{}/{}", c.project_name, c.task_name).truecolor(r, g, b),
+            c.commit.summary
+        ));
+
+        let mut details = Vec::new();
+        if let Some(ref author) = c.commit.author {
+            details.push(author.clone());
+        }
+        if let (Some(ins), Some(del)) = (c.commit.insertions, c.commit.deletions) {
+            details.push(format!(
+                "{} files, +{} -{}",
+                c.commit.files_changed.unwrap_or(0),
+                ins,
+                del
+            ));
+        }
+        if let Some(ref repo) = c.commit.repo_path {
+            details.push(repo.clone());
+        }
+        if !details.is_empty() {
+            output.push_str(&format!(
+                "                 {}\n",
+                details.join(" · ").truecolor(100, 100, 100)
+            ));
+        }
+    }
+
+    output.push_str(&"━".repeat(70));
+    output.push('\n');
+
+    output
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::database::fixtures::{local, row, seeded};
+
+    fn seeded_engine() -> StatsEngine {
+        let db = seeded(&[
+            // Monday and Tuesday of ISO week 41, then Monday of week 42
+            row("Acme", "api", local("2026-10-05 09:00"), 120).with_notes("#review"),
+            row("Acme", "ui", local("2026-10-06 14:00"), 60).with_notes("#meeting #review"),
+            row("Beta", "docs", local("2026-10-12 09:00"), 30),
+        ]);
+        StatsEngine::new(db, MateriaTheme::Fire)
+    }
+
+    fn keys(nodes: &[GroupNode]) -> Vec<&str> {
+        nodes.iter().map(|n| n.key.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_group_keys() {
+        assert_eq!(
+            GroupKey::parse_list("project,week").unwrap(),
+            vec![GroupKey::Project, GroupKey::Week]
+        );
+        assert_eq!(
+            GroupKey::parse_list("Tag×hour").unwrap(),
+            vec![GroupKey::Tag, GroupKey::Hour]
+        );
+        assert!(GroupKey::parse_list("project,week,day").is_err());
+        assert!(GroupKey::parse_list("day,day").is_err());
+        assert!(GroupKey::parse_list("year").is_err());
+    }
+
+    #[test]
+    fn test_default_preset_matches_project_task_grouping() {
+        let engine = seeded_engine();
+        let stats = engine.calculate_stats(None).unwrap();
+        assert_eq!(stats.total_seconds, 210 * 60);
+        assert_eq!(stats.entry_count, 3);
+        assert_eq!(stats.projects[0].project_name, "Acme");
+        assert_eq!(stats.projects[0].tasks.len(), 2);
+        assert!(stats.projects[0].project_id > 0);
+
+        let grouped = engine.group(&Period::all(), &DEFAULT_GROUPING).unwrap();
+        assert_eq!(keys(&grouped.groups), vec!["Acme", "Beta"]);
+        assert_eq!(keys(&grouped.groups[0].groups), vec!["api", "ui"]);
+    }
+
+    #[test]
+    fn test_two_level_and_time_groupings() {
+        let engine = seeded_engine();
+
+        let by_week = engine
+            .group(&Period::all(), &[GroupKey::Project, GroupKey::Week])
+            .unwrap();
+        assert_eq!(keys(&by_week.groups[0].groups), vec!["2026-W41"]);
+        assert_eq!(keys(&by_week.groups[1].groups), vec!["2026-W42"]);
+
+        let by_weekday = engine.group(&Period::all(), &[GroupKey::Weekday]).unwrap();
+        assert_eq!(keys(&by_weekday.groups), vec!["Mon", "Tue"]);
+        assert_eq!(by_weekday.groups[0].total_seconds, 150 * 60);
+
+        let by_hour = engine.group(&Period::all(), &[GroupKey::Hour]).unwrap();
+        assert_eq!(keys(&by_hour.groups), vec!["09:00", "14:00"]);
+
+        let by_task = engine.group(&Period::all(), &[GroupKey::Task]).unwrap();
+        assert_eq!(
+            keys(&by_task.groups),
+            vec!["Acme/api", "Acme/ui", "Beta/docs"]
+        );
+    }
+
+    #[test]
+    fn test_tag_grouping_counts_each_tag() {
+        let grouped = seeded_engine()
+            .group(&Period::all(), &[GroupKey::Tag])
+            .unwrap();
+        assert_eq!(
+            keys(&grouped.groups),
+            vec!["#review", "#meeting", "(untagged)"]
+        );
+        assert_eq!(grouped.groups[0].total_seconds, 180 * 60);
+        assert_eq!(grouped.groups[0].entry_count, 2);
+    }
+
+    #[test]
+    fn test_period_bounds_and_comparison() {
+        let engine = seeded_engine();
+        let week_42 = Period::range(Some("2026-10-12"), Some("2026-10-18")).unwrap();
+
+        let grouped = engine.group(&week_42, &[GroupKey::Project]).unwrap();
+        assert_eq!(keys(&grouped.groups), vec!["Beta"]);
+
+        let cmp = engine
+            .compare(&week_42, CompareTo::Previous, &DEFAULT_GROUPING)
+            .unwrap();
+        assert_eq!(cmp.total.current_seconds, 30 * 60);
+        assert_eq!(cmp.total.previous_seconds, 180 * 60);
+        assert_eq!(keys_of(&cmp.groups), vec!["Beta", "Acme"]);
+
+        let beta = &cmp.groups[0];
+        assert_eq!(beta.delta.change_percent, None);
+        assert!(beta.delta.formatted().contains("new"));
+
+        let acme = &cmp.groups[1];
+        assert_eq!(acme.delta.delta_seconds, -180 * 60);
+        assert_eq!(acme.delta.change_percent, Some(-100.0));
+        assert_eq!(keys_of(&acme.groups), vec!["api", "ui"]);
+
+        let csv = cmp.to_csv();
+        assert!(csv.contains("Acme,api,0,7200,-7200,-100.0"));
+        assert!(csv.contains("Beta,docs,1800,0,1800,\n"));
+    }
+
+    fn keys_of(nodes: &[DeltaNode]) -> Vec<&str> {
+        nodes.iter().map(|n| n.key.as_str()).collect()
+    }
+
+    #[test]
+    fn test_grouped_csv() {
+        let grouped = seeded_engine()
+            .group(&Period::all(), &[GroupKey::Project, GroupKey::Month])
+            .unwrap();
+        let csv = grouped.to_csv();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "project,month,seconds,hours,entries,percent");
+        assert_eq!(lines[1], "Acme,2026-10,10800,3.00,2,85.7");
+        assert_eq!(lines.len(), 3);
+        assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+    }
+
+    #[test]
+    fn test_grouped_code_activity_columns() {
+        let engine = seeded_engine();
+        let api = engine.db.list_entries(None).unwrap()[2].id;
+        let commit = crate::models::EntryCommit {
+            insertions: Some(40),
+            deletions: Some(4),
+            ..crate::models::EntryCommit::new("abc1234", "Add endpoint")
+        };
+        engine.db.replace_entry_commits(api, &[commit]).unwrap();
+
+        let grouped = engine
+            .group_with_code(&Period::all(), &[GroupKey::Project])
+            .unwrap();
+        let acme = grouped.groups[0].code.unwrap();
+        assert_eq!((acme.commits, acme.insertions, acme.deletions), (1, 40, 4));
+        assert_eq!(grouped.groups[1].code.unwrap().commits, 0);
+
+        let csv = grouped.to_csv();
+        let lines: Vec<_> = csv.lines().collect();
+        assert!(lines[0].ends_with(",commits,insertions,deletions,commits_per_hour"));
+        assert_eq!(lines[1], "Acme,10800,3.00,2,85.7,1,40,4,0.33");
+        assert!(engine
+            .group(&Period::all(), &[GroupKey::Project])
+            .unwrap()
+            .code
+            .is_none());
+    }
 
     #[test]
     fn test_progress_bar() {
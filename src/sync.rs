@@ -0,0 +1,870 @@
+//! Folder-based multi-device sync for MatteriaTrack
+//!
+//! Each device appends its changes to `<dir>/<device-id>.jsonl` and replays the
+//! logs of every other device it finds there. No file is ever written by more
+//! than one device, so any shared folder (Syncthing, NFS, a USB stick) works.
+//!
+//! Merge rules:
+//! - Projects, tasks and entries are identified by UUID, never by local row id.
+//! - Changes to the same entity are last-writer-wins on `(ts, device)`. The
+//!   timestamp is a hybrid clock that never goes behind a change already seen,
+//!   so an edit made after pulling always beats what it was based on.
+//! - Deletes are ordinary changes and follow the same rule.
+//! - A project or task created independently on two devices under the same
+//!   name is treated as one: the remote UUID becomes an alias of the local one.
+//! - After merging, at most one entry stays open. Older open entries are
+//!   finished at the start of the next one and the newest becomes active.
+
+use crate::config::Config;
+use crate::database::{self, Database};
+use crate::error::{Error, Result};
+use crate::models::EntryCommit;
+use crate::theme::MateriaTheme;
+use chrono::Utc;
+use colored::Colorize;
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+const LOG_EXTENSION: &str = "jsonl";
+const DEVICE_ID_FILE: &str = "device_id";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Project,
+    Task,
+    Entry,
+}
+
+impl EntityKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Project => "project",
+            Self::Task => "task",
+            Self::Entry => "entry",
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            Self::Project => "projects",
+            Self::Task => "tasks",
+            Self::Entry => "entries",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "project" => Some(Self::Project),
+            "task" => Some(Self::Task),
+            "entry" => Some(Self::Entry),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectData {
+    pub name: String,
+    pub color: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskData {
+    pub project: String,
+    pub name: String,
+    pub git_repo: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryData {
+    pub project: String,
+    pub task: String,
+    pub start: String,
+    pub end: Option<String>,
+    pub notes: Option<String>,
+    pub git_commits: Vec<String>,
+    pub commits: Vec<EntryCommit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", content = "data", rename_all = "snake_case")]
+pub enum ChangeOp {
+    Project(ProjectData),
+    Task(TaskData),
+    Entry(EntryData),
+    Delete(EntityKind),
+}
+
+impl ChangeOp {
+    fn kind(&self) -> EntityKind {
+        match self {
+            Self::Project(_) => EntityKind::Project,
+            Self::Task(_) => EntityKind::Task,
+            Self::Entry(_) => EntityKind::Entry,
+            Self::Delete(kind) => *kind,
+        }
+    }
+
+    /// Content hash used to notice local edits; `None` for deletes.
+    fn hash(&self) -> Option<String> {
+        if matches!(self, Self::Delete(_)) {
+            return None;
+        }
+        let json = serde_json::to_string(self).unwrap_or_default();
+        Some(format!("{:x}", Sha256::digest(json.as_bytes())))
+    }
+}
+
+/// One line of a device's change log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub device: String,
+    pub seq: u64,
+    pub ts: i64,
+    pub uuid: String,
+    pub change: ChangeOp,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    pub device: String,
+    pub remote_devices: usize,
+    pub pushed: usize,
+    pub pulled: usize,
+    pub superseded: usize,
+    pub skipped: usize,
+    pub timers_closed: usize,
+}
+
+pub struct Syncer<'a> {
+    db: &'a Database,
+    device_id: String,
+}
+
+impl<'a> Syncer<'a> {
+    pub fn new(db: &'a Database, device_id: impl Into<String>) -> Self {
+        Self {
+            db,
+            device_id: device_id.into(),
+        }
+    }
+
+    /// Merge every other device's log and reconcile the active timer in one
+    /// transaction, then publish local changes to `dir`.
+    pub fn sync(&self, dir: &Path) -> Result<SyncReport> {
+        fs::create_dir_all(dir)?;
+        let dir_key = dir.canonicalize()?.display().to_string();
+        let own_log = dir.join(format!("{}.{}", self.device_id, LOG_EXTENSION));
+
+        let mut report = SyncReport {
+            device: self.device_id.clone(),
+            ..Default::default()
+        };
+
+        let outgoing = self.db.transaction(|tx| {
+            database::assign_missing_uuids(tx)?;
+
+            let own_lines = read_complete_lines(&own_log)?.len() as u64;
+            let mut own_seq = cursor(tx, &self.device_id)?;
+            if get_meta(tx, "dir")?.as_deref() != Some(dir_key.as_str()) || own_lines < own_seq {
+                // New folder or lost log: replay everything and republish our state.
+                tx.execute_batch(
+                    "DELETE FROM sync_cursors; UPDATE sync_state SET hash = NULL WHERE deleted = 0;",
+                )?;
+                set_meta(tx, "dir", &dir_key)?;
+            }
+            own_seq = own_lines;
+
+            let mut clock = Clock::load(tx)?;
+            let mut outgoing = Vec::new();
+            collect_local_changes(tx, &self.device_id, &mut clock, &mut own_seq, &mut outgoing)?;
+
+            let mut incoming = Vec::new();
+            for (device, path) in remote_logs(dir, &self.device_id)? {
+                report.remote_devices += 1;
+                let lines = read_complete_lines(&path)?;
+                let start = cursor(tx, &device)? as usize;
+                for line in lines.iter().skip(start) {
+                    match serde_json::from_str::<Change>(line) {
+                        Ok(change) if change.device == device => incoming.push(change),
+                        _ => report.skipped += 1,
+                    }
+                }
+                set_cursor(tx, &device, lines.len() as u64)?;
+            }
+
+            incoming.sort_by(|a, b| (a.ts, &a.device, a.seq).cmp(&(b.ts, &b.device, b.seq)));
+            for change in &incoming {
+                clock.observe(change.ts);
+                apply_change(tx, change, &mut report)?;
+            }
+
+            report.timers_closed = reconcile_active_timers(tx)?;
+            collect_local_changes(tx, &self.device_id, &mut clock, &mut own_seq, &mut outgoing)?;
+
+            set_cursor(tx, &self.device_id, own_seq)?;
+            clock.save(tx)?;
+            Ok(outgoing)
+        })?;
+
+        // Only published once committed. If this write fails, the log is
+        // shorter than the saved cursor and the next sync republishes everything.
+        append_changes(&own_log, &outgoing)?;
+        report.pushed = outgoing.len();
+        Ok(report)
+    }
+}
+
+/// Stable identifier for this machine, created on first use.
+pub fn local_device_id() -> Result<String> {
+    let path = Config::config_dir()?.join(DEVICE_ID_FILE);
+    if let Ok(id) = fs::read_to_string(&path) {
+        let id = id.trim();
+        if !id.is_empty() {
+            return Ok(id.to_string());
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    fs::write(&path, &id)?;
+    Ok(id)
+}
+
+/// Hybrid logical clock in milliseconds, persisted in `sync_meta`.
+struct Clock {
+    last: i64,
+}
+
+impl Clock {
+    fn load(conn: &Connection) -> Result<Self> {
+        let last = get_meta(conn, "clock")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        Ok(Self { last })
+    }
+
+    fn tick(&mut self) -> i64 {
+        self.last = Utc::now().timestamp_millis().max(self.last + 1);
+        self.last
+    }
+
+    fn observe(&mut self, ts: i64) {
+        self.last = self.last.max(ts);
+    }
+
+    fn save(&self, conn: &Connection) -> Result<()> {
+        set_meta(conn, "clock", &self.last.to_string())
+    }
+}
+
+struct SyncState {
+    kind: EntityKind,
+    hash: Option<String>,
+    ts: i64,
+    device: String,
+    deleted: bool,
+}
+
+fn load_state(conn: &Connection, uuid: &str) -> Result<Option<SyncState>> {
+    conn.query_row(
+        "SELECT kind, hash, ts, device, deleted FROM sync_state WHERE uuid = ?1",
+        params![uuid],
+        |row| {
+            let kind: String = row.get(0)?;
+            Ok(SyncState {
+                kind: EntityKind::parse(&kind).unwrap_or(EntityKind::Entry),
+                hash: row.get(1)?,
+                ts: row.get(2)?,
+                device: row.get(3)?,
+                deleted: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(Into::into)
+}
+
+fn save_state(conn: &Connection, uuid: &str, op: &ChangeOp, ts: i64, device: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_state (uuid, kind, hash, ts, device, deleted) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            uuid,
+            op.kind().as_str(),
+            op.hash(),
+            ts,
+            device,
+            matches!(op, ChangeOp::Delete(_))
+        ],
+    )?;
+    Ok(())
+}
+
+fn get_meta(conn: &Connection, key: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT value FROM sync_meta WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(Into::into)
+}
+
+fn set_meta(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_meta (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+fn cursor(conn: &Connection, device: &str) -> Result<u64> {
+    let seq: Option<i64> = conn
+        .query_row(
+            "SELECT seq FROM sync_cursors WHERE device = ?1",
+            params![device],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(seq.unwrap_or(0) as u64)
+}
+
+fn set_cursor(conn: &Connection, device: &str, seq: u64) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_cursors (device, seq) VALUES (?1, ?2)",
+        params![device, seq as i64],
+    )?;
+    Ok(())
+}
+
+/// Current state of every synced entity, parents before children.
+fn snapshot(conn: &Connection) -> Result<Vec<(String, ChangeOp)>> {
+    let mut items = Vec::new();
+
+    let mut stmt =
+        conn.prepare("SELECT uuid, name, color, created_at, updated_at FROM projects ORDER BY id")?;
+    let projects = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                ChangeOp::Project(ProjectData {
+                    name: row.get(1)?,
+                    color: row.get(2)?,
+                    created_at: row.get(3)?,
+                    updated_at: row.get(4)?,
+                }),
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    items.extend(projects);
+
+    let mut stmt = conn.prepare(
+        "SELECT t.uuid, p.uuid, t.name, t.git_repo, t.created_at, t.updated_at \
+         FROM tasks t JOIN projects p ON t.project_id = p.id ORDER BY t.id",
+    )?;
+    let tasks = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                ChangeOp::Task(TaskData {
+                    project: row.get(1)?,
+                    name: row.get(2)?,
+                    git_repo: row.get(3)?,
+                    created_at: row.get(4)?,
+                    updated_at: row.get(5)?,
+                }),
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    items.extend(tasks);
+
+    let mut commits: HashMap<i64, Vec<EntryCommit>> = HashMap::new();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM entry_commits c ORDER BY c.committed_at, c.id",
+        database::COMMIT_COLUMNS
+    ))?;
+    for commit in stmt.query_map([], database::row_to_commit)? {
+        let commit = commit?;
+        commits
+            .entry(commit.entry_id)
+            .or_default()
+            .push(EntryCommit {
+                id: 0,
+                entry_id: 0,
+                ..commit
+            });
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT e.id, e.uuid, p.uuid, t.uuid, e.start_time, e.end_time, e.notes, e.git_commits \
+         FROM entries e JOIN projects p ON e.project_id = p.id JOIN tasks t ON e.task_id = t.id \
+         ORDER BY e.id",
+    )?;
+    let entries = stmt
+        .query_map([], |row| {
+            let git_commits: Option<String> = row.get(7)?;
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                EntryData {
+                    project: row.get(2)?,
+                    task: row.get(3)?,
+                    start: row.get(4)?,
+                    end: row.get(5)?,
+                    notes: row.get(6)?,
+                    git_commits: git_commits
+                        .and_then(|s| serde_json::from_str(&s).ok())
+                        .unwrap_or_default(),
+                    commits: Vec::new(),
+                },
+            ))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    for (id, uuid, mut data) in entries {
+        data.commits = commits.remove(&id).unwrap_or_default();
+        items.push((uuid, ChangeOp::Entry(data)));
+    }
+
+    Ok(items)
+}
+
+/// Diff the database against `sync_state` and turn every difference into a change.
+fn collect_local_changes(
+    conn: &Connection,
+    device: &str,
+    clock: &mut Clock,
+    seq: &mut u64,
+    out: &mut Vec<Change>,
+) -> Result<()> {
+    let mut push = |uuid: String, op: ChangeOp| -> Result<()> {
+        let ts = clock.tick();
+        *seq += 1;
+        save_state(conn, &uuid, &op, ts, device)?;
+        out.push(Change {
+            device: device.to_string(),
+            seq: *seq,
+            ts,
+            uuid,
+            change: op,
+        });
+        Ok(())
+    };
+
+    let mut live = HashSet::new();
+    for (uuid, op) in snapshot(conn)? {
+        let known = load_state(conn, &uuid)?.and_then(|s| s.hash);
+        live.insert(uuid.clone());
+        if known != op.hash() {
+            push(uuid, op)?;
+        }
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT s.uuid, s.kind FROM sync_state s \
+         WHERE s.deleted = 0 AND NOT EXISTS (SELECT 1 FROM sync_aliases a WHERE a.alias = s.uuid)",
+    )?;
+    let tracked = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    for (uuid, kind) in tracked {
+        if !live.contains(&uuid) {
+            if let Some(kind) = EntityKind::parse(&kind) {
+                push(uuid, ChangeOp::Delete(kind))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_change(conn: &Connection, change: &Change, report: &mut SyncReport) -> Result<()> {
+    if let Some(state) = load_state(conn, &change.uuid)? {
+        if (state.ts, state.device.as_str()) >= (change.ts, change.device.as_str()) {
+            report.superseded += 1;
+            return Ok(());
+        }
+        if state.kind != change.change.kind() {
+            report.skipped += 1;
+            return Ok(());
+        }
+    }
+
+    // Saved with local parent UUIDs so the hash matches the next snapshot.
+    let op = canonicalize(conn, &change.change)?;
+    let applied = match &op {
+        ChangeOp::Project(data) => apply_project(conn, &change.uuid, data)?,
+        ChangeOp::Task(data) => apply_task(conn, &change.uuid, data)?,
+        ChangeOp::Entry(data) => apply_entry(conn, &change.uuid, data)?,
+        ChangeOp::Delete(kind) => {
+            if let Some(id) = resolve(conn, *kind, &change.uuid)? {
+                conn.execute(
+                    &format!("DELETE FROM {} WHERE id = ?1", kind.table()),
+                    params![id],
+                )?;
+            }
+            true
+        }
+    };
+
+    if applied {
+        save_state(conn, &change.uuid, &op, change.ts, &change.device)?;
+        report.pulled += 1;
+    } else {
+        report.skipped += 1;
+    }
+    Ok(())
+}
+
+/// Local row id for a UUID, following name-collision aliases.
+fn resolve(conn: &Connection, kind: EntityKind, uuid: &str) -> Result<Option<i64>> {
+    let query = format!("SELECT id FROM {} WHERE uuid = ?1", kind.table());
+    if let Some(id) = conn
+        .query_row(&query, params![uuid], |row| row.get(0))
+        .optional()?
+    {
+        return Ok(Some(id));
+    }
+
+    let target: Option<String> = conn
+        .query_row(
+            "SELECT uuid FROM sync_aliases WHERE alias = ?1",
+            params![uuid],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match target {
+        Some(target) => conn
+            .query_row(&query, params![target], |row| row.get(0))
+            .optional()
+            .map_err(Into::into),
+        None => Ok(None),
+    }
+}
+
+/// The local UUID `uuid` stands for, if it is a name-collision alias.
+fn canonical_uuid(conn: &Connection, uuid: &str) -> Result<String> {
+    let target: Option<String> = conn
+        .query_row(
+            "SELECT uuid FROM sync_aliases WHERE alias = ?1",
+            params![uuid],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(target.unwrap_or_else(|| uuid.to_string()))
+}
+
+/// `op` with its project and task references replaced by local UUIDs.
+fn canonicalize(conn: &Connection, op: &ChangeOp) -> Result<ChangeOp> {
+    Ok(match op {
+        ChangeOp::Task(data) => ChangeOp::Task(TaskData {
+            project: canonical_uuid(conn, &data.project)?,
+            ..data.clone()
+        }),
+        ChangeOp::Entry(data) => ChangeOp::Entry(EntryData {
+            project: canonical_uuid(conn, &data.project)?,
+            task: canonical_uuid(conn, &data.task)?,
+            ..data.clone()
+        }),
+        other => other.clone(),
+    })
+}
+
+fn add_alias(conn: &Connection, alias: &str, uuid: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_aliases (alias, uuid) VALUES (?1, ?2)",
+        params![alias, uuid],
+    )?;
+    Ok(())
+}
+
+/// Treat UNIQUE violations (e.g. a rename onto an existing name) as "not applied".
+fn unless_conflict(result: rusqlite::Result<usize>) -> Result<bool> {
+    match result {
+        Ok(_) => Ok(true),
+        Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == ErrorCode::ConstraintViolation => {
+            Ok(false)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn apply_project(conn: &Connection, uuid: &str, data: &ProjectData) -> Result<bool> {
+    if let Some(id) = resolve(conn, EntityKind::Project, uuid)? {
+        return unless_conflict(conn.execute(
+            "UPDATE projects SET name = ?1, color = ?2, created_at = ?3, updated_at = ?4 WHERE id = ?5",
+            params![data.name, data.color, data.created_at, data.updated_at, id],
+        ));
+    }
+
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT uuid FROM projects WHERE name = ?1",
+            params![data.name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(existing) = existing {
+        add_alias(conn, uuid, &existing)?;
+        return Ok(true);
+    }
+
+    unless_conflict(conn.execute(
+        "INSERT INTO projects (name, color, created_at, updated_at, uuid) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![data.name, data.color, data.created_at, data.updated_at, uuid],
+    ))
+}
+
+fn apply_task(conn: &Connection, uuid: &str, data: &TaskData) -> Result<bool> {
+    let Some(project_id) = resolve(conn, EntityKind::Project, &data.project)? else {
+        return Ok(false);
+    };
+
+    if let Some(id) = resolve(conn, EntityKind::Task, uuid)? {
+        return unless_conflict(conn.execute(
+            "UPDATE tasks SET project_id = ?1, name = ?2, git_repo = ?3, created_at = ?4, updated_at = ?5 WHERE id = ?6",
+            params![project_id, data.name, data.git_repo, data.created_at, data.updated_at, id],
+        ));
+    }
+
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT uuid FROM tasks WHERE project_id = ?1 AND name = ?2",
+            params![project_id, data.name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(existing) = existing {
+        add_alias(conn, uuid, &existing)?;
+        return Ok(true);
+    }
+
+    unless_conflict(conn.execute(
+        "INSERT INTO tasks (project_id, name, git_repo, created_at, updated_at, uuid) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![project_id, data.name, data.git_repo, data.created_at, data.updated_at, uuid],
+    ))
+}
+
+fn apply_entry(conn: &Connection, uuid: &str, data: &EntryData) -> Result<bool> {
+    let (Some(project_id), Some(task_id)) = (
+        resolve(conn, EntityKind::Project, &data.project)?,
+        resolve(conn, EntityKind::Task, &data.task)?,
+    ) else {
+        return Ok(false);
+    };
+    let git_commits = serde_json::to_string(&data.git_commits)?;
+
+    let entry_id = match resolve(conn, EntityKind::Entry, uuid)? {
+        Some(id) => {
+            conn.execute(
+                "UPDATE entries SET project_id = ?1, task_id = ?2, start_time = ?3, end_time = ?4, notes = ?5, git_commits = ?6 WHERE id = ?7",
+                params![project_id, task_id, data.start, data.end, data.notes, git_commits, id],
+            )?;
+            id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO entries (project_id, task_id, start_time, end_time, notes, git_commits, uuid) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![project_id, task_id, data.start, data.end, data.notes, git_commits, uuid],
+            )?;
+            conn.last_insert_rowid()
+        }
+    };
+
+    conn.execute(
+        "DELETE FROM entry_commits WHERE entry_id = ?1",
+        params![entry_id],
+    )?;
+    for commit in &data.commits {
+        database::insert_commit(conn, entry_id, commit)?;
+    }
+    Ok(true)
+}
+
+/// Keep a single open entry: each older one is finished where the next begins.
+/// Returns how many entries were closed.
+fn reconcile_active_timers(conn: &Connection) -> Result<usize> {
+    let mut stmt = conn.prepare(
+        "SELECT id, start_time FROM entries WHERE end_time IS NULL ORDER BY start_time, uuid",
+    )?;
+    let open = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    for pair in open.windows(2) {
+        conn.execute(
+            "UPDATE entries SET end_time = ?1 WHERE id = ?2",
+            params![pair[1].1, pair[0].0],
+        )?;
+    }
+
+    match open.last() {
+        Some((id, _)) => conn.execute(
+            "INSERT OR REPLACE INTO active_tracking (id, entry_id) VALUES (1, ?1)",
+            params![id],
+        )?,
+        None => conn.execute("DELETE FROM active_tracking WHERE id = 1", [])?,
+    };
+
+    Ok(open.len().saturating_sub(1))
+}
+
+/// Other devices' logs in `dir`, as (device id, path).
+fn remote_logs(dir: &Path, own_device: &str) -> Result<Vec<(String, std::path::PathBuf)>> {
+    let mut logs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(LOG_EXTENSION) {
+            continue;
+        }
+        if let Some(device) = path.file_stem().and_then(|s| s.to_str()) {
+            if device != own_device {
+                logs.push((device.to_string(), path.clone()));
+            }
+        }
+    }
+    logs.sort();
+    Ok(logs)
+}
+
+/// Lines terminated by a newline; a trailing partial line (a write still in
+/// flight on the other device) is left for the next sync.
+fn read_complete_lines(path: &Path) -> Result<Vec<String>> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(content
+        .split_inclusive('\n')
+        .filter(|l| l.ends_with('\n'))
+        .map(|l| l.trim_end().to_string())
+        .collect())
+}
+
+fn append_changes(path: &Path, changes: &[Change]) -> Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let mut buf = String::new();
+    for change in changes {
+        buf.push_str(&serde_json::to_string(change)?);
+        buf.push('\n');
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| {
+            Error::Io(std::io::Error::new(
+                e.kind(),
+                format!("{}: {}", path.display(), e),
+            ))
+        })?;
+    file.write_all(buf.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+pub fn format_report(report: &SyncReport, theme: MateriaTheme) -> String {
+    let (r, g, b) = theme.primary_color();
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "\n{} {} {}\n",
+        theme.icon(),
+        "Sync Complete".truecolor(r, g, b).bold(),
+        theme.icon()
+    ));
+    output.push_str(&"━".repeat(50));
+    output.push('\n');
+
+    output.push_str(&format!("  Device:          {}\n", report.device));
+    output.push_str(&format!("  Other devices:   {}\n", report.remote_devices));
+    output.push_str(&format!("  Changes pushed:  {}\n", report.pushed));
+    output.push_str(&format!("  Changes pulled:  {}\n", report.pulled));
+    if report.superseded > 0 {
+        output.push_str(&format!(
+            "  Superseded:      {} (newer version already present)\n",
+            report.superseded
+        ));
+    }
+    if report.skipped > 0 {
+        output.push_str(&format!(
+            "  {}\n",
+            format!(
+                "Skipped:         {} (unreadable or unresolvable)",
+                report.skipped
+            )
+            .truecolor(255, 200, 50)
+        ));
+    }
+    if report.timers_closed > 0 {
+        output.push_str(&format!(
+            "  {}\n",
+            format!(
+                "Closed {} concurrent timer(s); only the newest stays active",
+                report.timers_closed
+            )
+            .truecolor(255, 200, 50)
+        ));
+    }
+
+    output.push_str(&"━".repeat(50));
+    output.push('\n');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_roundtrip() {
+        let change = Change {
+            device: "laptop".into(),
+            seq: 1,
+            ts: 42,
+            uuid: "u-1".into(),
+            change: ChangeOp::Delete(EntityKind::Task),
+        };
+        let json = serde_json::to_string(&change).unwrap();
+        assert!(json.contains("\"op\":\"delete\""));
+        assert_eq!(serde_json::from_str::<Change>(&json).unwrap(), change);
+    }
+
+    #[test]
+    fn test_clock_is_monotonic() {
+        let mut clock = Clock { last: 0 };
+        let future = Utc::now().timestamp_millis() + 60_000;
+        clock.observe(future);
+        assert!(clock.tick() > future);
+        let a = clock.tick();
+        assert!(clock.tick() > a);
+    }
+
+    #[test]
+    fn test_partial_line_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("x.jsonl");
+        fs::write(&path, "{\"a\":1}\n{\"b\":").unwrap();
+        assert_eq!(read_complete_lines(&path).unwrap(), vec!["{\"a\":1}"]);
+    }
+}
@@ -0,0 +1,473 @@
+//! Day timelines for MatteriaTrack
+//!
+//! Places each day's entries on an hour axis so it is visible when work
+//! happened, and marks the untracked gaps inside the configured working
+//! hours. Several days stack into a week view.
+
+use crate::config::ScheduleConfig;
+use crate::error::Result;
+use crate::models::EntryWithDetails;
+use crate::report::{valid_color, PALETTE};
+use crate::stats::format_seconds;
+use crate::theme::MateriaTheme;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone, Timelike};
+use colored::Colorize;
+use serde::Serialize;
+
+/// Gaps shorter than this are not worth pointing out.
+const MIN_GAP_MINUTES: i64 = 5;
+/// Width of the hour axis in characters.
+const AXIS_WIDTH: u32 = 48;
+const GAP_COLOR: (u8, u8, u8) = (220, 90, 90);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Timeline {
+    pub work_start: NaiveTime,
+    pub work_end: NaiveTime,
+    /// First hour on the axis
+    pub first_hour: u32,
+    /// Hour the axis ends at, exclusive
+    pub last_hour: u32,
+    pub days: Vec<DayTimeline>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DayTimeline {
+    pub date: NaiveDate,
+    pub working_day: bool,
+    pub blocks: Vec<TimelineBlock>,
+    /// Untracked stretches within working hours
+    pub gaps: Vec<Gap>,
+    pub tracked_seconds: i64,
+    pub untracked_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineBlock {
+    pub project: String,
+    pub task: String,
+    pub color: String,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub seconds: i64,
+    pub running: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Gap {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub seconds: i64,
+}
+
+impl Timeline {
+    /// Lay out `days`, clipping entries that cross midnight to each day.
+    pub fn build(
+        entries: &[EntryWithDetails],
+        days: &[NaiveDate],
+        schedule: &ScheduleConfig,
+    ) -> Result<Self> {
+        let (work_start, work_end) = schedule.working_hours()?;
+        let now = Local::now();
+        let mut colors: Vec<(String, String)> = Vec::new();
+
+        let mut timeline_days = Vec::new();
+        for &date in days {
+            let day_start = local(date, NaiveTime::MIN);
+            let day_end = local(date + Duration::days(1), NaiveTime::MIN);
+
+            let mut blocks = Vec::new();
+            for e in entries {
+                let start = e.entry.start.with_timezone(&Local).max(day_start);
+                let end = e
+                    .entry
+                    .end
+                    .map(|t| t.with_timezone(&Local))
+                    .unwrap_or(now)
+                    .min(day_end);
+                if end <= start {
+                    continue;
+                }
+                let color = match colors.iter().find(|(p, _)| *p == e.project_name) {
+                    Some((_, color)) => color.clone(),
+                    None => {
+                        let color = valid_color(e.project_color.as_deref())
+                            .unwrap_or(PALETTE[colors.len() % PALETTE.len()])
+                            .to_string();
+                        colors.push((e.project_name.clone(), color.clone()));
+                        color
+                    }
+                };
+                blocks.push(TimelineBlock {
+                    project: e.project_name.clone(),
+                    task: e.task_name.clone(),
+                    color,
+                    start,
+                    end,
+                    seconds: (end - start).num_seconds(),
+                    running: e.entry.end.is_none(),
+                });
+            }
+            blocks.sort_by_key(|b| b.start);
+
+            let working_day = schedule.is_work_day(date.weekday());
+            let gaps = if working_day {
+                // Don't count the rest of today as untracked yet
+                let until = local(date, work_end).min(now);
+                gaps(&blocks, local(date, work_start), until)
+            } else {
+                Vec::new()
+            };
+
+            timeline_days.push(DayTimeline {
+                date,
+                working_day,
+                tracked_seconds: blocks.iter().map(|b| b.seconds).sum(),
+                untracked_seconds: gaps.iter().map(|g| g.seconds).sum(),
+                blocks,
+                gaps,
+            });
+        }
+
+        let blocks = || timeline_days.iter().flat_map(|d| d.blocks.iter());
+        let first_hour = blocks()
+            .map(|b| b.start.hour())
+            .min()
+            .unwrap_or(24)
+            .min(work_start.hour());
+        let last_hour = blocks()
+            .map(|b| end_hour(b.end))
+            .max()
+            .unwrap_or(0)
+            .max(end_hour_of(work_end))
+            .min(24);
+
+        Ok(Self {
+            work_start,
+            work_end,
+            first_hour,
+            last_hour,
+            days: timeline_days,
+        })
+    }
+
+    /// Characters per hour on the axis.
+    fn columns_per_hour(&self) -> u32 {
+        (AXIS_WIDTH / (self.last_hour - self.first_hour).max(1)).clamp(1, 4)
+    }
+
+    pub fn format_pretty(&self, theme: MateriaTheme) -> String {
+        let (r, g, b) = theme.primary_color();
+        let per_hour = self.columns_per_hour();
+        let slot = Duration::minutes(60 / per_hour as i64);
+        let label_every = if per_hour >= 3 { 1 } else { 4 / per_hour };
+        let mut output = String::new();
+
+        let title = match (self.days.first(), self.days.last()) {
+            (Some(first), Some(last)) if first.date != last.date => format!(
+                "Timeline {} – {}",
+                first.date.format("%Y-%m-%d"),
+                last.date.format("%Y-%m-%d")
+            ),
+            (Some(day), _) => format!("Timeline {}", day.date.format("%A, %Y-%m-%d")),
+            _ => "Timeline".to_string(),
+        };
+        output.push_str(&format!(
+            "\n{} {} {}\n",
+            theme.icon(),
+            title.truecolor(r, g, b).bold(),
+            theme.materia_icon()
+        ));
+        output.push_str(
+            &format!(
+                "   Working hours {}–{}\n\n",
+                self.work_start.format("%H:%M"),
+                self.work_end.format("%H:%M")
+            )
+            .dimmed()
+            .to_string(),
+        );
+
+        // Hour labels above the axis
+        let mut axis = String::from("            ");
+        for hour in (self.first_hour..self.last_hour).step_by(label_every as usize) {
+            let width = (per_hour * label_every) as usize;
+            axis.push_str(&format!(
+                "{:<width$}",
+                format!("{:02}", hour),
+                width = width
+            ));
+        }
+        output.push_str(&format!("{}\n", axis.trim_end()));
+
+        for day in &self.days {
+            output.push_str(&format!("{} │", day.date.format("%a %d.%m.")));
+            let mut t = local(day.date, NaiveTime::MIN) + Duration::hours(self.first_hour as i64);
+            for _ in 0..(self.last_hour - self.first_hour) * per_hour {
+                let end = t + slot;
+                let half = slot / 2;
+                let block = day
+                    .blocks
+                    .iter()
+                    .map(|b| (overlap(b.start, b.end, t, end), b))
+                    .filter(|(o, _)| *o >= half)
+                    .max_by_key(|(o, _)| *o);
+                let cell = if let Some((_, block)) = block {
+                    let (br, bg, bb) = hex_rgb(&block.color);
+                    "█".truecolor(br, bg, bb).to_string()
+                } else if day
+                    .gaps
+                    .iter()
+                    .any(|gap| overlap(gap.start, gap.end, t, end) >= half)
+                {
+                    let (gr, gg, gb) = GAP_COLOR;
+                    "░".truecolor(gr, gg, gb).to_string()
+                } else {
+                    "·".dimmed().to_string()
+                };
+                output.push_str(&cell);
+                t = end;
+            }
+            output.push_str(&format!("│ {:>7}", format_seconds(day.tracked_seconds)));
+            if day.untracked_seconds > 0 {
+                let (gr, gg, gb) = GAP_COLOR;
+                output.push_str(&format!(
+                    " {}",
+                    format!("({} untracked)", format_seconds(day.untracked_seconds))
+                        .truecolor(gr, gg, gb)
+                ));
+            }
+            output.push('\n');
+        }
+
+        // A single day also lists its blocks and gaps in order
+        if let [day] = self.days.as_slice() {
+            output.push('\n');
+            let mut rows: Vec<(DateTime<Local>, String)> = day
+                .blocks
+                .iter()
+                .map(|b| {
+                    let (br, bg, bb) = hex_rgb(&b.color);
+                    let end = if b.running {
+                        "now  ".to_string()
+                    } else {
+                        b.end.format("%H:%M").to_string()
+                    };
+                    (
+                        b.start,
+                        format!(
+                            "   {}–{} {:>8}  {} {} / {}",
+                            b.start.format("%H:%M"),
+                            end,
+                            format_seconds(b.seconds),
+                            "■".truecolor(br, bg, bb),
+                            b.project.bold(),
+                            b.task
+                        ),
+                    )
+                })
+                .collect();
+            rows.extend(day.gaps.iter().map(|gap| {
+                let (gr, gg, gb) = GAP_COLOR;
+                (
+                    gap.start,
+                    format!(
+                        "   {}–{} {:>8}  {}",
+                        gap.start.format("%H:%M"),
+                        gap.end.format("%H:%M"),
+                        format_seconds(gap.seconds),
+                        "░ untracked".truecolor(gr, gg, gb)
+                    ),
+                )
+            }));
+            rows.sort_by_key(|(start, _)| *start);
+            for (_, row) in rows {
+                output.push_str(&row);
+                output.push('\n');
+            }
+        } else {
+            let tracked: i64 = self.days.iter().map(|d| d.tracked_seconds).sum();
+            let untracked: i64 = self.days.iter().map(|d| d.untracked_seconds).sum();
+            output.push_str(&format!(
+                "\n   Total {}, {} untracked during working hours\n",
+                format_seconds(tracked).truecolor(r, g, b).bold(),
+                format_seconds(untracked)
+            ));
+        }
+
+        // Legend
+        let mut legend: Vec<(&str, &str)> = Vec::new();
+        for block in self.days.iter().flat_map(|d| d.blocks.iter()) {
+            if !legend.iter().any(|(p, _)| *p == block.project) {
+                legend.push((&block.project, &block.color));
+            }
+        }
+        output.push_str("\n  ");
+        for (project, color) in legend {
+            let (br, bg, bb) = hex_rgb(color);
+            output.push_str(&format!("{} {}  ", "█".truecolor(br, bg, bb), project));
+        }
+        let (gr, gg, gb) = GAP_COLOR;
+        output.push_str(&format!(
+            "{} untracked  {} outside working hours\n\n",
+            "░".truecolor(gr, gg, gb),
+            "·".dimmed()
+        ));
+
+        output
+    }
+}
+
+/// `date` at `time` in local time, taking the earlier time when a DST change
+/// makes it ambiguous.
+fn local(date: NaiveDate, time: NaiveTime) -> DateTime<Local> {
+    Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&date.and_time(time)))
+}
+
+/// Untracked stretches of `[from, until)` between the blocks of a day.
+fn gaps(blocks: &[TimelineBlock], from: DateTime<Local>, until: DateTime<Local>) -> Vec<Gap> {
+    let mut gaps = Vec::new();
+    let mut cursor = from;
+    let mut push = |start: DateTime<Local>, end: DateTime<Local>| {
+        if end - start >= Duration::minutes(MIN_GAP_MINUTES) {
+            gaps.push(Gap {
+                start,
+                end,
+                seconds: (end - start).num_seconds(),
+            });
+        }
+    };
+    for block in blocks {
+        if block.start > cursor {
+            push(cursor, block.start.min(until));
+        }
+        cursor = cursor.max(block.end);
+        if cursor >= until {
+            break;
+        }
+    }
+    if cursor < until {
+        push(cursor, until);
+    }
+    gaps
+}
+
+fn overlap(
+    a_start: DateTime<Local>,
+    a_end: DateTime<Local>,
+    b_start: DateTime<Local>,
+    b_end: DateTime<Local>,
+) -> Duration {
+    (a_end.min(b_end) - a_start.max(b_start)).max(Duration::zero())
+}
+
+/// The hour an axis must reach to include `t`.
+fn end_hour(t: DateTime<Local>) -> u32 {
+    if t.time() == NaiveTime::MIN {
+        24
+    } else {
+        end_hour_of(t.time())
+    }
+}
+
+fn end_hour_of(t: NaiveTime) -> u32 {
+    if t.minute() == 0 && t.second() == 0 {
+        t.hour()
+    } else {
+        t.hour() + 1
+    }
+}
+
+fn hex_rgb(color: &str) -> (u8, u8, u8) {
+    let hex = color.trim_start_matches('#');
+    let channel = |i: usize, len: usize| {
+        let value = u8::from_str_radix(hex.get(i..i + len).unwrap_or("0"), 16).unwrap_or(0);
+        if len == 1 {
+            value * 17
+        } else {
+            value
+        }
+    };
+    if hex.len() == 3 {
+        (channel(0, 1), channel(1, 1), channel(2, 1))
+    } else {
+        (channel(0, 2), channel(2, 2), channel(4, 2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::fixtures::{local, row, seeded};
+
+    fn timeline(days: &[NaiveDate]) -> Timeline {
+        let db = seeded(&[
+            // Monday 5 October: late start, a lunch gap, then an evening stint
+            row("Acme", "work", local("2026-10-05 09:30"), 150),
+            row("Beta", "work", local("2026-10-05 13:00"), 240),
+            row("Acme", "work", local("2026-10-05 20:00"), 90),
+            // Saturday night into Sunday
+            row("Beta", "work", local("2026-10-10 23:00"), 120),
+        ]);
+        let entries = db
+            .list_entries_with_details_between(None, None, None)
+            .unwrap();
+        Timeline::build(&entries, days, &ScheduleConfig::default()).unwrap()
+    }
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    #[test]
+    fn test_blocks_and_gaps_within_working_hours() {
+        let timeline = timeline(&[date(5)]);
+        let day = &timeline.days[0];
+        assert!(day.working_day);
+        assert_eq!(day.blocks.len(), 3);
+        assert_eq!(day.tracked_seconds, (150 + 240 + 90) * 60);
+
+        let gaps: Vec<(u32, u32, i64)> = day
+            .gaps
+            .iter()
+            .map(|g| (g.start.hour(), g.end.hour(), g.seconds / 60))
+            .collect();
+        assert_eq!(gaps, vec![(9, 9, 30), (12, 13, 60)]);
+        assert_eq!(day.untracked_seconds, 90 * 60);
+        // The evening stint stretches the axis past working hours
+        assert_eq!((timeline.first_hour, timeline.last_hour), (9, 22));
+        assert_eq!(timeline.columns_per_hour(), 3);
+    }
+
+    #[test]
+    fn test_week_view_clips_across_midnight() {
+        let days: Vec<NaiveDate> = (5..12).map(date).collect();
+        let timeline = timeline(&days);
+        assert_eq!(timeline.days.len(), 7);
+
+        let saturday = &timeline.days[5];
+        let sunday = &timeline.days[6];
+        assert!(!saturday.working_day);
+        assert!(saturday.gaps.is_empty());
+        assert_eq!(saturday.tracked_seconds, 60 * 60);
+        assert_eq!(sunday.tracked_seconds, 60 * 60);
+        assert_eq!(sunday.blocks[0].start.hour(), 0);
+        assert_eq!(timeline.first_hour, 0);
+        assert_eq!(timeline.last_hour, 24);
+
+        // Tuesday has nothing tracked, so the whole working day is a gap
+        assert_eq!(timeline.days[1].untracked_seconds, 8 * 3600);
+        assert!(timeline
+            .format_pretty(MateriaTheme::Ice)
+            .contains("untracked during working hours"));
+    }
+
+    #[test]
+    fn test_hex_rgb() {
+        assert_eq!(hex_rgb("#3498db"), (0x34, 0x98, 0xdb));
+        assert_eq!(hex_rgb("#fff"), (255, 255, 255));
+    }
+}
@@ -0,0 +1,473 @@
+//! Weekly timesheets for MatteriaTrack
+//!
+//! Lays one ISO week out as a project/task × weekday grid with row and
+//! column totals, built on [`StatsEngine`] groupings. Rendered for the
+//! terminal, or as CSV, Markdown or HTML for submission.
+
+use crate::error::Result;
+use crate::period::Period;
+use crate::stats::{csv_field, GroupKey, GroupNode, StatsEngine};
+use crate::theme::MateriaTheme;
+use chrono::{NaiveDate, Weekday};
+use colored::Colorize;
+use serde::Serialize;
+
+const LABEL_WIDTH: usize = 32;
+const CELL_WIDTH: usize = 7;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Timesheet {
+    pub week: String,
+    pub days: Vec<NaiveDate>,
+    pub rows: Vec<TimesheetRow>,
+    /// Per weekday, Monday first
+    pub day_totals: Vec<i64>,
+    pub total_seconds: i64,
+    pub rounding_minutes: Option<u32>,
+    pub by_project: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimesheetRow {
+    pub project: String,
+    /// `None` when the sheet has one row per project
+    pub task: Option<String>,
+    /// Seconds per weekday, Monday first
+    pub days: Vec<i64>,
+    pub total_seconds: i64,
+}
+
+impl TimesheetRow {
+    pub fn label(&self) -> String {
+        match &self.task {
+            Some(task) => format!("{} / {}", self.project, task),
+            None => self.project.clone(),
+        }
+    }
+}
+
+impl Timesheet {
+    /// Lay out `week` with one row per task (or per project), rounding each
+    /// cell to the nearest `round` minutes. Totals add up the rounded cells.
+    pub fn build(
+        engine: &StatsEngine,
+        week: &Period,
+        by_project: bool,
+        round: Option<u32>,
+    ) -> Result<Self> {
+        let keys: &[GroupKey] = if by_project {
+            &[GroupKey::Project, GroupKey::Weekday]
+        } else {
+            &[GroupKey::Project, GroupKey::Task, GroupKey::Weekday]
+        };
+        let grouped = engine.group(week, keys)?;
+
+        let mut rows = Vec::new();
+        for project in &grouped.groups {
+            if by_project {
+                rows.push(row(&project.key, None, &project.groups, round));
+            } else {
+                for task in &project.groups {
+                    rows.push(row(&project.key, Some(&task.key), &task.groups, round));
+                }
+            }
+        }
+        rows.retain(|r| r.total_seconds > 0);
+
+        let day_totals: Vec<i64> = (0..7)
+            .map(|d| rows.iter().map(|r| r.days[d]).sum())
+            .collect();
+
+        Ok(Self {
+            week: week.label.clone(),
+            days: week.days(),
+            total_seconds: day_totals.iter().sum(),
+            day_totals,
+            rows,
+            rounding_minutes: round.filter(|m| *m > 0),
+            by_project,
+        })
+    }
+
+    fn has_tasks(&self) -> bool {
+        !self.by_project
+    }
+
+    fn day_header(&self, i: usize, format: &str) -> String {
+        self.days
+            .get(i)
+            .map(|d| d.format(format).to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn format_pretty(&self, theme: MateriaTheme, decimal: bool) -> String {
+        let (r, g, b) = theme.primary_color();
+        let (sr, sg, sb) = theme.secondary_color();
+        let width = LABEL_WIDTH + 1 + (CELL_WIDTH + 1) * 8;
+        let mut output = String::new();
+
+        output.push_str(&format!(
+            "\n{} {} {}\n",
+            theme.icon(),
+            format!("Timesheet {}", self.week).truecolor(r, g, b).bold(),
+            theme.materia_icon()
+        ));
+        if let Some(minutes) = self.rounding_minutes {
+            output.push_str(
+                &format!("   Rounded to {} minutes\n", minutes)
+                    .dimmed()
+                    .to_string(),
+            );
+        }
+        output.push_str(&"━".repeat(width));
+        output.push('\n');
+
+        let label_header = if self.has_tasks() {
+            "Project / Task"
+        } else {
+            "Project"
+        };
+        output.push_str(&format!("{:<w$} ", label_header.bold(), w = LABEL_WIDTH));
+        for i in 0..7 {
+            let header = format!("{:>w$}", self.day_header(i, "%a %d"), w = CELL_WIDTH);
+            output.push_str(&format!("{} ", header.truecolor(sr, sg, sb)));
+        }
+        output.push_str(&format!("{:>w$}\n", "Total".bold(), w = CELL_WIDTH));
+        output.push_str(&"─".repeat(width));
+        output.push('\n');
+
+        if self.rows.is_empty() {
+            output.push_str("  No time tracked this week.\n");
+        }
+
+        for row in &self.rows {
+            output.push_str(&format!("{} ", pad_label(&row.label())));
+            for secs in &row.days {
+                output.push_str(&format!(
+                    "{:>w$} ",
+                    cell(*secs, decimal, "·"),
+                    w = CELL_WIDTH
+                ));
+            }
+            let total = format!("{:>w$}", hours(row.total_seconds, decimal), w = CELL_WIDTH);
+            output.push_str(&format!("{}\n", total.truecolor(r, g, b)));
+        }
+
+        output.push_str(&"─".repeat(width));
+        output.push('\n');
+        output.push_str(&format!("{} ", pad_label("Total").bold()));
+        for secs in &self.day_totals {
+            let total = format!("{:>w$}", cell(*secs, decimal, "·"), w = CELL_WIDTH);
+            output.push_str(&format!("{} ", total.bold()));
+        }
+        let total = format!("{:>w$}", hours(self.total_seconds, decimal), w = CELL_WIDTH);
+        output.push_str(&format!("{}\n", total.truecolor(r, g, b).bold()));
+        output.push_str(&"━".repeat(width));
+        output.push('\n');
+
+        output
+    }
+
+    pub fn to_csv(&self, decimal: bool) -> String {
+        let mut header = vec!["project".to_string()];
+        if self.has_tasks() {
+            header.push("task".into());
+        }
+        header.extend((0..7).map(|i| self.day_header(i, "%a %Y-%m-%d")));
+        header.push("total".into());
+        let mut csv = header.join(",") + "\n";
+
+        let mut line = |label: Vec<String>, days: &[i64], total: i64| {
+            let mut fields = label;
+            fields.extend(days.iter().map(|s| cell(*s, decimal, "")));
+            fields.push(hours(total, decimal));
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        };
+
+        for row in &self.rows {
+            let mut label = vec![csv_field(&row.project)];
+            if self.has_tasks() {
+                label.push(csv_field(row.task.as_deref().unwrap_or("")));
+            }
+            line(label, &row.days, row.total_seconds);
+        }
+        let mut label = vec!["Total".to_string()];
+        if self.has_tasks() {
+            label.push(String::new());
+        }
+        line(label, &self.day_totals, self.total_seconds);
+
+        csv
+    }
+
+    pub fn to_markdown(&self, decimal: bool) -> String {
+        let tasks = self.has_tasks();
+        let mut md = format!("## Timesheet {}\n\n", self.week);
+        if let Some(minutes) = self.rounding_minutes {
+            md.push_str(&format!("_Rounded to {} minutes._\n\n", minutes));
+        }
+
+        md.push_str(if tasks {
+            "| Project | Task |"
+        } else {
+            "| Project |"
+        });
+        for i in 0..7 {
+            md.push_str(&format!(" {} |", self.day_header(i, "%a %m-%d")));
+        }
+        md.push_str(" Total |\n");
+        md.push_str(if tasks { "|---|---|" } else { "|---|" });
+        md.push_str(&"---:|".repeat(8));
+        md.push('\n');
+
+        for row in &self.rows {
+            md.push_str(&format!("| {} |", markdown_cell(&row.project)));
+            if tasks {
+                md.push_str(&format!(
+                    " {} |",
+                    markdown_cell(row.task.as_deref().unwrap_or(""))
+                ));
+            }
+            for secs in &row.days {
+                md.push_str(&format!(" {} |", cell(*secs, decimal, "")));
+            }
+            md.push_str(&format!(" **{}** |\n", hours(row.total_seconds, decimal)));
+        }
+
+        md.push_str(if tasks {
+            "| **Total** | |"
+        } else {
+            "| **Total** |"
+        });
+        for secs in &self.day_totals {
+            match cell(*secs, decimal, "") {
+                total if total.is_empty() => md.push_str("  |"),
+                total => md.push_str(&format!(" **{}** |", total)),
+            }
+        }
+        md.push_str(&format!(" **{}** |\n", hours(self.total_seconds, decimal)));
+        md
+    }
+
+    /// A standalone HTML page with the grid, styled in the theme colours.
+    pub fn to_html(&self, theme: MateriaTheme, decimal: bool) -> String {
+        let (r, g, b) = theme.primary_color();
+        let (sr, sg, sb) = theme.secondary_color();
+        let tasks = self.has_tasks();
+        let mut html = String::new();
+
+        html.push_str(&format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Timesheet {week}</title>\n<style>\n\
+             body {{ font-family: system-ui, sans-serif; margin: 2rem; color: #222; }}\n\
+             h1 {{ color: rgb({r}, {g}, {b}); }}\n\
+             table {{ border-collapse: collapse; }}\n\
+             th, td {{ padding: 0.35rem 0.75rem; border-bottom: 1px solid #ddd; }}\n\
+             thead th {{ background: rgb({r}, {g}, {b}); color: #fff; }}\n\
+             td.num, th.num {{ text-align: right; font-variant-numeric: tabular-nums; }}\n\
+             tfoot td {{ font-weight: bold; border-top: 2px solid rgb({sr}, {sg}, {sb}); }}\n\
+             </style>\n</head>\n<body>\n<h1>Timesheet {week}</h1>\n",
+            week = html_escape(&self.week),
+        ));
+        if let Some(minutes) = self.rounding_minutes {
+            html.push_str(&format!("<p>Rounded to {} minutes.</p>\n", minutes));
+        }
+
+        html.push_str("<table>\n<thead>\n<tr><th>Project</th>");
+        if tasks {
+            html.push_str("<th>Task</th>");
+        }
+        for i in 0..7 {
+            html.push_str(&format!(
+                "<th class=\"num\">{}</th>",
+                self.day_header(i, "%a %d.%m.")
+            ));
+        }
+        html.push_str("<th class=\"num\">Total</th></tr>\n</thead>\n<tbody>\n");
+
+        let cells = |days: &[i64], total: i64| {
+            let mut out: String = days
+                .iter()
+                .map(|s| format!("<td class=\"num\">{}</td>", cell(*s, decimal, "")))
+                .collect();
+            out.push_str(&format!(
+                "<td class=\"num\"><strong>{}</strong></td>",
+                hours(total, decimal)
+            ));
+            out
+        };
+
+        for row in &self.rows {
+            html.push_str(&format!("<tr><td>{}</td>", html_escape(&row.project)));
+            if tasks {
+                html.push_str(&format!(
+                    "<td>{}</td>",
+                    html_escape(row.task.as_deref().unwrap_or(""))
+                ));
+            }
+            html.push_str(&cells(&row.days, row.total_seconds));
+            html.push_str("</tr>\n");
+        }
+
+        html.push_str("</tbody>\n<tfoot>\n<tr><td>Total</td>");
+        if tasks {
+            html.push_str("<td></td>");
+        }
+        html.push_str(&cells(&self.day_totals, self.total_seconds));
+        html.push_str("</tr>\n</tfoot>\n</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+fn row(
+    project: &str,
+    task: Option<&str>,
+    weekdays: &[GroupNode],
+    round: Option<u32>,
+) -> TimesheetRow {
+    let mut days = vec![0; 7];
+    for node in weekdays {
+        if let Ok(weekday) = node.key.parse::<Weekday>() {
+            days[weekday.num_days_from_monday() as usize] =
+                round_seconds(node.total_seconds, round);
+        }
+    }
+    TimesheetRow {
+        project: project.to_string(),
+        task: task.map(String::from),
+        total_seconds: days.iter().sum(),
+        days,
+    }
+}
+
+/// Round to the nearest multiple of `minutes`; halves round up.
+fn round_seconds(seconds: i64, minutes: Option<u32>) -> i64 {
+    match minutes {
+        Some(m) if m > 0 => {
+            let step = m as i64 * 60;
+            (seconds + step / 2) / step * step
+        }
+        _ => seconds,
+    }
+}
+
+/// `7.25` in decimal mode, `7:15` otherwise.
+fn hours(seconds: i64, decimal: bool) -> String {
+    if decimal {
+        format!("{:.2}", seconds as f64 / 3600.0)
+    } else {
+        let minutes = (seconds + 30) / 60;
+        format!("{}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+fn cell(seconds: i64, decimal: bool, empty: &str) -> String {
+    if seconds == 0 {
+        empty.to_string()
+    } else {
+        hours(seconds, decimal)
+    }
+}
+
+fn pad_label(label: &str) -> String {
+    if label.chars().count() > LABEL_WIDTH {
+        let cut: String = label.chars().take(LABEL_WIDTH - 1).collect();
+        format!("{}…", cut)
+    } else {
+        format!("{:<w$}", label, w = LABEL_WIDTH)
+    }
+}
+
+pub(crate) fn markdown_cell(s: &str) -> String {
+    s.replace('|', "\\|")
+}
+
+pub(crate) fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::fixtures::{local, row, seeded};
+
+    fn sheet(by_project: bool, round: Option<u32>) -> Timesheet {
+        let db = seeded(&[
+            row("Acme", "api", local("2026-10-05 09:00"), 127),
+            row("Acme", "api", local("2026-10-07 09:00"), 60),
+            row("Acme", "ui", local("2026-10-07 14:00"), 52),
+            row("Beta", "docs", local("2026-10-11 10:00"), 30),
+            // Next week, outside the sheet
+            row("Beta", "docs", local("2026-10-12 10:00"), 30),
+        ]);
+
+        let engine = StatsEngine::new(db, MateriaTheme::Fire);
+        Timesheet::build(
+            &engine,
+            &Period::week("2026-W41").unwrap(),
+            by_project,
+            round,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_grid_totals() {
+        let sheet = sheet(false, None);
+        assert_eq!(sheet.rows.len(), 3);
+        assert_eq!(sheet.rows[0].label(), "Acme / api");
+        assert_eq!(sheet.rows[0].days[0], 127 * 60);
+        assert_eq!(sheet.rows[0].days[2], 60 * 60);
+        assert_eq!(sheet.day_totals[2], 112 * 60);
+        assert_eq!(sheet.day_totals[6], 30 * 60);
+        assert_eq!(sheet.total_seconds, 269 * 60);
+
+        let by_project = self::sheet(true, None);
+        assert_eq!(by_project.rows.len(), 2);
+        assert_eq!(by_project.rows[0].total_seconds, 239 * 60);
+    }
+
+    #[test]
+    fn test_rounding_applies_per_cell() {
+        let sheet = sheet(false, Some(15));
+        assert_eq!(sheet.rows[0].days[0], 120 * 60);
+        assert_eq!(sheet.rows[1].days[2], 45 * 60);
+        assert_eq!(sheet.total_seconds, (120 + 60 + 45 + 30) * 60);
+        assert_eq!(round_seconds(7 * 60 + 30, Some(15)), 15 * 60);
+    }
+
+    #[test]
+    fn test_hours_formats() {
+        assert_eq!(hours(7 * 3600 + 15 * 60, false), "7:15");
+        assert_eq!(hours(7 * 3600 + 15 * 60, true), "7.25");
+        assert_eq!(cell(0, true, "-"), "-");
+    }
+
+    #[test]
+    fn test_exports() {
+        let sheet = sheet(false, None);
+
+        let csv = sheet.to_csv(true);
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "project,task,Mon 2026-10-05,Tue 2026-10-06,Wed 2026-10-07,Thu 2026-10-08,\
+             Fri 2026-10-09,Sat 2026-10-10,Sun 2026-10-11,total"
+        );
+        assert_eq!(lines[1], "Acme,api,2.12,,1.00,,,,,3.12");
+        assert_eq!(lines.last().unwrap(), &"Total,,2.12,,1.87,,,,0.50,4.48");
+
+        let md = sheet.to_markdown(false);
+        assert!(md.contains("| Acme | api | 2:07 |  | 1:00 |"));
+        assert!(md.contains("| **Total** | | **2:07** |"));
+
+        let html = sheet.to_html(MateriaTheme::Fire, false);
+        assert!(html.contains("<td>Acme</td><td>api</td>"));
+        assert!(html.contains("<tfoot>"));
+        assert_eq!(html_escape("<a & b>"), "&lt;a &amp; b&gt;");
+    }
+}
@@ -3,9 +3,9 @@
 use crate::config::Config;
 use crate::database::Database;
 use crate::error::{Result, TrackingError};
-use crate::models::{apply_time_offset, Entry, Project, Task};
+use crate::integrations::git::GitIntegration;
+use crate::models::{apply_time_offset, Entry, EntryCommit, Project, Task};
 use chrono::{DateTime, Utc};
-use git2::Repository;
 use std::path::Path;
 
 pub struct TrackingEngine {
@@ -18,6 +18,9 @@ impl TrackingEngine {
         Self { db, config }
     }
 
+    /// Start a new entry. The active-timer check and the insert run in one
+    /// `BEGIN IMMEDIATE` transaction, so concurrent `track` calls cannot both
+    /// start a timer.
     pub fn start_tracking(
         &self,
         project_name: &str,
@@ -25,37 +28,44 @@ impl TrackingEngine {
         begin_offset: Option<&str>,
         notes: Option<&str>,
     ) -> Result<(Entry, Project, Task)> {
-        if let Some(active) = self.db.get_active_tracking()? {
-            let project = self.db.get_project(active.project_id)?.unwrap();
-            let task = self.db.get_task(active.task_id)?.unwrap();
-            return Err(TrackingError::AlreadyTracking(format!(
-                "{} -> {}",
-                project.name, task.name
-            ))
-            .into());
-        }
+        self.db.immediate(|db| {
+            if let Some(active) = db.get_active_tracking()? {
+                let project = db.get_project(active.project_id)?.unwrap();
+                let task = db.get_task(active.task_id)?.unwrap();
+                return Err(TrackingError::AlreadyTracking(format!(
+                    "{} -> {}",
+                    project.name, task.name
+                ))
+                .into());
+            }
 
-        let project = self.db.get_or_create_project(project_name)?;
-        let task = self.db.get_or_create_task(project.id, task_name)?;
+            let project = db.get_or_create_project(project_name)?;
+            let task = db.get_or_create_task(project.id, task_name)?;
 
-        let start = if let Some(offset) = begin_offset {
-            apply_time_offset(Utc::now(), offset).unwrap_or_else(Utc::now)
-        } else {
-            Utc::now()
-        };
+            let start = if let Some(offset) = begin_offset {
+                apply_time_offset(Utc::now(), offset).unwrap_or_else(Utc::now)
+            } else {
+                Utc::now()
+            };
 
-        let mut entry = Entry::new(project.id, task.id).with_start(start);
+            let mut entry = Entry::new(project.id, task.id).with_start(start);
 
-        if let Some(n) = notes {
-            entry = entry.with_notes(n);
-        }
+            if let Some(n) = notes {
+                entry = entry.with_notes(n);
+            }
 
-        self.db.create_entry(&mut entry)?;
-        self.db.set_active_tracking(entry.id)?;
+            db.create_entry(&mut entry)?;
+            db.set_active_tracking(entry.id)?;
 
-        Ok((entry, project, task))
+            Ok((entry, project, task))
+        })
     }
 
+    /// Finish the active entry, optionally switching it to `new_task`.
+    ///
+    /// Commits are collected before `BEGIN IMMEDIATE`, so the write lock is
+    /// not held while walking the repository; the transaction then re-checks
+    /// the active entry before writing.
     pub fn finish_tracking(
         &self,
         new_task: Option<&str>,
@@ -63,60 +73,97 @@ impl TrackingEngine {
         end_offset: Option<&str>,
         notes: Option<&str>,
     ) -> Result<(Entry, Project, Task)> {
-        let mut entry = self
-            .db
-            .get_active_tracking()?
-            .ok_or(TrackingError::NotTracking)?;
-
-        let project = self
-            .db
-            .get_project(entry.project_id)?
-            .ok_or_else(|| TrackingError::ProjectNotFound(entry.project_id.to_string()))?;
-
-        let mut task = self
-            .db
-            .get_task(entry.task_id)?
-            .ok_or_else(|| TrackingError::TaskNotFound(entry.task_id.to_string()))?;
+        loop {
+            let active = self
+                .db
+                .get_active_tracking()?
+                .ok_or(TrackingError::NotTracking)?;
+
+            let start = begin_offset
+                .and_then(|offset| apply_time_offset(active.start, offset))
+                .unwrap_or(active.start);
+
+            let end_time = if let Some(offset) = end_offset {
+                apply_time_offset(Utc::now(), offset).unwrap_or_else(Utc::now)
+            } else {
+                Utc::now()
+            };
+
+            let commits = if self.config.tracking.auto_import_git {
+                Some(self.commits_for(&active, new_task, start, end_time)?)
+            } else {
+                None
+            };
+
+            let finished = self.db.immediate(|db| {
+                let mut entry = db
+                    .get_active_tracking()?
+                    .ok_or(TrackingError::NotTracking)?;
+                if entry.id != active.id {
+                    // Finished and restarted elsewhere meanwhile: start over.
+                    return Ok(None);
+                }
 
-        if let Some(task_name) = new_task {
-            task = self.db.get_or_create_task(project.id, task_name)?;
-            entry.task_id = task.id;
-        }
+                let project = db
+                    .get_project(entry.project_id)?
+                    .ok_or_else(|| TrackingError::ProjectNotFound(entry.project_id.to_string()))?;
 
-        if let Some(offset) = begin_offset {
-            if let Some(new_start) = apply_time_offset(entry.start, offset) {
-                entry.start = new_start;
-            }
-        }
+                let mut task = db
+                    .get_task(entry.task_id)?
+                    .ok_or_else(|| TrackingError::TaskNotFound(entry.task_id.to_string()))?;
 
-        let end_time = if let Some(offset) = end_offset {
-            apply_time_offset(Utc::now(), offset).unwrap_or_else(Utc::now)
-        } else {
-            Utc::now()
-        };
+                if let Some(task_name) = new_task {
+                    task = db.get_or_create_task(project.id, task_name)?;
+                    entry.task_id = task.id;
+                }
 
-        entry.finish_at(end_time);
+                entry.start = start;
+                entry.finish_at(end_time);
 
-        if let Some(n) = notes {
-            entry.notes = Some(n.to_string());
-        }
+                if let Some(n) = notes {
+                    entry.notes = Some(n.to_string());
+                }
 
-        if self.config.tracking.auto_import_git {
-            if let Ok(Some(repo_path)) = self.config.git_repo_path() {
-                if let Ok(commits) = get_recent_commits(&repo_path, entry.start, end_time) {
-                    entry.git_commits = commits;
+                if let Some(ref commits) = commits {
+                    entry.git_commits = commits.iter().map(EntryCommit::label).collect();
                 }
-            } else if let Some(ref repo) = task.git_repo {
-                if let Ok(commits) = get_recent_commits(Path::new(repo), entry.start, end_time) {
-                    entry.git_commits = commits;
+
+                db.update_entry(&entry)?;
+                if let Some(ref commits) = commits {
+                    db.replace_entry_commits(entry.id, commits)?;
                 }
+                db.clear_active_tracking()?;
+
+                Ok(Some((entry, project, task)))
+            })?;
+
+            if let Some(finished) = finished {
+                return Ok(finished);
             }
         }
+    }
 
-        self.db.update_entry(&entry)?;
-        self.db.clear_active_tracking()?;
+    /// Commits made between `since` and `until` in the configured repository,
+    /// or else in the repository of the task the entry is finished as.
+    fn commits_for(
+        &self,
+        entry: &Entry,
+        new_task: Option<&str>,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<EntryCommit>> {
+        if let Ok(Some(repo_path)) = self.config.git_repo_path() {
+            return Ok(get_recent_commits(&repo_path, since, until).unwrap_or_default());
+        }
 
-        Ok((entry, project, task))
+        let task = match new_task {
+            Some(name) => self.db.get_task_by_name(entry.project_id, name)?,
+            None => self.db.get_task(entry.task_id)?,
+        };
+        Ok(task
+            .and_then(|t| t.git_repo)
+            .map(|repo| get_recent_commits(Path::new(&repo), since, until).unwrap_or_default())
+            .unwrap_or_default())
     }
 
     pub fn get_status(&self) -> Result<Option<(Entry, Project, Task)>> {
@@ -132,12 +179,14 @@ impl TrackingEngine {
     }
 
     pub fn cancel_tracking(&self) -> Result<Option<Entry>> {
-        if let Some(entry) = self.db.get_active_tracking()? {
-            self.db.clear_active_tracking()?;
-            self.db.delete_entry(entry.id)?;
-            return Ok(Some(entry));
-        }
-        Ok(None)
+        self.db.immediate(|db| {
+            if let Some(entry) = db.get_active_tracking()? {
+                db.clear_active_tracking()?;
+                db.delete_entry(entry.id)?;
+                return Ok(Some(entry));
+            }
+            Ok(None)
+        })
     }
 
     pub fn amend_entry(
@@ -189,38 +238,9 @@ fn get_recent_commits<P: AsRef<Path>>(
     repo_path: P,
     since: DateTime<Utc>,
     until: DateTime<Utc>,
-) -> Result<Vec<String>> {
-    let repo = Repository::open(repo_path)?;
-    let mut revwalk = repo.revwalk()?;
-    revwalk.push_head()?;
-
-    let mut commits = Vec::new();
-    let since_ts = since.timestamp();
-    let until_ts = until.timestamp();
-
-    for oid in revwalk.flatten() {
-        if let Ok(commit) = repo.find_commit(oid) {
-            let commit_time = commit.time().seconds();
-
-            if commit_time < since_ts {
-                break;
-            }
-
-            if commit_time <= until_ts {
-                let short_id = commit.id().to_string()[..7].to_string();
-                let message = commit
-                    .summary()
-                    .unwrap_or("")
-                    .chars()
-                    .take(50)
-                    .collect::<String>();
-
-                commits.push(format!("{}: {}", short_id, message));
-            }
-        }
-    }
-
-    Ok(commits)
+) -> Result<Vec<EntryCommit>> {
+    let commits = GitIntegration::new().get_commits_in_range(repo_path.as_ref(), since, until)?;
+    Ok(commits.iter().map(|c| c.to_entry_commit()).collect())
 }
 
 pub fn format_duration_short(seconds: i64) -> String {
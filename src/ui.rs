@@ -1,7 +1,9 @@
 //! Terminal UI dashboard for MatteriaTrack
 
+use crate::absence::Absence;
 use crate::database::Database;
 use crate::error::Result;
+use crate::heatmap::{self, Heatmap};
 use crate::models::{Entry, EntryWithDetails, Project, Task, TimeStats};
 use crate::stats::StatsEngine;
 use crate::theme::MateriaTheme;
@@ -22,11 +24,20 @@ use ratatui::{
 use std::io;
 use std::time::Duration as StdDuration;
 
+const TABS: [&str; 5] = [
+    "📋 Entries",
+    "🏆 Projects",
+    "📊 Stats",
+    "🟩 Heatmap",
+    "❓ Help",
+];
+
 pub struct App {
     db: Database,
     theme: MateriaTheme,
     selected_tab: usize,
     entries: Vec<EntryWithDetails>,
+    absences: Vec<Absence>,
     projects: Vec<Project>,
     #[allow(dead_code)]
     stats: Option<TimeStats>,
@@ -38,6 +49,7 @@ pub struct App {
 impl App {
     pub fn new(db: Database, theme: MateriaTheme) -> Result<Self> {
         let entries = db.list_entries_with_details(None)?;
+        let absences = db.list_absences(None, None)?;
         let projects = db.list_projects()?;
 
         // Stats engine placeholder - we don't need a separate DB for stats
@@ -54,6 +66,7 @@ impl App {
             theme,
             selected_tab: 0,
             entries,
+            absences,
             projects,
             stats: None,
             active_entry: active,
@@ -64,6 +77,7 @@ impl App {
 
     pub fn refresh_data(&mut self) -> Result<()> {
         self.entries = self.db.list_entries_with_details(None)?;
+        self.absences = self.db.list_absences(None, None)?;
         self.projects = self.db.list_projects()?;
         self.active_entry = self.db.get_active_tracking()?.and_then(|entry| {
             let project = self.db.get_project(entry.project_id).ok()??;
@@ -116,11 +130,11 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut A
                     match key.code {
                         KeyCode::Char('q') | KeyCode::Esc => app.should_quit = true,
                         KeyCode::Tab | KeyCode::Right => {
-                            app.selected_tab = (app.selected_tab + 1) % 4;
+                            app.selected_tab = (app.selected_tab + 1) % TABS.len();
                         }
                         KeyCode::BackTab | KeyCode::Left => {
                             app.selected_tab = if app.selected_tab == 0 {
-                                3
+                                TABS.len() - 1
                             } else {
                                 app.selected_tab - 1
                             };
@@ -168,7 +182,8 @@ fn ui(f: &mut Frame, app: &App) {
         0 => draw_entries(f, app, chunks[2], primary),
         1 => draw_projects(f, app, chunks[2], primary),
         2 => draw_stats(f, app, chunks[2], primary),
-        3 => draw_help(f, app, chunks[2], primary),
+        3 => draw_heatmap(f, app, chunks[2], primary),
+        4 => draw_help(f, app, chunks[2], primary),
         _ => {}
     }
 
@@ -176,8 +191,7 @@ fn ui(f: &mut Frame, app: &App) {
 }
 
 fn draw_header(f: &mut Frame, app: &App, area: Rect, primary: Color) {
-    let titles = vec!["📋 Entries", "🏆 Projects", "📊 Stats", "❓ Help"];
-    let tabs = Tabs::new(titles)
+    let tabs = Tabs::new(TABS.to_vec())
         .block(
             Block::default()
                 .borders(Borders::ALL)
@@ -370,6 +384,67 @@ fn draw_stats(f: &mut Frame, app: &App, area: Rect, primary: Color) {
     f.render_widget(paragraph, area);
 }
 
+fn draw_heatmap(f: &mut Frame, app: &App, area: Rect, primary: Color) {
+    // Weekday labels take 4 columns, every week 2 more
+    let weeks = (area.width.saturating_sub(6) as usize / 2).clamp(1, heatmap::DEFAULT_WEEKS);
+    let today = Local::now().date_naive();
+    let (from, to) = Heatmap::trailing_range(today, weeks);
+    let map = Heatmap::build(&app.entries, from, to, today)
+        .with_absences(app.absences.iter().map(|a| a.date));
+    let colors: Vec<Color> = heatmap::gradient(app.theme)
+        .iter()
+        .map(|(r, g, b)| Color::Rgb(*r, *g, *b))
+        .collect();
+
+    let mut lines = vec![Line::from(format!("    {}", map.month_header()))];
+    let columns = map.weeks();
+    for (row, label) in heatmap::WEEKDAY_LABELS.iter().enumerate() {
+        let mut spans = vec![Span::raw(format!("{:<4}", label))];
+        for week in &columns {
+            spans.push(match week[row] {
+                Some(day) => Span::styled(
+                    "■ ",
+                    Style::default().fg(colors[map.level(map.seconds_on(day))]),
+                ),
+                None => Span::raw("  "),
+            });
+        }
+        lines.push(Line::from(spans));
+    }
+
+    lines.push(Line::from(""));
+    let mut legend = vec![Span::raw("    Less ")];
+    legend.extend(
+        colors
+            .iter()
+            .map(|c| Span::styled("■ ", Style::default().fg(*c))),
+    );
+    legend.push(Span::raw("More"));
+    lines.push(Line::from(legend));
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::raw("    Current streak: "),
+        Span::styled(
+            heatmap::days(map.current_streak),
+            Style::default().fg(primary).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("   Longest streak: "),
+        Span::styled(
+            heatmap::days(map.longest_streak.days),
+            Style::default().fg(primary),
+        ),
+        Span::raw(format!("   Active days: {}", map.active_days)),
+    ]));
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" 🟩 Activity ({} weeks) ", weeks)),
+    );
+
+    f.render_widget(paragraph, area);
+}
+
 fn draw_help(f: &mut Frame, _app: &App, area: Rect, primary: Color) {
     let help_text = vec![
         Line::from(vec![Span::styled(
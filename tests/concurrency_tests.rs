@@ -0,0 +1,177 @@
+//! Several `mtrack` processes racing on one database: status bars polling
+//! while `track`/`finish` run must never see "database is locked", and at
+//! most one entry may ever be active.
+
+use materiatrack::Database;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::thread;
+use tempfile::TempDir;
+
+const RACERS: usize = 8;
+const ROUNDS: usize = 5;
+
+fn mtrack(home: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_materiatrack"))
+        .args(args)
+        .env("HOME", home)
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("XDG_DATA_HOME")
+        .env_remove("MTRACK_PROFILE")
+        .output()
+        .expect("failed to run materiatrack")
+}
+
+fn db_path(home: &Path) -> PathBuf {
+    let data = home.join(".local/share/materiatrack/materiatrack.db");
+    if data.exists() {
+        data
+    } else {
+        // macOS keeps local data under Library/Application Support.
+        home.join("Library/Application Support/materiatrack/materiatrack.db")
+    }
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string() + &String::from_utf8_lossy(&output.stdout)
+}
+
+/// Run `args_for(i)` in `RACERS` processes at once.
+fn race(home: &Path, args_for: impl Fn(usize) -> Vec<String>) -> Vec<Output> {
+    thread::scope(|s| {
+        let handles: Vec<_> = (0..RACERS)
+            .map(|i| {
+                let args = args_for(i);
+                s.spawn(move || {
+                    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                    mtrack(home, &args)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    })
+}
+
+fn assert_single_active(home: &Path) {
+    let db = Database::open(db_path(home)).unwrap();
+    let open: Vec<_> = db
+        .list_entries(None)
+        .unwrap()
+        .into_iter()
+        .filter(|e| e.is_active())
+        .collect();
+    assert!(open.len() <= 1, "{} entries are active", open.len());
+
+    let active = db.get_active_tracking().unwrap();
+    assert_eq!(active.map(|e| e.id), open.first().map(|e| e.id));
+}
+
+fn setup() -> TempDir {
+    let home = tempfile::tempdir().unwrap();
+    // Create the config and database before the race starts.
+    assert!(mtrack(home.path(), &["status"]).status.success());
+    home
+}
+
+#[test]
+fn test_racing_track_starts_exactly_one_timer() {
+    let home = setup();
+
+    for round in 0..ROUNDS {
+        let outputs = race(home.path(), |i| {
+            vec![
+                "track".into(),
+                "-p".into(),
+                "Race".into(),
+                "-t".into(),
+                format!("r{}-{}", round, i),
+            ]
+        });
+
+        let started = outputs.iter().filter(|o| o.status.success()).count();
+        assert_eq!(started, 1, "round {}: {} timers started", round, started);
+        for output in outputs.iter().filter(|o| !o.status.success()) {
+            let message = stderr(output);
+            assert!(!message.contains("locked"), "round {}: {}", round, message);
+        }
+        assert_single_active(home.path());
+
+        assert!(mtrack(home.path(), &["finish"]).status.success());
+        assert_single_active(home.path());
+    }
+
+    let db = Database::open(db_path(home.path())).unwrap();
+    assert_eq!(db.list_entries(None).unwrap().len(), ROUNDS);
+}
+
+#[test]
+fn test_racing_finish_and_switch_close_the_timer_once() {
+    let home = setup();
+
+    for round in 0..ROUNDS {
+        assert!(mtrack(home.path(), &["track", "-p", "Race", "-t", "work"])
+            .status
+            .success());
+
+        let outputs = race(home.path(), |i| {
+            if i % 2 == 0 {
+                vec!["finish".into()]
+            } else {
+                vec!["finish".into(), "-t".into(), format!("switched-{}", i)]
+            }
+        });
+
+        let winners: Vec<usize> = (0..RACERS)
+            .filter(|&i| outputs[i].status.success())
+            .collect();
+        assert_eq!(winners.len(), 1, "round {}: winners {:?}", round, winners);
+        for output in outputs.iter().filter(|o| !o.status.success()) {
+            // Losers must fail on the missing timer, not on argument parsing.
+            assert_eq!(output.status.code(), Some(1), "{}", stderr(output));
+        }
+        assert_single_active(home.path());
+
+        let db = Database::open(db_path(home.path())).unwrap();
+        let entries = db.list_entries_with_details(None).unwrap();
+        let last = entries.iter().max_by_key(|e| e.entry.id).unwrap();
+        let expected = match winners[0] {
+            i if i % 2 == 0 => "work".to_string(),
+            i => format!("switched-{}", i),
+        };
+        assert_eq!(last.task_name, expected, "round {}", round);
+    }
+}
+
+#[test]
+fn test_status_bar_polling_during_writes() {
+    let home = setup();
+
+    thread::scope(|s| {
+        let pollers: Vec<_> = (0..4)
+            .map(|_| {
+                s.spawn(|| {
+                    (0..15)
+                        .map(|_| mtrack(home.path(), &["statusbar"]))
+                        .filter(|o| !o.status.success())
+                        .map(|o| stderr(&o))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        for i in 0..10 {
+            let task = format!("t{}", i);
+            let track = mtrack(home.path(), &["track", "-p", "Poll", "-t", &task]);
+            assert!(track.status.success(), "{}", stderr(&track));
+            let finish = mtrack(home.path(), &["finish"]);
+            assert!(finish.status.success(), "{}", stderr(&finish));
+        }
+
+        for poller in pollers {
+            let failures = poller.join().unwrap();
+            assert!(failures.is_empty(), "statusbar failed: {:?}", failures);
+        }
+    });
+
+    assert_single_active(home.path());
+}
@@ -0,0 +1,253 @@
+//! Two-device sync scenarios: each device has its own temp directory and
+//! database, and both exchange change logs through a shared folder.
+
+use chrono::{Duration, Utc};
+use materiatrack::sync::Syncer;
+use materiatrack::{Database, Entry};
+use tempfile::TempDir;
+
+/// (project, task, start, end, notes)
+type EntryRow = (String, String, String, Option<String>, Option<String>);
+
+struct Device {
+    name: &'static str,
+    _home: TempDir,
+    db: Database,
+}
+
+impl Device {
+    fn new(name: &'static str) -> Self {
+        let home = tempfile::tempdir().unwrap();
+        let db = Database::open(home.path().join("materiatrack.db")).unwrap();
+        Self {
+            name,
+            _home: home,
+            db,
+        }
+    }
+
+    fn sync(&self, shared: &TempDir) -> materiatrack::sync::SyncReport {
+        Syncer::new(&self.db, self.name)
+            .sync(shared.path())
+            .unwrap()
+    }
+
+    fn log(&self, project: &str, task: &str, start_hours_ago: i64, hours: i64) -> Entry {
+        let p = self.db.get_or_create_project(project).unwrap();
+        let t = self.db.get_or_create_task(p.id, task).unwrap();
+        let mut entry =
+            Entry::new(p.id, t.id).with_start(Utc::now() - Duration::hours(start_hours_ago));
+        entry.end = Some(entry.start + Duration::hours(hours));
+        self.db.create_entry(&mut entry).unwrap();
+        entry
+    }
+
+    fn start(&self, project: &str, task: &str, minutes_ago: i64) -> Entry {
+        let p = self.db.get_or_create_project(project).unwrap();
+        let t = self.db.get_or_create_task(p.id, task).unwrap();
+        let mut entry =
+            Entry::new(p.id, t.id).with_start(Utc::now() - Duration::minutes(minutes_ago));
+        self.db.create_entry(&mut entry).unwrap();
+        self.db.set_active_tracking(entry.id).unwrap();
+        entry
+    }
+
+    /// Entries as comparable tuples, independent of local row ids.
+    fn entries(&self) -> Vec<EntryRow> {
+        let mut entries: Vec<_> = self
+            .db
+            .list_entries_with_details(None)
+            .unwrap()
+            .into_iter()
+            .map(|e| {
+                (
+                    e.project_name,
+                    e.task_name,
+                    e.entry.start.to_rfc3339(),
+                    e.entry.end.map(|t| t.to_rfc3339()),
+                    e.entry.notes,
+                )
+            })
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    fn project_names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self
+            .db
+            .list_projects()
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+fn sync_both(a: &Device, b: &Device, shared: &TempDir) {
+    a.sync(shared);
+    b.sync(shared);
+    a.sync(shared);
+}
+
+#[test]
+fn test_entries_from_both_devices_converge() {
+    let shared = tempfile::tempdir().unwrap();
+    let laptop = Device::new("laptop");
+    let desktop = Device::new("desktop");
+
+    laptop.log("Acme", "Design", 5, 1);
+    laptop.log("Acme", "Review", 3, 1);
+    desktop.log("Internal", "Ops", 4, 2);
+
+    sync_both(&laptop, &desktop, &shared);
+
+    assert_eq!(laptop.entries().len(), 3);
+    assert_eq!(laptop.entries(), desktop.entries());
+    assert_eq!(laptop.project_names(), vec!["Acme", "Internal"]);
+
+    // A second round with no edits exchanges nothing.
+    assert_eq!(laptop.sync(&shared).pushed, 0);
+    assert_eq!(desktop.sync(&shared).pushed, 0);
+}
+
+#[test]
+fn test_same_project_name_on_both_devices_is_merged() {
+    let shared = tempfile::tempdir().unwrap();
+    let laptop = Device::new("laptop");
+    let desktop = Device::new("desktop");
+
+    laptop.log("Acme", "Dev", 6, 1);
+    desktop.log("Acme", "Dev", 2, 1);
+
+    sync_both(&laptop, &desktop, &shared);
+    desktop.sync(&shared);
+
+    assert_eq!(laptop.project_names(), vec!["Acme"]);
+    assert_eq!(desktop.project_names(), vec!["Acme"]);
+    assert_eq!(laptop.entries().len(), 2);
+    assert_eq!(laptop.entries(), desktop.entries());
+
+    // Entries pulled under an alias don't bounce back and forth.
+    for _ in 0..3 {
+        assert_eq!(laptop.sync(&shared).pushed, 0);
+        assert_eq!(desktop.sync(&shared).pushed, 0);
+    }
+}
+
+#[test]
+fn test_concurrent_edit_last_writer_wins_everywhere() {
+    let shared = tempfile::tempdir().unwrap();
+    let laptop = Device::new("laptop");
+    let desktop = Device::new("desktop");
+
+    let entry = laptop.log("Acme", "Dev", 3, 1);
+    sync_both(&laptop, &desktop, &shared);
+
+    let mut on_laptop = laptop.db.get_entry(entry.id).unwrap().unwrap();
+    on_laptop.notes = Some("from laptop".into());
+    laptop.db.update_entry(&on_laptop).unwrap();
+
+    let desktop_id = desktop.db.list_entries(None).unwrap()[0].id;
+    let mut on_desktop = desktop.db.get_entry(desktop_id).unwrap().unwrap();
+    on_desktop.notes = Some("from desktop".into());
+    desktop.db.update_entry(&on_desktop).unwrap();
+
+    // The laptop publishes first, so the desktop's edit is the later write.
+    // Changes stamped in the same millisecond fall back to the device id.
+    laptop.sync(&shared);
+    std::thread::sleep(std::time::Duration::from_millis(2));
+    let report = desktop.sync(&shared);
+    assert_eq!(report.superseded, 1);
+    laptop.sync(&shared);
+
+    assert_eq!(laptop.entries(), desktop.entries());
+    assert_eq!(laptop.entries()[0].4.as_deref(), Some("from desktop"));
+}
+
+#[test]
+fn test_deletes_propagate() {
+    let shared = tempfile::tempdir().unwrap();
+    let laptop = Device::new("laptop");
+    let desktop = Device::new("desktop");
+
+    let keep = laptop.log("Acme", "Dev", 5, 1);
+    let gone = laptop.log("Acme", "Dev", 2, 1);
+    sync_both(&laptop, &desktop, &shared);
+    assert_eq!(desktop.entries().len(), 2);
+
+    laptop.db.delete_entry(gone.id).unwrap();
+    sync_both(&laptop, &desktop, &shared);
+
+    assert_eq!(desktop.entries().len(), 1);
+    assert_eq!(desktop.entries()[0].2, keep.start.to_rfc3339());
+}
+
+#[test]
+fn test_only_one_timer_stays_active() {
+    let shared = tempfile::tempdir().unwrap();
+    let laptop = Device::new("laptop");
+    let desktop = Device::new("desktop");
+
+    let older = laptop.start("Acme", "Dev", 90);
+    let newer = desktop.start("Internal", "Ops", 30);
+
+    sync_both(&laptop, &desktop, &shared);
+    desktop.sync(&shared);
+
+    for device in [&laptop, &desktop] {
+        let open: Vec<_> = device
+            .db
+            .list_entries(None)
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.is_active())
+            .collect();
+        assert_eq!(
+            open.len(),
+            1,
+            "{} has more than one open entry",
+            device.name
+        );
+        assert_eq!(open[0].start.to_rfc3339(), newer.start.to_rfc3339());
+
+        let active = device.db.get_active_tracking().unwrap().unwrap();
+        assert_eq!(active.start.to_rfc3339(), newer.start.to_rfc3339());
+
+        let closed = device
+            .db
+            .list_entries(None)
+            .unwrap()
+            .into_iter()
+            .find(|e| e.start.to_rfc3339() == older.start.to_rfc3339())
+            .unwrap();
+        assert_eq!(
+            closed.end.map(|t| t.to_rfc3339()),
+            Some(newer.start.to_rfc3339())
+        );
+    }
+
+    assert_eq!(laptop.entries(), desktop.entries());
+}
+
+#[test]
+fn test_failed_log_write_is_republished() {
+    let shared = tempfile::tempdir().unwrap();
+    let laptop = Device::new("laptop");
+    let desktop = Device::new("desktop");
+    laptop.log("Acme", "Dev", 3, 1);
+
+    // A directory in place of the log makes the append fail after the commit.
+    let log = shared.path().join("laptop.jsonl");
+    std::fs::create_dir(&log).unwrap();
+    assert!(Syncer::new(&laptop.db, laptop.name)
+        .sync(shared.path())
+        .is_err());
+    std::fs::remove_dir(&log).unwrap();
+
+    assert!(laptop.sync(&shared).pushed > 0);
+    desktop.sync(&shared);
+    assert_eq!(laptop.entries(), desktop.entries());
+}